@@ -58,6 +58,18 @@ pub enum QueryError {
     },
 }
 
+#[derive(thiserror::Error, Debug)]
+pub enum SimulateTransactionError {
+    #[error("Transaction is invalid: {error}")]
+    InvalidTransaction { error: near_primitives::errors::InvalidTxError },
+    #[error("The state of shard {shard_id} is not available on the node")]
+    UnavailableShard { shard_id: near_primitives::types::ShardId },
+    #[error("Transaction produced more than {limit} receipts")]
+    TooManyReceipts { limit: usize },
+    #[error("Internal error occurred: {error_message}")]
+    InternalError { error_message: String },
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     /// The block is already known
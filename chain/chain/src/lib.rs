@@ -1,7 +1,10 @@
 pub use block_processing_utils::{BlockProcessingArtifact, DoneApplyChunkCallback};
 pub use chain::{check_known, collect_receipts, Chain, ChainUpdate, MAX_ORPHAN_SIZE};
 pub use doomslug::{Doomslug, DoomslugBlockProductionReadiness, DoomslugThresholdMode};
-pub use lightclient::{create_light_client_block_view, get_epoch_block_producers_view};
+pub use lightclient::{
+    create_light_client_block_view, get_epoch_block_producers_view, light_client_block_hash,
+    LightClientBlockError, LightClientState,
+};
 pub use near_chain_primitives::{self, Error};
 pub use near_primitives::receipt::ReceiptResult;
 pub use store::{ChainStore, ChainStoreAccess, ChainStoreUpdate};
@@ -20,6 +23,7 @@ mod metrics;
 pub mod migrations;
 pub mod missing_chunks;
 pub mod resharding;
+pub mod resharding_proposal;
 mod state_request_tracker;
 pub mod state_snapshot_actor;
 mod store;
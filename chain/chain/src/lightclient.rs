@@ -1,10 +1,16 @@
+use std::collections::HashMap;
+
 use near_chain_primitives::Error;
 use near_epoch_manager::EpochManagerAdapter;
-use near_primitives::block::BlockHeader;
+use near_primitives::block::{Approval, ApprovalInner, BlockHeader};
 use near_primitives::hash::{hash, CryptoHash};
-use near_primitives::types::EpochId;
+use near_primitives::merkle::combine_hash;
+use near_primitives::types::validator_stake::ValidatorStake;
+use near_primitives::types::{AccountId, Balance, BlockHeight, EpochId};
 use near_primitives::views::validator_stake_view::ValidatorStakeView;
-use near_primitives::views::{BlockHeaderInnerLiteView, LightClientBlockView};
+use near_primitives::views::{
+    BlockHeaderInnerLiteView, LightClientBlockLiteView, LightClientBlockView,
+};
 
 use crate::ChainStoreAccess;
 
@@ -71,3 +77,159 @@ pub fn create_light_client_block_view(
         approvals_after_next,
     })
 }
+
+/// Hash of the block described by the light client block.
+pub fn light_client_block_hash(block: &LightClientBlockView) -> CryptoHash {
+    LightClientBlockLiteView {
+        prev_block_hash: block.prev_block_hash,
+        inner_rest_hash: block.inner_rest_hash,
+        inner_lite: block.inner_lite.clone(),
+    }
+    .hash()
+}
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum LightClientBlockError {
+    #[error("Block at height {height} is not after the head at height {head_height}")]
+    NotAfterHead { height: BlockHeight, head_height: BlockHeight },
+    #[error("Block is neither in the epoch of the head nor in the next one")]
+    UnexpectedEpoch,
+    #[error("Block producers of the epoch of the block are not known")]
+    UnknownBlockProducers,
+    #[error("First block of the next epoch doesn't contain the next block producers")]
+    MissingNextBlockProducers,
+    #[error("Invalid approval signature of {account_id}")]
+    InvalidSignature { account_id: AccountId },
+    #[error("Block approved by {approved_stake} out of {total_stake} stake")]
+    NotEnoughApprovals { approved_stake: Balance, total_stake: Balance },
+    #[error("Next block producers don't match the hash in the block header")]
+    InvalidNextBlockProducers,
+}
+
+/// State of a light client, which can be advanced only with blocks approved by the block
+/// producers it trusts.
+///
+/// The verification doesn't need access to the chain, so a `LightClientState` can be used
+/// outside of a node, e.g. to check the light client blocks returned by the RPC.
+#[derive(Clone, Debug)]
+pub struct LightClientState {
+    head: LightClientBlockLiteView,
+    /// Ordered block producers of the epoch of the head and of the next epoch, if known.
+    epoch_block_producers: HashMap<CryptoHash, Vec<ValidatorStakeView>>,
+}
+
+impl LightClientState {
+    /// Creates a light client trusting `head`.
+    ///
+    /// # Arguments
+    ///  * `head` - trusted block
+    ///  * `block_producers` - the ordered list of block producers in the epoch of `head`
+    ///  * `next_block_producers` - the ordered list of block producers in the next epoch,
+    ///                   e.g. `next_bps` of the trusted light client block, if known
+    pub fn new(
+        head: LightClientBlockLiteView,
+        block_producers: Vec<ValidatorStakeView>,
+        next_block_producers: Option<Vec<ValidatorStakeView>>,
+    ) -> Self {
+        let mut epoch_block_producers = HashMap::new();
+        epoch_block_producers.insert(head.inner_lite.epoch_id, block_producers);
+        if let Some(next_block_producers) = next_block_producers {
+            epoch_block_producers.insert(head.inner_lite.next_epoch_id, next_block_producers);
+        }
+        Self { head, epoch_block_producers }
+    }
+
+    pub fn head(&self) -> &LightClientBlockLiteView {
+        &self.head
+    }
+
+    /// Checks that `block` can follow the current head, without updating the state.
+    pub fn validate(&self, block: &LightClientBlockView) -> Result<(), LightClientBlockError> {
+        let inner_lite = &block.inner_lite;
+        let head_lite = &self.head.inner_lite;
+        if inner_lite.height <= head_lite.height {
+            return Err(LightClientBlockError::NotAfterHead {
+                height: inner_lite.height,
+                head_height: head_lite.height,
+            });
+        }
+        if inner_lite.epoch_id != head_lite.epoch_id
+            && inner_lite.epoch_id != head_lite.next_epoch_id
+        {
+            return Err(LightClientBlockError::UnexpectedEpoch);
+        }
+        if inner_lite.epoch_id == head_lite.next_epoch_id && block.next_bps.is_none() {
+            return Err(LightClientBlockError::MissingNextBlockProducers);
+        }
+        let block_producers = self
+            .epoch_block_producers
+            .get(&inner_lite.epoch_id)
+            .ok_or(LightClientBlockError::UnknownBlockProducers)?;
+
+        // The block is final once the block after the next one is approved by 2/3 of the stake,
+        // approvals of which endorse the next block.
+        let next_block_hash =
+            combine_hash(&block.next_block_inner_hash, &light_client_block_hash(block));
+        let approval_message = Approval::get_data_for_sig(
+            &ApprovalInner::Endorsement(next_block_hash),
+            inner_lite.height + 2,
+        );
+        let mut total_stake: Balance = 0;
+        let mut approved_stake: Balance = 0;
+        for (bp, approval) in block_producers.iter().zip(block.approvals_after_next.iter()) {
+            let bp = bp.clone().into_validator_stake();
+            total_stake += bp.stake();
+            let Some(signature) = approval else {
+                continue;
+            };
+            if !signature.verify(&approval_message, bp.public_key()) {
+                return Err(LightClientBlockError::InvalidSignature {
+                    account_id: bp.take_account_id(),
+                });
+            }
+            approved_stake += bp.stake();
+        }
+        if approved_stake * 3 <= total_stake * 2 {
+            return Err(LightClientBlockError::NotEnoughApprovals { approved_stake, total_stake });
+        }
+
+        if let Some(next_bps) = &block.next_bps {
+            let next_bps = next_bps.iter().cloned().map(ValidatorStake::from);
+            // Blocks produced before `BlockHeaderV3` hash the legacy representation of the stakes.
+            if CryptoHash::hash_borsh_iter(next_bps.clone()) != inner_lite.next_bp_hash
+                && CryptoHash::hash_borsh_iter(next_bps.map(|bp| bp.into_v1()))
+                    != inner_lite.next_bp_hash
+            {
+                return Err(LightClientBlockError::InvalidNextBlockProducers);
+            }
+        }
+        Ok(())
+    }
+
+    /// Validates `block` and makes it the new head.
+    pub fn update(&mut self, block: &LightClientBlockView) -> Result<(), LightClientBlockError> {
+        self.validate(block)?;
+        self.head = LightClientBlockLiteView {
+            prev_block_hash: block.prev_block_hash,
+            inner_rest_hash: block.inner_rest_hash,
+            inner_lite: block.inner_lite.clone(),
+        };
+        if let Some(next_bps) = &block.next_bps {
+            self.epoch_block_producers.insert(block.inner_lite.next_epoch_id, next_bps.clone());
+        }
+        let head_lite = &self.head.inner_lite;
+        self.epoch_block_producers.retain(|epoch_id, _| {
+            *epoch_id == head_lite.epoch_id || *epoch_id == head_lite.next_epoch_id
+        });
+        Ok(())
+    }
+
+    /// Advances the light client through a sequence of blocks, as returned by the
+    /// `EXPERIMENTAL_light_client_blocks` RPC. On error, the head is the last valid block.
+    pub fn update_all<'a>(
+        &mut self,
+        blocks: impl IntoIterator<Item = &'a LightClientBlockView>,
+    ) -> Result<(), LightClientBlockError> {
+        blocks.into_iter().try_for_each(|block| self.update(block))
+    }
+}
@@ -0,0 +1,254 @@
+//! Deterministic proposal of the next shard layout from the statistics of the
+//! shards. Shards whose state size, gas usage or number of delayed receipts
+//! exceed the given thresholds are split in two, at the account which
+//! balances the state size and the gas usage between the two halves.
+use near_chain_primitives::Error;
+use near_primitives::shard_layout::ShardLayout;
+use near_primitives::types::{AccountId, Gas, ShardId};
+use std::collections::BTreeMap;
+
+/// Statistics of a shard, collected over an epoch.
+#[derive(serde::Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShardStats {
+    pub shard_id: ShardId,
+    /// Size of the keys and values of the accounts in the shard, in bytes.
+    pub state_size: u64,
+    /// Gas used by the chunks of the shard.
+    pub gas_used: Gas,
+    /// Sum of the gas limits of the chunks of the shard.
+    pub gas_limit: Gas,
+    /// Largest number of delayed receipts in the shard after a block.
+    pub max_delayed_receipts: u64,
+}
+
+/// Limits above which a shard is split.
+#[derive(Debug, Clone)]
+pub struct SplitThresholds {
+    pub max_state_size: u64,
+    /// Percentage of the gas limit used by the chunks of a shard.
+    pub max_gas_used_percent: u64,
+    pub max_delayed_receipts: u64,
+    /// Maximum number of shards to split, the most loaded shards are split
+    /// first.
+    pub max_splits: usize,
+}
+
+/// Size and gas usage of an account, used to pick the boundary account of a
+/// split.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountLoad {
+    pub account_id: AccountId,
+    pub state_size: u64,
+    pub gas_used: Gas,
+}
+
+impl ShardStats {
+    /// Load of the shard relative to the thresholds, in per mille of the
+    /// most exceeded threshold. The shard should be split if it is above
+    /// 1000.
+    pub fn load_per_mille(&self, thresholds: &SplitThresholds) -> u64 {
+        let ratio = |value: u128, max: u128| {
+            if max == 0 {
+                0
+            } else {
+                (value * 1000 / max).try_into().unwrap_or(u64::MAX)
+            }
+        };
+        [
+            ratio(self.state_size.into(), thresholds.max_state_size.into()),
+            ratio(
+                u128::from(self.gas_used) * 100,
+                u128::from(self.gas_limit) * u128::from(thresholds.max_gas_used_percent),
+            ),
+            ratio(self.max_delayed_receipts.into(), thresholds.max_delayed_receipts.into()),
+        ]
+        .into_iter()
+        .max()
+        .unwrap()
+    }
+}
+
+/// Returns the shards which should be split, the most loaded first. Ties are
+/// broken by the shard id, so that the result only depends on the stats.
+pub fn shards_to_split(stats: &[ShardStats], thresholds: &SplitThresholds) -> Vec<ShardId> {
+    let mut loads: Vec<_> = stats
+        .iter()
+        .map(|stats| (stats.load_per_mille(thresholds), stats.shard_id))
+        .filter(|(load, _)| *load > 1000)
+        .collect();
+    loads.sort_by(|(load_a, shard_a), (load_b, shard_b)| {
+        load_b.cmp(load_a).then(shard_a.cmp(shard_b))
+    });
+    loads.into_iter().take(thresholds.max_splits).map(|(_, shard_id)| shard_id).collect()
+}
+
+/// Picks the account at which a shard is split, given its accounts sorted by
+/// account id. The state size and the gas usage weigh equally, relative to
+/// their totals in the shard. Returns `None` if the shard has fewer than two
+/// accounts.
+pub fn pick_boundary_account(accounts: &[AccountLoad]) -> Option<AccountId> {
+    if accounts.len() < 2 {
+        return None;
+    }
+    let total_size: u128 = accounts.iter().map(|account| u128::from(account.state_size)).sum();
+    let total_gas: u128 = accounts.iter().map(|account| u128::from(account.gas_used)).sum();
+    // Weight of an account scaled by `total_size * total_gas` to stay in
+    // integers. A total of zero would zero out all weights, so it is left out.
+    let weight = |account: &AccountLoad| {
+        let size = u128::from(account.state_size);
+        let gas = u128::from(account.gas_used);
+        match (total_size, total_gas) {
+            (0, _) => gas,
+            (_, 0) => size,
+            _ => size * total_gas + gas * total_size,
+        }
+    };
+    let total_weight: u128 = accounts.iter().map(weight).sum();
+    // The boundary account is the first account of the right half, so the
+    // left half is never empty.
+    let mut left_weight = weight(&accounts[0]);
+    let mut best = (total_weight.abs_diff(2 * left_weight), 1);
+    for (index, account) in accounts.iter().enumerate().skip(1) {
+        let imbalance = total_weight.abs_diff(2 * left_weight);
+        if imbalance < best.0 {
+            best = (imbalance, index);
+        }
+        left_weight += weight(account);
+    }
+    Some(accounts[best.1].account_id.clone())
+}
+
+/// Returns the shard layout following `shard_layout` in which every shard of
+/// `boundary_accounts` is split in two at the given account.
+pub fn split_shard_layout(
+    shard_layout: &ShardLayout,
+    boundary_accounts: &BTreeMap<ShardId, AccountId>,
+) -> Result<ShardLayout, Error> {
+    let num_shards = shard_layout.shard_ids().count() as ShardId;
+    if let Some(&shard_id) = boundary_accounts.keys().find(|&&shard_id| shard_id >= num_shards) {
+        return Err(Error::InvalidShardId(shard_id));
+    }
+    let old_boundary_accounts: &[AccountId] = match shard_layout.boundary_accounts() {
+        Some(boundary_accounts) => boundary_accounts,
+        None if num_shards == 1 => &[],
+        None => {
+            return Err(Error::Other(
+                "shards of a V0 shard layout can't be split by account".to_string(),
+            ))
+        }
+    };
+    let mut new_boundary_accounts = vec![];
+    let mut shards_split_map = vec![];
+    for shard_id in shard_layout.shard_ids() {
+        if shard_id > 0 {
+            new_boundary_accounts.push(old_boundary_accounts[shard_id as usize - 1].clone());
+        }
+        let first_new_shard_id = new_boundary_accounts.len() as ShardId;
+        let Some(account_id) = boundary_accounts.get(&shard_id) else {
+            shards_split_map.push(vec![first_new_shard_id]);
+            continue;
+        };
+        let lower = new_boundary_accounts.last();
+        let upper = old_boundary_accounts.get(shard_id as usize);
+        if lower.map_or(false, |lower| account_id <= lower)
+            || upper.map_or(false, |upper| account_id >= upper)
+        {
+            return Err(Error::Other(format!(
+                "boundary account {} is not inside of shard {}",
+                account_id, shard_id
+            )));
+        }
+        new_boundary_accounts.push(account_id.clone());
+        shards_split_map.push(vec![first_new_shard_id, first_new_shard_id + 1]);
+    }
+    Ok(ShardLayout::v1(new_boundary_accounts, Some(shards_split_map), shard_layout.version() + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        pick_boundary_account, shards_to_split, split_shard_layout, AccountLoad, ShardStats,
+        SplitThresholds,
+    };
+    use near_primitives::shard_layout::{account_id_to_shard_id, ShardLayout};
+    use near_primitives::types::AccountId;
+    use std::collections::BTreeMap;
+
+    fn account(account_id: &str, state_size: u64, gas_used: u64) -> AccountLoad {
+        AccountLoad { account_id: account_id.parse().unwrap(), state_size, gas_used }
+    }
+
+    #[test]
+    fn test_shards_to_split() {
+        let thresholds = SplitThresholds {
+            max_state_size: 1000,
+            max_gas_used_percent: 50,
+            max_delayed_receipts: 100,
+            max_splits: 2,
+        };
+        let stats = |shard_id, state_size, gas_used, max_delayed_receipts| ShardStats {
+            shard_id,
+            state_size,
+            gas_used,
+            gas_limit: 1000,
+            max_delayed_receipts,
+        };
+        let all_stats = vec![
+            stats(0, 500, 100, 0),
+            // Over the gas threshold: 60% used.
+            stats(1, 500, 600, 0),
+            // Over the state size threshold by as much as shard 1 over gas.
+            stats(2, 1200, 100, 0),
+            // The most congested.
+            stats(3, 500, 100, 500),
+        ];
+        assert_eq!(all_stats[1].load_per_mille(&thresholds), 1200);
+        assert_eq!(shards_to_split(&all_stats, &thresholds), vec![3, 1]);
+        let thresholds = SplitThresholds { max_splits: 10, ..thresholds };
+        assert_eq!(shards_to_split(&all_stats, &thresholds), vec![3, 1, 2]);
+    }
+
+    #[test]
+    fn test_pick_boundary_account() {
+        assert_eq!(pick_boundary_account(&[account("a.near", 100, 0)]), None);
+        let accounts =
+            [account("a.near", 10, 0), account("b.near", 10, 0), account("c.near", 20, 0)];
+        assert_eq!(pick_boundary_account(&accounts), Some("c.near".parse().unwrap()));
+        // Gas usage weighs as much as the state size.
+        let accounts = [
+            account("a.near", 10, 0),
+            account("b.near", 10, 0),
+            account("c.near", 20, 0),
+            account("d.near", 0, 100),
+        ];
+        assert_eq!(pick_boundary_account(&accounts), Some("d.near".parse().unwrap()));
+        // The boundary is never the first account.
+        let accounts = [account("a.near", 1000, 0), account("b.near", 1, 0)];
+        assert_eq!(pick_boundary_account(&accounts), Some("b.near".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_split_shard_layout() {
+        let shard_layout = ShardLayout::get_simple_nightshade_layout();
+        let boundary_account: AccountId = "tge-lockup.sweat".parse().unwrap();
+        let shard_id = account_id_to_shard_id(&boundary_account, &shard_layout);
+        let new_layout =
+            split_shard_layout(&shard_layout, &BTreeMap::from([(shard_id, boundary_account)]))
+                .unwrap();
+        assert_eq!(new_layout, ShardLayout::get_simple_nightshade_layout_v2());
+
+        let not_in_shard = BTreeMap::from([(0, "zzz".parse().unwrap())]);
+        assert!(split_shard_layout(&shard_layout, &not_in_shard).is_err());
+        let invalid_shard = BTreeMap::from([(4, "zzz".parse().unwrap())]);
+        assert!(split_shard_layout(&shard_layout, &invalid_shard).is_err());
+
+        let single_shard = ShardLayout::v0_single_shard();
+        let new_layout =
+            split_shard_layout(&single_shard, &BTreeMap::from([(0, "m".parse().unwrap())]))
+                .unwrap();
+        assert_eq!(
+            new_layout,
+            ShardLayout::v1(vec!["m".parse().unwrap()], Some(vec![vec![0, 1]]), 1)
+        );
+    }
+}
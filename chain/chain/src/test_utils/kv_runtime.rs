@@ -38,7 +38,7 @@ use near_primitives::validator_mandates::AssignmentWeight;
 use near_primitives::version::{ProtocolVersion, PROTOCOL_VERSION};
 use near_primitives::views::{
     AccessKeyInfoView, AccessKeyList, CallResult, ContractCodeView, EpochValidatorInfo,
-    QueryRequest, QueryResponse, QueryResponseKind, ViewStateResult,
+    QueryRequest, QueryResponse, QueryResponseKind, TransactionSimulationView, ViewStateResult,
 };
 use near_store::test_utils::TestTriesBuilder;
 use near_store::{
@@ -1271,6 +1271,25 @@ impl RuntimeAdapter for KeyValueRuntime {
         }
     }
 
+    fn simulate_transaction(
+        &self,
+        _state_roots: &HashMap<ShardUId, StateRoot>,
+        _block_height: BlockHeight,
+        _block_timestamp: u64,
+        _prev_block_hash: &CryptoHash,
+        _block_hash: &CryptoHash,
+        _epoch_id: &EpochId,
+        _gas_price: Balance,
+        _transaction: &SignedTransaction,
+        _verify_signature: bool,
+    ) -> Result<TransactionSimulationView, near_chain_primitives::error::SimulateTransactionError>
+    {
+        Err(near_chain_primitives::error::SimulateTransactionError::InternalError {
+            error_message: "simulating transactions is not supported by KeyValueRuntime"
+                .to_string(),
+        })
+    }
+
     fn obtain_state_part(
         &self,
         _shard_id: ShardId,
@@ -0,0 +1,145 @@
+use crate::lightclient::{light_client_block_hash, LightClientBlockError, LightClientState};
+use near_crypto::Signature;
+use near_primitives::block::ApprovalInner;
+use near_primitives::hash::{hash, CryptoHash};
+use near_primitives::merkle::combine_hash;
+use near_primitives::test_utils::create_test_signer;
+use near_primitives::types::validator_stake::ValidatorStake;
+use near_primitives::types::BlockHeight;
+use near_primitives::validator_signer::{InMemoryValidatorSigner, ValidatorSigner};
+use near_primitives::views::validator_stake_view::ValidatorStakeView;
+use near_primitives::views::{
+    BlockHeaderInnerLiteView, LightClientBlockLiteView, LightClientBlockView,
+};
+
+struct Epoch {
+    id: CryptoHash,
+    signers: Vec<InMemoryValidatorSigner>,
+}
+
+impl Epoch {
+    fn new(index: u8, names: &[&str]) -> Self {
+        Self { id: hash(&[index]), signers: names.iter().map(|n| create_test_signer(n)).collect() }
+    }
+
+    fn block_producers(&self) -> Vec<ValidatorStakeView> {
+        self.signers
+            .iter()
+            .map(|s| ValidatorStake::new_v1(s.validator_id().clone(), s.public_key(), 100).into())
+            .collect()
+    }
+}
+
+/// Creates a light client block in `epoch`, approved by the first `approvals` block producers.
+fn make_block(
+    height: BlockHeight,
+    epoch: &Epoch,
+    next_epoch: &Epoch,
+    with_next_bps: bool,
+    approvals: usize,
+) -> LightClientBlockView {
+    let next_bps = next_epoch.block_producers();
+    let mut block = LightClientBlockView {
+        prev_block_hash: hash(&height.to_le_bytes()),
+        next_block_inner_hash: hash(&(height + 1).to_le_bytes()),
+        inner_lite: BlockHeaderInnerLiteView {
+            height,
+            epoch_id: epoch.id,
+            next_epoch_id: next_epoch.id,
+            prev_state_root: CryptoHash::default(),
+            outcome_root: CryptoHash::default(),
+            timestamp: height,
+            timestamp_nanosec: height,
+            next_bp_hash: CryptoHash::hash_borsh_iter(
+                next_bps.iter().cloned().map(ValidatorStake::from),
+            ),
+            block_merkle_root: CryptoHash::default(),
+        },
+        inner_rest_hash: CryptoHash::default(),
+        next_bps: with_next_bps.then_some(next_bps),
+        approvals_after_next: vec![],
+    };
+    let next_block_hash =
+        combine_hash(&block.next_block_inner_hash, &light_client_block_hash(&block));
+    let inner = ApprovalInner::Endorsement(next_block_hash);
+    block.approvals_after_next = epoch
+        .signers
+        .iter()
+        .enumerate()
+        .map(|(i, s)| (i < approvals).then(|| Box::new(s.sign_approval(&inner, height + 2))))
+        .collect();
+    block
+}
+
+fn lite(block: &LightClientBlockView) -> LightClientBlockLiteView {
+    LightClientBlockLiteView {
+        prev_block_hash: block.prev_block_hash,
+        inner_rest_hash: block.inner_rest_hash,
+        inner_lite: block.inner_lite.clone(),
+    }
+}
+
+#[test]
+fn test_light_client_epoch_sequence() {
+    let epochs = [
+        Epoch::new(0, &["test0", "test1", "test2"]),
+        Epoch::new(1, &["test1", "test2", "test3"]),
+        Epoch::new(2, &["test3", "test4", "test5", "test6"]),
+        Epoch::new(3, &["test6"]),
+    ];
+    let trusted = make_block(10, &epochs[0], &epochs[1], true, 3);
+    let mut state =
+        LightClientState::new(lite(&trusted), epochs[0].block_producers(), trusted.next_bps);
+    let blocks = [
+        make_block(20, &epochs[1], &epochs[2], true, 3),
+        make_block(30, &epochs[2], &epochs[3], true, 3),
+        // Block in the same epoch as the head doesn't need the next block producers.
+        make_block(35, &epochs[2], &epochs[3], false, 3),
+    ];
+    state.update_all(&blocks).unwrap();
+    assert_eq!(state.head().hash(), light_client_block_hash(&blocks[2]));
+
+    // Block of the next epoch signed by the block producers of the current one.
+    let forged_epoch = Epoch::new(3, &["test3", "test4", "test5", "test6"]);
+    let forged = make_block(40, &forged_epoch, &epochs[3], true, 4);
+    assert!(matches!(state.validate(&forged), Err(LightClientBlockError::InvalidSignature { .. })));
+}
+
+#[test]
+fn test_light_client_invalid_blocks() {
+    let epochs =
+        [Epoch::new(0, &["test0", "test1", "test2"]), Epoch::new(1, &["test1", "test2", "test3"])];
+    let trusted = make_block(10, &epochs[0], &epochs[1], true, 3);
+    let state =
+        LightClientState::new(lite(&trusted), epochs[0].block_producers(), trusted.next_bps);
+
+    assert_eq!(
+        state.validate(&make_block(10, &epochs[1], &epochs[0], true, 3)),
+        Err(LightClientBlockError::NotAfterHead { height: 10, head_height: 10 })
+    );
+    assert_eq!(
+        state.validate(&make_block(20, &epochs[1], &epochs[0], false, 3)),
+        Err(LightClientBlockError::MissingNextBlockProducers)
+    );
+    let other = Epoch::new(5, &["test0"]);
+    assert_eq!(
+        state.validate(&make_block(20, &other, &epochs[0], true, 1)),
+        Err(LightClientBlockError::UnexpectedEpoch)
+    );
+    // 2 of 3 equal stakes is not more than 2/3.
+    assert_eq!(
+        state.validate(&make_block(20, &epochs[1], &epochs[0], true, 2)),
+        Err(LightClientBlockError::NotEnoughApprovals { approved_stake: 200, total_stake: 300 })
+    );
+    let mut block = make_block(20, &epochs[1], &epochs[0], true, 3);
+    block.approvals_after_next[0] = Some(Box::new(Signature::default()));
+    assert_eq!(
+        state.validate(&block),
+        Err(LightClientBlockError::InvalidSignature { account_id: "test1".parse().unwrap() })
+    );
+    // Tampering with the next block producers breaks the hash in the header.
+    let mut block = make_block(20, &epochs[1], &epochs[0], true, 3);
+    block.next_bps.as_mut().unwrap().pop();
+    assert_eq!(state.validate(&block), Err(LightClientBlockError::InvalidNextBlockProducers));
+    assert_eq!(state.validate(&make_block(20, &epochs[1], &epochs[0], true, 3)), Ok(()));
+}
@@ -1,6 +1,7 @@
 mod challenges;
 mod doomslug;
 mod gc;
+mod lightclient;
 mod simple_chain;
 mod sync_chain;
 
@@ -32,7 +32,7 @@ use near_primitives::version::{
     ProtocolVersion, MIN_GAS_PRICE_NEP_92, MIN_GAS_PRICE_NEP_92_FIX, MIN_PROTOCOL_VERSION_NEP_92,
     MIN_PROTOCOL_VERSION_NEP_92_FIX,
 };
-use near_primitives::views::{QueryRequest, QueryResponse};
+use near_primitives::views::{QueryRequest, QueryResponse, TransactionSimulationView};
 use near_store::{PartialStorage, ShardTries, Store, Trie, WrappedTrieChanges};
 
 pub use near_epoch_manager::EpochManagerAdapter;
@@ -412,6 +412,22 @@ pub trait RuntimeAdapter: Send + Sync {
         request: &QueryRequest,
     ) -> Result<QueryResponse, near_chain_primitives::error::QueryError>;
 
+    /// Executes the transaction and all receipts it produces on top of the
+    /// given post-state roots of `block_hash` without committing anything.
+    /// `state_roots` must contain all shards the execution touches.
+    fn simulate_transaction(
+        &self,
+        state_roots: &HashMap<ShardUId, StateRoot>,
+        block_height: BlockHeight,
+        block_timestamp: u64,
+        prev_block_hash: &CryptoHash,
+        block_hash: &CryptoHash,
+        epoch_id: &EpochId,
+        gas_price: Balance,
+        transaction: &SignedTransaction,
+        verify_signature: bool,
+    ) -> Result<TransactionSimulationView, near_chain_primitives::error::SimulateTransactionError>;
+
     /// Get part of the state corresponding to the given state root.
     /// `prev_hash` is a block whose post state root is `state_root`.
     /// Returns error when storage is inconsistent.
@@ -1,9 +1,11 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use actix::Message;
 
+use near_pool::policy::{RoundRobinPolicy, TransactionOrderingPolicy};
 use near_pool::types::PoolIterator;
-use near_pool::{InsertTransactionResult, PoolIteratorWrapper, TransactionPool};
+use near_pool::{InsertTransactionResult, PoolIteratorWrapper, SignerQuota, TransactionPool};
 use near_primitives::shard_layout::{account_id_to_shard_uid, ShardLayout, ShardUId};
 use near_primitives::{
     epoch_manager::RngSeed,
@@ -42,11 +44,36 @@ pub struct ShardedTransactionPool {
     /// If set, new transactions that bring the size of the pool over this limit will be rejected.
     /// The size is tracked and enforced separately for each shard.
     pool_size_limit: Option<u64>,
+
+    /// Limits on transactions of a single signer, enforced separately for each shard.
+    signer_quota: SignerQuota,
+
+    /// Decides the order of transactions in the pools.
+    ordering_policy: Arc<dyn TransactionOrderingPolicy>,
 }
 
 impl ShardedTransactionPool {
     pub fn new(rng_seed: RngSeed, pool_size_limit: Option<u64>) -> Self {
-        Self { tx_pools: HashMap::new(), rng_seed, pool_size_limit }
+        Self {
+            tx_pools: HashMap::new(),
+            rng_seed,
+            pool_size_limit,
+            signer_quota: SignerQuota::default(),
+            ordering_policy: Arc::new(RoundRobinPolicy),
+        }
+    }
+
+    pub fn with_signer_quota(mut self, signer_quota: SignerQuota) -> Self {
+        self.signer_quota = signer_quota;
+        self
+    }
+
+    pub fn with_ordering_policy(
+        mut self,
+        ordering_policy: Arc<dyn TransactionOrderingPolicy>,
+    ) -> Self {
+        self.ordering_policy = ordering_policy;
+        self
     }
 
     pub fn get_pool_iterator(&mut self, shard_uid: ShardUId) -> Option<PoolIteratorWrapper<'_>> {
@@ -68,6 +95,18 @@ impl ShardedTransactionPool {
         }
     }
 
+    /// Returns the pools of all shards that had transactions inserted for them.
+    pub fn pools(&self) -> impl Iterator<Item = (ShardUId, &TransactionPool)> {
+        self.tx_pools.iter().map(|(shard_uid, pool)| (*shard_uid, pool))
+    }
+
+    /// Returns all transactions in the pools together with the shard they were inserted for.
+    pub fn transactions(&self) -> impl Iterator<Item = (ShardUId, &SignedTransaction)> {
+        self.tx_pools
+            .iter()
+            .flat_map(|(shard_uid, pool)| pool.transactions().map(move |tx| (*shard_uid, tx)))
+    }
+
     /// Computes a deterministic random seed for given `shard_id`.
     /// This seed is used to randomize the transaction pool.
     /// For better security we want the seed to different in each shard.
@@ -86,6 +125,8 @@ impl ShardedTransactionPool {
                 self.pool_size_limit,
                 &shard_uid.to_string(),
             )
+            .with_signer_quota(self.signer_quota)
+            .with_ordering_policy(self.ordering_policy.clone())
         })
     }
 
@@ -101,7 +142,8 @@ impl ShardedTransactionPool {
         for tx in transactions {
             reintroduced_count += match pool.insert_transaction(tx.clone()) {
                 InsertTransactionResult::Success | InsertTransactionResult::Duplicate => 1,
-                InsertTransactionResult::NoSpaceLeft => 0,
+                InsertTransactionResult::NoSpaceLeft
+                | InsertTransactionResult::SignerQuotaExceeded => 0,
             }
         }
         reintroduced_count
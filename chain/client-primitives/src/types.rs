@@ -6,16 +6,19 @@ use near_primitives::hash::CryptoHash;
 use near_primitives::merkle::{MerklePath, PartialMerkleTree};
 use near_primitives::network::PeerId;
 use near_primitives::sharding::ChunkHash;
+use near_primitives::transaction::SignedTransaction;
 use near_primitives::types::{
     AccountId, BlockHeight, BlockReference, EpochId, EpochReference, MaybeBlockId, ShardId,
     TransactionOrReceiptId,
 };
 use near_primitives::views::validator_stake_view::ValidatorStakeView;
 use near_primitives::views::{
-    BlockView, ChunkView, DownloadStatusView, EpochValidatorInfo, ExecutionOutcomeWithIdView,
-    GasPriceView, LightClientBlockLiteView, LightClientBlockView, MaintenanceWindowsView,
-    QueryRequest, QueryResponse, ReceiptView, ShardSyncDownloadView, SplitStorageInfoView,
-    StateChangesKindsView, StateChangesRequestView, StateChangesView, SyncStatusView, TxStatusView,
+    BlockView, ChunkView, ContractStorageReportView, DownloadStatusView, EpochValidatorInfo,
+    ExecutionOutcomeWithIdView, GasPriceView, LightClientBlockLiteView, LightClientBlockView,
+    MaintenanceWindowsView, QueryRequest, QueryResponse, ReceiptView, ShardPendingTransactionsView,
+    ShardSyncDownloadView, SplitStorageInfoView, StateChangesKindsView, StateChangesRequestView,
+    StateChangesView, SyncStatusView, TransactionPoolShardStatsView, TransactionSimulationView,
+    TxStatusView, ValidatorPerformanceView,
 };
 pub use near_primitives::views::{StatusResponse, StatusSyncInfo};
 use std::collections::HashMap;
@@ -554,6 +557,151 @@ pub enum QueryError {
     Unreachable { error_message: String },
 }
 
+/// Executes a transaction and all the receipts it produces on top of the state after the given
+/// block, without committing anything.
+#[derive(Clone, Debug)]
+pub struct SimulateTransaction {
+    pub transaction: SignedTransaction,
+    pub block_reference: BlockReference,
+    pub verify_signature: bool,
+}
+
+impl Message for SimulateTransaction {
+    type Result = Result<TransactionSimulationView, SimulateTransactionError>;
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SimulateTransactionError {
+    #[error("Block either has never been observed on the node or has been garbage collected: {block_reference:?}")]
+    UnknownBlock { block_reference: near_primitives::types::BlockReference },
+    #[error(
+        "The data for block #{block_height} is garbage collected on this node, use an archival node to fetch historical data"
+    )]
+    GarbageCollectedBlock {
+        block_height: near_primitives::types::BlockHeight,
+        block_hash: near_primitives::hash::CryptoHash,
+    },
+    #[error("The node does not track the shard ID {requested_shard_id}")]
+    UnavailableShard { requested_shard_id: near_primitives::types::ShardId },
+    #[error("Transaction is invalid: {error}")]
+    InvalidTransaction { error: near_primitives::errors::InvalidTxError },
+    #[error("Transaction produced more than {limit} receipts")]
+    TooManyReceipts { limit: usize },
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+    // NOTE: Currently, the underlying errors are too broad, and while we tried to handle
+    // expected cases, we cannot statically guarantee that no other errors will be returned
+    // in the future.
+    // TODO #3851: Remove this variant once we can exhaustively match all the underlying errors
+    #[error("It is a bug if you receive this error type, please, report this incident: https://github.com/near/nearcore/issues/new/choose. Details: {error_message}")]
+    Unreachable { error_message: String },
+}
+
+impl From<near_chain_primitives::error::SimulateTransactionError> for SimulateTransactionError {
+    fn from(error: near_chain_primitives::error::SimulateTransactionError) -> Self {
+        match error {
+            near_chain_primitives::error::SimulateTransactionError::InvalidTransaction {
+                error,
+            } => Self::InvalidTransaction { error },
+            near_chain_primitives::error::SimulateTransactionError::UnavailableShard {
+                shard_id,
+            } => Self::UnavailableShard { requested_shard_id: shard_id },
+            near_chain_primitives::error::SimulateTransactionError::TooManyReceipts { limit } => {
+                Self::TooManyReceipts { limit }
+            }
+            near_chain_primitives::error::SimulateTransactionError::InternalError {
+                error_message,
+            } => Self::InternalError { error_message },
+        }
+    }
+}
+
+impl From<near_chain_primitives::error::Error> for SimulateTransactionError {
+    fn from(error: near_chain_primitives::error::Error) -> Self {
+        match error {
+            near_chain_primitives::error::Error::IOErr(error) => {
+                Self::InternalError { error_message: error.to_string() }
+            }
+            near_chain_primitives::error::Error::DBNotFoundErr(error_message) => {
+                Self::InternalError { error_message }
+            }
+            _ => Self::Unreachable { error_message: error.to_string() },
+        }
+    }
+}
+
+/// Aggregates storage used by contract data of an account by key prefix, as
+/// of the state after the given block.
+#[derive(Clone, Debug)]
+pub struct GetContractStorageReport {
+    pub block_reference: BlockReference,
+    pub account_id: AccountId,
+    /// Prefixes to group keys by. Keys are grouped by their first byte if empty.
+    pub prefixes: Vec<Vec<u8>>,
+    /// Number of largest keys to report; capped by the view client.
+    pub top: usize,
+    /// Key to start from, `next_key` of the previous report.
+    pub start_key: Option<Vec<u8>>,
+    /// Maximum number of keys to scan; capped by the view client.
+    pub max_keys: Option<usize>,
+}
+
+impl Message for GetContractStorageReport {
+    type Result = Result<ContractStorageReportView, GetContractStorageReportError>;
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GetContractStorageReportError {
+    #[error("Block either has never been observed on the node or has been garbage collected: {block_reference:?}")]
+    UnknownBlock { block_reference: near_primitives::types::BlockReference },
+    #[error(
+        "The data for block #{block_height} is garbage collected on this node, use an archival node to fetch historical data"
+    )]
+    GarbageCollectedBlock {
+        block_height: near_primitives::types::BlockHeight,
+        block_hash: near_primitives::hash::CryptoHash,
+    },
+    #[error("The node does not track the shard ID {requested_shard_id}")]
+    UnavailableShard { requested_shard_id: near_primitives::types::ShardId },
+    #[error("Flat storage of the node doesn't contain state for block #{block_height}")]
+    UnavailableState {
+        block_height: near_primitives::types::BlockHeight,
+        block_hash: near_primitives::hash::CryptoHash,
+    },
+    #[error(
+        "Account {requested_account_id} does not exist while viewing at block #{block_height}"
+    )]
+    UnknownAccount {
+        requested_account_id: near_primitives::types::AccountId,
+        block_height: near_primitives::types::BlockHeight,
+        block_hash: near_primitives::hash::CryptoHash,
+    },
+    #[error("Request can include at most {limit} prefixes")]
+    TooManyPrefixes { limit: usize },
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+    // NOTE: Currently, the underlying errors are too broad, and while we tried to handle
+    // expected cases, we cannot statically guarantee that no other errors will be returned
+    // in the future.
+    // TODO #3851: Remove this variant once we can exhaustively match all the underlying errors
+    #[error("It is a bug if you receive this error type, please, report this incident: https://github.com/near/nearcore/issues/new/choose. Details: {error_message}")]
+    Unreachable { error_message: String },
+}
+
+impl From<near_chain_primitives::error::Error> for GetContractStorageReportError {
+    fn from(error: near_chain_primitives::error::Error) -> Self {
+        match error {
+            near_chain_primitives::error::Error::IOErr(error) => {
+                Self::InternalError { error_message: error.to_string() }
+            }
+            near_chain_primitives::error::Error::DBNotFoundErr(error_message) => {
+                Self::InternalError { error_message }
+            }
+            _ => Self::Unreachable { error_message: error.to_string() },
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Status {
     pub is_health_check: bool,
@@ -643,6 +791,19 @@ impl Message for GetNextLightClientBlock {
     type Result = Result<Option<Arc<LightClientBlockView>>, GetNextLightClientBlockError>;
 }
 
+/// Sequence of light client blocks, each following the previous one as if returned by
+/// `GetNextLightClientBlock`, from the block with `last_block_hash` up to the head.
+#[derive(Debug)]
+pub struct GetLightClientBlocks {
+    pub last_block_hash: CryptoHash,
+    /// Maximal number of blocks returned; capped by the view client.
+    pub limit: Option<u64>,
+}
+
+impl Message for GetLightClientBlocks {
+    type Result = Result<Vec<Arc<LightClientBlockView>>, GetNextLightClientBlockError>;
+}
+
 #[derive(Debug)]
 pub struct GetNetworkInfo {}
 
@@ -784,6 +945,21 @@ impl Message for GetValidatorOrdered {
     type Result = Result<Vec<ValidatorStakeView>, GetValidatorInfoError>;
 }
 
+/// Block and chunk production of `account_id` in epoch `epoch_id`, or in the
+/// current epoch if `epoch_id` is not set. Only a limited number of heights is
+/// scanned per request, starting at `start_height` or at the start of the
+/// epoch.
+#[derive(Debug)]
+pub struct GetValidatorPerformance {
+    pub account_id: AccountId,
+    pub epoch_id: Option<EpochId>,
+    pub start_height: Option<BlockHeight>,
+}
+
+impl Message for GetValidatorPerformance {
+    type Result = Result<ValidatorPerformanceView, GetValidatorInfoError>;
+}
+
 #[derive(Debug)]
 pub struct GetStateChanges {
     pub block_hash: CryptoHash,
@@ -1122,6 +1298,46 @@ impl From<std::io::Error> for GetSplitStorageInfoError {
     }
 }
 
+/// Transactions in the transaction pool that were signed by the given account and/or with the
+/// given access key.
+#[derive(Debug)]
+pub struct GetPendingTransactions {
+    pub signer_id: Option<AccountId>,
+    pub public_key: Option<near_crypto::PublicKey>,
+}
+
+impl Message for GetPendingTransactions {
+    type Result = Result<Vec<ShardPendingTransactionsView>, GetTransactionPoolError>;
+}
+
+#[derive(Debug)]
+pub struct GetTransactionPoolStats {}
+
+impl Message for GetTransactionPoolStats {
+    type Result = Result<Vec<TransactionPoolShardStatsView>, GetTransactionPoolError>;
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GetTransactionPoolError {
+    #[error("IO Error: {0}")]
+    IOError(String),
+    // NOTE: Currently, the underlying errors are too broad, and while we tried to handle
+    // expected cases, we cannot statically guarantee that no other errors will be returned
+    // in the future.
+    // TODO #3851: Remove this variant once we can exhaustively match all the underlying errors
+    #[error("It is a bug if you receive this error type, please, report this incident: https://github.com/near/nearcore/issues/new/choose. Details: {0}")]
+    Unreachable(String),
+}
+
+impl From<near_chain_primitives::Error> for GetTransactionPoolError {
+    fn from(error: near_chain_primitives::Error) -> Self {
+        match error {
+            near_chain_primitives::Error::IOErr(error) => Self::IOError(error.to_string()),
+            _ => Self::Unreachable(error.to_string()),
+        }
+    }
+}
+
 #[cfg(feature = "sandbox")]
 #[derive(Debug)]
 pub enum SandboxMessage {
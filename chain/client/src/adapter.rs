@@ -7,7 +7,7 @@ use near_network::types::{
 use near_o11y::WithSpanContextExt;
 use near_primitives::block::{Approval, Block, BlockHeader};
 use near_primitives::challenge::Challenge;
-use near_primitives::chunk_validation::ChunkStateWitness;
+use near_primitives::chunk_validation::EncodedChunkStateWitness;
 use near_primitives::errors::InvalidTxError;
 use near_primitives::hash::CryptoHash;
 use near_primitives::network::{AnnounceAccount, PeerId};
@@ -134,11 +134,14 @@ pub enum ProcessTxResponse {
     /// The node being queried does not track the shard needed and therefore cannot provide userful
     /// response.
     DoesNotTrackShard,
+    /// The signer of the transaction has used up its quota in the transaction pool, so the
+    /// transaction was dropped.
+    SignerQuotaExceeded,
 }
 
 #[derive(actix::Message, Debug, PartialEq, Eq)]
 #[rtype(result = "()")]
-pub struct ChunkStateWitnessMessage(pub ChunkStateWitness);
+pub struct ChunkStateWitnessMessage(pub EncodedChunkStateWitness);
 
 pub struct Adapter {
     /// Address of the client actor.
@@ -339,7 +342,7 @@ impl near_network::client::Client for Adapter {
         }
     }
 
-    async fn chunk_state_witness(&self, witness: ChunkStateWitness) {
+    async fn chunk_state_witness(&self, witness: EncodedChunkStateWitness) {
         match self.client_addr.send(ChunkStateWitnessMessage(witness).with_span_context()).await {
             Ok(()) => {}
             Err(err) => tracing::error!("mailbox error: {err}"),
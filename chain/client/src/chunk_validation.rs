@@ -5,15 +5,25 @@ use near_chain::types::RuntimeAdapter;
 use near_chain_primitives::Error;
 use near_epoch_manager::EpochManagerAdapter;
 use near_network::types::{NetworkRequests, PeerManagerMessageRequest};
+use near_primitives::block::Tip;
 use near_primitives::checked_feature;
 use near_primitives::chunk_validation::{
-    ChunkEndorsement, ChunkEndorsementInner, ChunkEndorsementMessage, ChunkStateWitness,
+    ChunkEndorsement, ChunkEndorsementInner, ChunkEndorsementMessage, ChunkStateTransition,
+    ChunkStateWitness, ChunkStateWitnessSize, EncodedChunkStateWitness,
+    MAX_CHUNK_STATE_WITNESS_SIZE,
 };
 use near_primitives::sharding::ShardChunkHeader;
-use near_primitives::types::EpochId;
+use near_primitives::transaction::SignedTransaction;
+use near_primitives::types::{BlockHeight, EpochId, ShardId};
+use near_primitives::utils::get_chunk_state_witness_key;
 use near_primitives::validator_signer::ValidatorSigner;
+use near_store::{DBCol, Store, HEAD_KEY};
 
-use crate::Client;
+use crate::{metrics, Client};
+
+/// Number of last heights at which witnesses are kept in
+/// `DBCol::LatestChunkStateWitnesses` and `DBCol::InvalidChunkStateWitnesses`.
+const NUM_LATEST_WITNESS_HEIGHTS: BlockHeight = 20;
 
 /// A module that handles chunk validation logic. Chunk validation refers to a
 /// critical process of stateless validation, where chunk validators (certain
@@ -26,6 +36,11 @@ pub struct ChunkValidator {
     epoch_manager: Arc<dyn EpochManagerAdapter>,
     network_sender: Sender<PeerManagerMessageRequest>,
     runtime_adapter: Arc<dyn RuntimeAdapter>,
+    /// Store to save witnesses to for debugging, see `save_invalid_witnesses`
+    /// and `save_latest_witnesses` in the client config.
+    store: Store,
+    save_invalid_witnesses: bool,
+    save_latest_witnesses: bool,
 }
 
 impl ChunkValidator {
@@ -34,8 +49,19 @@ impl ChunkValidator {
         epoch_manager: Arc<dyn EpochManagerAdapter>,
         network_sender: Sender<PeerManagerMessageRequest>,
         runtime_adapter: Arc<dyn RuntimeAdapter>,
+        store: Store,
+        save_invalid_witnesses: bool,
+        save_latest_witnesses: bool,
     ) -> Self {
-        Self { my_signer, epoch_manager, network_sender, runtime_adapter }
+        Self {
+            my_signer,
+            epoch_manager,
+            network_sender,
+            runtime_adapter,
+            store,
+            save_invalid_witnesses,
+            save_latest_witnesses,
+        }
     }
 
     /// Performs the chunk validation logic. When done, it will send the chunk
@@ -65,8 +91,14 @@ impl ChunkValidator {
         let network_sender = self.network_sender.clone();
         let signer = self.my_signer.clone().unwrap();
         let runtime_adapter = self.runtime_adapter.clone();
+        let store = self.store.clone();
+        let save_invalid_witnesses = self.save_invalid_witnesses;
+        let save_latest_witnesses = self.save_latest_witnesses;
         rayon::spawn(move || match validate_chunk(&state_witness, runtime_adapter.as_ref()) {
             Ok(()) => {
+                if save_latest_witnesses {
+                    save_witness(&store, DBCol::LatestChunkStateWitnesses, &state_witness);
+                }
                 tracing::debug!(
                     target: "chunk_validation",
                     chunk_hash=?chunk_header.chunk_hash(),
@@ -87,12 +119,69 @@ impl ChunkValidator {
             }
             Err(err) => {
                 tracing::error!("Failed to validate chunk: {:?}", err);
+                if save_invalid_witnesses {
+                    save_witness(&store, DBCol::InvalidChunkStateWitnesses, &state_witness);
+                }
             }
         });
         Ok(())
     }
 }
 
+/// Saves the witness to `col` so that it can be replayed later with
+/// `neard view-state replay-chunk-state-witness`. Only the witnesses of the
+/// last `NUM_LATEST_WITNESS_HEIGHTS` heights are kept. Failures are only
+/// logged.
+fn save_witness(store: &Store, col: DBCol, state_witness: &ChunkStateWitness) {
+    let chunk_header = &state_witness.chunk_header;
+    if let Err(err) = try_save_witness(store, col, state_witness) {
+        tracing::warn!(
+            target: "chunk_validation",
+            ?err,
+            chunk_hash=?chunk_header.chunk_hash(),
+            %col,
+            "Failed to save chunk state witness",
+        );
+    }
+}
+
+fn try_save_witness(
+    store: &Store,
+    col: DBCol,
+    state_witness: &ChunkStateWitness,
+) -> std::io::Result<()> {
+    let chunk_header = &state_witness.chunk_header;
+    let height = chunk_header.height_created();
+    let shard_id = chunk_header.shard_id();
+    // Invalid witnesses come from peers, so their heights can't be trusted.
+    // They are pruned relative to the head of the chain instead, and only one
+    // of them is kept per height and shard.
+    let last_height = if col == DBCol::InvalidChunkStateWitnesses {
+        let Some(head) = store.get_ser::<Tip>(DBCol::BlockMisc, HEAD_KEY)? else {
+            return Ok(());
+        };
+        if height > head.height + NUM_LATEST_WITNESS_HEIGHTS
+            || height + NUM_LATEST_WITNESS_HEIGHTS <= head.height
+        {
+            return Ok(());
+        }
+        let mut prefix = height.to_be_bytes().to_vec();
+        prefix.extend_from_slice(&shard_id.to_be_bytes());
+        if store.iter_prefix(col, &prefix).next().is_some() {
+            return Ok(());
+        }
+        head.height
+    } else {
+        height
+    };
+    let key = get_chunk_state_witness_key(height, shard_id, &chunk_header.chunk_hash());
+    let mut store_update = store.store_update();
+    let min_height = last_height.saturating_sub(NUM_LATEST_WITNESS_HEIGHTS);
+    store_update.delete_range(col, &0u64.to_be_bytes(), &min_height.to_be_bytes());
+    store_update.set_ser(col, &key, state_witness)?;
+    store_update.commit()
+}
+
 /// The actual chunk validation logic.
 fn validate_chunk(
     state_witness: &ChunkStateWitness,
@@ -108,10 +197,65 @@ fn validate_chunk(
     Ok(())
 }
 
+/// Builds the state witness of a chunk with the given header and transactions.
+fn build_chunk_state_witness(
+    chunk_header: ShardChunkHeader,
+    new_transactions: Vec<SignedTransaction>,
+) -> ChunkStateWitness {
+    // TODO(#10265): Fill in the state transitions and the source receipts.
+    ChunkStateWitness {
+        state_root: chunk_header.prev_state_root(),
+        chunk_header,
+        main_state_transition: ChunkStateTransition::default(),
+        implicit_transitions: vec![],
+        source_receipt_proofs: Default::default(),
+        new_transactions,
+    }
+}
+
+fn record_witness_size_metrics(
+    shard_id: ShardId,
+    size: &ChunkStateWitnessSize,
+    encoded_size: usize,
+) {
+    let shard_id = shard_id.to_string();
+    metrics::CHUNK_STATE_WITNESS_RAW_SIZE
+        .with_label_values(&[&shard_id])
+        .observe(size.total as f64);
+    metrics::CHUNK_STATE_WITNESS_ENCODED_SIZE
+        .with_label_values(&[&shard_id])
+        .observe(encoded_size as f64);
+    for (component, component_size) in [
+        ("main_state_transition", size.main_state_transition),
+        ("implicit_transitions", size.implicit_transitions),
+        ("source_receipt_proofs", size.source_receipt_proofs),
+        ("new_transactions", size.new_transactions),
+    ] {
+        metrics::CHUNK_STATE_WITNESS_COMPONENT_SIZE
+            .with_label_values(&[&shard_id, component])
+            .observe(component_size as f64);
+    }
+}
+
 impl Client {
     /// Responds to a network request to verify a `ChunkStateWitness`, which is
     /// sent by chunk producers after they produce a chunk.
-    pub fn process_chunk_state_witness(&mut self, witness: ChunkStateWitness) -> Result<(), Error> {
+    pub fn process_chunk_state_witness(
+        &mut self,
+        encoded_witness: EncodedChunkStateWitness,
+    ) -> Result<(), Error> {
+        let witness = match encoded_witness.decode(MAX_CHUNK_STATE_WITNESS_SIZE) {
+            Ok((witness, _raw_size)) => witness,
+            Err(err) => {
+                tracing::debug!(
+                    target: "chunk_validation",
+                    ?err,
+                    encoded_size = encoded_witness.size_bytes(),
+                    "Failed to decode chunk state witness",
+                );
+                return Err(Error::InvalidChunkStateWitness);
+            }
+        };
         // TODO(#10265): We'll need to fetch some data from the chain; at the very least we need
         // the previous block to exist, and we need the previous chunks' receipt roots.
         // Some of this depends on delayed chunk execution. Also, if the previous block
@@ -120,6 +264,49 @@ impl Client {
         self.chunk_validator.start_validating_chunk(witness)
     }
 
+    /// Drops transactions from the end of `transactions` so that the state
+    /// witness of the chunk produced after `last_header` stays within
+    /// `max_chunk_state_witness_size`. The dropped transactions stay in the
+    /// pool.
+    pub(crate) fn limit_transactions_by_witness_size(
+        &self,
+        epoch_id: &EpochId,
+        last_header: &ShardChunkHeader,
+        shard_id: ShardId,
+        mut transactions: Vec<SignedTransaction>,
+    ) -> Result<Vec<SignedTransaction>, Error> {
+        let protocol_version = self.epoch_manager.get_epoch_protocol_version(epoch_id)?;
+        if !checked_feature!("stable", ChunkValidation, protocol_version) {
+            return Ok(transactions);
+        }
+        // The header of the new chunk doesn't exist yet. It differs in size
+        // from the last one only by its validator proposals.
+        let mut witness_size =
+            build_chunk_state_witness(last_header.clone(), vec![]).size().total as u64;
+        let num_included = transactions
+            .iter()
+            .take_while(|tx| {
+                witness_size += tx.get_size();
+                witness_size <= self.config.max_chunk_state_witness_size
+            })
+            .count();
+        let num_excluded = transactions.len() - num_included;
+        if num_excluded > 0 {
+            tracing::debug!(
+                target: "chunk_validation",
+                shard_id,
+                num_included,
+                num_excluded,
+                "Chunk state witness size limit reached, leaving transactions in the pool",
+            );
+            metrics::CHUNK_WITNESS_EXCLUDED_TRANSACTIONS
+                .with_label_values(&[&shard_id.to_string()])
+                .inc_by(num_excluded as u64);
+            transactions.truncate(num_included);
+        }
+        Ok(transactions)
+    }
+
     /// Distributes the chunk state witness to chunk validators that are
     /// selected to validate this chunk.
     pub fn send_chunk_state_witness_to_chunk_validators(
@@ -136,10 +323,21 @@ impl Client {
             chunk_header.shard_id(),
             chunk_header.height_created(),
         )?;
-        let witness = ChunkStateWitness {
-            chunk_header: chunk_header.clone(),
-            state_root: chunk_header.prev_state_root(),
-        };
+        let chunk = self.chain.get_chunk(&chunk_header.chunk_hash())?;
+        let witness =
+            build_chunk_state_witness(chunk_header.clone(), chunk.transactions().to_vec());
+        let size = witness.size();
+        let (encoded_witness, _raw_size) = EncodedChunkStateWitness::encode(&witness)?;
+        record_witness_size_metrics(chunk_header.shard_id(), &size, encoded_witness.size_bytes());
+        if size.total as u64 > self.config.max_chunk_state_witness_size {
+            tracing::warn!(
+                target: "chunk_validation",
+                chunk_hash=?chunk_header.chunk_hash(),
+                ?size,
+                max_size = self.config.max_chunk_state_witness_size,
+                "Chunk state witness is larger than the configured limit",
+            );
+        }
         tracing::debug!(
             target: "chunk_validation",
             "Sending chunk state witness for chunk {:?} to chunk validators {:?}",
@@ -147,7 +345,10 @@ impl Client {
             chunk_validators.keys(),
         );
         self.network_adapter.send(PeerManagerMessageRequest::NetworkRequests(
-            NetworkRequests::ChunkStateWitness(chunk_validators.into_keys().collect(), witness),
+            NetworkRequests::ChunkStateWitness(
+                chunk_validators.into_keys().collect(),
+                encoded_witness,
+            ),
         ));
         Ok(())
     }
@@ -14,6 +14,7 @@ use crate::SyncAdapter;
 use crate::SyncMessage;
 use crate::{metrics, SyncStatus};
 use actix_rt::ArbiterHandle;
+use borsh::BorshDeserialize;
 use chrono::DateTime;
 use chrono::Utc;
 use itertools::Itertools;
@@ -35,7 +36,9 @@ use near_chain::{
     BlockProcessingArtifact, BlockStatus, Chain, ChainGenesis, ChainStoreAccess,
     DoneApplyChunkCallback, Doomslug, DoomslugThresholdMode, Provenance,
 };
-use near_chain_configs::{ClientConfig, LogSummaryStyle, UpdateableClientConfig};
+use near_chain_configs::{
+    ClientConfig, LogSummaryStyle, TransactionPoolOrdering, UpdateableClientConfig,
+};
 use near_chunks::adapter::ShardsManagerRequestFromClient;
 use near_chunks::client::ShardedTransactionPool;
 use near_chunks::logic::{
@@ -54,7 +57,10 @@ use near_network::types::{
 };
 use near_o11y::log_assert;
 use near_o11y::WithSpanContextExt;
-use near_pool::InsertTransactionResult;
+use near_pool::policy::{
+    PrepaidGasPolicy, PrioritizedAccountsPolicy, RoundRobinPolicy, TransactionOrderingPolicy,
+};
+use near_pool::{InsertTransactionResult, SignerQuota};
 use near_primitives::block::{Approval, ApprovalInner, ApprovalMessage, Block, BlockHeader, Tip};
 use near_primitives::block_header::ApprovalType;
 use near_primitives::challenge::{Challenge, ChallengeBody};
@@ -78,9 +84,11 @@ use near_primitives::unwrap_or_return;
 use near_primitives::utils::MaybeValidated;
 use near_primitives::validator_signer::ValidatorSigner;
 use near_primitives::version::PROTOCOL_VERSION;
-use near_primitives::views::{CatchupStatusView, DroppedReason};
+use near_primitives::views::{
+    CatchupStatusView, DroppedReason, ShardPendingTransactionsView, TransactionPoolShardStatsView,
+};
 use near_store::metadata::DbKind;
-use near_store::ShardUId;
+use near_store::{DBCol, ShardUId};
 use std::cmp::max;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
@@ -265,8 +273,21 @@ impl Client {
             chain.store(),
             chain_config.background_migration_threads,
         )?;
+        let ordering_policy: Arc<dyn TransactionOrderingPolicy> =
+            match &config.transaction_pool_ordering {
+                TransactionPoolOrdering::RoundRobin => Arc::new(RoundRobinPolicy),
+                TransactionPoolOrdering::Prioritized { priorities } => {
+                    Arc::new(PrioritizedAccountsPolicy::new(priorities.clone()))
+                }
+                TransactionPoolOrdering::PrepaidGas => Arc::new(PrepaidGasPolicy),
+            };
         let sharded_tx_pool =
-            ShardedTransactionPool::new(rng_seed, config.transaction_pool_size_limit);
+            ShardedTransactionPool::new(rng_seed, config.transaction_pool_size_limit)
+                .with_signer_quota(SignerQuota {
+                    count_limit: config.transaction_pool_signer_count_limit,
+                    size_limit: config.transaction_pool_signer_size_limit,
+                })
+                .with_ordering_policy(ordering_policy);
         let sync_status = SyncStatus::AwaitingPeers;
         let genesis_block = chain.genesis_block();
         let epoch_sync = EpochSync::new(
@@ -337,6 +358,9 @@ impl Client {
             epoch_manager.clone(),
             network_adapter.clone().into_sender(),
             runtime_adapter.clone(),
+            chain.store().store().clone(),
+            config.save_invalid_witnesses,
+            config.save_latest_witnesses,
         );
         Ok(Self {
             #[cfg(feature = "test_features")]
@@ -859,6 +883,12 @@ impl Client {
             *chunk_extra.state_root(),
             &prev_block_header,
         )?;
+        let transactions = self.limit_transactions_by_witness_size(
+            epoch_id,
+            &last_header,
+            shard_id,
+            transactions,
+        )?;
         #[cfg(feature = "test_features")]
         let transactions = Self::maybe_insert_invalid_transaction(
             transactions,
@@ -1743,12 +1773,7 @@ impl Client {
             let last_header = Chain::get_prev_chunk_header(epoch_manager, block, shard_id).unwrap();
             match self.produce_chunk(*block.hash(), &epoch_id, last_header, next_height, shard_id) {
                 Ok(Some((encoded_chunk, merkle_paths, receipts))) => {
-                    if let Err(err) = self.send_chunk_state_witness_to_chunk_validators(
-                        &epoch_id,
-                        &encoded_chunk.cloned_header(),
-                    ) {
-                        tracing::error!(target: "client", ?err, "Failed to send chunk state witness to chunk validators");
-                    }
+                    let chunk_header = encoded_chunk.cloned_header();
                     self.persist_and_distribute_encoded_chunk(
                         encoded_chunk,
                         merkle_paths,
@@ -1756,6 +1781,12 @@ impl Client {
                         validator_id.clone(),
                     )
                     .expect("Failed to process produced chunk");
+                    // The witness is built from the persisted chunk.
+                    if let Err(err) =
+                        self.send_chunk_state_witness_to_chunk_validators(&epoch_id, &chunk_header)
+                    {
+                        tracing::error!(target: "client", ?err, "Failed to send chunk state witness to chunk validators");
+                    }
                 }
                 Ok(None) => {}
                 Err(err) => {
@@ -2202,6 +2233,10 @@ impl Client {
                             trace!(target: "client", ?shard_uid, tx_hash = ?tx.get_hash(), "Duplicate transaction, not forwarding it.");
                             return Ok(ProcessTxResponse::ValidTx);
                         }
+                        InsertTransactionResult::SignerQuotaExceeded => {
+                            trace!(target: "client", ?shard_uid, tx_hash = ?tx.get_hash(), "Signer has too many transactions in the pool, dropping the transaction.");
+                            return Ok(ProcessTxResponse::SignerQuotaExceeded);
+                        }
                         InsertTransactionResult::NoSpaceLeft => {
                             if is_forwarded {
                                 trace!(target: "client", ?shard_uid, tx_hash = ?tx.get_hash(), "Transaction pool is full, dropping the transaction.");
@@ -2270,6 +2305,153 @@ impl Client {
         Ok(false)
     }
 
+    /// Saves the transactions of the pool to the database, replacing the previously saved ones,
+    /// so that they can be restored after a restart with `restore_transaction_pool`.
+    pub fn save_transaction_pool(&self) -> Result<(), Error> {
+        let _span = debug_span!(target: "client", "save_transaction_pool").entered();
+        let store = self.chain.store().store();
+        let mut store_update = store.store_update();
+        store_update.delete_all(DBCol::TransactionPool);
+        let mut num_saved = 0;
+        for (shard_uid, tx) in self.sharded_tx_pool.transactions() {
+            let key = [shard_uid.to_bytes().as_slice(), tx.get_hash().as_ref()].concat();
+            store_update
+                .set_ser(DBCol::TransactionPool, &key, tx)
+                .map_err(near_chain::Error::from)?;
+            num_saved += 1;
+        }
+        store_update.commit().map_err(near_chain::Error::from)?;
+        debug!(target: "client", num_saved, "Saved the transaction pool");
+        Ok(())
+    }
+
+    /// Reinserts the transactions saved by `save_transaction_pool` into the pool. Transactions
+    /// that have expired or are no longer valid at the current head are dropped.
+    pub fn restore_transaction_pool(&mut self) -> Result<(), Error> {
+        let _span = debug_span!(target: "client", "restore_transaction_pool").entered();
+        let store = self.chain.store().store().clone();
+        let mut saved_transactions = vec![];
+        for item in store.iter(DBCol::TransactionPool) {
+            let (_, value) = item.map_err(near_chain::Error::from)?;
+            match SignedTransaction::try_from_slice(&value) {
+                Ok(tx) => saved_transactions.push(tx),
+                Err(err) => warn!(target: "client", ?err, "Failed to decode a saved transaction"),
+            }
+        }
+        if saved_transactions.is_empty() {
+            return Ok(());
+        }
+        // Only validators record transactions, see `process_tx_internal`.
+        if self.validator_signer.is_none() {
+            return Ok(());
+        }
+
+        let head = self.chain.head()?;
+        let cur_block_header = self.chain.head_header()?;
+        let gas_price = cur_block_header.next_gas_price();
+        let epoch_id = self.epoch_manager.get_epoch_id_from_prev_block(&head.last_block_hash)?;
+        let protocol_version = self.epoch_manager.get_epoch_protocol_version(&epoch_id)?;
+        let num_saved = saved_transactions.len();
+        let mut num_restored = 0;
+        for tx in saved_transactions {
+            if self
+                .chain
+                .store()
+                .check_transaction_validity_period(
+                    &cur_block_header,
+                    &tx.transaction.block_hash,
+                    self.chain.transaction_validity_period,
+                )
+                .is_err()
+            {
+                trace!(target: "client", tx_hash = ?tx.get_hash(), "Saved transaction has expired");
+                continue;
+            }
+            // The shard layout may have changed since the transaction was saved.
+            let shard_id =
+                self.epoch_manager.account_id_to_shard_id(&tx.transaction.signer_id, &epoch_id)?;
+            let shard_uid = self.epoch_manager.shard_id_to_uid(shard_id, &epoch_id)?;
+            let Ok(chunk_extra) = self.chain.get_chunk_extra(&head.last_block_hash, &shard_uid)
+            else {
+                trace!(target: "client", ?shard_uid, tx_hash = ?tx.get_hash(), "No state root to validate a saved transaction");
+                continue;
+            };
+            if let Some(err) = self
+                .runtime_adapter
+                .validate_tx(
+                    gas_price,
+                    Some(*chunk_extra.state_root()),
+                    &tx,
+                    true,
+                    &epoch_id,
+                    protocol_version,
+                )
+                .expect("no storage errors")
+            {
+                trace!(target: "client", tx_hash = ?tx.get_hash(), ?err, "Saved transaction is no longer valid");
+                continue;
+            }
+            match self.sharded_tx_pool.insert_transaction(shard_uid, tx) {
+                InsertTransactionResult::Success => num_restored += 1,
+                InsertTransactionResult::Duplicate
+                | InsertTransactionResult::NoSpaceLeft
+                | InsertTransactionResult::SignerQuotaExceeded => {}
+            }
+        }
+        info!(target: "client", num_restored, num_saved, "Restored the transaction pool");
+        Ok(())
+    }
+
+    /// Returns the transactions in the pool signed by the given account and/or with the given
+    /// access key, grouped by shard.
+    ///
+    /// Only validators record transactions in the pool, see `process_tx_internal`, so on other
+    /// nodes the result is always empty.
+    pub fn pending_transactions(
+        &self,
+        signer_id: Option<&AccountId>,
+        public_key: Option<&near_crypto::PublicKey>,
+    ) -> Vec<ShardPendingTransactionsView> {
+        let mut shards = vec![];
+        for (shard_uid, pool) in self.sharded_tx_pool.pools() {
+            let transactions: Vec<_> = pool
+                .transactions()
+                .filter(|tx| {
+                    signer_id.map_or(true, |id| id == &tx.transaction.signer_id)
+                        && public_key.map_or(true, |key| key == &tx.transaction.public_key)
+                })
+                .map(|tx| tx.clone().into())
+                .collect();
+            if !transactions.is_empty() {
+                shards.push(ShardPendingTransactionsView {
+                    shard_id: shard_uid.shard_id(),
+                    transactions,
+                });
+            }
+        }
+        shards.sort_by_key(|shard| shard.shard_id);
+        shards
+    }
+
+    /// Returns the number and size of transactions in the pool of each shard. Like
+    /// `pending_transactions`, it's always empty on nodes which aren't validators.
+    pub fn transaction_pool_stats(&self) -> Vec<TransactionPoolShardStatsView> {
+        // Pools of the shards from before resharding are left empty, so they are skipped.
+        let mut shards: Vec<_> = self
+            .sharded_tx_pool
+            .pools()
+            .filter(|(_, pool)| pool.len() > 0)
+            .map(|(shard_uid, pool)| TransactionPoolShardStatsView {
+                shard_id: shard_uid.shard_id(),
+                transaction_count: pool.len() as u64,
+                transaction_size: pool.transaction_size(),
+                signer_count: pool.signer_count() as u64,
+            })
+            .collect();
+        shards.sort_by_key(|shard| shard.shard_id);
+        shards
+    }
+
     /// Walks through all the ongoing state syncs for future epochs and processes them
     pub fn run_catchup(
         &mut self,
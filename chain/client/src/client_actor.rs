@@ -43,8 +43,9 @@ use near_chunks::adapter::ShardsManagerRequestFromClient;
 use near_chunks::client::ShardsManagerResponse;
 use near_chunks::logic::cares_about_shard_this_or_next_epoch;
 use near_client_primitives::types::{
-    Error, GetClientConfig, GetClientConfigError, GetNetworkInfo, NetworkInfoResponse,
-    StateSyncStatus, Status, StatusError, StatusSyncInfo, SyncStatus,
+    Error, GetClientConfig, GetClientConfigError, GetNetworkInfo, GetPendingTransactions,
+    GetTransactionPoolError, GetTransactionPoolStats, NetworkInfoResponse, StateSyncStatus, Status,
+    StatusError, StatusSyncInfo, SyncStatus,
 };
 use near_epoch_manager::shard_tracker::ShardTracker;
 use near_epoch_manager::EpochManagerAdapter;
@@ -66,7 +67,9 @@ use near_primitives::unwrap_or_return;
 use near_primitives::utils::{from_timestamp, MaybeValidated};
 use near_primitives::validator_signer::ValidatorSigner;
 use near_primitives::version::PROTOCOL_VERSION;
-use near_primitives::views::{DetailedDebugStatus, ValidatorInfo};
+use near_primitives::views::{
+    DetailedDebugStatus, ShardPendingTransactionsView, TransactionPoolShardStatsView, ValidatorInfo,
+};
 #[cfg(feature = "test_features")]
 use near_store::DBCol;
 use near_store::ShardUId;
@@ -86,6 +89,8 @@ const STATUS_WAIT_TIME_MULTIPLIER: u64 = 10;
 /// `max_block_production_time` times this multiplier is how long we wait before rebroadcasting
 /// the current `head`
 const HEAD_STALL_MULTIPLIER: u32 = 4;
+/// How often the transaction pool is saved to the database, in addition to saving it on shutdown.
+const TRANSACTION_POOL_SAVE_PERIOD: Duration = Duration::from_secs(60);
 
 pub struct ClientActor {
     /// Adversarial controls
@@ -114,6 +119,7 @@ pub struct ClientActor {
     doomslug_timer_next_attempt: DateTime<Utc>,
     sync_timer_next_attempt: DateTime<Utc>,
     sync_started: bool,
+    transaction_pool_save_next_attempt: DateTime<Utc>,
     state_parts_task_scheduler: Box<dyn Fn(ApplyStatePartsRequest)>,
     block_catch_up_scheduler: Box<dyn Fn(BlockCatchUpRequest)>,
     state_split_scheduler: Box<dyn Fn(StateSplitRequest)>,
@@ -206,6 +212,8 @@ impl ClientActor {
             doomslug_timer_next_attempt: now,
             sync_timer_next_attempt: now,
             sync_started: false,
+            transaction_pool_save_next_attempt: now
+                + chrono::Duration::from_std(TRANSACTION_POOL_SAVE_PERIOD).unwrap(),
             state_parts_task_scheduler: create_sync_job_scheduler::<ApplyStatePartsRequest>(
                 sync_jobs_actor_addr.clone(),
             ),
@@ -239,6 +247,10 @@ impl Actor for ClientActor {
             self.block_production_started = true;
         }
 
+        if let Err(err) = self.client.restore_transaction_pool() {
+            warn!(target: "client", ?err, "Failed to restore the transaction pool");
+        }
+
         // Start triggers
         self.schedule_triggers(ctx);
 
@@ -249,6 +261,12 @@ impl Actor for ClientActor {
             error!(target: "client", ?err, "Failed to update network chain info");
         }
     }
+
+    /// Saves the transaction pool so that pending transactions survive the restart.
+    fn stopping(&mut self, _ctx: &mut Self::Context) -> actix::Running {
+        self.try_save_transaction_pool();
+        actix::Running::Stop
+    }
 }
 
 impl ClientActor {
@@ -1188,6 +1206,21 @@ impl ClientActor {
                 .to_std()
                 .unwrap_or(delay),
         );
+
+        self.transaction_pool_save_next_attempt = self.run_timer(
+            TRANSACTION_POOL_SAVE_PERIOD,
+            self.transaction_pool_save_next_attempt,
+            ctx,
+            |act, _ctx| act.try_save_transaction_pool(),
+            "transaction_pool_save",
+        );
+        delay = core::cmp::min(
+            delay,
+            self.transaction_pool_save_next_attempt
+                .signed_duration_since(now)
+                .to_std()
+                .unwrap_or(delay),
+        );
         timer.observe_duration();
         delay
     }
@@ -1212,6 +1245,16 @@ impl ClientActor {
         self.process_accepted_blocks(accepted_blocks);
     }
 
+    fn try_save_transaction_pool(&mut self) {
+        // Only validators record transactions, so there is nothing to save otherwise.
+        if self.client.validator_signer.is_none() {
+            return;
+        }
+        if let Err(err) = self.client.save_transaction_pool() {
+            error!(target: "client", ?err, "Failed to save the transaction pool");
+        }
+    }
+
     fn try_handle_block_production(&mut self) {
         let _span = debug_span!(target: "client", "try_handle_block_production").entered();
         if let Err(err) = self.handle_block_production() {
@@ -1966,6 +2009,38 @@ impl Handler<WithSpanContext<GetClientConfig>> for ClientActor {
     }
 }
 
+impl Handler<WithSpanContext<GetPendingTransactions>> for ClientActor {
+    type Result = Result<Vec<ShardPendingTransactionsView>, GetTransactionPoolError>;
+
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<GetPendingTransactions>,
+        _: &mut Context<Self>,
+    ) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        tracing::debug!(target: "client", ?msg);
+
+        Ok(self.client.pending_transactions(msg.signer_id.as_ref(), msg.public_key.as_ref()))
+    }
+}
+
+impl Handler<WithSpanContext<GetTransactionPoolStats>> for ClientActor {
+    type Result = Result<Vec<TransactionPoolShardStatsView>, GetTransactionPoolError>;
+
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<GetTransactionPoolStats>,
+        _: &mut Context<Self>,
+    ) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        tracing::debug!(target: "client", ?msg);
+
+        Ok(self.client.transaction_pool_stats())
+    }
+}
+
 impl Handler<WithSpanContext<SyncMessage>> for ClientActor {
     type Result = ();
 
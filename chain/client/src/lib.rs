@@ -1,11 +1,12 @@
 pub use near_client_primitives::types::{
     Error, GetBlock, GetBlockProof, GetBlockProofResponse, GetBlockWithMerkleTree, GetChunk,
-    GetClientConfig, GetExecutionOutcome, GetExecutionOutcomeResponse,
-    GetExecutionOutcomesForBlock, GetGasPrice, GetMaintenanceWindows, GetNetworkInfo,
-    GetNextLightClientBlock, GetProtocolConfig, GetReceipt, GetSplitStorageInfo, GetStateChanges,
-    GetStateChangesInBlock, GetStateChangesWithCauseInBlock,
-    GetStateChangesWithCauseInBlockForTrackedShards, GetValidatorInfo, GetValidatorOrdered, Query,
-    QueryError, Status, StatusResponse, SyncStatus, TxStatus, TxStatusError,
+    GetClientConfig, GetContractStorageReport, GetExecutionOutcome, GetExecutionOutcomeResponse,
+    GetExecutionOutcomesForBlock, GetGasPrice, GetLightClientBlocks, GetMaintenanceWindows,
+    GetNetworkInfo, GetNextLightClientBlock, GetPendingTransactions, GetProtocolConfig, GetReceipt,
+    GetSplitStorageInfo, GetStateChanges, GetStateChangesInBlock, GetStateChangesWithCauseInBlock,
+    GetStateChangesWithCauseInBlockForTrackedShards, GetTransactionPoolStats, GetValidatorInfo,
+    GetValidatorOrdered, GetValidatorPerformance, Query, QueryError, SimulateTransaction, Status,
+    StatusResponse, SyncStatus, TxStatus, TxStatusError,
 };
 
 pub use crate::adapter::{
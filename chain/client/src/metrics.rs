@@ -365,6 +365,45 @@ pub(crate) static PRODUCE_CHUNK_TIME: Lazy<HistogramVec> = Lazy::new(|| {
     .unwrap()
 });
 
+pub(crate) static CHUNK_STATE_WITNESS_RAW_SIZE: Lazy<HistogramVec> = Lazy::new(|| {
+    try_create_histogram_vec(
+        "near_chunk_state_witness_raw_size",
+        "Uncompressed size of the state witnesses of produced chunks in bytes",
+        &["shard_id"],
+        Some(exponential_buckets(1000.0, 2.0, 20).unwrap()),
+    )
+    .unwrap()
+});
+
+pub(crate) static CHUNK_STATE_WITNESS_ENCODED_SIZE: Lazy<HistogramVec> = Lazy::new(|| {
+    try_create_histogram_vec(
+        "near_chunk_state_witness_encoded_size",
+        "Compressed size of the state witnesses of produced chunks in bytes, as sent over the network",
+        &["shard_id"],
+        Some(exponential_buckets(1000.0, 2.0, 20).unwrap()),
+    )
+    .unwrap()
+});
+
+pub(crate) static CHUNK_STATE_WITNESS_COMPONENT_SIZE: Lazy<HistogramVec> = Lazy::new(|| {
+    try_create_histogram_vec(
+        "near_chunk_state_witness_component_size",
+        "Uncompressed size of the components of the state witnesses of produced chunks in bytes",
+        &["shard_id", "component"],
+        Some(exponential_buckets(100.0, 2.0, 24).unwrap()),
+    )
+    .unwrap()
+});
+
+pub(crate) static CHUNK_WITNESS_EXCLUDED_TRANSACTIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "near_chunk_state_witness_excluded_transactions_total",
+        "Number of transactions left out of produced chunks to stay within max_chunk_state_witness_size",
+        &["shard_id"],
+    )
+    .unwrap()
+});
+
 pub(crate) static VIEW_CLIENT_MESSAGE_TIME: Lazy<HistogramVec> = Lazy::new(|| {
     try_create_histogram_vec(
         "near_view_client_messages_processing_time",
@@ -375,6 +414,15 @@ pub(crate) static VIEW_CLIENT_MESSAGE_TIME: Lazy<HistogramVec> = Lazy::new(|| {
     .unwrap()
 });
 
+pub(crate) static VIEW_CLIENT_COLD_STORAGE_QUERIES: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "near_view_client_cold_storage_queries_total",
+        "Number of state queries for blocks which are only available in the cold storage",
+        &["request_type"],
+    )
+    .unwrap()
+});
+
 pub(crate) static PRODUCE_AND_DISTRIBUTE_CHUNK_TIME: Lazy<HistogramVec> = Lazy::new(|| {
     try_create_histogram_vec(
         "near_produce_and_distribute_chunk_time",
@@ -505,6 +553,25 @@ pub(crate) static STATE_SYNC_EXTERNAL_PARTS_SIZE_DOWNLOADED: Lazy<IntCounterVec>
         .unwrap()
     });
 
+pub(crate) static STATE_SYNC_EXTERNAL_REQUEST_RETRIES: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "near_state_sync_external_request_retries_total",
+        "Requests to an external storage retried after a transient failure",
+        &["shard_id"],
+    )
+    .unwrap()
+});
+
+pub(crate) static STATE_SYNC_EXTERNAL_PARTS_CHECKSUM_MISMATCH: Lazy<IntCounterVec> =
+    Lazy::new(|| {
+        try_create_int_counter_vec(
+            "near_state_sync_external_parts_checksum_mismatch_total",
+            "State parts downloaded from an external storage with a checksum mismatch",
+            &["shard_id"],
+        )
+        .unwrap()
+    });
+
 pub(crate) static STATE_SYNC_DUMP_PUT_OBJECT_ELAPSED: Lazy<HistogramVec> = Lazy::new(|| {
     try_create_histogram_vec(
         "near_state_sync_dump_put_object_elapsed_sec",
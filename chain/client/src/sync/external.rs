@@ -1,6 +1,9 @@
 use crate::metrics;
 use futures::TryStreamExt;
 use near_primitives::types::{EpochId, ShardId};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use sha2::Digest;
 use std::io::{Read, Write};
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -24,11 +27,40 @@ pub enum ExternalConnection {
         reqwest_client: Arc<reqwest::Client>,
         bucket: String,
     },
+    /// Read-only mirror served by an HTTP(S) file server.
+    Http {
+        reqwest_client: Arc<reqwest::Client>,
+        base_url: String,
+        // Whether to verify parts against the `<part>.sha256` files.
+        verify_checksums: bool,
+    },
+    /// Azure Blob Storage or a compatible service.
+    AzureBlob {
+        reqwest_client: Arc<reqwest::Client>,
+        endpoint: String,
+        container: String,
+        // Shared access signature. Uploading requires it, and so does listing
+        // unless the container allows anonymous listing.
+        sas_token: Option<String>,
+    },
 }
 
 const GCS_ENCODE_SET: &percent_encoding::AsciiSet =
     &percent_encoding::NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_');
 
+/// Same as `GCS_ENCODE_SET` but keeps the path structure of a location.
+const URL_PATH_ENCODE_SET: &percent_encoding::AsciiSet =
+    &GCS_ENCODE_SET.remove(b'~').remove(b'/').remove(b'=');
+
+/// Requests to HTTP based storages are attempted at most this many times.
+const REQUEST_MAX_ATTEMPTS: u32 = 5;
+const REQUEST_INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+const REQUEST_MAX_BACKOFF: Duration = Duration::from_secs(2);
+
+const AZURE_API_VERSION: &str = "2021-08-06";
+/// User-defined blob metadata holding the SHA-256 of a state part.
+const AZURE_CHECKSUM_HEADER: &str = "x-ms-meta-sha256";
+
 impl ExternalConnection {
     pub async fn get_part(
         &self,
@@ -75,6 +107,43 @@ impl ExternalConnection {
                     }
                 }
             }
+            ExternalConnection::Http { reqwest_client, base_url, verify_checksums } => {
+                let url = http_url(base_url, location);
+                let response = send_with_retries(shard_id, || reqwest_client.get(&url)).await?;
+                let bytes = response.bytes().await?.to_vec();
+                tracing::debug!(target: "sync", %shard_id, location, num_bytes = bytes.len(), "HTTP state_part request finished");
+                if *verify_checksums {
+                    let checksum_url = format!("{url}.sha256");
+                    let checksum =
+                        send_with_retries(shard_id, || reqwest_client.get(&checksum_url))
+                            .await?
+                            .text()
+                            .await?;
+                    // The file is in the `sha256sum` format: the digest followed by the file name.
+                    let expected = checksum.split_whitespace().next().unwrap_or_default();
+                    verify_checksum(shard_id, location, &bytes, expected)?;
+                }
+                Ok(bytes)
+            }
+            ExternalConnection::AzureBlob { reqwest_client, endpoint, container, sas_token } => {
+                let url = azure_blob_url(endpoint, container, location, sas_token.as_deref());
+                let response = send_with_retries(shard_id, || {
+                    reqwest_client.get(&url).header("x-ms-version", AZURE_API_VERSION)
+                })
+                .await?;
+                let expected = response
+                    .headers()
+                    .get(AZURE_CHECKSUM_HEADER)
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_string);
+                let bytes = response.bytes().await?.to_vec();
+                tracing::debug!(target: "sync", %shard_id, location, num_bytes = bytes.len(), "Azure state_part request finished");
+                // Blobs uploaded by other tools may lack the checksum.
+                if let Some(expected) = expected {
+                    verify_checksum(shard_id, location, &bytes, &expected)?;
+                }
+                Ok(bytes)
+            }
         }
     }
 
@@ -127,6 +196,24 @@ impl ExternalConnection {
                 tracing::debug!(target: "state_sync_dump", shard_id, part_length = state_part.len(), ?location, "Wrote a state part to GCS");
                 Ok(())
             }
+            ExternalConnection::Http { .. } => {
+                anyhow::bail!("HTTP external storage is read-only")
+            }
+            ExternalConnection::AzureBlob { reqwest_client, endpoint, container, sas_token } => {
+                let url = azure_blob_url(endpoint, container, location, sas_token.as_deref());
+                let checksum = sha256_hex(state_part);
+                send_with_retries(shard_id, || {
+                    reqwest_client
+                        .put(&url)
+                        .header("x-ms-version", AZURE_API_VERSION)
+                        .header("x-ms-blob-type", "BlockBlob")
+                        .header(AZURE_CHECKSUM_HEADER, &checksum)
+                        .body(state_part.to_vec())
+                })
+                .await?;
+                tracing::debug!(target: "state_sync_dump", shard_id, part_length = state_part.len(), ?location, "Wrote a state part to Azure");
+                Ok(())
+            }
         }
     }
 
@@ -197,10 +284,172 @@ impl ExternalConnection {
                     .flatten()
                     .collect())
             }
+            ExternalConnection::Http { reqwest_client, base_url, .. } => {
+                let url = format!("{}/", http_url(base_url, directory_path));
+                tracing::debug!(target: "state_sync_dump", shard_id, ?directory_path, "List state parts over HTTP");
+                let listing =
+                    send_with_retries(shard_id, || reqwest_client.get(&url)).await?.text().await?;
+                Ok(file_names_from_html_listing(&listing))
+            }
+            ExternalConnection::AzureBlob { reqwest_client, endpoint, container, sas_token } => {
+                let prefix = format!("{}/", directory_path);
+                tracing::debug!(target: "state_sync_dump", shard_id, ?directory_path, "List state parts in Azure");
+                let mut file_names = vec![];
+                let mut marker = String::new();
+                // The results are paginated, each page points to the next one with a marker.
+                loop {
+                    let mut url = format!(
+                        "{}/{}?restype=container&comp=list&delimiter=%2F&prefix={}",
+                        endpoint.trim_end_matches('/'),
+                        container,
+                        percent_encoding::percent_encode(prefix.as_bytes(), GCS_ENCODE_SET),
+                    );
+                    if !marker.is_empty() {
+                        url.push_str("&marker=");
+                        url.extend(percent_encoding::percent_encode(
+                            marker.as_bytes(),
+                            GCS_ENCODE_SET,
+                        ));
+                    }
+                    if let Some(sas_token) = sas_token {
+                        url.push('&');
+                        url.push_str(sas_token.trim_start_matches('?'));
+                    }
+                    let response = send_with_retries(shard_id, || {
+                        reqwest_client.get(&url).header("x-ms-version", AZURE_API_VERSION)
+                    })
+                    .await?
+                    .text()
+                    .await?;
+                    let (names, next_marker) = parse_azure_blob_list(&response);
+                    file_names.extend(
+                        names.into_iter().map(|name| Self::extract_file_name_from_full_path(name)),
+                    );
+                    match next_marker {
+                        Some(next_marker) => marker = next_marker,
+                        None => break,
+                    }
+                }
+                Ok(file_names)
+            }
         }
     }
 }
 
+/// Sends a request built by `make_request`, retrying with an exponential
+/// backoff on connection errors, timeouts and server-side errors.  Other error
+/// statuses, e.g. 404, are returned as errors right away.
+async fn send_with_retries(
+    shard_id: ShardId,
+    make_request: impl Fn() -> reqwest::RequestBuilder,
+) -> Result<reqwest::Response, anyhow::Error> {
+    let mut backoff = REQUEST_INITIAL_BACKOFF;
+    let mut attempt = 1;
+    loop {
+        let result = make_request().send().await;
+        let is_transient = match &result {
+            Ok(response) => {
+                response.status().is_server_error()
+                    || response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS
+            }
+            Err(err) => err.is_connect() || err.is_timeout(),
+        };
+        if !is_transient || attempt >= REQUEST_MAX_ATTEMPTS {
+            return Ok(result?.error_for_status()?);
+        }
+        tracing::debug!(target: "sync", shard_id, attempt, ?backoff, result = ?result.map(|response| response.status()), "Retrying a request to the external storage");
+        metrics::STATE_SYNC_EXTERNAL_REQUEST_RETRIES
+            .with_label_values(&[&shard_id.to_string()])
+            .inc();
+        tokio::time::sleep(backoff).await;
+        backoff = std::cmp::min(backoff * 2, REQUEST_MAX_BACKOFF);
+        attempt += 1;
+    }
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(sha2::Sha256::digest(data))
+}
+
+fn verify_checksum(
+    shard_id: ShardId,
+    location: &str,
+    data: &[u8],
+    expected: &str,
+) -> Result<(), anyhow::Error> {
+    let actual = sha256_hex(data);
+    if actual.eq_ignore_ascii_case(expected) {
+        return Ok(());
+    }
+    metrics::STATE_SYNC_EXTERNAL_PARTS_CHECKSUM_MISMATCH
+        .with_label_values(&[&shard_id.to_string()])
+        .inc();
+    anyhow::bail!("Checksum mismatch of {location}: expected {expected}, got {actual}")
+}
+
+fn http_url(base_url: &str, location: &str) -> String {
+    format!(
+        "{}/{}",
+        base_url.trim_end_matches('/'),
+        percent_encoding::percent_encode(location.as_bytes(), URL_PATH_ENCODE_SET)
+    )
+}
+
+fn azure_blob_url(
+    endpoint: &str,
+    container: &str,
+    location: &str,
+    sas_token: Option<&str>,
+) -> String {
+    let url = http_url(&format!("{}/{}", endpoint.trim_end_matches('/'), container), location);
+    match sas_token {
+        Some(sas_token) => format!("{}?{}", url, sas_token.trim_start_matches('?')),
+        None => url,
+    }
+}
+
+static HTML_LINK_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?i)href\s*=\s*["']([^"']*)["']"#).unwrap());
+static AZURE_BLOB_NAME_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"<Blob>\s*<Name>([^<]*)</Name>").unwrap());
+static AZURE_NEXT_MARKER_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"<NextMarker>([^<]+)</NextMarker>").unwrap());
+
+/// Extracts names of the files linked from a directory listing page, as
+/// generated by nginx, Apache, `python -m http.server` and the like.
+/// Links to subdirectories and to other hosts are skipped.
+fn file_names_from_html_listing(html: &str) -> Vec<String> {
+    let mut file_names: Vec<String> = vec![];
+    for captures in HTML_LINK_RE.captures_iter(html) {
+        let link = captures[1].split(|c: char| c == '?' || c == '#').next().unwrap_or_default();
+        if link.is_empty() || link.ends_with('/') || link.contains("://") {
+            continue;
+        }
+        let file_name = link.rsplit('/').next().unwrap_or_default();
+        let file_name = percent_encoding::percent_decode_str(file_name).decode_utf8_lossy();
+        if !file_name.is_empty() && !file_names.iter().any(|name| name == &file_name) {
+            file_names.push(file_name.into_owned());
+        }
+    }
+    file_names
+}
+
+/// Parses a page of the Azure `List Blobs` response.  Returns the blob names
+/// and the marker of the next page if there is one.
+fn parse_azure_blob_list(xml: &str) -> (Vec<String>, Option<String>) {
+    let unescape = |s: &str| {
+        s.replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&amp;", "&")
+    };
+    let names =
+        AZURE_BLOB_NAME_RE.captures_iter(xml).map(|captures| unescape(&captures[1])).collect();
+    let next_marker = AZURE_NEXT_MARKER_RE.captures(xml).map(|captures| unescape(&captures[1]));
+    (names, next_marker)
+}
+
 /// Construct a location on the external storage.
 pub fn external_storage_location(
     chain_id: &str,
@@ -313,6 +562,32 @@ pub fn create_bucket_readwrite(
     create_bucket(bucket, region, timeout, creds)
 }
 
+/// Creates a client for the HTTP based storages.
+pub fn create_http_client(timeout: Duration) -> reqwest::Client {
+    // Ensure requests finish in finite amount of time.
+    reqwest::Client::builder().timeout(timeout).build().unwrap()
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct AzureCredentialsConfig {
+    sas_token: String,
+}
+
+/// Returns a shared access signature for Azure Blob Storage, read from the
+/// credentials file if one is given, or from the `AZURE_STORAGE_SAS_TOKEN`
+/// environment variable otherwise.
+pub fn azure_sas_token(credentials_file: Option<PathBuf>) -> Result<Option<String>, anyhow::Error> {
+    match credentials_file {
+        Some(credentials_file) => {
+            let json_config_str = std::fs::read_to_string(credentials_file)?;
+            let credentials_config: AzureCredentialsConfig =
+                serde_json::from_str(&json_config_str)?;
+            Ok(Some(credentials_config.sas_token))
+        }
+        None => Ok(std::env::var("AZURE_STORAGE_SAS_TOKEN").ok()),
+    }
+}
+
 fn create_bucket(
     bucket: &str,
     region: &str,
@@ -328,16 +603,342 @@ fn create_bucket(
 #[cfg(test)]
 mod test {
     use crate::sync::external::{
-        get_num_parts_from_filename, get_part_id_from_filename, is_part_filename, part_filename,
-        ExternalConnection,
+        azure_sas_token, file_names_from_html_listing, get_num_parts_from_filename,
+        get_part_id_from_filename, is_part_filename, parse_azure_blob_list, part_filename,
+        sha256_hex, ExternalConnection, AZURE_CHECKSUM_HEADER,
     };
     use near_o11y::testonly::init_test_logger;
     use rand::distributions::{Alphanumeric, DistString};
+    use std::collections::{BTreeMap, HashMap};
+    use std::io::{BufRead, Read, Write};
+    use std::path::PathBuf;
+    use std::sync::Arc;
 
     fn random_string(rand_len: usize) -> String {
         Alphanumeric.sample_string(&mut rand::thread_rng(), rand_len)
     }
 
+    /// Serves files from `root_dir` over HTTP and generates listings for
+    /// directories, like a static file server would.  The first `failures`
+    /// requests are answered with 503.  Returns the base URL of the server.
+    fn serve_dir(root_dir: PathBuf, failures: usize) -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let mut failures = failures;
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = std::io::BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut header = String::new();
+                while reader.read_line(&mut header).unwrap() > 2 {
+                    header.clear();
+                }
+                let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+                let path = percent_encoding::percent_decode_str(path).decode_utf8_lossy();
+                let path = root_dir.join(path.trim_start_matches('/'));
+                let (status, body) = if failures > 0 {
+                    failures -= 1;
+                    ("503 Service Unavailable", vec![])
+                } else if path.is_dir() {
+                    let mut listing = String::from("<html><body><a href=\"../\">../</a>\n");
+                    for entry in std::fs::read_dir(&path).unwrap() {
+                        let name = entry.unwrap().file_name().into_string().unwrap();
+                        listing.push_str(&format!("<a href=\"{name}\">{name}</a>\n"));
+                    }
+                    listing.push_str("</body></html>");
+                    ("200 OK", listing.into_bytes())
+                } else if let Ok(data) = std::fs::read(&path) {
+                    ("200 OK", data)
+                } else {
+                    ("404 Not Found", vec![])
+                };
+                write!(
+                    stream,
+                    "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                )
+                .unwrap();
+                stream.write_all(&body).unwrap();
+            }
+        });
+        base_url
+    }
+
+    /// Implements the part of the Azure Blob Storage REST API used by
+    /// `ExternalConnection::AzureBlob` for a single `container`, keeping the
+    /// blobs in memory.  Listings are split into pages of two blobs.  Requests
+    /// without `sig=<signature>` in the query are answered with 403.  Returns
+    /// the endpoint of the server.
+    fn serve_azure(container: &'static str, signature: &'static str) -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let mut blobs = BTreeMap::<String, (Vec<u8>, Option<String>)>::new();
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = std::io::BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut headers = HashMap::new();
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    let Some((name, value)) = header.trim_end().split_once(':') else {
+                        break;
+                    };
+                    headers.insert(name.to_ascii_lowercase(), value.trim().to_string());
+                }
+                let mut request_line = request_line.split_whitespace();
+                let method = request_line.next().unwrap_or_default();
+                let target = request_line.next().unwrap_or("/");
+                let (path, query) = target.split_once('?').unwrap_or((target, ""));
+                let decode = |s: &str| {
+                    percent_encoding::percent_decode_str(s).decode_utf8_lossy().to_string()
+                };
+                let path = decode(path);
+                let query: HashMap<&str, String> = query
+                    .split('&')
+                    .filter_map(|param| param.split_once('='))
+                    .map(|(key, value)| (key, decode(value)))
+                    .collect();
+
+                let mut response_headers = String::new();
+                let (status, body) = if query.get("sig").map(String::as_str) != Some(signature) {
+                    ("403 Forbidden", vec![])
+                } else if query.get("comp").map(String::as_str) == Some("list") {
+                    let prefix = query.get("prefix").cloned().unwrap_or_default();
+                    let marker = query.get("marker").cloned().unwrap_or_default();
+                    let mut names = blobs.keys().filter(|name| {
+                        name.strip_prefix(&prefix).map_or(false, |name| !name.contains('/'))
+                            && name.as_str() > marker.as_str()
+                    });
+                    let page: Vec<_> = names.by_ref().take(2).collect();
+                    let mut listing = String::from("<EnumerationResults><Blobs>");
+                    for name in &page {
+                        listing.push_str(&format!("<Blob><Name>{name}</Name></Blob>"));
+                    }
+                    listing.push_str("</Blobs><NextMarker>");
+                    if names.next().is_some() {
+                        listing.push_str(page.last().unwrap());
+                    }
+                    listing.push_str("</NextMarker></EnumerationResults>");
+                    ("200 OK", listing.into_bytes())
+                } else if let Some(name) = path.strip_prefix(&format!("/{container}/")) {
+                    match method {
+                        "PUT"
+                            if headers.get("x-ms-blob-type").map(String::as_str)
+                                == Some("BlockBlob") =>
+                        {
+                            let len = headers["content-length"].parse().unwrap();
+                            let mut data = vec![0; len];
+                            reader.read_exact(&mut data).unwrap();
+                            let checksum = headers.get(AZURE_CHECKSUM_HEADER).cloned();
+                            blobs.insert(name.to_string(), (data, checksum));
+                            ("201 Created", vec![])
+                        }
+                        "GET" => match blobs.get(name) {
+                            Some((data, checksum)) => {
+                                if let Some(checksum) = checksum {
+                                    response_headers =
+                                        format!("{AZURE_CHECKSUM_HEADER}: {checksum}\r\n");
+                                }
+                                ("200 OK", data.clone())
+                            }
+                            None => ("404 Not Found", vec![]),
+                        },
+                        _ => ("400 Bad Request", vec![]),
+                    }
+                } else {
+                    ("404 Not Found", vec![])
+                };
+                write!(
+                    stream,
+                    "HTTP/1.1 {status}\r\n{response_headers}Content-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                )
+                .unwrap();
+                stream.write_all(&body).unwrap();
+            }
+        });
+        endpoint
+    }
+
+    fn azure_connection(endpoint: String, sas_token: Option<String>) -> ExternalConnection {
+        ExternalConnection::AzureBlob {
+            reqwest_client: Arc::new(reqwest::Client::default()),
+            endpoint,
+            container: "parts".to_string(),
+            sas_token,
+        }
+    }
+
+    fn http_connection(base_url: String, verify_checksums: bool) -> ExternalConnection {
+        ExternalConnection::Http {
+            reqwest_client: Arc::new(reqwest::Client::default()),
+            base_url,
+            verify_checksums,
+        }
+    }
+
+    #[test]
+    fn test_http_list_and_download() {
+        init_test_logger();
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let root_dir = tempfile::tempdir().unwrap();
+        let dir = "chain_id=test/epoch_height=1/epoch_id=test/shard_id=0";
+        std::fs::create_dir_all(root_dir.path().join(dir)).unwrap();
+        let mut parts = vec![];
+        for part_id in 0..3 {
+            let filename = part_filename(part_id, 3);
+            let data: Vec<u8> = random_string(1000).into();
+            let path = root_dir.path().join(dir).join(&filename);
+            std::fs::write(&path, &data).unwrap();
+            let checksum = format!("{}  {}\n", sha256_hex(&data), filename);
+            std::fs::write(path.with_file_name(format!("{filename}.sha256")), checksum).unwrap();
+            parts.push((filename, data));
+        }
+
+        // The first requests fail and have to be retried.
+        let connection = http_connection(serve_dir(root_dir.path().to_path_buf(), 2), true);
+
+        let mut files = rt.block_on(connection.list_state_parts(0, dir)).unwrap();
+        files.retain(|file| is_part_filename(file));
+        files.sort();
+        assert_eq!(files, parts.iter().map(|(filename, _)| filename.clone()).collect::<Vec<_>>());
+
+        for (filename, data) in &parts {
+            let location = format!("{dir}/{filename}");
+            let download_data = rt.block_on(connection.get_part(0, &location)).unwrap();
+            assert_eq!(&download_data, data);
+        }
+
+        // Missing parts are not retried until the attempts run out.
+        let location = format!("{dir}/{}", part_filename(3, 3));
+        assert!(rt.block_on(connection.get_part(0, &location)).is_err());
+
+        // The mirror is read-only.
+        assert!(rt.block_on(connection.put_state_part(b"data", 0, &location)).is_err());
+    }
+
+    #[test]
+    fn test_http_checksum_mismatch() {
+        init_test_logger();
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let root_dir = tempfile::tempdir().unwrap();
+        let filename = part_filename(0, 1);
+        std::fs::write(root_dir.path().join(&filename), b"corrupted").unwrap();
+        std::fs::write(
+            root_dir.path().join(format!("{filename}.sha256")),
+            format!("{}  {}\n", sha256_hex(b"original"), filename),
+        )
+        .unwrap();
+        let base_url = serve_dir(root_dir.path().to_path_buf(), 0);
+
+        let connection = http_connection(base_url.clone(), true);
+        let result = rt.block_on(connection.get_part(0, &filename));
+        assert!(result.is_err(), "{:?}", result);
+
+        // Without verification the part is returned as it is.
+        let connection = http_connection(base_url, false);
+        let download_data = rt.block_on(connection.get_part(0, &filename)).unwrap();
+        assert_eq!(download_data, b"corrupted");
+    }
+
+    #[test]
+    fn test_azure_put_list_download() {
+        init_test_logger();
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let endpoint = serve_azure("parts", "secret");
+        let credentials_file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(credentials_file.path(), r#"{"sas_token": "?sv=2021-08-06&sig=secret"}"#)
+            .unwrap();
+        let sas_token = azure_sas_token(Some(credentials_file.path().to_path_buf())).unwrap();
+        let connection = azure_connection(endpoint.clone(), sas_token);
+
+        let dir = "chain_id=test/epoch_height=1/epoch_id=test/shard_id=0";
+        let mut parts = vec![];
+        for part_id in 0..5 {
+            let filename = part_filename(part_id, 5);
+            let data: Vec<u8> = random_string(1000).into();
+            let location = format!("{dir}/{filename}");
+            rt.block_on(connection.put_state_part(&data, 0, &location)).unwrap();
+            parts.push((filename, data));
+        }
+        // Blobs in nested directories are not listed.
+        let location = format!("{dir}/nested/{}", part_filename(0, 1));
+        rt.block_on(connection.put_state_part(b"data", 0, &location)).unwrap();
+
+        // The listing spans multiple pages.
+        let mut files = rt.block_on(connection.list_state_parts(0, dir)).unwrap();
+        files.sort();
+        assert_eq!(files, parts.iter().map(|(filename, _)| filename.clone()).collect::<Vec<_>>());
+
+        for (filename, data) in &parts {
+            let location = format!("{dir}/{filename}");
+            let download_data = rt.block_on(connection.get_part(0, &location)).unwrap();
+            assert_eq!(&download_data, data);
+        }
+
+        // Missing parts and requests without a valid signature fail.
+        let location = format!("{dir}/{}", part_filename(5, 6));
+        assert!(rt.block_on(connection.get_part(0, &location)).is_err());
+        let connection = azure_connection(endpoint, None);
+        let location = format!("{dir}/{}", parts[0].0);
+        assert!(rt.block_on(connection.get_part(0, &location)).is_err());
+        assert!(rt.block_on(connection.put_state_part(b"data", 0, &location)).is_err());
+    }
+
+    #[test]
+    fn test_file_names_from_html_listing() {
+        // Format of nginx autoindex.
+        let html = r#"<html>
+<head><title>Index of /shard_id=0/</title></head>
+<body>
+<h1>Index of /shard_id=0/</h1><hr><pre><a href="../">../</a>
+<a href="subdir/">subdir/</a>
+<a href="state_part_000000_of_000002">state_part_000000_of_000002</a>  17-Oct-2026 10:00  1000
+<a href="state_part_000001_of_000002">state_part_000001_of_000002</a>  17-Oct-2026 10:00  1000
+<a href="state_part_000001_of_000002.sha256">state_part_000001_of_000002.sha256</a>
+<a href="https://example.com/other">other</a>
+<a href='weird%20name?C=M;O=A'>weird name</a>
+</pre><hr></body>
+</html>"#;
+        assert_eq!(
+            file_names_from_html_listing(html),
+            vec![
+                "state_part_000000_of_000002",
+                "state_part_000001_of_000002",
+                "state_part_000001_of_000002.sha256",
+                "weird name",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_azure_blob_list() {
+        let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+<EnumerationResults ServiceEndpoint="http://127.0.0.1:10000/account" ContainerName="parts">
+  <Prefix>dir/</Prefix>
+  <Blobs>
+    <BlobPrefix><Name>dir/subdir/</Name></BlobPrefix>
+    <Blob><Name>dir/state_part_000000_of_000002</Name><Properties /></Blob>
+    <Blob><Name>dir/a&amp;b</Name><Properties /></Blob>
+  </Blobs>
+  <NextMarker>2!76!marker</NextMarker>
+</EnumerationResults>"#;
+        let (names, next_marker) = parse_azure_blob_list(xml);
+        assert_eq!(names, vec!["dir/state_part_000000_of_000002", "dir/a&b"]);
+        assert_eq!(next_marker.as_deref(), Some("2!76!marker"));
+
+        let (names, next_marker) = parse_azure_blob_list(
+            "<EnumerationResults><Blobs /><NextMarker /></EnumerationResults>",
+        );
+        assert!(names.is_empty());
+        assert_eq!(next_marker, None);
+    }
+
     #[test]
     fn test_match_filename() {
         let filename = part_filename(5, 15);
@@ -22,7 +22,8 @@
 
 use crate::metrics;
 use crate::sync::external::{
-    create_bucket_readonly, external_storage_location, ExternalConnection,
+    azure_sas_token, create_bucket_readonly, create_http_client, external_storage_location,
+    ExternalConnection,
 };
 use actix_rt::ArbiterHandle;
 use chrono::{DateTime, Duration, Utc};
@@ -164,6 +165,7 @@ impl StateSync {
                 location,
                 num_concurrent_requests,
                 num_concurrent_requests_during_catchup,
+                credentials_file,
             }) => {
                 let external = match location {
                     ExternalStorageLocation::S3 { bucket, region, .. } => {
@@ -181,6 +183,22 @@ impl StateSync {
                         reqwest_client: Arc::new(reqwest::Client::default()),
                         bucket: bucket.clone(),
                     },
+                    ExternalStorageLocation::Http { base_url, verify_checksums } => {
+                        ExternalConnection::Http {
+                            reqwest_client: Arc::new(create_http_client(timeout)),
+                            base_url: base_url.clone(),
+                            verify_checksums: *verify_checksums,
+                        }
+                    }
+                    ExternalStorageLocation::AzureBlob { endpoint, container } => {
+                        ExternalConnection::AzureBlob {
+                            reqwest_client: Arc::new(create_http_client(timeout)),
+                            endpoint: endpoint.clone(),
+                            container: container.clone(),
+                            sas_token: azure_sas_token(credentials_file.clone())
+                                .expect("Failed to read the Azure shared access signature"),
+                        }
+                    }
                 };
                 let num_permits = if catchup {
                     *num_concurrent_requests_during_catchup
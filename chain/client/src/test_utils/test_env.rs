@@ -522,7 +522,9 @@ impl TestEnv {
             | ProcessTxResponse::RequestRouted
             | ProcessTxResponse::ValidTx => (),
             ProcessTxResponse::InvalidTx(e) => return Err(e),
-            ProcessTxResponse::DoesNotTrackShard => panic!("test setup is buggy"),
+            ProcessTxResponse::DoesNotTrackShard | ProcessTxResponse::SignerQuotaExceeded => {
+                panic!("test setup is buggy")
+            }
         }
         let max_iters = 100;
         let tip = self.clients[0].chain.head().unwrap();
@@ -1,14 +1,18 @@
 use crate::test_utils::TestEnv;
+use crate::ProcessTxResponse;
 use assert_matches::assert_matches;
 use near_chain::{test_utils, ChainGenesis, Provenance};
+use near_chunks::client::ShardedTransactionPool;
 use near_crypto::vrf::Value;
-use near_crypto::{KeyType, PublicKey, Signature};
+use near_crypto::{InMemorySigner, KeyType, PublicKey, Signature};
 use near_network::types::{NetworkRequests, PeerManagerMessageRequest};
+use near_pool::SignerQuota;
 use near_primitives::block::Block;
 use near_primitives::network::PeerId;
 use near_primitives::sharding::ShardChunkHeader;
 use near_primitives::sharding::ShardChunkHeaderV3;
 use near_primitives::test_utils::create_test_signer;
+use near_primitives::transaction::SignedTransaction;
 use near_primitives::types::validator_stake::ValidatorStake;
 use near_primitives::utils::MaybeValidated;
 use std::sync::Arc;
@@ -142,3 +146,69 @@ fn test_bad_block_signature() {
     let _ =
         env.clients[0].process_block_test(MaybeValidated::from(block), Provenance::NONE).unwrap();
 }
+
+/// A transaction dropped because its signer used up its quota in the pool must not be reported
+/// as accepted.
+#[test]
+fn test_signer_quota_exceeded() {
+    let mut env = TestEnv::builder(ChainGenesis::test()).build();
+    env.clients[0].sharded_tx_pool = ShardedTransactionPool::new([0; 32], None)
+        .with_signer_quota(SignerQuota { count_limit: Some(1), size_limit: None });
+    let genesis_hash = *env.clients[0].chain.genesis().hash();
+    let signer = InMemorySigner::from_seed("test0".parse().unwrap(), KeyType::ED25519, "test0");
+    let send_money = |nonce| {
+        SignedTransaction::send_money(
+            nonce,
+            "test0".parse().unwrap(),
+            "test1".parse().unwrap(),
+            &signer,
+            1,
+            genesis_hash,
+        )
+    };
+    assert_eq!(env.clients[0].process_tx(send_money(1), false, false), ProcessTxResponse::ValidTx);
+    assert_eq!(
+        env.clients[0].process_tx(send_money(2), false, false),
+        ProcessTxResponse::SignerQuotaExceeded
+    );
+}
+
+#[test]
+fn test_pending_transactions() {
+    let mut env = TestEnv::builder(ChainGenesis::test()).build();
+    let genesis_hash = *env.clients[0].chain.genesis().hash();
+    let send_money = |signer_id: &str, nonce| {
+        let signer =
+            InMemorySigner::from_seed(signer_id.parse().unwrap(), KeyType::ED25519, signer_id);
+        SignedTransaction::send_money(
+            nonce,
+            signer_id.parse().unwrap(),
+            "test2".parse().unwrap(),
+            &signer,
+            1,
+            genesis_hash,
+        )
+    };
+    let transactions = [send_money("test0", 1), send_money("test0", 2), send_money("test1", 1)];
+    for tx in &transactions {
+        assert_eq!(env.clients[0].process_tx(tx.clone(), false, false), ProcessTxResponse::ValidTx);
+    }
+
+    let pending = env.clients[0].pending_transactions(Some(&"test0".parse().unwrap()), None);
+    assert_eq!(pending.len(), 1);
+    let mut nonces: Vec<_> = pending[0].transactions.iter().map(|tx| tx.nonce).collect();
+    nonces.sort();
+    assert_eq!(nonces, [1, 2]);
+    let pending =
+        env.clients[0].pending_transactions(None, Some(&transactions[2].transaction.public_key));
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].transactions.len(), 1);
+    assert_eq!(pending[0].transactions[0].hash, transactions[2].get_hash());
+    assert!(env.clients[0].pending_transactions(Some(&"test2".parse().unwrap()), None).is_empty());
+
+    let stats = env.clients[0].transaction_pool_stats();
+    assert_eq!(stats.len(), 1);
+    assert_eq!(stats[0].transaction_count, 3);
+    assert_eq!(stats[0].signer_count, 2);
+    assert_eq!(stats[0].transaction_size, transactions.iter().map(|tx| tx.get_size()).sum::<u64>());
+}
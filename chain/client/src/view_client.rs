@@ -7,7 +7,7 @@ use crate::adapter::{
 };
 use crate::{
     metrics, sync, GetChunk, GetExecutionOutcomeResponse, GetNextLightClientBlock, GetStateChanges,
-    GetStateChangesInBlock, GetValidatorInfo, GetValidatorOrdered,
+    GetStateChangesInBlock, GetValidatorInfo, GetValidatorOrdered, GetValidatorPerformance,
 };
 use actix::{Actor, Addr, Handler, SyncArbiter, SyncContext};
 use near_async::messaging::CanSend;
@@ -19,12 +19,13 @@ use near_chain_configs::{ClientConfig, ProtocolConfigView};
 use near_chain_primitives::error::EpochErrorResultToChainError;
 use near_client_primitives::types::{
     Error, GetBlock, GetBlockError, GetBlockProof, GetBlockProofError, GetBlockProofResponse,
-    GetBlockWithMerkleTree, GetChunkError, GetExecutionOutcome, GetExecutionOutcomeError,
-    GetExecutionOutcomesForBlock, GetGasPrice, GetGasPriceError, GetMaintenanceWindows,
-    GetMaintenanceWindowsError, GetNextLightClientBlockError, GetProtocolConfig,
-    GetProtocolConfigError, GetReceipt, GetReceiptError, GetSplitStorageInfo,
-    GetSplitStorageInfoError, GetStateChangesError, GetStateChangesWithCauseInBlock,
-    GetStateChangesWithCauseInBlockForTrackedShards, GetValidatorInfoError, Query, QueryError,
+    GetBlockWithMerkleTree, GetChunkError, GetContractStorageReport, GetContractStorageReportError,
+    GetExecutionOutcome, GetExecutionOutcomeError, GetExecutionOutcomesForBlock, GetGasPrice,
+    GetGasPriceError, GetLightClientBlocks, GetMaintenanceWindows, GetMaintenanceWindowsError,
+    GetNextLightClientBlockError, GetProtocolConfig, GetProtocolConfigError, GetReceipt,
+    GetReceiptError, GetSplitStorageInfo, GetSplitStorageInfoError, GetStateChangesError,
+    GetStateChangesWithCauseInBlock, GetStateChangesWithCauseInBlockForTrackedShards,
+    GetValidatorInfoError, Query, QueryError, SimulateTransaction, SimulateTransactionError,
     TxStatus, TxStatusError,
 };
 use near_epoch_manager::shard_tracker::ShardTracker;
@@ -37,29 +38,35 @@ use near_o11y::{handler_debug_span, OpenTelemetrySpanExt, WithSpanContext, WithS
 use near_performance_metrics_macros::perf;
 use near_primitives::block::{Block, BlockHeader};
 use near_primitives::epoch_manager::epoch_info::EpochInfo;
+use near_primitives::errors::StorageError;
 use near_primitives::hash::CryptoHash;
 use near_primitives::merkle::{merklize, PartialMerkleTree};
 use near_primitives::network::AnnounceAccount;
 use near_primitives::receipt::Receipt;
-use near_primitives::sharding::ShardChunk;
+use near_primitives::sharding::{ChunkHash, ShardChunk};
 use near_primitives::state_sync::{
     ShardStateSyncResponse, ShardStateSyncResponseHeader, ShardStateSyncResponseV3,
 };
 use near_primitives::static_clock::StaticClock;
+use near_primitives::trie_key::TrieKey;
 use near_primitives::types::{
-    AccountId, BlockHeight, BlockId, BlockReference, EpochReference, Finality, MaybeBlockId,
-    ShardId, SyncCheckpoint, TransactionOrReceiptId, ValidatorInfoIdentifier,
+    AccountId, BlockHeight, BlockId, BlockReference, EpochHeight, EpochId, EpochReference,
+    Finality, MaybeBlockId, NumBlocks, ShardId, SyncCheckpoint, TransactionOrReceiptId,
+    ValidatorInfoIdentifier,
 };
 use near_primitives::views::validator_stake_view::ValidatorStakeView;
 use near_primitives::views::{
-    BlockView, ChunkView, EpochValidatorInfo, ExecutionOutcomeWithIdView, ExecutionStatusView,
-    FinalExecutionOutcomeView, FinalExecutionOutcomeViewEnum, GasPriceView, LightClientBlockView,
-    MaintenanceWindowsView, QueryRequest, QueryResponse, ReceiptView, SplitStorageInfoView,
-    StateChangesKindsView, StateChangesView, TxExecutionStatus, TxStatusView,
+    BlockSlotStatus, BlockSlotView, BlockView, ChunkSlotStatus, ChunkSlotView, ChunkView,
+    ContractStorageReportView, EpochValidatorInfo, ExecutionOutcomeWithIdView, ExecutionStatusView,
+    FinalExecutionOutcomeView, FinalExecutionOutcomeViewEnum, GasPriceView, KickoutRiskView,
+    LightClientBlockView, MaintenanceWindowsView, ProductionProjectionView, QueryRequest,
+    QueryResponse, ReceiptView, SplitStorageInfoView, StateChangesKindsView, StateChangesView,
+    TransactionSimulationView, TxExecutionStatus, TxStatusView, ValidatorEpochPerformanceView,
+    ValidatorPerformanceView,
 };
-use near_store::flat::{FlatStorageReadyStatus, FlatStorageStatus};
+use near_store::flat::{FlatStorageError, FlatStorageReadyStatus, FlatStorageStatus};
 use near_store::{DBCol, COLD_HEAD_KEY, FINAL_HEAD_KEY, HEAD_KEY};
-use std::cmp::Ordering;
+use std::cmp::{max, min, Ordering};
 use std::collections::{BTreeSet, HashMap, VecDeque};
 use std::hash::Hash;
 use std::sync::{Arc, Mutex, RwLock};
@@ -70,6 +77,14 @@ use tracing::{error, info, warn};
 const QUERY_REQUEST_LIMIT: usize = 500;
 /// Waiting time between requests, in ms
 const REQUEST_WAIT_TIME: u64 = 1000;
+/// Max number of light client blocks returned by a single `GetLightClientBlocks`.
+const MAX_LIGHT_CLIENT_BLOCKS_PER_REQUEST: u64 = 100;
+/// Limits on the size of a contract storage report request.
+const MAX_CONTRACT_STORAGE_REPORT_PREFIXES: usize = 100;
+const MAX_CONTRACT_STORAGE_REPORT_TOP_KEYS: usize = 1000;
+const MAX_CONTRACT_STORAGE_REPORT_KEYS: usize = 100_000;
+/// Max number of heights scanned by a single `GetValidatorPerformance`.
+const MAX_VALIDATOR_PERFORMANCE_HEIGHTS: BlockHeight = 1000;
 
 const POISONED_LOCK_ERR: &str = "The lock was poisoned.";
 
@@ -116,6 +131,46 @@ impl ViewClientRequestManager {
 }
 
 impl ViewClientActor {
+    /// Returns the light client block following the block with `last_block_hash`: the last final
+    /// block of the next epoch, or the last final block seen from `head` if the next epoch
+    /// is the current one. Returns None if there is no newer light client block.
+    fn get_next_light_client_block(
+        &self,
+        last_block_hash: &CryptoHash,
+        head: &Tip,
+    ) -> Result<Option<Arc<LightClientBlockView>>, near_chain::Error> {
+        let last_block_header = self.chain.get_block_header(last_block_hash)?;
+        let last_epoch_id = last_block_header.epoch_id().clone();
+        let last_next_epoch_id = last_block_header.next_epoch_id().clone();
+        let last_height = last_block_header.height();
+
+        if last_epoch_id == head.epoch_id || last_next_epoch_id == head.epoch_id {
+            let head_header = self.chain.get_block_header(&head.last_block_hash)?;
+            let ret = Chain::create_light_client_block(
+                &head_header,
+                self.epoch_manager.as_ref(),
+                self.chain.store(),
+            )?;
+
+            if ret.inner_lite.height <= last_height {
+                Ok(None)
+            } else {
+                Ok(Some(Arc::new(ret)))
+            }
+        } else {
+            match self.chain.store().get_epoch_light_client_block(&last_next_epoch_id.0) {
+                Ok(light_block) => Ok(Some(light_block)),
+                Err(e) => {
+                    if let near_chain::Error::DBNotFoundErr(_) = e {
+                        Ok(None)
+                    } else {
+                        Err(e)
+                    }
+                }
+            }
+        }
+    }
+
     /// Maximum number of state requests allowed per `view_client_throttle_period`.
     const MAX_NUM_STATE_REQUESTS: usize = 30;
 
@@ -337,12 +392,22 @@ impl ViewClientActor {
             .map_err(|err| QueryError::InternalError { error_message: err.to_string() })?;
 
         let tip = self.chain.head();
+        // The tail is read from the hot store even if the view client runs on
+        // top of the split storage.  On archival nodes data below it can only
+        // come from the cold store.
+        let is_cold_query =
+            self.config.archive && self.chain.tail().map_or(false, |tail| header.height() < tail);
         let chunk_extra =
             self.chain.get_chunk_extra(header.hash(), &shard_uid).map_err(|err| match err {
                 near_chain::near_chain_primitives::Error::DBNotFoundErr(_) => match tip {
                     Ok(tip) => {
                         let gc_stop_height = self.runtime.get_gc_stop_height(&tip.last_block_hash);
-                        if !self.config.archive && header.height() < gc_stop_height {
+                        // An archival node without the view client reading
+                        // from the split storage can't serve data which has
+                        // been moved to the cold store.
+                        if (!self.config.archive && header.height() < gc_stop_height)
+                            || is_cold_query
+                        {
                             QueryError::GarbageCollectedBlock {
                                 block_height: header.height(),
                                 block_hash: *header.hash(),
@@ -359,6 +424,12 @@ impl ViewClientActor {
                 _ => QueryError::Unreachable { error_message: err.to_string() },
             })?;
 
+        if is_cold_query {
+            metrics::VIEW_CLIENT_COLD_STORAGE_QUERIES
+                .with_label_values(&[query_request_kind(&msg.request)])
+                .inc();
+        }
+
         let state_root = chunk_extra.state_root();
         match self.runtime.query(
             shard_uid,
@@ -418,6 +489,157 @@ impl ViewClientActor {
         }
     }
 
+    fn handle_simulate_transaction(
+        &mut self,
+        msg: SimulateTransaction,
+    ) -> Result<TransactionSimulationView, SimulateTransactionError> {
+        let header = match self.get_block_header_by_reference(&msg.block_reference) {
+            Ok(Some(header)) => header,
+            Ok(None) | Err(near_chain::near_chain_primitives::Error::DBNotFoundErr(_)) => {
+                return Err(SimulateTransactionError::UnknownBlock {
+                    block_reference: msg.block_reference,
+                })
+            }
+            Err(err) => return Err(err.into()),
+        };
+        let tip = self.chain.head()?;
+        if !self.config.archive
+            && header.height() < self.runtime.get_gc_stop_height(&tip.last_block_hash)
+        {
+            return Err(SimulateTransactionError::GarbageCollectedBlock {
+                block_height: header.height(),
+                block_hash: *header.hash(),
+            });
+        }
+
+        // The transaction may touch any shard, so hand over all the state
+        // available on the node and let the runtime complain if it needs a
+        // shard which isn't tracked.
+        let shard_layout =
+            self.epoch_manager.get_shard_layout(header.epoch_id()).map_err(|err| {
+                SimulateTransactionError::InternalError { error_message: err.to_string() }
+            })?;
+        let mut state_roots = HashMap::new();
+        for shard_uid in shard_layout.shard_uids() {
+            match self.chain.get_chunk_extra(header.hash(), &shard_uid) {
+                Ok(chunk_extra) => {
+                    state_roots.insert(shard_uid, *chunk_extra.state_root());
+                }
+                Err(near_chain::near_chain_primitives::Error::DBNotFoundErr(_)) => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        Ok(self.runtime.simulate_transaction(
+            &state_roots,
+            header.height(),
+            header.raw_timestamp(),
+            header.prev_hash(),
+            header.hash(),
+            header.epoch_id(),
+            header.next_gas_price(),
+            &msg.transaction,
+            msg.verify_signature,
+        )?)
+    }
+
+    fn handle_get_contract_storage_report(
+        &self,
+        msg: GetContractStorageReport,
+    ) -> Result<ContractStorageReportView, GetContractStorageReportError> {
+        if msg.prefixes.len() > MAX_CONTRACT_STORAGE_REPORT_PREFIXES {
+            return Err(GetContractStorageReportError::TooManyPrefixes {
+                limit: MAX_CONTRACT_STORAGE_REPORT_PREFIXES,
+            });
+        }
+        let header = match self.get_block_header_by_reference(&msg.block_reference) {
+            Ok(Some(header)) => header,
+            Ok(None) | Err(near_chain::near_chain_primitives::Error::DBNotFoundErr(_)) => {
+                return Err(GetContractStorageReportError::UnknownBlock {
+                    block_reference: msg.block_reference,
+                })
+            }
+            Err(err) => return Err(err.into()),
+        };
+        let tip = self.chain.head()?;
+        if !self.config.archive
+            && header.height() < self.runtime.get_gc_stop_height(&tip.last_block_hash)
+        {
+            return Err(GetContractStorageReportError::GarbageCollectedBlock {
+                block_height: header.height(),
+                block_hash: *header.hash(),
+            });
+        }
+
+        let internal_error = |err: &dyn std::fmt::Display| {
+            GetContractStorageReportError::InternalError { error_message: err.to_string() }
+        };
+        let epoch_id = header.epoch_id();
+        let shard_id = self
+            .epoch_manager
+            .account_id_to_shard_id(&msg.account_id, epoch_id)
+            .map_err(|err| internal_error(&err))?;
+        if !self.shard_tracker.care_about_shard(
+            self.validator_account_id.as_ref(),
+            header.prev_hash(),
+            shard_id,
+            true,
+        ) {
+            return Err(GetContractStorageReportError::UnavailableShard {
+                requested_shard_id: shard_id,
+            });
+        }
+        let shard_uid = self
+            .epoch_manager
+            .shard_id_to_uid(shard_id, epoch_id)
+            .map_err(|err| internal_error(&err))?;
+        let unavailable_state = || GetContractStorageReportError::UnavailableState {
+            block_height: header.height(),
+            block_hash: *header.hash(),
+        };
+        let chunk_view = self
+            .runtime
+            .get_flat_storage_manager()
+            .chunk_view(shard_uid, *header.hash())
+            .ok_or_else(unavailable_state)?;
+
+        let account_key = TrieKey::Account { account_id: msg.account_id.clone() }.to_vec();
+        match chunk_view.get_value(&account_key) {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                return Err(GetContractStorageReportError::UnknownAccount {
+                    requested_account_id: msg.account_id,
+                    block_height: header.height(),
+                    block_hash: *header.hash(),
+                })
+            }
+            Err(StorageError::FlatStorageBlockNotSupported(_)) => return Err(unavailable_state()),
+            Err(err) => return Err(internal_error(&err)),
+        }
+        let storage_usage_config =
+            self.runtime.get_protocol_config(epoch_id)?.runtime_config.fees.storage_usage_config;
+        near_store::flat::contract_storage_report(
+            &chunk_view,
+            *header.hash(),
+            header.height(),
+            &msg.account_id,
+            &near_store::flat::ContractStorageReportOptions {
+                prefixes: &msg.prefixes,
+                top: msg.top.min(MAX_CONTRACT_STORAGE_REPORT_TOP_KEYS),
+                start_key: msg.start_key.as_deref(),
+                max_keys: msg
+                    .max_keys
+                    .unwrap_or(MAX_CONTRACT_STORAGE_REPORT_KEYS)
+                    .min(MAX_CONTRACT_STORAGE_REPORT_KEYS),
+            },
+            storage_usage_config.num_extra_bytes_record,
+        )
+        .map_err(|err| match err {
+            FlatStorageError::BlockNotSupported(_) => unavailable_state(),
+            FlatStorageError::StorageInternalError(_) => internal_error(&err),
+        })
+    }
+
     // Return the lowest status the node can proof
     fn get_tx_execution_status(
         &self,
@@ -609,6 +831,293 @@ impl ViewClientActor {
             _ => Ok(false),
         }
     }
+
+    /// Collects block and chunk production slots of `msg.account_id` in at
+    /// most `MAX_VALIDATOR_PERFORMANCE_HEIGHTS` heights of the requested epoch.
+    fn get_validator_performance(
+        &self,
+        msg: GetValidatorPerformance,
+    ) -> Result<ValidatorPerformanceView, near_chain::Error> {
+        let head = self.chain.head()?;
+        let head_epoch_height = self.epoch_manager.get_epoch_info(&head.epoch_id)?.epoch_height();
+        let requested_epoch_height = match &msg.epoch_id {
+            Some(epoch_id) => {
+                let epoch_height = self.epoch_manager.get_epoch_info(epoch_id)?.epoch_height();
+                if epoch_height > head_epoch_height {
+                    // The requested epoch hasn't started yet.
+                    return Err(near_chain::Error::EpochOutOfBounds(epoch_id.clone()));
+                }
+                epoch_height
+            }
+            None => head_epoch_height,
+        };
+
+        // Walk back epoch by epoch from the head to find the last block of the
+        // requested epoch on the canonical chain.
+        let mut block_hash = head.last_block_hash;
+        let (block_info, first_block_info) = loop {
+            let block_info = self.epoch_manager.get_block_info(&block_hash)?;
+            let first_block_info =
+                self.epoch_manager.get_block_info(block_info.epoch_first_block())?;
+            let epoch_height =
+                self.epoch_manager.get_epoch_info(block_info.epoch_id())?.epoch_height();
+            if epoch_height <= requested_epoch_height {
+                break (block_info, first_block_info);
+            }
+            block_hash = *first_block_info.prev_hash();
+        };
+        let epoch_id = block_info.epoch_id();
+        if let Some(requested_epoch_id) = &msg.epoch_id {
+            if requested_epoch_id != epoch_id {
+                // The requested epoch is not on the canonical chain.
+                return Err(near_chain::Error::EpochOutOfBounds(requested_epoch_id.clone()));
+            }
+        }
+
+        let start_height = max(
+            msg.start_height.unwrap_or_default(),
+            // Genesis is not produced by anyone.
+            max(first_block_info.height(), self.chain.genesis().height() + 1),
+        );
+        let epoch = self.get_validator_epoch_performance(
+            &msg.account_id,
+            epoch_id,
+            requested_epoch_height,
+            start_height,
+            block_info.height(),
+        )?;
+        let kickout_risk = self.get_kickout_risk(&msg.account_id, &head)?;
+        Ok(ValidatorPerformanceView { account_id: msg.account_id, epoch, kickout_risk })
+    }
+
+    /// Lists the slots of `account_id` in the heights of an epoch starting at
+    /// `start_height`, up to `epoch_end_height` or to the per-request limit.
+    /// Slots are attributed the same way as in the epoch info aggregator, so
+    /// the counts match the `validators` RPC.
+    fn get_validator_epoch_performance(
+        &self,
+        account_id: &AccountId,
+        epoch_id: &EpochId,
+        epoch_height: EpochHeight,
+        start_height: BlockHeight,
+        epoch_end_height: BlockHeight,
+    ) -> Result<ValidatorEpochPerformanceView, near_chain::Error> {
+        let end_height = min(
+            epoch_end_height,
+            start_height.saturating_add(MAX_VALIDATOR_PERFORMANCE_HEIGHTS - 1),
+        );
+        let mut blocks = vec![];
+        let mut chunks = vec![];
+        for height in start_height..=end_height {
+            let header = match self.chain.get_block_header_by_height(height) {
+                Ok(header) => header,
+                // Skipped heights are accounted for when processing the next block.
+                Err(near_chain::Error::DBNotFoundErr(_)) => continue,
+                Err(err) => return Err(err),
+            };
+            let prev_height = match header.prev_height() {
+                Some(prev_height) => prev_height,
+                None => self.chain.get_block_header(header.prev_hash())?.height(),
+            };
+
+            for skipped_height in prev_height + 1..height {
+                if &self.epoch_manager.get_block_producer(epoch_id, skipped_height)? != account_id {
+                    continue;
+                }
+                let orphan = self
+                    .chain
+                    .store()
+                    .get_all_block_hashes_by_height(skipped_height)?
+                    .get(epoch_id)
+                    .and_then(|hashes| hashes.iter().min().copied());
+                blocks.push(BlockSlotView {
+                    height: skipped_height,
+                    status: if orphan.is_some() {
+                        BlockSlotStatus::Orphaned
+                    } else {
+                        BlockSlotStatus::Missed
+                    },
+                    block_hash: orphan,
+                    missing_approvals: vec![],
+                });
+            }
+            if &self.epoch_manager.get_block_producer(epoch_id, height)? == account_id {
+                let approvers =
+                    self.epoch_manager.get_epoch_block_approvers_ordered(header.prev_hash())?;
+                let missing_approvals = approvers
+                    .into_iter()
+                    .zip(header.approvals())
+                    .filter(|(_, approval)| approval.is_none())
+                    .map(|((approver, _), _)| approver.account_id)
+                    .collect();
+                blocks.push(BlockSlotView {
+                    height,
+                    status: BlockSlotStatus::Produced,
+                    block_hash: Some(*header.hash()),
+                    missing_approvals,
+                });
+            }
+
+            // Chunk producers are taken at the height right after the previous
+            // block, even if the block itself is at a later height.
+            let chunk_height = prev_height + 1;
+            for (shard_id, included) in header.chunk_mask().iter().enumerate() {
+                let shard_id = shard_id as ShardId;
+                if &self.epoch_manager.get_chunk_producer(epoch_id, chunk_height, shard_id)?
+                    != account_id
+                {
+                    continue;
+                }
+                let (status, chunk_hash) = if *included {
+                    // The block body may be garbage collected already.
+                    let chunk_hash = match self.chain.get_block(header.hash()) {
+                        Ok(block) => {
+                            block.chunks().get(shard_id as usize).map(|chunk| chunk.chunk_hash())
+                        }
+                        Err(_) => None,
+                    };
+                    (ChunkSlotStatus::Produced, chunk_hash)
+                } else {
+                    match self.find_late_chunk(&header, chunk_height, shard_id)? {
+                        Some(chunk_hash) => (ChunkSlotStatus::Late, Some(chunk_hash)),
+                        None => (ChunkSlotStatus::Missed, None),
+                    }
+                };
+                chunks.push(ChunkSlotView {
+                    height: chunk_height,
+                    block_height: height,
+                    shard_id,
+                    status,
+                    chunk_hash,
+                });
+            }
+        }
+
+        Ok(ValidatorEpochPerformanceView {
+            epoch_id: epoch_id.clone(),
+            epoch_height,
+            start_height,
+            end_height,
+            next_start_height: (end_height < epoch_end_height).then_some(end_height + 1),
+            num_produced_blocks: blocks
+                .iter()
+                .filter(|slot| slot.status == BlockSlotStatus::Produced)
+                .count() as NumBlocks,
+            num_expected_blocks: blocks.len() as NumBlocks,
+            num_produced_chunks: chunks
+                .iter()
+                .filter(|slot| slot.status == ChunkSlotStatus::Produced)
+                .count() as NumBlocks,
+            num_expected_chunks: chunks.len() as NumBlocks,
+            blocks,
+            chunks,
+        })
+    }
+
+    /// Looks for a chunk for `shard_id` built on top of the previous block of
+    /// `header` which this node has received, but which is not included in the
+    /// block.
+    fn find_late_chunk(
+        &self,
+        header: &BlockHeader,
+        from_height: BlockHeight,
+        shard_id: ShardId,
+    ) -> Result<Option<ChunkHash>, near_chain::Error> {
+        for height in from_height..=header.height() {
+            for chunk_hash in self.chain.store().get_all_chunk_hashes_by_height(height)? {
+                let Ok(chunk) = self.chain.get_chunk(&chunk_hash) else {
+                    continue;
+                };
+                if chunk.shard_id() == shard_id && &chunk.prev_block_hash() == header.prev_hash() {
+                    return Ok(Some(chunk_hash));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Projects whether `account_id` will be kicked out at the end of the
+    /// current epoch, mirroring the checks in `EpochManager::compute_kickout_info`.
+    fn get_kickout_risk(
+        &self,
+        account_id: &AccountId,
+        head: &Tip,
+    ) -> Result<Option<KickoutRiskView>, near_chain::Error> {
+        let epoch_manager = self.epoch_manager.as_ref();
+        let validator_info = epoch_manager
+            .get_validator_info(ValidatorInfoIdentifier::BlockHash(head.last_block_hash))?;
+        let Some(info) = validator_info
+            .current_validators
+            .into_iter()
+            .find(|validator| &validator.account_id == account_id)
+        else {
+            return Ok(None);
+        };
+        let config = epoch_manager.get_epoch_config(&head.epoch_id)?;
+        let estimated_epoch_end_height = max(
+            head.height,
+            epoch_manager.get_epoch_start_height(&head.last_block_hash)? + config.epoch_length - 1,
+        );
+
+        let shard_ids = epoch_manager.shard_ids(&head.epoch_id)?;
+        let mut remaining_blocks = 0;
+        let mut remaining_chunks = 0;
+        for height in head.height + 1..=estimated_epoch_end_height {
+            if &epoch_manager.get_block_producer(&head.epoch_id, height)? == account_id {
+                remaining_blocks += 1;
+            }
+            for &shard_id in &shard_ids {
+                if &epoch_manager.get_chunk_producer(&head.epoch_id, height, shard_id)?
+                    == account_id
+                {
+                    remaining_chunks += 1;
+                }
+            }
+        }
+
+        let blocks = production_projection(
+            info.num_produced_blocks,
+            info.num_expected_blocks,
+            remaining_blocks,
+            config.block_producer_kickout_threshold,
+        );
+        let chunks = production_projection(
+            info.num_produced_chunks,
+            info.num_expected_chunks,
+            remaining_chunks,
+            config.chunk_producer_kickout_threshold,
+        );
+        let below_threshold = info.num_produced_blocks * 100
+            < u64::from(config.block_producer_kickout_threshold) * info.num_expected_blocks
+            || info.num_produced_chunks * 100
+                < u64::from(config.chunk_producer_kickout_threshold) * info.num_expected_chunks;
+        let kickout_unavoidable =
+            blocks.min_remaining_to_produce.is_none() || chunks.min_remaining_to_produce.is_none();
+        Ok(Some(KickoutRiskView {
+            epoch_id: head.epoch_id.clone(),
+            estimated_epoch_end_height,
+            block_producer_kickout_threshold: config.block_producer_kickout_threshold,
+            chunk_producer_kickout_threshold: config.chunk_producer_kickout_threshold,
+            blocks,
+            chunks,
+            below_threshold,
+            kickout_unavoidable,
+        }))
+    }
+}
+
+/// Computes how many of the remaining slots must be produced so that
+/// `produced * 100 >= threshold * expected` holds at the end of the epoch.
+fn production_projection(
+    produced: NumBlocks,
+    expected: NumBlocks,
+    remaining_expected: NumBlocks,
+    threshold: u8,
+) -> ProductionProjectionView {
+    let required = (u64::from(threshold) * (expected + remaining_expected)).div_ceil(100);
+    let min_remaining_to_produce = Some(required.saturating_sub(produced))
+        .filter(|&to_produce| to_produce <= remaining_expected);
+    ProductionProjectionView { produced, expected, remaining_expected, min_remaining_to_produce }
 }
 
 impl Actor for ViewClientActor {
@@ -627,6 +1136,42 @@ impl Handler<WithSpanContext<Query>> for ViewClientActor {
     }
 }
 
+impl Handler<WithSpanContext<SimulateTransaction>> for ViewClientActor {
+    type Result = Result<TransactionSimulationView, SimulateTransactionError>;
+
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<SimulateTransaction>,
+        _: &mut Self::Context,
+    ) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        tracing::debug!(target: "client", ?msg);
+        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+            .with_label_values(&["SimulateTransaction"])
+            .start_timer();
+        self.handle_simulate_transaction(msg)
+    }
+}
+
+impl Handler<WithSpanContext<GetContractStorageReport>> for ViewClientActor {
+    type Result = Result<ContractStorageReportView, GetContractStorageReportError>;
+
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<GetContractStorageReport>,
+        _: &mut Self::Context,
+    ) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        tracing::debug!(target: "client", ?msg);
+        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+            .with_label_values(&["GetContractStorageReport"])
+            .start_timer();
+        self.handle_get_contract_storage_report(msg)
+    }
+}
+
 /// Handles retrieving block from the chain.
 impl Handler<WithSpanContext<GetBlock>> for ViewClientActor {
     type Result = Result<BlockView, GetBlockError>;
@@ -814,6 +1359,25 @@ impl Handler<WithSpanContext<GetValidatorOrdered>> for ViewClientActor {
         })?)
     }
 }
+
+impl Handler<WithSpanContext<GetValidatorPerformance>> for ViewClientActor {
+    type Result = Result<ValidatorPerformanceView, GetValidatorInfoError>;
+
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<GetValidatorPerformance>,
+        _: &mut Self::Context,
+    ) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        tracing::debug!(target: "client", ?msg);
+        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+            .with_label_values(&["GetValidatorPerformance"])
+            .start_timer();
+        Ok(self.get_validator_performance(msg)?)
+    }
+}
+
 /// Returns a list of change kinds per account in a store for a given block.
 impl Handler<WithSpanContext<GetStateChangesInBlock>> for ViewClientActor {
     type Result = Result<StateChangesKindsView, GetStateChangesError>;
@@ -952,37 +1516,42 @@ impl Handler<WithSpanContext<GetNextLightClientBlock>> for ViewClientActor {
         let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
             .with_label_values(&["GetNextLightClientBlock"])
             .start_timer();
-        let last_block_header = self.chain.get_block_header(&msg.last_block_hash)?;
-        let last_epoch_id = last_block_header.epoch_id().clone();
-        let last_next_epoch_id = last_block_header.next_epoch_id().clone();
-        let last_height = last_block_header.height();
         let head = self.chain.head()?;
+        Ok(self.get_next_light_client_block(&msg.last_block_hash, &head)?)
+    }
+}
 
-        if last_epoch_id == head.epoch_id || last_next_epoch_id == head.epoch_id {
-            let head_header = self.chain.get_block_header(&head.last_block_hash)?;
-            let ret = Chain::create_light_client_block(
-                &head_header,
-                self.epoch_manager.as_ref(),
-                self.chain.store(),
-            )?;
+impl Handler<WithSpanContext<GetLightClientBlocks>> for ViewClientActor {
+    type Result = Result<Vec<Arc<LightClientBlockView>>, GetNextLightClientBlockError>;
 
-            if ret.inner_lite.height <= last_height {
-                Ok(None)
-            } else {
-                Ok(Some(Arc::new(ret)))
-            }
-        } else {
-            match self.chain.store().get_epoch_light_client_block(&last_next_epoch_id.0) {
-                Ok(light_block) => Ok(Some(light_block)),
-                Err(e) => {
-                    if let near_chain::Error::DBNotFoundErr(_) = e {
-                        Ok(None)
-                    } else {
-                        Err(e.into())
-                    }
-                }
-            }
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<GetLightClientBlocks>,
+        _: &mut Self::Context,
+    ) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        tracing::debug!(target: "client", ?msg);
+        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+            .with_label_values(&["GetLightClientBlocks"])
+            .start_timer();
+        let limit = msg
+            .limit
+            .unwrap_or(MAX_LIGHT_CLIENT_BLOCKS_PER_REQUEST)
+            .min(MAX_LIGHT_CLIENT_BLOCKS_PER_REQUEST);
+        // Head is fixed for the whole request, so that the sequence ends with the block
+        // returned for the head rather than chasing new blocks.
+        let head = self.chain.head()?;
+        let mut blocks = vec![];
+        let mut last_block_hash = msg.last_block_hash;
+        while (blocks.len() as u64) < limit {
+            let Some(block) = self.get_next_light_client_block(&last_block_hash, &head)? else {
+                break;
+            };
+            last_block_hash = near_chain::light_client_block_hash(&block);
+            blocks.push(block);
         }
+        Ok(blocks)
     }
 }
 
@@ -1587,6 +2156,19 @@ impl Handler<WithSpanContext<GetSplitStorageInfo>> for ViewClientActor {
     }
 }
 
+/// Returns name of the query request kind as used in the `request_type` field
+/// of JSON RPC queries.
+fn query_request_kind(request: &QueryRequest) -> &'static str {
+    match request {
+        QueryRequest::ViewAccount { .. } => "view_account",
+        QueryRequest::ViewCode { .. } => "view_code",
+        QueryRequest::ViewState { .. } => "view_state",
+        QueryRequest::ViewAccessKey { .. } => "view_access_key",
+        QueryRequest::ViewAccessKeyList { .. } => "view_access_key_list",
+        QueryRequest::CallFunction { .. } => "call_function",
+    }
+}
+
 /// Starts the View Client in a new arbiter (thread).
 pub fn start_view_client(
     validator_account_id: Option<AccountId>,
@@ -42,6 +42,7 @@ mod proposals;
 mod reward_calculator;
 mod shard_assignment;
 pub mod shard_tracker;
+pub mod simulation;
 pub mod test_utils;
 #[cfg(test)]
 mod tests;
@@ -447,7 +448,7 @@ impl EpochManager {
     /// - A validator is kicked out if he produced too few blocks or chunks
     /// - If all validators are either previously kicked out or to be kicked out, we choose one not to
     /// kick out
-    fn compute_kickout_info(
+    pub(crate) fn compute_kickout_info(
         config: &EpochConfig,
         epoch_info: &EpochInfo,
         block_validator_tracker: &HashMap<ValidatorId, ValidatorStats>,
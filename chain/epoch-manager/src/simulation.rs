@@ -0,0 +1,315 @@
+//! Offline simulation of validator selection and rewards for future epochs.
+//!
+//! The simulation runs the same code as `EpochManager::finalize_epoch`, but
+//! instead of aggregating real blocks it assumes every validator produces a
+//! configured percentage of the blocks and chunks assigned to it. Randomness
+//! seeds are derived from the epoch height, so shard assignments are
+//! representative but won't match the real chain.
+
+use crate::proposals::proposals_to_epoch_info;
+use crate::reward_calculator::NUM_NS_IN_SECOND;
+use crate::{EpochManager, RewardCalculator};
+use near_chain_configs::GenesisConfig;
+use near_crypto::{KeyType, PublicKey};
+use near_primitives::epoch_manager::epoch_info::EpochInfo;
+use near_primitives::epoch_manager::EpochConfig;
+use near_primitives::errors::EpochError;
+use near_primitives::hash::{hash, CryptoHash};
+use near_primitives::types::validator_stake::ValidatorStake;
+use near_primitives::types::{
+    AccountId, Balance, ShardId, ValidatorId, ValidatorKickoutReason, ValidatorStats,
+};
+use near_primitives::version::ProtocolVersion;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+/// Validator sets of the two known epochs and stake proposals made so far.
+#[derive(Clone, Debug)]
+pub struct EpochSimulationState {
+    /// Epoch T whose production determines kickouts and rewards.
+    pub epoch_info: Arc<EpochInfo>,
+    /// Epoch T+1 which is already selected.
+    pub next_epoch_info: Arc<EpochInfo>,
+    /// Proposals made during epoch T.
+    pub proposals: BTreeMap<AccountId, ValidatorStake>,
+    pub total_supply: Balance,
+}
+
+impl EpochSimulationState {
+    /// Takes the state as of `last_block_hash`, including the proposals
+    /// included in the chain up to that block.
+    pub fn from_epoch_manager(
+        epoch_manager: &EpochManager,
+        last_block_hash: &CryptoHash,
+    ) -> Result<Self, EpochError> {
+        let block_info = epoch_manager.get_block_info(last_block_hash)?;
+        let epoch_info = epoch_manager.get_epoch_info(block_info.epoch_id())?;
+        let next_epoch_id = epoch_manager.get_next_epoch_id(last_block_hash)?;
+        let next_epoch_info = epoch_manager.get_epoch_info(&next_epoch_id)?;
+        let proposals =
+            epoch_manager.get_epoch_info_aggregator_upto_last(last_block_hash)?.all_proposals;
+        Ok(Self {
+            epoch_info,
+            next_epoch_info,
+            proposals,
+            total_supply: *block_info.total_supply(),
+        })
+    }
+
+    /// Adds a stake proposal of `account_id`, replacing its previous one.
+    /// Zero `stake` means unstaking.
+    pub fn propose(&mut self, account_id: AccountId, stake: Balance) {
+        // Keys don't affect validator selection, so accounts which never
+        // validated get a placeholder.
+        let public_key = match self.proposals.get(&account_id) {
+            Some(proposal) => proposal.public_key().clone(),
+            None => [&self.next_epoch_info, &self.epoch_info]
+                .iter()
+                .find_map(|epoch_info| epoch_info.get_validator_by_account(&account_id))
+                .map(|validator| validator.public_key().clone())
+                .unwrap_or_else(|| PublicKey::empty(KeyType::ED25519)),
+        };
+        self.proposals
+            .insert(account_id.clone(), ValidatorStake::new(account_id, public_key, stake));
+    }
+}
+
+pub struct EpochSimulator {
+    pub config: EpochConfig,
+    pub reward_calculator: RewardCalculator,
+    pub genesis_protocol_version: ProtocolVersion,
+    /// Protocol version of all simulated epochs.
+    pub protocol_version: ProtocolVersion,
+    /// Duration of an epoch in nanoseconds, used for the reward calculation.
+    pub epoch_duration: u64,
+    /// Percentage of assigned blocks and chunks produced by validators which
+    /// are not listed in `uptime`.
+    pub default_uptime: u8,
+    pub uptime: HashMap<AccountId, u8>,
+}
+
+impl EpochSimulator {
+    pub fn new(
+        config: EpochConfig,
+        reward_calculator: RewardCalculator,
+        genesis_protocol_version: ProtocolVersion,
+        protocol_version: ProtocolVersion,
+    ) -> Self {
+        // Expected duration if blocks are produced at the rate assumed by the
+        // inflation parameters.
+        let epoch_duration = reward_calculator.num_seconds_per_year * NUM_NS_IN_SECOND
+            / reward_calculator.num_blocks_per_year
+            * config.epoch_length;
+        Self {
+            config,
+            reward_calculator,
+            genesis_protocol_version,
+            protocol_version,
+            epoch_duration,
+            default_uptime: 100,
+            uptime: HashMap::new(),
+        }
+    }
+
+    /// Uses the staking parameters from genesis, with the epoch config of
+    /// `protocol_version`.
+    pub fn from_genesis_config(
+        genesis_config: &GenesisConfig,
+        protocol_version: ProtocolVersion,
+    ) -> Self {
+        let all_epoch_config =
+            EpochManager::new_all_epoch_config_with_test_overrides(genesis_config, None);
+        Self::new(
+            all_epoch_config.for_protocol_version(protocol_version),
+            RewardCalculator::new(genesis_config),
+            genesis_config.protocol_version,
+            protocol_version,
+        )
+    }
+
+    /// Selects the initial validator set from `validators` the same way it is
+    /// done at genesis.
+    pub fn genesis_state(
+        &self,
+        validators: Vec<ValidatorStake>,
+        total_supply: Balance,
+    ) -> Result<EpochSimulationState, EpochError> {
+        let validator_reward =
+            HashMap::from([(self.reward_calculator.protocol_treasury_account.clone(), 0u128)]);
+        let epoch_info = proposals_to_epoch_info(
+            &self.config,
+            [0; 32],
+            &EpochInfo::default(),
+            validators,
+            HashMap::default(),
+            validator_reward,
+            0,
+            self.protocol_version,
+            self.protocol_version,
+        )?;
+        // As on the real chain, the first two epochs share the same validators.
+        let epoch_info = Arc::new(epoch_info);
+        Ok(EpochSimulationState {
+            next_epoch_info: epoch_info.clone(),
+            epoch_info,
+            proposals: BTreeMap::new(),
+            total_supply,
+        })
+    }
+
+    /// Finalizes epoch T of `state` and returns the selected epoch T+2.
+    /// Afterwards `state` is advanced by one epoch, with no proposals.
+    pub fn simulate_epoch(
+        &self,
+        state: &mut EpochSimulationState,
+    ) -> Result<Arc<EpochInfo>, EpochError> {
+        let epoch_info = &state.epoch_info;
+        let next_epoch_info = &state.next_epoch_info;
+
+        let mut validator_kickout = HashMap::new();
+        for (account_id, proposal) in &state.proposals {
+            if proposal.stake() == 0
+                && *next_epoch_info.stake_change().get(account_id).unwrap_or(&0) != 0
+            {
+                validator_kickout.insert(account_id.clone(), ValidatorKickoutReason::Unstaked);
+            }
+        }
+        let (block_tracker, chunk_tracker) = self.production_stats(epoch_info);
+        let (kickout, validator_block_chunk_stats) = EpochManager::compute_kickout_info(
+            &self.config,
+            epoch_info,
+            &block_tracker,
+            &chunk_tracker,
+            &HashMap::new(),
+            next_epoch_info.validator_kickout(),
+        );
+        validator_kickout.extend(kickout);
+
+        let validator_stake =
+            epoch_info.validators_iter().map(|r| r.account_and_stake()).collect::<HashMap<_, _>>();
+        let (validator_reward, minted_amount) = self.reward_calculator.calculate_reward(
+            validator_block_chunk_stats,
+            &validator_stake,
+            state.total_supply,
+            epoch_info.protocol_version(),
+            self.genesis_protocol_version,
+            self.epoch_duration,
+        );
+
+        let rng_seed = hash(&next_epoch_info.epoch_height().to_le_bytes()).0;
+        let proposals = std::mem::take(&mut state.proposals).into_values().collect();
+        let next_next_epoch_info = match proposals_to_epoch_info(
+            &self.config,
+            rng_seed,
+            next_epoch_info,
+            proposals,
+            validator_kickout,
+            validator_reward,
+            minted_amount,
+            self.protocol_version,
+            epoch_info.protocol_version(),
+        ) {
+            Ok(next_next_epoch_info) => next_next_epoch_info,
+            // Same fallback as in `EpochManager::finalize_epoch`.
+            Err(EpochError::ThresholdError { .. } | EpochError::NotEnoughValidators { .. }) => {
+                let mut epoch_info = EpochInfo::clone(next_epoch_info);
+                *epoch_info.epoch_height_mut() += 1;
+                epoch_info
+            }
+            Err(err) => return Err(err),
+        };
+
+        let next_next_epoch_info = Arc::new(next_next_epoch_info);
+        state.total_supply += minted_amount;
+        state.epoch_info =
+            std::mem::replace(&mut state.next_epoch_info, next_next_epoch_info.clone());
+        Ok(next_next_epoch_info)
+    }
+
+    fn uptime(&self, account_id: &AccountId) -> u8 {
+        self.uptime.get(account_id).copied().unwrap_or(self.default_uptime)
+    }
+
+    /// Block and chunk production of the epoch, assuming no heights are skipped.
+    fn production_stats(
+        &self,
+        epoch_info: &EpochInfo,
+    ) -> (
+        HashMap<ValidatorId, ValidatorStats>,
+        HashMap<ShardId, HashMap<ValidatorId, ValidatorStats>>,
+    ) {
+        let mut block_tracker: HashMap<ValidatorId, ValidatorStats> = HashMap::new();
+        let mut chunk_tracker: HashMap<ShardId, HashMap<ValidatorId, ValidatorStats>> =
+            HashMap::new();
+        let num_shards = epoch_info.chunk_producers_settlement().len() as ShardId;
+        let start_height = epoch_info.epoch_height() * self.config.epoch_length;
+        for height in start_height..start_height + self.config.epoch_length {
+            let block_producer = EpochManager::block_producer_from_info(epoch_info, height);
+            block_tracker.entry(block_producer).or_default().expected += 1;
+            for shard_id in 0..num_shards {
+                let chunk_producer =
+                    EpochManager::chunk_producer_from_info(epoch_info, height, shard_id);
+                chunk_tracker
+                    .entry(shard_id)
+                    .or_default()
+                    .entry(chunk_producer)
+                    .or_default()
+                    .expected += 1;
+            }
+        }
+        for (validator_id, stats) in
+            block_tracker.iter_mut().chain(chunk_tracker.values_mut().flatten())
+        {
+            let uptime = self.uptime(epoch_info.validator_account_id(*validator_id));
+            stats.produced = stats.expected * u64::from(uptime) / 100;
+        }
+        (block_tracker, chunk_tracker)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EpochSimulator;
+    use crate::test_utils::{default_reward_calculator, epoch_config, stake};
+    use near_primitives::types::{AccountId, ValidatorKickoutReason};
+    use near_primitives::version::PROTOCOL_VERSION;
+
+    #[test]
+    fn test_simulate_unstake_and_offline_validator() {
+        let config = epoch_config(10, 1, 3, 0, 90, 60, 0).for_protocol_version(PROTOCOL_VERSION);
+        let accounts: Vec<AccountId> =
+            ["test1", "test2", "test3"].iter().map(|account| account.parse().unwrap()).collect();
+        let mut simulator = EpochSimulator::new(
+            config,
+            default_reward_calculator(),
+            PROTOCOL_VERSION,
+            PROTOCOL_VERSION,
+        );
+        simulator.uptime.insert(accounts[2].clone(), 0);
+        let validators = accounts.iter().map(|account| stake(account.clone(), 1_000_000)).collect();
+        let mut state = simulator.genesis_state(validators, 1_000_000_000).unwrap();
+        assert_eq!(state.epoch_info.validators_iter().len(), 3);
+
+        state.propose(accounts[1].clone(), 0);
+        let epoch_info = simulator.simulate_epoch(&mut state).unwrap();
+        assert_eq!(epoch_info.epoch_height(), 2);
+        let kickout = epoch_info.validator_kickout();
+        assert_eq!(kickout.get(&accounts[1]), Some(&ValidatorKickoutReason::Unstaked));
+        assert!(matches!(
+            kickout.get(&accounts[2]),
+            Some(
+                ValidatorKickoutReason::NotEnoughBlocks { produced: 0, .. }
+                    | ValidatorKickoutReason::NotEnoughChunks { produced: 0, .. }
+            )
+        ));
+        let validators: Vec<_> =
+            epoch_info.validators_iter().map(|validator| validator.take_account_id()).collect();
+        assert_eq!(validators, vec![accounts[0].clone()]);
+
+        // The state moved on by one epoch.
+        assert_eq!(state.epoch_info.epoch_height(), 1);
+        assert!(state.proposals.is_empty());
+        let epoch_info = simulator.simulate_epoch(&mut state).unwrap();
+        assert_eq!(epoch_info.epoch_height(), 3);
+    }
+}
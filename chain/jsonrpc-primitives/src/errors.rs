@@ -35,6 +35,9 @@ pub enum RpcErrorKind {
 pub enum RpcRequestValidationErrorKind {
     MethodNotFound { method_name: String },
     ParseError { error_message: String },
+    InvalidRequest { error_message: String },
+    BatchSizeExceeded { batch_size: usize, max_batch_size: usize },
+    RateLimitExceeded { method_name: String },
 }
 
 /// A general Server Error
@@ -98,6 +101,18 @@ impl RpcError {
         }
     }
 
+    /// Create an error for valid JSON which isn't a JSON RPC request.
+    pub fn invalid_request(e: String) -> Self {
+        RpcError {
+            code: -32_600,
+            message: "Invalid Request".to_owned(),
+            data: Some(Value::String(e.clone())),
+            error_struct: Some(RpcErrorKind::RequestValidationError(
+                RpcRequestValidationErrorKind::InvalidRequest { error_message: e },
+            )),
+        }
+    }
+
     pub fn serialization_error(e: String) -> Self {
         RpcError::new_internal_error(Some(Value::String(e.clone())), e)
     }
@@ -148,6 +163,33 @@ impl RpcError {
             )),
         }
     }
+
+    /// Create an error for a batch request with too many entries.
+    pub fn batch_size_exceeded(batch_size: usize, max_batch_size: usize) -> Self {
+        RpcError {
+            code: -32_600,
+            message: "Invalid Request".to_owned(),
+            data: Some(Value::String(format!(
+                "Batch of {} requests exceeds the limit of {}",
+                batch_size, max_batch_size
+            ))),
+            error_struct: Some(RpcErrorKind::RequestValidationError(
+                RpcRequestValidationErrorKind::BatchSizeExceeded { batch_size, max_batch_size },
+            )),
+        }
+    }
+
+    /// Create an error for a request rejected by the rate limiter.
+    pub fn rate_limit_exceeded(method: String) -> Self {
+        RpcError {
+            code: -32_005,
+            message: "Rate limit exceeded".to_owned(),
+            data: Some(Value::String(method.clone())),
+            error_struct: Some(RpcErrorKind::RequestValidationError(
+                RpcRequestValidationErrorKind::RateLimitExceeded { method_name: method },
+            )),
+        }
+    }
 }
 
 impl fmt::Display for RpcError {
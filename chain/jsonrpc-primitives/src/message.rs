@@ -142,6 +142,14 @@ pub struct Notification {
     pub params: Value,
 }
 
+impl Notification {
+    /// Turns the notification into a request with a null ID so that it can be
+    /// processed like one.  The result must not be sent back.
+    pub fn into_request(self) -> Request {
+        Request { jsonrpc: Version, method: self.method, params: self.params, id: Value::Null }
+    }
+}
+
 /// One message of the JSON RPC protocol.
 ///
 /// One message, directly mapped from the structures of the protocol. See the
@@ -0,0 +1,86 @@
+use near_primitives::types::{AccountId, BlockReference, StoreKey};
+use near_primitives::views::ContractStorageReportView;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Aggregates storage used by contract data of an account by key prefix, as of
+/// the state after the given block.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcContractStorageReportRequest {
+    #[serde(flatten)]
+    pub block_reference: BlockReference,
+    pub account_id: AccountId,
+    /// Base64 encoded key prefixes. Keys are grouped by their first byte if
+    /// no prefixes are given.
+    #[serde(default)]
+    pub prefixes: Vec<StoreKey>,
+    /// Number of largest keys to report.
+    #[serde(default = "default_top")]
+    pub top: usize,
+    /// Base64 encoded key to start from, `next_key` of the previous report.
+    #[serde(default)]
+    pub start_key: Option<StoreKey>,
+    /// Maximum number of keys to scan. The node caps it at 100000. If there
+    /// are more keys, the report has `next_key` set.
+    #[serde(default)]
+    pub max_keys: Option<usize>,
+}
+
+fn default_top() -> usize {
+    10
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcContractStorageReportResponse {
+    #[serde(flatten)]
+    pub report: ContractStorageReportView,
+}
+
+#[derive(thiserror::Error, Debug, Serialize, Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcContractStorageReportError {
+    #[error("Block either has never been observed on the node or has been garbage collected: {block_reference:?}")]
+    UnknownBlock { block_reference: BlockReference },
+    #[error(
+        "The data for block #{block_height} is garbage collected on this node, use an archival node to fetch historical data"
+    )]
+    GarbageCollectedBlock {
+        block_height: near_primitives::types::BlockHeight,
+        block_hash: near_primitives::hash::CryptoHash,
+    },
+    #[error("The node does not track the shard ID {requested_shard_id}")]
+    UnavailableShard { requested_shard_id: near_primitives::types::ShardId },
+    #[error("Flat storage of the node doesn't contain state for block #{block_height}")]
+    UnavailableState {
+        block_height: near_primitives::types::BlockHeight,
+        block_hash: near_primitives::hash::CryptoHash,
+    },
+    #[error(
+        "Account {requested_account_id} does not exist while viewing at block #{block_height}"
+    )]
+    UnknownAccount {
+        requested_account_id: AccountId,
+        block_height: near_primitives::types::BlockHeight,
+        block_hash: near_primitives::hash::CryptoHash,
+    },
+    #[error("Request can include at most {limit} prefixes")]
+    TooManyPrefixes { limit: usize },
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<RpcContractStorageReportError> for crate::errors::RpcError {
+    fn from(error: RpcContractStorageReportError) -> Self {
+        let error_data = Some(Value::String(error.to_string()));
+        let error_data_value = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcContractStorageReportError: {:?}", err),
+                )
+            }
+        };
+        Self::new_internal_or_handler_error(error_data, error_data_value)
+    }
+}
@@ -13,6 +13,14 @@ pub struct RpcLightClientNextBlockRequest {
     pub last_block_hash: near_primitives::hash::CryptoHash,
 }
 
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct RpcLightClientBlocksRequest {
+    pub last_block_hash: near_primitives::hash::CryptoHash,
+    /// Maximal number of blocks to return. The node caps it at 100.
+    #[serde(default)]
+    pub limit: Option<u64>,
+}
+
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct RpcLightClientExecutionProofResponse {
     pub outcome_proof: near_primitives::views::ExecutionOutcomeWithIdView,
@@ -27,6 +35,14 @@ pub struct RpcLightClientNextBlockResponse {
     pub light_client_block: Option<Arc<near_primitives::views::LightClientBlockView>>,
 }
 
+/// Light client blocks following the requested one, in order. Each block can be verified
+/// with the block producers given by the previous one, see `near_chain::LightClientState`.
+/// Empty if the light client is already up to date.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct RpcLightClientBlocksResponse {
+    pub light_client_blocks: Vec<Arc<near_primitives::views::LightClientBlockView>>,
+}
+
 #[derive(thiserror::Error, Debug, serde::Serialize, serde::Deserialize)]
 #[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum RpcLightClientProofError {
@@ -3,6 +3,7 @@ pub mod changes;
 pub mod chunks;
 pub mod client_config;
 pub mod config;
+pub mod contract_storage;
 pub mod entity_debug;
 pub mod gas_price;
 pub mod light_client;
@@ -11,7 +12,10 @@ pub mod network_info;
 pub mod query;
 pub mod receipts;
 pub mod sandbox;
+pub mod simulate_transaction;
 pub mod split_storage;
 pub mod status;
+pub mod subscriptions;
+pub mod transaction_pool;
 pub mod transactions;
 pub mod validator;
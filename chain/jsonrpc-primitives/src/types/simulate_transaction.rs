@@ -0,0 +1,67 @@
+use near_primitives::transaction::SignedTransaction;
+use near_primitives::types::BlockReference;
+use near_primitives::views::TransactionSimulationView;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Executes a transaction and all the receipts it produces on top of the state after the given
+/// block. Nothing is committed and the transaction is not broadcast.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcSimulateTransactionRequest {
+    #[serde(rename = "signed_tx_base64")]
+    pub signed_transaction: SignedTransaction,
+    #[serde(flatten)]
+    pub block_reference: BlockReference,
+    /// Allows simulating transactions for which the caller doesn't have the
+    /// signer's private key.
+    #[serde(default = "default_verify_signature")]
+    pub verify_signature: bool,
+}
+
+fn default_verify_signature() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcSimulateTransactionResponse {
+    #[serde(flatten)]
+    pub result: TransactionSimulationView,
+}
+
+#[derive(thiserror::Error, Debug, Serialize, Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcSimulateTransactionError {
+    #[error("Block either has never been observed on the node or has been garbage collected: {block_reference:?}")]
+    UnknownBlock { block_reference: BlockReference },
+    #[error(
+        "The data for block #{block_height} is garbage collected on this node, use an archival node to fetch historical data"
+    )]
+    GarbageCollectedBlock {
+        block_height: near_primitives::types::BlockHeight,
+        block_hash: near_primitives::hash::CryptoHash,
+    },
+    #[error("The node does not track the shard ID {requested_shard_id}")]
+    UnavailableShard { requested_shard_id: near_primitives::types::ShardId },
+    #[error("Transaction is invalid: {error}")]
+    InvalidTransaction { error: near_primitives::errors::InvalidTxError },
+    #[error("Transaction produced more than {limit} receipts")]
+    TooManyReceipts { limit: usize },
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<RpcSimulateTransactionError> for crate::errors::RpcError {
+    fn from(error: RpcSimulateTransactionError) -> Self {
+        let error_data = Some(Value::String(error.to_string()));
+        let error_data_value = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcSimulateTransactionError: {:?}", err),
+                )
+            }
+        };
+        Self::new_internal_or_handler_error(error_data, error_data_value)
+    }
+}
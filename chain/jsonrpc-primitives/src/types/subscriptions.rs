@@ -0,0 +1,78 @@
+use serde_json::Value;
+
+/// Identifier of a subscription, unique within a single WebSocket connection.
+pub type SubscriptionId = u64;
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct RpcSubscribeBlocksRequest {
+    #[serde(default)]
+    pub finality: near_primitives::types::Finality,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct RpcSubscribeChunksRequest {
+    #[serde(default)]
+    pub finality: near_primitives::types::Finality,
+    /// If not provided, chunks of all shards are pushed.
+    #[serde(default)]
+    pub shard_ids: Option<Vec<near_primitives::types::ShardId>>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct RpcSubscribeAccountChangesRequest {
+    #[serde(default)]
+    pub finality: near_primitives::types::Finality,
+    pub account_ids: Vec<near_primitives::types::AccountId>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct RpcSubscribeResponse {
+    pub subscription: SubscriptionId,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct RpcUnsubscribeRequest {
+    pub subscription: SubscriptionId,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct RpcUnsubscribeResponse {
+    pub unsubscribed: bool,
+}
+
+/// Params of the `subscription` notification pushed to the client.
+///
+/// `result` has the same shape as the response of the corresponding polling
+/// method, i.e. `block`, `chunk`, `EXPERIMENTAL_changes` or `tx`.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct RpcSubscriptionNotification {
+    pub subscription: SubscriptionId,
+    pub result: Value,
+}
+
+#[derive(thiserror::Error, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcSubscriptionError {
+    #[error("Subscription methods are only available over a WebSocket connection")]
+    WebSocketRequired,
+    #[error("Too many subscriptions on a single connection, the limit is {limit}")]
+    TooManySubscriptions { limit: usize },
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<RpcSubscriptionError> for crate::errors::RpcError {
+    fn from(error: RpcSubscriptionError) -> Self {
+        let error_data = Some(Value::String(error.to_string()));
+        let error_data_value = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcSubscriptionError: {:?}", err),
+                )
+            }
+        };
+        Self::new_internal_or_handler_error(error_data, error_data_value)
+    }
+}
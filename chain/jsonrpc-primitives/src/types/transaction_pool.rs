@@ -0,0 +1,57 @@
+use near_primitives::types::AccountId;
+use near_primitives::views::{ShardPendingTransactionsView, TransactionPoolShardStatsView};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Requests the transactions in the transaction pool signed by the given account and/or with the
+/// given access key. At least one of the filters has to be specified.
+///
+/// Only validators keep transactions in their pool, other nodes forward them to the chunk
+/// producers, so on non-validator RPC nodes the result is always empty.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcPendingTransactionsRequest {
+    pub signer_id: Option<AccountId>,
+    pub public_key: Option<near_crypto::PublicKey>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcPendingTransactionsResponse {
+    pub shards: Vec<ShardPendingTransactionsView>,
+}
+
+/// Requests the number and size of the transactions in the transaction pool of each shard. Like
+/// the pending transactions, the result is always empty on non-validator RPC nodes.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcTransactionPoolStatsRequest {}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcTransactionPoolStatsResponse {
+    pub shards: Vec<TransactionPoolShardStatsView>,
+}
+
+#[derive(thiserror::Error, Debug, Serialize, Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcTransactionPoolError {
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<RpcTransactionPoolError> for crate::errors::RpcError {
+    fn from(error: RpcTransactionPoolError) -> Self {
+        let error_data = match &error {
+            RpcTransactionPoolError::InternalError { .. } => Some(Value::String(error.to_string())),
+        };
+
+        let error_data_value = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcTransactionPoolError: {:?}", err),
+                )
+            }
+        };
+
+        Self::new_internal_or_handler_error(error_data, error_data_value)
+    }
+}
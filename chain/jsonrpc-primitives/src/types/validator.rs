@@ -31,6 +31,24 @@ pub struct RpcValidatorResponse {
     pub validator_info: near_primitives::views::EpochValidatorInfo,
 }
 
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct RpcValidatorPerformanceRequest {
+    pub account_id: near_primitives::types::AccountId,
+    /// Epoch to report. Defaults to the current epoch.
+    #[serde(default)]
+    pub epoch_id: Option<near_primitives::types::EpochId>,
+    /// First height to report. Defaults to the start of the epoch. Use
+    /// `next_start_height` of the previous response to continue a report.
+    #[serde(default)]
+    pub start_height: Option<near_primitives::types::BlockHeight>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct RpcValidatorPerformanceResponse {
+    #[serde(flatten)]
+    pub performance: near_primitives::views::ValidatorPerformanceView,
+}
+
 impl From<RpcValidatorError> for crate::errors::RpcError {
     fn from(error: RpcValidatorError) -> Self {
         let error_data = match &error {
@@ -8,7 +8,9 @@ use near_jsonrpc_primitives::types::changes::{
 use near_jsonrpc_primitives::types::transactions::{
     RpcTransactionResponse, RpcTransactionStatusRequest,
 };
-use near_jsonrpc_primitives::types::validator::RpcValidatorsOrderedRequest;
+use near_jsonrpc_primitives::types::validator::{
+    RpcValidatorPerformanceRequest, RpcValidatorPerformanceResponse, RpcValidatorsOrderedRequest,
+};
 use near_primitives::hash::CryptoHash;
 use near_primitives::types::{BlockId, BlockReference, EpochReference, MaybeBlockId, ShardId};
 use near_primitives::views::validator_stake_view::ValidatorStakeView;
@@ -236,6 +238,14 @@ impl JsonRpcClient {
         call_method(&self.client, &self.server_addr, "EXPERIMENTAL_validators_ordered", request)
     }
 
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_validator_performance(
+        &self,
+        request: RpcValidatorPerformanceRequest,
+    ) -> RpcRequest<RpcValidatorPerformanceResponse> {
+        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_validator_performance", request)
+    }
+
     #[allow(non_snake_case)]
     pub fn EXPERIMENTAL_receipt(
         &self,
@@ -261,6 +271,62 @@ impl JsonRpcClient {
         call_method(&self.client, &self.server_addr, "EXPERIMENTAL_split_storage_info", request)
     }
 
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_pending_transactions(
+        &self,
+        request: near_jsonrpc_primitives::types::transaction_pool::RpcPendingTransactionsRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::transaction_pool::RpcPendingTransactionsResponse>
+    {
+        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_pending_transactions", request)
+    }
+
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_tx_pool_stats(
+        &self,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::transaction_pool::RpcTransactionPoolStatsResponse>
+    {
+        call_method(
+            &self.client,
+            &self.server_addr,
+            "EXPERIMENTAL_tx_pool_stats",
+            near_jsonrpc_primitives::types::transaction_pool::RpcTransactionPoolStatsRequest {},
+        )
+    }
+
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_light_client_blocks(
+        &self,
+        request: near_jsonrpc_primitives::types::light_client::RpcLightClientBlocksRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::light_client::RpcLightClientBlocksResponse>
+    {
+        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_light_client_blocks", request)
+    }
+
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_simulate_transaction(
+        &self,
+        request: near_jsonrpc_primitives::types::simulate_transaction::RpcSimulateTransactionRequest,
+    ) -> RpcRequest<
+        near_jsonrpc_primitives::types::simulate_transaction::RpcSimulateTransactionResponse,
+    > {
+        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_simulate_transaction", request)
+    }
+
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_contract_storage_report(
+        &self,
+        request: near_jsonrpc_primitives::types::contract_storage::RpcContractStorageReportRequest,
+    ) -> RpcRequest<
+        near_jsonrpc_primitives::types::contract_storage::RpcContractStorageReportResponse,
+    > {
+        call_method(
+            &self.client,
+            &self.server_addr,
+            "EXPERIMENTAL_contract_storage_report",
+            request,
+        )
+    }
+
     pub fn validators(
         &self,
         epoch_id_or_block_id: Option<EpochReference>,
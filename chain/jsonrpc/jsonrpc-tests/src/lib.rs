@@ -46,7 +46,7 @@ pub fn start_all_with_validity_period_and_no_epoch_sync(
 
     let addr = tcp::ListenerAddr::reserve_for_test();
     start_http(
-        RpcConfig::new(addr),
+        RpcConfig { enable_debug_rpc: true, ..RpcConfig::new(addr) },
         TEST_GENESIS_CONFIG.clone(),
         actor_handles.client_actor,
         actor_handles.view_client_actor.clone(),
@@ -6,14 +6,22 @@ use futures::{future, FutureExt};
 use serde_json::json;
 
 use near_actix_test_utils::run_actix;
-use near_crypto::{KeyType, PublicKey, Signature};
+use near_crypto::{InMemorySigner, KeyType, PublicKey, Signature};
 use near_jsonrpc::client::{new_client, ChunkId};
+use near_jsonrpc_primitives::errors::RpcErrorKind;
+use near_jsonrpc_primitives::types::contract_storage::RpcContractStorageReportRequest;
+use near_jsonrpc_primitives::types::light_client::RpcLightClientBlocksRequest;
 use near_jsonrpc_primitives::types::query::QueryResponseKind;
-use near_jsonrpc_primitives::types::validator::RpcValidatorsOrderedRequest;
+use near_jsonrpc_primitives::types::simulate_transaction::RpcSimulateTransactionRequest;
+use near_jsonrpc_primitives::types::transaction_pool::RpcPendingTransactionsRequest;
+use near_jsonrpc_primitives::types::validator::{
+    RpcValidatorPerformanceRequest, RpcValidatorsOrderedRequest,
+};
 use near_network::test_utils::wait_or_timeout;
 use near_o11y::testonly::init_test_logger;
 use near_primitives::account::{AccessKey, AccessKeyPermission};
 use near_primitives::hash::CryptoHash;
+use near_primitives::transaction::SignedTransaction;
 use near_primitives::types::{BlockId, BlockReference, EpochId, SyncCheckpoint};
 use near_primitives::views::QueryRequest;
 
@@ -459,6 +467,206 @@ fn test_validators_ordered() {
     });
 }
 
+#[test]
+fn test_validator_performance() {
+    test_with_client!(test_utils::NodeType::Validator, client, async move {
+        let request = |account_id: &str, epoch_id, start_height| RpcValidatorPerformanceRequest {
+            account_id: account_id.parse().unwrap(),
+            epoch_id,
+            start_height,
+        };
+
+        let performance =
+            client.EXPERIMENTAL_validator_performance(request("test1", None, None)).await.unwrap();
+        let performance = performance.performance;
+        assert_eq!(performance.account_id.as_str(), "test1");
+        let epoch = &performance.epoch;
+        assert_eq!(epoch.next_start_height, None);
+        assert_eq!(epoch.num_expected_blocks, epoch.blocks.len() as u64);
+        assert_eq!(epoch.num_expected_chunks, epoch.chunks.len() as u64);
+        let kickout_risk = performance.kickout_risk.unwrap();
+        assert_eq!(kickout_risk.epoch_id, epoch.epoch_id);
+        assert!(!kickout_risk.kickout_unavoidable);
+
+        let start_height = epoch.end_height + 1;
+        let performance = client
+            .EXPERIMENTAL_validator_performance(request(
+                "test1",
+                Some(epoch.epoch_id.clone()),
+                Some(start_height),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(performance.performance.epoch.start_height, start_height);
+
+        let performance = client
+            .EXPERIMENTAL_validator_performance(request("unknown", None, None))
+            .await
+            .unwrap();
+        let performance = performance.performance;
+        assert!(performance.epoch.blocks.is_empty());
+        assert!(performance.kickout_risk.is_none());
+
+        let error = client
+            .EXPERIMENTAL_validator_performance(request(
+                "test1",
+                Some(EpochId(CryptoHash::hash_bytes(b"unknown"))),
+                None,
+            ))
+            .await
+            .unwrap_err();
+        match error.error_struct {
+            Some(RpcErrorKind::HandlerError(error)) => {
+                assert_eq!(error["name"], "UNKNOWN_EPOCH");
+            }
+            error => panic!("unexpected error: {:?}", error),
+        }
+    });
+}
+
+#[test]
+fn test_tx_pool_stats_empty() {
+    test_with_client!(test_utils::NodeType::Validator, client, async move {
+        let stats = client.EXPERIMENTAL_tx_pool_stats().await.unwrap();
+        assert!(stats.shards.is_empty());
+        let pending = client
+            .EXPERIMENTAL_pending_transactions(RpcPendingTransactionsRequest {
+                signer_id: Some("test1".parse().unwrap()),
+                public_key: None,
+            })
+            .await
+            .unwrap();
+        assert!(pending.shards.is_empty());
+    });
+}
+
+#[test]
+fn test_pending_transactions_requires_filter() {
+    test_with_client!(test_utils::NodeType::Validator, client, async move {
+        let result = client
+            .EXPERIMENTAL_pending_transactions(RpcPendingTransactionsRequest {
+                signer_id: None,
+                public_key: None,
+            })
+            .await;
+        assert!(result.is_err());
+    });
+}
+
+#[test]
+fn test_simulate_transaction_unknown_block() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let signer = InMemorySigner::from_seed("test1".parse().unwrap(), KeyType::ED25519, "test1");
+        let transaction = SignedTransaction::send_money(
+            1,
+            "test1".parse().unwrap(),
+            "test2".parse().unwrap(),
+            &signer,
+            100,
+            CryptoHash::default(),
+        );
+        let error = client
+            .EXPERIMENTAL_simulate_transaction(RpcSimulateTransactionRequest {
+                signed_transaction: transaction,
+                block_reference: BlockReference::BlockId(BlockId::Hash(CryptoHash::hash_bytes(
+                    b"unknown",
+                ))),
+                verify_signature: true,
+            })
+            .await
+            .unwrap_err();
+        match error.error_struct {
+            Some(RpcErrorKind::HandlerError(error)) => {
+                assert_eq!(error["name"], "UNKNOWN_BLOCK");
+            }
+            error => panic!("unexpected error: {:?}", error),
+        }
+    });
+}
+
+#[test]
+fn test_light_client_blocks() {
+    test_with_client!(test_utils::NodeType::Validator, client, async move {
+        let genesis = client.block_by_id(BlockId::Height(0)).await.unwrap();
+        wait_or_timeout(100, 10000, || async {
+            let blocks = client
+                .EXPERIMENTAL_light_client_blocks(RpcLightClientBlocksRequest {
+                    last_block_hash: genesis.header.hash,
+                    limit: None,
+                })
+                .await
+                .unwrap()
+                .light_client_blocks;
+            if blocks.is_empty() {
+                return ControlFlow::Continue(());
+            }
+            let heights: Vec<_> = blocks.iter().map(|b| b.inner_lite.height).collect();
+            assert!(heights[0] > 0);
+            assert!(heights.windows(2).all(|w| w[0] < w[1]), "{:?}", heights);
+            ControlFlow::Break(())
+        })
+        .await
+        .unwrap();
+
+        let error = client
+            .EXPERIMENTAL_light_client_blocks(RpcLightClientBlocksRequest {
+                last_block_hash: CryptoHash::hash_bytes(b"unknown"),
+                limit: Some(1),
+            })
+            .await
+            .unwrap_err();
+        match error.error_struct {
+            Some(RpcErrorKind::HandlerError(error)) => {
+                assert_eq!(error["name"], "UNKNOWN_BLOCK");
+            }
+            error => panic!("unexpected error: {:?}", error),
+        }
+    });
+}
+
+#[test]
+fn test_contract_storage_report() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let request = |account_id: &str, block_reference| RpcContractStorageReportRequest {
+            block_reference,
+            account_id: account_id.parse().unwrap(),
+            prefixes: vec![b"m".to_vec().into()],
+            top: 10,
+            start_key: None,
+            max_keys: None,
+        };
+        let genesis = BlockReference::BlockId(BlockId::Height(0));
+
+        let report =
+            client.EXPERIMENTAL_contract_storage_report(request("test1", genesis.clone())).await;
+        let report = report.unwrap().report;
+        assert_eq!(report.block_height, 0);
+        assert_eq!(report.total_keys, 0);
+        assert_eq!(report.prefixes.len(), 1);
+        assert!(report.largest_keys.is_empty());
+        assert_eq!(report.next_key, None);
+
+        for (request, name) in [
+            (request("unknown", genesis), "UNKNOWN_ACCOUNT"),
+            (
+                request(
+                    "test1",
+                    BlockReference::BlockId(BlockId::Hash(CryptoHash::hash_bytes(b"unknown"))),
+                ),
+                "UNKNOWN_BLOCK",
+            ),
+        ] {
+            let error = client.EXPERIMENTAL_contract_storage_report(request).await.unwrap_err();
+            match error.error_struct {
+                Some(RpcErrorKind::HandlerError(error)) => {
+                    assert_eq!(error["name"], name);
+                }
+                error => panic!("unexpected error: {:?}", error),
+            }
+        }
+    });
+}
+
 /// Retrieve genesis config via JSON RPC.
 /// WARNING: Be mindful about changing genesis structure as it is part of the public protocol!
 #[test]
@@ -582,3 +790,95 @@ fn test_get_chunk_with_object_in_params() {
         assert_eq!(chunk.header.chunk_hash, same_chunk.header.chunk_hash);
     });
 }
+
+/// Batch requests are answered with a batch of responses in the same order.
+#[test]
+fn test_batch_request() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let batch = json!([
+            {"jsonrpc": "2.0", "id": 1, "method": "block", "params": {"block_id": 0}},
+            {"jsonrpc": "2.0", "id": 2, "method": "no_such_method", "params": []},
+        ]);
+        let response: serde_json::Value = client
+            .client
+            .post(&client.server_addr)
+            .insert_header(("Content-Type", "application/json"))
+            .send_json(&batch)
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+
+        let responses = response.as_array().unwrap();
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0]["id"], json!(1));
+        assert_eq!(responses[0]["result"]["header"]["height"], json!(0));
+        assert_eq!(responses[1]["id"], json!(2));
+        assert_eq!(responses[1]["error"]["cause"]["name"], json!("METHOD_NOT_FOUND"));
+    });
+}
+
+/// Notifications in a batch are not answered and malformed entries are
+/// answered with an Invalid Request error.
+#[test]
+fn test_batch_request_notifications() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let batch = json!([
+            {"jsonrpc": "2.0", "method": "status", "params": []},
+            {"jsonrpc": "2.0", "id": 1, "method": "block", "params": {"block_id": 0}},
+            1,
+        ]);
+        let response: serde_json::Value = client
+            .client
+            .post(&client.server_addr)
+            .insert_header(("Content-Type", "application/json"))
+            .send_json(&batch)
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+
+        let responses = response.as_array().unwrap();
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0]["id"], json!(1));
+        assert_eq!(responses[0]["result"]["header"]["height"], json!(0));
+        assert_eq!(responses[1]["id"], json!(null));
+        assert_eq!(responses[1]["error"]["code"], json!(-32600));
+        assert_eq!(responses[1]["error"]["cause"]["name"], json!("INVALID_REQUEST"));
+
+        // A batch of notifications only gets no response at all.
+        let batch = json!([{"jsonrpc": "2.0", "method": "status", "params": []}]);
+        let response = client
+            .client
+            .post(&client.server_addr)
+            .insert_header(("Content-Type", "application/json"))
+            .send_json(&batch)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), awc::http::StatusCode::NO_CONTENT);
+    });
+}
+
+/// Batches larger than the configured limit are rejected as a whole.
+#[test]
+fn test_batch_request_too_large() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let request = json!({"jsonrpc": "2.0", "id": 1, "method": "status", "params": []});
+        let batch = serde_json::Value::Array(vec![request; 101]);
+        let response: serde_json::Value = client
+            .client
+            .post(&client.server_addr)
+            .insert_header(("Content-Type", "application/json"))
+            .send_json(&batch)
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+
+        assert_eq!(response["error"]["name"], json!("REQUEST_VALIDATION_ERROR"));
+        assert_eq!(response["error"]["cause"]["name"], json!("BATCH_SIZE_EXCEEDED"));
+    });
+}
@@ -0,0 +1,162 @@
+use actix::System;
+use awc::ws;
+use futures::{SinkExt, Stream, StreamExt};
+use serde_json::json;
+
+use near_actix_test_utils::run_actix;
+use near_jsonrpc::client::new_client;
+use near_jsonrpc_primitives::errors::RpcErrorKind;
+use near_jsonrpc_primitives::message::{from_slice, Message};
+use near_o11y::testonly::init_test_logger;
+
+use near_jsonrpc_tests::{self as test_utils, test_with_client};
+
+/// Waits for the next JSON RPC message on the WebSocket connection.
+async fn next_message(
+    connection: &mut (impl Stream<Item = Result<ws::Frame, ws::ProtocolError>> + Unpin),
+) -> Message {
+    loop {
+        match connection.next().await.unwrap().unwrap() {
+            ws::Frame::Text(data) => return from_slice(&data).unwrap(),
+            ws::Frame::Ping(_) | ws::Frame::Pong(_) => continue,
+            frame => panic!("unexpected WebSocket frame: {:?}", frame),
+        }
+    }
+}
+
+/// Subscribes to new blocks and checks that they are pushed in increasing
+/// height order, each one building on the previous one.
+#[test]
+fn test_subscribe_blocks() {
+    init_test_logger();
+
+    run_actix(async {
+        let (_, addr) = test_utils::start_all(test_utils::NodeType::Validator);
+
+        actix::spawn(async move {
+            let (_, mut connection) =
+                awc::Client::new().ws(format!("ws://{}/ws", addr)).connect().await.unwrap();
+            let request = json!({
+                "jsonrpc": "2.0",
+                "id": "dontcare",
+                "method": "subscribe_blocks",
+                "params": {"finality": "optimistic"},
+            });
+            connection.send(ws::Message::Text(request.to_string().into())).await.unwrap();
+
+            let subscription = match next_message(&mut connection).await {
+                Message::Response(response) => response.result.unwrap()["subscription"].clone(),
+                message => panic!("expected a response, got {:?}", message),
+            };
+
+            let mut last_height = None;
+            let mut last_hash = None;
+            for _ in 0..3 {
+                match next_message(&mut connection).await {
+                    Message::Notification(notification) => {
+                        assert_eq!(notification.method, "subscription");
+                        assert_eq!(notification.params["subscription"], subscription);
+                        let header = &notification.params["result"]["header"];
+                        let height = header["height"].as_u64().unwrap();
+                        assert!(last_height < Some(height));
+                        if let Some(last_hash) = last_hash.take() {
+                            assert_eq!(header["prev_hash"], last_hash);
+                        }
+                        last_height = Some(height);
+                        last_hash = Some(header["hash"].clone());
+                    }
+                    message => panic!("expected a notification, got {:?}", message),
+                }
+            }
+            System::current().stop();
+        });
+    });
+}
+
+/// Regular JSON RPC requests are served over the WebSocket as well.
+#[test]
+fn test_websocket_regular_request() {
+    init_test_logger();
+
+    run_actix(async {
+        let (_, addr) = test_utils::start_all(test_utils::NodeType::NonValidator);
+
+        actix::spawn(async move {
+            let (_, mut connection) =
+                awc::Client::new().ws(format!("ws://{}/ws", addr)).connect().await.unwrap();
+            let request = json!({
+                "jsonrpc": "2.0",
+                "id": "dontcare",
+                "method": "block",
+                "params": {"block_id": 0},
+            });
+            connection.send(ws::Message::Text(request.to_string().into())).await.unwrap();
+
+            match next_message(&mut connection).await {
+                Message::Response(response) => {
+                    assert_eq!(response.id, json!("dontcare"));
+                    assert_eq!(response.result.unwrap()["header"]["height"], json!(0));
+                }
+                message => panic!("expected a response, got {:?}", message),
+            }
+            System::current().stop();
+        });
+    });
+}
+
+/// A message split into continuation frames is rejected once it exceeds
+/// `json_payload_max_size`, even if it is sent as a first and a last frame
+/// which are both within the limit.
+#[test]
+fn test_websocket_continuation_size_limit() {
+    init_test_logger();
+
+    run_actix(async {
+        let (_, addr) = test_utils::start_all(test_utils::NodeType::NonValidator);
+
+        actix::spawn(async move {
+            let (_, mut connection) =
+                awc::Client::new().ws(format!("ws://{}/ws", addr)).connect().await.unwrap();
+            let max_size = near_jsonrpc::RpcLimitsConfig::default().json_payload_max_size;
+            let half = vec![b' '; max_size / 2 + 1];
+            connection
+                .send(ws::Message::Continuation(ws::Item::FirstText(half.clone().into())))
+                .await
+                .unwrap();
+            connection.send(ws::Message::Continuation(ws::Item::Last(half.into()))).await.unwrap();
+
+            loop {
+                match connection.next().await.unwrap().unwrap() {
+                    ws::Frame::Close(reason) => {
+                        assert_eq!(reason.unwrap().code, ws::CloseCode::Size);
+                        break;
+                    }
+                    ws::Frame::Ping(_) | ws::Frame::Pong(_) => continue,
+                    frame => panic!("unexpected WebSocket frame: {:?}", frame),
+                }
+            }
+            System::current().stop();
+        });
+    });
+}
+
+/// Subscription methods are rejected over plain HTTP.
+#[test]
+fn test_subscribe_over_http() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let error = test_utils::call_method::<serde_json::Value>(
+            &client.client,
+            &client.server_addr,
+            "subscribe_blocks",
+            json!({"finality": "final"}),
+        )
+        .await
+        .unwrap_err();
+        match error.error_struct {
+            Some(RpcErrorKind::HandlerError(error)) => {
+                assert_eq!(error["name"], "WEB_SOCKET_REQUIRED");
+            }
+            error => panic!("unexpected error: {:?}", error),
+        }
+    });
+}
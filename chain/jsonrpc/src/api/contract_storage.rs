@@ -0,0 +1,57 @@
+use near_client_primitives::types::GetContractStorageReportError;
+use near_jsonrpc_primitives::errors::RpcParseError;
+use near_jsonrpc_primitives::types::contract_storage::{
+    RpcContractStorageReportError, RpcContractStorageReportRequest,
+};
+use serde_json::Value;
+
+use super::{Params, RpcFrom, RpcRequest};
+
+impl RpcRequest for RpcContractStorageReportRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcFrom<actix::MailboxError> for RpcContractStorageReportError {
+    fn rpc_from(error: actix::MailboxError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<GetContractStorageReportError> for RpcContractStorageReportError {
+    fn rpc_from(error: GetContractStorageReportError) -> Self {
+        match error {
+            GetContractStorageReportError::UnknownBlock { block_reference } => {
+                Self::UnknownBlock { block_reference }
+            }
+            GetContractStorageReportError::GarbageCollectedBlock { block_height, block_hash } => {
+                Self::GarbageCollectedBlock { block_height, block_hash }
+            }
+            GetContractStorageReportError::UnavailableShard { requested_shard_id } => {
+                Self::UnavailableShard { requested_shard_id }
+            }
+            GetContractStorageReportError::UnavailableState { block_height, block_hash } => {
+                Self::UnavailableState { block_height, block_hash }
+            }
+            GetContractStorageReportError::UnknownAccount {
+                requested_account_id,
+                block_height,
+                block_hash,
+            } => Self::UnknownAccount { requested_account_id, block_height, block_hash },
+            GetContractStorageReportError::TooManyPrefixes { limit } => {
+                Self::TooManyPrefixes { limit }
+            }
+            GetContractStorageReportError::InternalError { error_message } => {
+                Self::InternalError { error_message }
+            }
+            GetContractStorageReportError::Unreachable { ref error_message } => {
+                tracing::warn!(target: "jsonrpc", "Unreachable error occurred: {}", error_message);
+                crate::metrics::RPC_UNREACHABLE_ERROR_COUNT
+                    .with_label_values(&["GetContractStorageReportError"])
+                    .inc();
+                Self::InternalError { error_message: error.to_string() }
+            }
+        }
+    }
+}
@@ -7,8 +7,9 @@ use near_client_primitives::types::{
 };
 use near_jsonrpc_primitives::errors::RpcParseError;
 use near_jsonrpc_primitives::types::light_client::{
-    RpcLightClientExecutionProofRequest, RpcLightClientNextBlockError,
-    RpcLightClientNextBlockRequest, RpcLightClientNextBlockResponse, RpcLightClientProofError,
+    RpcLightClientBlocksRequest, RpcLightClientBlocksResponse, RpcLightClientExecutionProofRequest,
+    RpcLightClientNextBlockError, RpcLightClientNextBlockRequest, RpcLightClientNextBlockResponse,
+    RpcLightClientProofError,
 };
 use near_primitives::views::LightClientBlockView;
 
@@ -28,6 +29,18 @@ impl RpcRequest for RpcLightClientNextBlockRequest {
     }
 }
 
+impl RpcRequest for RpcLightClientBlocksRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcFrom<Vec<Arc<LightClientBlockView>>> for RpcLightClientBlocksResponse {
+    fn rpc_from(light_client_blocks: Vec<Arc<LightClientBlockView>>) -> Self {
+        Self { light_client_blocks }
+    }
+}
+
 impl RpcFrom<Option<Arc<LightClientBlockView>>> for RpcLightClientNextBlockResponse {
     fn rpc_from(light_client_block: Option<Arc<LightClientBlockView>>) -> Self {
         Self { light_client_block }
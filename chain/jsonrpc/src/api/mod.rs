@@ -8,6 +8,7 @@ mod changes;
 mod chunks;
 mod client_config;
 mod config;
+mod contract_storage;
 mod gas_price;
 mod light_client;
 mod maintenance;
@@ -15,8 +16,11 @@ mod network_info;
 mod query;
 mod receipts;
 mod sandbox;
+mod simulate_transaction;
 mod split_storage;
 mod status;
+mod subscriptions;
+mod transaction_pool;
 mod transactions;
 mod validator;
 
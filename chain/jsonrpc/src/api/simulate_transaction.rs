@@ -0,0 +1,50 @@
+use near_client_primitives::types::SimulateTransactionError;
+use near_jsonrpc_primitives::errors::RpcParseError;
+use near_jsonrpc_primitives::types::simulate_transaction::{
+    RpcSimulateTransactionError, RpcSimulateTransactionRequest,
+};
+use serde_json::Value;
+
+use super::{Params, RpcFrom, RpcRequest};
+
+impl RpcRequest for RpcSimulateTransactionRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcFrom<actix::MailboxError> for RpcSimulateTransactionError {
+    fn rpc_from(error: actix::MailboxError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<SimulateTransactionError> for RpcSimulateTransactionError {
+    fn rpc_from(error: SimulateTransactionError) -> Self {
+        match error {
+            SimulateTransactionError::UnknownBlock { block_reference } => {
+                Self::UnknownBlock { block_reference }
+            }
+            SimulateTransactionError::GarbageCollectedBlock { block_height, block_hash } => {
+                Self::GarbageCollectedBlock { block_height, block_hash }
+            }
+            SimulateTransactionError::UnavailableShard { requested_shard_id } => {
+                Self::UnavailableShard { requested_shard_id }
+            }
+            SimulateTransactionError::InvalidTransaction { error } => {
+                Self::InvalidTransaction { error }
+            }
+            SimulateTransactionError::TooManyReceipts { limit } => Self::TooManyReceipts { limit },
+            SimulateTransactionError::InternalError { error_message } => {
+                Self::InternalError { error_message }
+            }
+            SimulateTransactionError::Unreachable { ref error_message } => {
+                tracing::warn!(target: "jsonrpc", "Unreachable error occurred: {}", error_message);
+                crate::metrics::RPC_UNREACHABLE_ERROR_COUNT
+                    .with_label_values(&["SimulateTransactionError"])
+                    .inc();
+                Self::InternalError { error_message: error.to_string() }
+            }
+        }
+    }
+}
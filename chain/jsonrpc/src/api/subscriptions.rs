@@ -0,0 +1,39 @@
+use serde_json::Value;
+
+use near_jsonrpc_primitives::errors::RpcParseError;
+use near_jsonrpc_primitives::types::subscriptions::{
+    RpcSubscribeAccountChangesRequest, RpcSubscribeBlocksRequest, RpcSubscribeChunksRequest,
+    RpcUnsubscribeRequest,
+};
+
+use super::{Params, RpcRequest};
+
+impl RpcRequest for RpcSubscribeBlocksRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        if value.is_null() {
+            return Ok(Self { finality: Default::default() });
+        }
+        Params::new(value).try_singleton(|finality| Ok(Self { finality })).unwrap_or_parse()
+    }
+}
+
+impl RpcRequest for RpcSubscribeChunksRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        if value.is_null() {
+            return Ok(Self { finality: Default::default(), shard_ids: None });
+        }
+        Params::parse(value)
+    }
+}
+
+impl RpcRequest for RpcSubscribeAccountChangesRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcRequest for RpcUnsubscribeRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::new(value).try_singleton(|subscription| Ok(Self { subscription })).unwrap_or_parse()
+    }
+}
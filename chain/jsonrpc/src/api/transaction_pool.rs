@@ -0,0 +1,49 @@
+use near_client_primitives::types::GetTransactionPoolError;
+use near_jsonrpc_primitives::errors::RpcParseError;
+use near_jsonrpc_primitives::types::transaction_pool::{
+    RpcPendingTransactionsRequest, RpcTransactionPoolError, RpcTransactionPoolStatsRequest,
+};
+use serde_json::Value;
+
+use super::{Params, RpcFrom, RpcRequest};
+
+impl RpcRequest for RpcPendingTransactionsRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        let request: Self = Params::parse(value)?;
+        if request.signer_id.is_none() && request.public_key.is_none() {
+            return Err(RpcParseError(
+                "At least one of `signer_id` and `public_key` has to be provided".to_string(),
+            ));
+        }
+        Ok(request)
+    }
+}
+
+impl RpcRequest for RpcTransactionPoolStatsRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcFrom<actix::MailboxError> for RpcTransactionPoolError {
+    fn rpc_from(error: actix::MailboxError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<GetTransactionPoolError> for RpcTransactionPoolError {
+    fn rpc_from(error: GetTransactionPoolError) -> Self {
+        match error {
+            GetTransactionPoolError::IOError(error_message) => {
+                Self::InternalError { error_message }
+            }
+            GetTransactionPoolError::Unreachable(ref error_message) => {
+                tracing::warn!(target: "jsonrpc", "Unreachable error occurred: {}", error_message);
+                crate::metrics::RPC_UNREACHABLE_ERROR_COUNT
+                    .with_label_values(&["RpcTransactionPoolError"])
+                    .inc();
+                Self::InternalError { error_message: error.to_string() }
+            }
+        }
+    }
+}
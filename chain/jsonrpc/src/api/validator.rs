@@ -3,7 +3,8 @@ use serde_json::Value;
 use near_client_primitives::types::GetValidatorInfoError;
 use near_jsonrpc_primitives::errors::RpcParseError;
 use near_jsonrpc_primitives::types::validator::{
-    RpcValidatorError, RpcValidatorRequest, RpcValidatorsOrderedRequest,
+    RpcValidatorError, RpcValidatorPerformanceRequest, RpcValidatorRequest,
+    RpcValidatorsOrderedRequest,
 };
 use near_primitives::types::EpochReference;
 
@@ -27,6 +28,12 @@ impl RpcRequest for RpcValidatorsOrderedRequest {
     }
 }
 
+impl RpcRequest for RpcValidatorPerformanceRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
 impl RpcFrom<actix::MailboxError> for RpcValidatorError {
     fn rpc_from(error: actix::MailboxError) -> Self {
         Self::InternalError { error_message: error.to_string() }
@@ -12,21 +12,24 @@ use futures::FutureExt;
 use near_chain_configs::GenesisConfig;
 use near_client::{
     ClientActor, DebugStatus, GetBlock, GetBlockProof, GetChunk, GetClientConfig,
-    GetExecutionOutcome, GetGasPrice, GetMaintenanceWindows, GetNetworkInfo,
-    GetNextLightClientBlock, GetProtocolConfig, GetReceipt, GetStateChanges,
-    GetStateChangesInBlock, GetValidatorInfo, GetValidatorOrdered, ProcessTxRequest,
-    ProcessTxResponse, Query, Status, TxStatus, ViewClientActor,
+    GetContractStorageReport, GetExecutionOutcome, GetGasPrice, GetLightClientBlocks,
+    GetMaintenanceWindows, GetNetworkInfo, GetNextLightClientBlock, GetPendingTransactions,
+    GetProtocolConfig, GetReceipt, GetStateChanges, GetStateChangesInBlock,
+    GetTransactionPoolStats, GetValidatorInfo, GetValidatorOrdered, GetValidatorPerformance,
+    ProcessTxRequest, ProcessTxResponse, Query, SimulateTransaction, Status, TxStatus,
+    ViewClientActor,
 };
 use near_client_primitives::types::GetSplitStorageInfo;
 pub use near_jsonrpc_client as client;
 use near_jsonrpc_primitives::errors::RpcError;
-use near_jsonrpc_primitives::message::{Message, Request};
+use near_jsonrpc_primitives::message::{Message, Notification, Request};
 use near_jsonrpc_primitives::types::config::RpcProtocolConfigResponse;
 use near_jsonrpc_primitives::types::entity_debug::{EntityDebugHandler, EntityQuery};
 use near_jsonrpc_primitives::types::query::RpcQueryRequest;
 use near_jsonrpc_primitives::types::split_storage::{
     RpcSplitStorageInfoRequest, RpcSplitStorageInfoResponse,
 };
+use near_jsonrpc_primitives::types::subscriptions::RpcSubscriptionError;
 use near_jsonrpc_primitives::types::transactions::{
     RpcSendTransactionRequest, RpcTransactionResponse,
 };
@@ -39,6 +42,8 @@ use near_primitives::transaction::SignedTransaction;
 use near_primitives::types::{AccountId, BlockHeight};
 use near_primitives::views::{QueryRequest, TxExecutionStatus};
 use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::net::IpAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -47,6 +52,8 @@ use tracing::{error, info};
 
 mod api;
 mod metrics;
+mod rate_limiter;
+mod websocket;
 
 #[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug)]
 pub struct RpcPollingConfig {
@@ -63,15 +70,59 @@ impl Default for RpcPollingConfig {
     }
 }
 
+fn default_max_subscriptions_per_connection() -> usize {
+    64
+}
+
+fn default_max_batch_size() -> usize {
+    100
+}
+
+/// Limit of a token bucket rate limiter: a client can make up to `burst`
+/// requests at once, after which its requests are let through at `qps`
+/// requests per second.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct RpcRateLimit {
+    pub burst: u64,
+    pub qps: f64,
+}
+
+/// Rate limits of JSON RPC requests.  Limits are applied per client IP
+/// address and method name, methods without a limit in `per_method` share a
+/// single limit per client.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct RpcRateLimitsConfig {
+    /// Limit shared by all methods which don't have a limit in `per_method`.
+    /// If not set, such methods aren't rate limited.
+    #[serde(default)]
+    pub default: Option<RpcRateLimit>,
+    /// Limits of specific methods, by method name.
+    #[serde(default)]
+    pub per_method: BTreeMap<String, RpcRateLimit>,
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
 pub struct RpcLimitsConfig {
     /// Maximum byte size of the json payload.
     pub json_payload_max_size: usize,
+    /// Maximum number of active subscriptions on a single WebSocket connection.
+    #[serde(default = "default_max_subscriptions_per_connection")]
+    pub max_subscriptions_per_connection: usize,
+    /// Maximum number of requests in a single JSON RPC batch.
+    #[serde(default = "default_max_batch_size")]
+    pub max_batch_size: usize,
+    #[serde(default)]
+    pub rate_limits: RpcRateLimitsConfig,
 }
 
 impl Default for RpcLimitsConfig {
     fn default() -> Self {
-        Self { json_payload_max_size: 10 * 1024 * 1024 }
+        Self {
+            json_payload_max_size: 10 * 1024 * 1024,
+            max_subscriptions_per_connection: default_max_subscriptions_per_connection(),
+            max_batch_size: default_max_batch_size(),
+            rate_limits: Default::default(),
+        }
     }
 }
 
@@ -153,6 +204,7 @@ impl near_jsonrpc_primitives::types::transactions::RpcTransactionError {
             ProcessTxResponse::DoesNotTrackShard | ProcessTxResponse::RequestRouted => {
                 Self::DoesNotTrackShard
             }
+            ProcessTxResponse::SignerQuotaExceeded => Self::SignerQuotaExceeded,
             internal_error => Self::InternalError { debug_info: format!("{:?}", internal_error) },
         }
     }
@@ -224,6 +276,9 @@ struct JsonRpcHandler {
     view_client_addr: Addr<ViewClientActor>,
     peer_manager_addr: Option<Addr<PeerManagerActor>>,
     polling_config: RpcPollingConfig,
+    limits_config: RpcLimitsConfig,
+    rate_limiter: Arc<rate_limiter::RateLimiter>,
+    block_feeds: Arc<websocket::BlockFeeds>,
     genesis_config: GenesisConfig,
     enable_debug_rpc: bool,
     debug_pages_src_path: Option<PathBuf>,
@@ -231,21 +286,105 @@ struct JsonRpcHandler {
 }
 
 impl JsonRpcHandler {
-    pub async fn process(&self, message: Message) -> Result<Message, HttpError> {
+    /// Processes a single request or a batch of requests.
+    ///
+    /// `client_ip` is the address of the client the message came from, if
+    /// known.  It's used for rate limiting.  Returns `None` if there is
+    /// nothing to answer, i.e. the message consisted of notifications only.
+    pub async fn process(
+        &self,
+        message: Message,
+        client_ip: Option<IpAddr>,
+    ) -> Result<Option<Message>, HttpError> {
         let id = message.id();
         match message {
             Message::Request(request) => {
-                Ok(Message::response(id, self.process_request(request).await))
+                Ok(Some(Message::response(id, self.process_request(request, client_ip).await)))
             }
-            _ => Ok(Message::error(RpcError::parse_error(
+            Message::Notification(notification) => {
+                self.process_notification(notification, client_ip).await;
+                Ok(None)
+            }
+            Message::Batch(messages) => Ok(self.process_batch(messages, client_ip).await),
+            _ => Ok(Some(Message::error(RpcError::parse_error(
                 "JSON RPC Request format was expected".to_owned(),
-            ))),
+            )))),
+        }
+    }
+
+    /// Processes requests of a batch concurrently and returns a batch of
+    /// responses in the same order.
+    ///
+    /// Notifications are processed as well but, as required by JSON RPC 2.0,
+    /// they are not answered.  Returns `None` if nothing in the batch is
+    /// answered.
+    async fn process_batch(
+        &self,
+        messages: Vec<Message>,
+        client_ip: Option<IpAddr>,
+    ) -> Option<Message> {
+        if messages.is_empty() {
+            return Some(Message::error(RpcError::invalid_request(
+                "JSON RPC batch must not be empty".to_owned(),
+            )));
+        }
+        let max_batch_size = self.limits_config.max_batch_size;
+        if messages.len() > max_batch_size {
+            return Some(Message::error(RpcError::batch_size_exceeded(
+                messages.len(),
+                max_batch_size,
+            )));
+        }
+        let responses = messages.into_iter().map(|message| async move {
+            match message {
+                Message::Request(request) => {
+                    let id = request.id.clone();
+                    Some(Message::response(id, self.process_request(request, client_ip).await))
+                }
+                Message::Notification(notification) => {
+                    self.process_notification(notification, client_ip).await;
+                    None
+                }
+                _ => Some(Message::error(RpcError::invalid_request(
+                    "JSON RPC Request format was expected".to_owned(),
+                ))),
+            }
+        });
+        let responses: Vec<_> =
+            futures::future::join_all(responses).await.into_iter().flatten().collect();
+        if responses.is_empty() {
+            None
+        } else {
+            Some(Message::Batch(responses))
+        }
+    }
+
+    /// Processes a notification, i.e. a request the client expects no
+    /// response to.
+    async fn process_notification(&self, notification: Notification, client_ip: Option<IpAddr>) {
+        let method = notification.method.clone();
+        if let Err(err) = self.process_request(notification.into_request(), client_ip).await {
+            tracing::debug!(target: "jsonrpc", ?err, %method, "Failed to process notification");
+        }
+    }
+
+    /// Returns an error if the client exceeded the rate limit of the method.
+    fn check_rate_limit(&self, client_ip: Option<IpAddr>, method: &str) -> Result<(), RpcError> {
+        if self.rate_limiter.try_acquire(client_ip, method) {
+            Ok(())
+        } else {
+            Err(RpcError::rate_limit_exceeded(method.to_string()))
         }
     }
 
     // `process_request` increments affected metrics but the request processing is done by
     // `process_request_internal`.
-    async fn process_request(&self, request: Request) -> Result<Value, RpcError> {
+    async fn process_request(
+        &self,
+        request: Request,
+        client_ip: Option<IpAddr>,
+    ) -> Result<Value, RpcError> {
+        self.check_rate_limit(client_ip, &request.method)?;
         let timer = Instant::now();
         let (metrics_name, response) = self.process_request_internal(request).await;
 
@@ -303,6 +442,9 @@ impl JsonRpcHandler {
                 };
                 (metrics_name.to_string(), process_query_response(self.query(params).await))
             }
+            method if websocket::SUBSCRIPTION_METHODS.contains(&method) => {
+                (method_name, Err(RpcSubscriptionError::WebSocketRequired.into()))
+            }
             _ => {
                 ("UNSUPPORTED_METHOD".to_string(), Err(RpcError::method_not_found(request.method)))
             }
@@ -360,6 +502,9 @@ impl JsonRpcHandler {
                 })
                 .await
             }
+            "EXPERIMENTAL_light_client_blocks" => {
+                process_method_call(request, |params| self.light_client_blocks(params)).await
+            }
             "EXPERIMENTAL_light_client_proof" => {
                 process_method_call(request, |params| {
                     self.light_client_execution_outcome_proof(params)
@@ -378,12 +523,27 @@ impl JsonRpcHandler {
             "EXPERIMENTAL_validators_ordered" => {
                 process_method_call(request, |params| self.validators_ordered(params)).await
             }
+            "EXPERIMENTAL_validator_performance" if self.enable_debug_rpc => {
+                process_method_call(request, |params| self.validator_performance(params)).await
+            }
             "EXPERIMENTAL_maintenance_windows" => {
                 process_method_call(request, |params| self.maintenance_windows(params)).await
             }
             "EXPERIMENTAL_split_storage_info" => {
                 process_method_call(request, |params| self.split_storage_info(params)).await
             }
+            "EXPERIMENTAL_pending_transactions" => {
+                process_method_call(request, |params| self.pending_transactions(params)).await
+            }
+            "EXPERIMENTAL_tx_pool_stats" => {
+                process_method_call(request, |params| self.tx_pool_stats(params)).await
+            }
+            "EXPERIMENTAL_simulate_transaction" => {
+                process_method_call(request, |params| self.simulate_transaction(params)).await
+            }
+            "EXPERIMENTAL_contract_storage_report" => {
+                process_method_call(request, |params| self.contract_storage_report(params)).await
+            }
             #[cfg(feature = "sandbox")]
             "sandbox_patch_state" => {
                 process_method_call(request, |params| self.sandbox_patch_state(params)).await
@@ -948,6 +1108,22 @@ impl JsonRpcHandler {
         Ok(response.rpc_into())
     }
 
+    async fn light_client_blocks(
+        &self,
+        request: near_jsonrpc_primitives::types::light_client::RpcLightClientBlocksRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::light_client::RpcLightClientBlocksResponse,
+        near_jsonrpc_primitives::types::light_client::RpcLightClientNextBlockError,
+    > {
+        let response = self
+            .view_client_send(GetLightClientBlocks {
+                last_block_hash: request.last_block_hash,
+                limit: request.limit,
+            })
+            .await?;
+        Ok(response.rpc_into())
+    }
+
     async fn light_client_execution_outcome_proof(
         &self,
         request: near_jsonrpc_primitives::types::light_client::RpcLightClientExecutionProofRequest,
@@ -1029,6 +1205,25 @@ impl JsonRpcHandler {
         Ok(validators)
     }
 
+    async fn validator_performance(
+        &self,
+        request: near_jsonrpc_primitives::types::validator::RpcValidatorPerformanceRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::validator::RpcValidatorPerformanceResponse,
+        near_jsonrpc_primitives::types::validator::RpcValidatorError,
+    > {
+        let performance = self
+            .view_client_send(GetValidatorPerformance {
+                account_id: request.account_id,
+                epoch_id: request.epoch_id,
+                start_height: request.start_height,
+            })
+            .await?;
+        Ok(near_jsonrpc_primitives::types::validator::RpcValidatorPerformanceResponse {
+            performance,
+        })
+    }
+
     /// If experimental_debug_pages_src_path config is set, reads the html file from that
     /// directory. Otherwise, returns None.
     fn read_html_file_override(&self, html_file: &'static str) -> Option<String> {
@@ -1077,6 +1272,78 @@ impl JsonRpcHandler {
         let split_storage = self.view_client_send(GetSplitStorageInfo {}).await?;
         Ok(RpcSplitStorageInfoResponse { result: split_storage })
     }
+
+    async fn pending_transactions(
+        &self,
+        request_data: near_jsonrpc_primitives::types::transaction_pool::RpcPendingTransactionsRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::transaction_pool::RpcPendingTransactionsResponse,
+        near_jsonrpc_primitives::types::transaction_pool::RpcTransactionPoolError,
+    > {
+        let shards = self
+            .client_send(GetPendingTransactions {
+                signer_id: request_data.signer_id,
+                public_key: request_data.public_key,
+            })
+            .await?;
+        Ok(near_jsonrpc_primitives::types::transaction_pool::RpcPendingTransactionsResponse {
+            shards,
+        })
+    }
+
+    async fn tx_pool_stats(
+        &self,
+        _request_data: near_jsonrpc_primitives::types::transaction_pool::RpcTransactionPoolStatsRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::transaction_pool::RpcTransactionPoolStatsResponse,
+        near_jsonrpc_primitives::types::transaction_pool::RpcTransactionPoolError,
+    > {
+        let shards = self.client_send(GetTransactionPoolStats {}).await?;
+        Ok(near_jsonrpc_primitives::types::transaction_pool::RpcTransactionPoolStatsResponse {
+            shards,
+        })
+    }
+
+    async fn simulate_transaction(
+        &self,
+        request_data: near_jsonrpc_primitives::types::simulate_transaction::RpcSimulateTransactionRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::simulate_transaction::RpcSimulateTransactionResponse,
+        near_jsonrpc_primitives::types::simulate_transaction::RpcSimulateTransactionError,
+    > {
+        let result = self
+            .view_client_send(SimulateTransaction {
+                transaction: request_data.signed_transaction,
+                block_reference: request_data.block_reference,
+                verify_signature: request_data.verify_signature,
+            })
+            .await?;
+        Ok(near_jsonrpc_primitives::types::simulate_transaction::RpcSimulateTransactionResponse {
+            result,
+        })
+    }
+
+    async fn contract_storage_report(
+        &self,
+        request_data: near_jsonrpc_primitives::types::contract_storage::RpcContractStorageReportRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::contract_storage::RpcContractStorageReportResponse,
+        near_jsonrpc_primitives::types::contract_storage::RpcContractStorageReportError,
+    > {
+        let report = self
+            .view_client_send(GetContractStorageReport {
+                block_reference: request_data.block_reference,
+                account_id: request_data.account_id,
+                prefixes: request_data.prefixes.into_iter().map(Into::into).collect(),
+                top: request_data.top,
+                start_key: request_data.start_key.map(Into::into),
+                max_keys: request_data.max_keys,
+            })
+            .await?;
+        Ok(near_jsonrpc_primitives::types::contract_storage::RpcContractStorageReportResponse {
+            report,
+        })
+    }
 }
 
 #[cfg(feature = "sandbox")]
@@ -1289,12 +1556,16 @@ impl JsonRpcHandler {
 }
 
 fn rpc_handler(
+    req: HttpRequest,
     message: web::Json<Message>,
     handler: web::Data<JsonRpcHandler>,
 ) -> impl Future<Output = Result<HttpResponse, HttpError>> {
+    let client_ip = req.peer_addr().map(|addr| addr.ip());
     let response = async move {
-        let message = handler.process(message.0).await?;
-        Ok(HttpResponse::Ok().json(&message))
+        match handler.process(message.0, client_ip).await? {
+            Some(message) => Ok(HttpResponse::Ok().json(&message)),
+            None => Ok(HttpResponse::NoContent().finish()),
+        }
     };
     response.boxed()
 }
@@ -1468,7 +1739,8 @@ async fn display_debug_html(
 /// Starts HTTP server(s) listening for RPC requests.
 ///
 /// Starts an HTTP server which handles JSON RPC calls as well as states
-/// endpoints such as `/status`, `/health`, `/metrics` etc.  Depending on
+/// endpoints such as `/status`, `/health`, `/metrics` etc.  JSON RPC calls and
+/// subscriptions are also served over a WebSocket at `/ws`.  Depending on
 /// configuration may also start another HTTP server just for providing
 /// Prometheus metrics (i.e. covering the `/metrics` path).
 ///
@@ -1497,6 +1769,11 @@ pub fn start_http(
     let cors_allowed_origins_clone = cors_allowed_origins.clone();
     info!(target:"network", "Starting http server at {}", addr);
     let mut servers = Vec::new();
+    // The limiter is shared by all workers so that limits apply to the server
+    // as a whole.  Likewise, a single block feed serves the subscriptions of
+    // all workers.
+    let rate_limiter = Arc::new(rate_limiter::RateLimiter::new(limits_config.rate_limits.clone()));
+    let block_feeds = Arc::new(websocket::BlockFeeds::default());
     let listener = HttpServer::new(move || {
         App::new()
             .wrap(get_cors(&cors_allowed_origins))
@@ -1505,6 +1782,9 @@ pub fn start_http(
                 view_client_addr: view_client_addr.clone(),
                 peer_manager_addr: peer_manager_addr.clone(),
                 polling_config,
+                limits_config: limits_config.clone(),
+                rate_limiter: rate_limiter.clone(),
+                block_feeds: block_feeds.clone(),
                 genesis_config: genesis_config.clone(),
                 enable_debug_rpc,
                 debug_pages_src_path: debug_pages_src_path.clone().map(Into::into),
@@ -1513,6 +1793,7 @@ pub fn start_http(
             .app_data(web::JsonConfig::default().limit(limits_config.json_payload_max_size))
             .wrap(middleware::Logger::default())
             .service(web::resource("/").route(web::post().to(rpc_handler)))
+            .service(web::resource("/ws").route(web::get().to(websocket::ws_handler)))
             .service(
                 web::resource("/status")
                     .route(web::get().to(status_handler))
@@ -1,4 +1,6 @@
-use near_o11y::metrics::{exponential_buckets, HistogramVec, IntCounter, IntCounterVec};
+use near_o11y::metrics::{
+    exponential_buckets, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
+};
 use once_cell::sync::Lazy;
 
 pub static RPC_PROCESSING_TIME: Lazy<HistogramVec> = Lazy::new(|| {
@@ -55,3 +57,34 @@ pub static RPC_UNREACHABLE_ERROR_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
     )
     .unwrap()
 });
+pub static WEBSOCKET_CONNECTIONS: Lazy<IntGauge> = Lazy::new(|| {
+    near_o11y::metrics::try_create_int_gauge(
+        "near_rpc_websocket_connections",
+        "Number of currently open WebSocket RPC connections",
+    )
+    .unwrap()
+});
+pub static WEBSOCKET_ACTIVE_SUBSCRIPTIONS: Lazy<IntGaugeVec> = Lazy::new(|| {
+    near_o11y::metrics::try_create_int_gauge_vec(
+        "near_rpc_websocket_active_subscriptions",
+        "Number of currently active WebSocket subscriptions, by subscription method",
+        &["method"],
+    )
+    .unwrap()
+});
+pub static WEBSOCKET_NOTIFICATIONS_SENT: Lazy<IntCounterVec> = Lazy::new(|| {
+    near_o11y::metrics::try_create_int_counter_vec(
+        "near_rpc_websocket_notifications_total",
+        "Total count of notifications pushed to WebSocket subscribers, by subscription method",
+        &["method"],
+    )
+    .unwrap()
+});
+pub static RPC_RATE_LIMITED_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    near_o11y::metrics::try_create_int_counter_vec(
+        "near_rpc_rate_limited_total",
+        "Total count of RPC requests rejected by the rate limiter, by rate limited method",
+        &["method"],
+    )
+    .unwrap()
+});
@@ -0,0 +1,209 @@
+use crate::{metrics, RpcRateLimit, RpcRateLimitsConfig};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Maximum number of tracked buckets.  Once reached, idle buckets are dropped
+/// at most once per `PRUNE_INTERVAL`, and requests which would need a new
+/// bucket are rejected until there is room again.  A bucket is idle if it has
+/// refilled completely, at which point forgetting it doesn't change the
+/// behaviour of the limiter.
+const MAX_TRACKED_BUCKETS: usize = 100_000;
+const PRUNE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Label used in metrics for methods limited by the default limit.
+const DEFAULT_LIMIT_LABEL: &str = "*";
+
+struct Bucket {
+    limit: RpcRateLimit,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(limit: RpcRateLimit, now: Instant) -> Self {
+        Self { limit, tokens: limit.burst as f64, updated: now }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.limit.qps).min(self.limit.burst as f64);
+        self.updated = now;
+    }
+
+    fn is_full(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        self.tokens >= self.limit.burst as f64
+    }
+
+    fn try_acquire(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        if self.tokens < 1. {
+            return false;
+        }
+        self.tokens -= 1.;
+        true
+    }
+}
+
+struct Buckets {
+    buckets: HashMap<(IpAddr, String), Bucket>,
+    last_pruned: Option<Instant>,
+}
+
+/// Token bucket rate limiter of JSON RPC requests, keyed by client IP address
+/// and method name.  All methods without a limit of their own share a single
+/// bucket per client, so that the method names sent by clients can't multiply
+/// the buckets.
+///
+/// A single limiter is shared by all workers of the HTTP server.
+pub(crate) struct RateLimiter {
+    config: RpcRateLimitsConfig,
+    buckets: Mutex<Buckets>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RpcRateLimitsConfig) -> Self {
+        Self { config, buckets: Mutex::new(Buckets { buckets: HashMap::new(), last_pruned: None }) }
+    }
+
+    /// Takes a token from the bucket of given client and method.
+    ///
+    /// Returns false if the bucket is empty, i.e. the request should be
+    /// rejected.  Requests of clients with unknown address and of methods
+    /// without a configured limit are always let through.
+    pub fn try_acquire(&self, client: Option<IpAddr>, method: &str) -> bool {
+        let Some(client) = client else { return true };
+        let (limit, label) = match self.config.per_method.get(method) {
+            Some(limit) => (*limit, method),
+            None => match self.config.default {
+                Some(limit) => (limit, DEFAULT_LIMIT_LABEL),
+                None => return true,
+            },
+        };
+        self.try_acquire_at(client, label, limit, Instant::now())
+    }
+
+    fn try_acquire_at(
+        &self,
+        client: IpAddr,
+        label: &str,
+        limit: RpcRateLimit,
+        now: Instant,
+    ) -> bool {
+        let mut buckets = self.buckets.lock().unwrap();
+        let Buckets { buckets, last_pruned } = &mut *buckets;
+        let key = (client, label.to_string());
+        let at_capacity = |buckets: &HashMap<_, _>| {
+            buckets.len() >= MAX_TRACKED_BUCKETS && !buckets.contains_key(&key)
+        };
+        if at_capacity(buckets)
+            && last_pruned.map_or(true, |last_pruned| now >= last_pruned + PRUNE_INTERVAL)
+        {
+            buckets.retain(|_, bucket| !bucket.is_full(now));
+            *last_pruned = Some(now);
+        }
+        let acquired = if at_capacity(buckets) {
+            false
+        } else {
+            buckets.entry(key).or_insert_with(|| Bucket::new(limit, now)).try_acquire(now)
+        };
+        if !acquired {
+            metrics::RPC_RATE_LIMITED_COUNT.with_label_values(&[label]).inc();
+        }
+        acquired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(default: Option<RpcRateLimit>, per_method: &[(&str, RpcRateLimit)]) -> RateLimiter {
+        RateLimiter::new(RpcRateLimitsConfig {
+            default,
+            per_method: per_method
+                .iter()
+                .map(|(method, limit)| (method.to_string(), *limit))
+                .collect(),
+        })
+    }
+
+    #[test]
+    fn test_bucket_refill() {
+        let now = Instant::now();
+        let mut bucket = Bucket::new(RpcRateLimit { burst: 2, qps: 10. }, now);
+        assert!(bucket.try_acquire(now));
+        assert!(bucket.try_acquire(now));
+        assert!(!bucket.try_acquire(now));
+        assert!(!bucket.try_acquire(now + Duration::from_millis(50)));
+        assert!(bucket.try_acquire(now + Duration::from_millis(100)));
+        assert!(!bucket.is_full(now + Duration::from_millis(200)));
+        assert!(bucket.is_full(now + Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn test_limits_per_client_and_method() {
+        let limit = RpcRateLimit { burst: 1, qps: 0.001 };
+        let limiter = limiter(None, &[("query", limit)]);
+        let client1 = Some("10.0.0.1".parse().unwrap());
+        let client2 = Some("10.0.0.2".parse().unwrap());
+
+        assert!(limiter.try_acquire(client1, "query"));
+        assert!(!limiter.try_acquire(client1, "query"));
+        // Other clients have their own buckets.
+        assert!(limiter.try_acquire(client2, "query"));
+        // Methods without a limit are not limited.
+        assert!(limiter.try_acquire(client1, "block"));
+        assert!(limiter.try_acquire(client1, "block"));
+        // Clients with unknown address are not limited.
+        assert!(limiter.try_acquire(None, "query"));
+        assert!(limiter.try_acquire(None, "query"));
+    }
+
+    #[test]
+    fn test_default_limit() {
+        let limiter = limiter(Some(RpcRateLimit { burst: 1, qps: 0.001 }), &[]);
+        let client = Some("10.0.0.1".parse().unwrap());
+
+        assert!(limiter.try_acquire(client, "block"));
+        assert!(!limiter.try_acquire(client, "block"));
+        // Methods without a limit of their own share the default bucket.
+        assert!(!limiter.try_acquire(client, "chunk"));
+    }
+
+    #[test]
+    fn test_default_limit_distinct_methods() {
+        let limiter = limiter(Some(RpcRateLimit { burst: 10, qps: 0.001 }), &[]);
+        let client = Some("10.0.0.1".parse().unwrap());
+
+        let acquired =
+            (0..1000).filter(|i| limiter.try_acquire(client, &format!("method{i}"))).count();
+        assert_eq!(acquired, 10);
+        assert_eq!(limiter.buckets.lock().unwrap().buckets.len(), 1);
+    }
+
+    #[test]
+    fn test_max_tracked_buckets() {
+        let limit = RpcRateLimit { burst: 2, qps: 1. };
+        let limiter = limiter(Some(limit), &[]);
+        let now = Instant::now();
+        let client = |i: usize| IpAddr::from((i as u128).to_be_bytes());
+
+        for i in 0..MAX_TRACKED_BUCKETS {
+            assert!(limiter.try_acquire_at(client(i), DEFAULT_LIMIT_LABEL, limit, now));
+        }
+        // No bucket is idle, so new clients are rejected rather than tracked.
+        let new_client = client(MAX_TRACKED_BUCKETS);
+        assert!(!limiter.try_acquire_at(new_client, DEFAULT_LIMIT_LABEL, limit, now));
+        assert_eq!(limiter.buckets.lock().unwrap().buckets.len(), MAX_TRACKED_BUCKETS);
+        // Clients which are already tracked are still served.
+        assert!(limiter.try_acquire_at(client(0), DEFAULT_LIMIT_LABEL, limit, now));
+
+        // Once the buckets refill, they are dropped to make room.
+        let later = now + Duration::from_secs(10);
+        assert!(limiter.try_acquire_at(new_client, DEFAULT_LIMIT_LABEL, limit, later));
+        assert_eq!(limiter.buckets.lock().unwrap().buckets.len(), 1);
+    }
+}
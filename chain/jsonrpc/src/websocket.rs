@@ -0,0 +1,599 @@
+//! WebSocket endpoint of the JSON RPC server.
+//!
+//! A WebSocket connection accepts the same JSON RPC requests as the HTTP
+//! endpoint and additionally the following subscription methods:
+//!
+//! - `subscribe_blocks` pushes a `block` response for every new block,
+//! - `subscribe_chunks` pushes a `chunk` response for every new chunk,
+//!   optionally restricted to a set of shards,
+//! - `subscribe_account_changes` pushes an `EXPERIMENTAL_changes` response
+//!   for every block which changed any of the given accounts,
+//! - `subscribe_tx_status` pushes a `tx` response every time the execution
+//!   status of the transaction advances, until `wait_until` is reached.
+//!
+//! Each of these returns a subscription id.  Pushed data is sent as
+//! `subscription` notifications carrying that id, and `unsubscribe` cancels a
+//! subscription.
+//!
+//! New blocks come from a [`BlockFeeds`] shared by all connections of the
+//! server: a single task per finality follows the head through the
+//! `ViewClientActor` and broadcasts every new block to the subscriptions.
+
+use crate::api::RpcRequest;
+use crate::{metrics, serialize_response, JsonRpcHandler};
+use actix_codec::{Decoder, Encoder};
+use actix_http::ws;
+use actix_web::body::BodyStream;
+use actix_web::{web, Error as HttpError, HttpRequest, HttpResponse};
+use bytes::{Bytes, BytesMut};
+use futures::channel::mpsc;
+use futures::{SinkExt, StreamExt};
+use near_client::{GetBlock, GetChunk, GetStateChanges, TxStatus};
+use near_jsonrpc_primitives::errors::{RpcError, RpcParseError};
+use near_jsonrpc_primitives::message::{self, Message, Request};
+use near_jsonrpc_primitives::types::blocks::{RpcBlockError, RpcBlockResponse};
+use near_jsonrpc_primitives::types::changes::{
+    RpcStateChangesError, RpcStateChangesInBlockResponse,
+};
+use near_jsonrpc_primitives::types::chunks::{RpcChunkError, RpcChunkResponse};
+use near_jsonrpc_primitives::types::subscriptions::{
+    RpcSubscribeAccountChangesRequest, RpcSubscribeBlocksRequest, RpcSubscribeChunksRequest,
+    RpcSubscribeResponse, RpcSubscriptionError, RpcSubscriptionNotification, RpcUnsubscribeRequest,
+    RpcUnsubscribeResponse, SubscriptionId,
+};
+use near_jsonrpc_primitives::types::transactions::{
+    RpcTransactionError, RpcTransactionResponse, RpcTransactionStatusRequest,
+};
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::{BlockHeight, BlockId, BlockReference, Finality};
+use near_primitives::views::{BlockView, StateChangesRequestView};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+
+const SUBSCRIPTION_NOTIFICATION_METHOD: &str = "subscription";
+
+/// Maximum number of messages waiting to be written to a single connection.
+/// Once the queue is full, producers wait which slows down subscriptions of
+/// clients that don't keep up.
+const SEND_QUEUE_SIZE: usize = 128;
+
+/// Maximum number of blocks published at once when the head advances by more
+/// than one block, e.g. after the node was busy for a while.  Older blocks are
+/// skipped.
+const MAX_CATCH_UP_BLOCKS: usize = 64;
+
+/// Number of blocks a subscription may fall behind its feed before it starts
+/// missing blocks.
+const BLOCK_FEED_QUEUE_SIZE: usize = 2 * MAX_CATCH_UP_BLOCKS;
+
+enum Subscription {
+    Blocks(RpcSubscribeBlocksRequest),
+    Chunks(RpcSubscribeChunksRequest),
+    AccountChanges(RpcSubscribeAccountChangesRequest),
+    TxStatus(RpcTransactionStatusRequest),
+}
+
+impl Subscription {
+    fn parse(request: Request) -> Result<Result<Self, RpcParseError>, Request> {
+        Ok(match request.method.as_ref() {
+            "subscribe_blocks" => RpcRequest::parse(request.params).map(Self::Blocks),
+            "subscribe_chunks" => RpcRequest::parse(request.params).map(Self::Chunks),
+            "subscribe_account_changes" => {
+                RpcRequest::parse(request.params).map(Self::AccountChanges)
+            }
+            "subscribe_tx_status" => RpcRequest::parse(request.params).map(Self::TxStatus),
+            _ => return Err(request),
+        })
+    }
+
+    fn method_name(&self) -> &'static str {
+        match self {
+            Self::Blocks(_) => "subscribe_blocks",
+            Self::Chunks(_) => "subscribe_chunks",
+            Self::AccountChanges(_) => "subscribe_account_changes",
+            Self::TxStatus(_) => "subscribe_tx_status",
+        }
+    }
+}
+
+/// Methods which are only available over a WebSocket connection.
+pub(crate) const SUBSCRIPTION_METHODS: [&str; 5] = [
+    "subscribe_account_changes",
+    "subscribe_blocks",
+    "subscribe_chunks",
+    "subscribe_tx_status",
+    "unsubscribe",
+];
+
+/// Upgrades the HTTP connection to a WebSocket one and starts serving JSON RPC
+/// requests and subscriptions over it.
+pub(crate) async fn ws_handler(
+    req: HttpRequest,
+    payload: web::Payload,
+    handler: web::Data<JsonRpcHandler>,
+) -> Result<HttpResponse, HttpError> {
+    let mut response = ws::handshake(req.head())?;
+    let codec = ws::Codec::new().max_size(handler.limits_config.json_payload_max_size);
+    let (sender, receiver) = mpsc::channel(SEND_QUEUE_SIZE);
+
+    let mut encoder = codec.clone();
+    let outgoing = receiver.map(move |message: ws::Message| {
+        let mut buf = BytesMut::new();
+        encoder.encode(message, &mut buf).map(|()| buf.freeze())
+    });
+    let session = Session {
+        handler,
+        client_ip: req.peer_addr().map(|addr| addr.ip()),
+        sender,
+        subscriptions: HashMap::new(),
+        next_subscription_id: 0,
+    };
+    actix::spawn(session.run(payload, codec));
+
+    let response = response.body(BodyStream::new(outgoing));
+    Ok(HttpResponse::from(response).map_into_boxed_body())
+}
+
+/// State of a single WebSocket connection.
+struct Session {
+    handler: web::Data<JsonRpcHandler>,
+    client_ip: Option<IpAddr>,
+    sender: mpsc::Sender<ws::Message>,
+    subscriptions: HashMap<SubscriptionId, (&'static str, JoinHandle<()>)>,
+    next_subscription_id: SubscriptionId,
+}
+
+impl Session {
+    /// Reads frames from the client until the connection is closed.
+    ///
+    /// When this returns, all subscriptions of the connection are cancelled
+    /// and the sending half is dropped which terminates the response stream.
+    async fn run(mut self, mut payload: web::Payload, mut codec: ws::Codec) {
+        metrics::WEBSOCKET_CONNECTIONS.inc();
+        let mut buf = BytesMut::new();
+        let mut continuation: Option<BytesMut> = None;
+        'read: while let Some(chunk) = payload.next().await {
+            match chunk {
+                Ok(chunk) => buf.extend_from_slice(&chunk),
+                Err(err) => {
+                    tracing::debug!(target: "jsonrpc", ?err, "Failed to read WebSocket payload");
+                    break;
+                }
+            }
+            loop {
+                let frame = match codec.decode(&mut buf) {
+                    Ok(Some(frame)) => frame,
+                    Ok(None) => break,
+                    Err(err) => {
+                        tracing::debug!(target: "jsonrpc", ?err, "Invalid WebSocket frame");
+                        self.send(ws::Message::Close(Some(ws::CloseCode::Protocol.into()))).await;
+                        break 'read;
+                    }
+                };
+                match frame {
+                    ws::Frame::Text(data) | ws::Frame::Binary(data) => {
+                        self.process_data(data).await;
+                    }
+                    ws::Frame::Continuation(item) => {
+                        let (data, is_last) = match item {
+                            ws::Item::FirstText(data) | ws::Item::FirstBinary(data) => {
+                                continuation = Some(BytesMut::new());
+                                (data, false)
+                            }
+                            ws::Item::Continue(data) => (data, false),
+                            ws::Item::Last(data) => (data, true),
+                        };
+                        // A `Continue` or `Last` frame without a first frame
+                        // is dropped.
+                        let Some(message) = continuation.as_mut() else {
+                            continue;
+                        };
+                        message.extend_from_slice(&data);
+                        if message.len() > self.handler.limits_config.json_payload_max_size {
+                            let reason = ws::CloseCode::Size.into();
+                            self.send(ws::Message::Close(Some(reason))).await;
+                            break 'read;
+                        }
+                        if is_last {
+                            let message = continuation.take().unwrap();
+                            self.process_data(message.freeze()).await;
+                        }
+                    }
+                    ws::Frame::Ping(data) => self.send(ws::Message::Pong(data)).await,
+                    ws::Frame::Pong(_) => {}
+                    ws::Frame::Close(reason) => {
+                        self.send(ws::Message::Close(reason)).await;
+                        break 'read;
+                    }
+                }
+            }
+        }
+        for (_, (method_name, task)) in self.subscriptions.drain() {
+            task.abort();
+            metrics::WEBSOCKET_ACTIVE_SUBSCRIPTIONS.with_label_values(&[method_name]).dec();
+        }
+        metrics::WEBSOCKET_CONNECTIONS.dec();
+    }
+
+    async fn send(&mut self, message: ws::Message) {
+        // An error means the response stream has been dropped, i.e. the
+        // connection is being torn down anyway.
+        let _ = self.sender.send(message).await;
+    }
+
+    async fn process_data(&mut self, data: Bytes) {
+        let request = match message::from_slice(&data) {
+            Ok(Message::Request(request)) => request,
+            Ok(_) => {
+                let response = Message::error(RpcError::parse_error(
+                    "JSON RPC Request format was expected".to_owned(),
+                ));
+                send_message(&mut self.sender, &response).await;
+                return;
+            }
+            Err(broken) => {
+                send_message(&mut self.sender, &broken.reply()).await;
+                return;
+            }
+        };
+        let id = request.id.clone();
+        let result = match Subscription::parse(request) {
+            Ok(subscription) => subscription
+                .map_err(RpcError::from)
+                .and_then(|subscription| {
+                    self.handler.check_rate_limit(self.client_ip, subscription.method_name())?;
+                    Ok(self.subscribe(subscription)?)
+                })
+                .and_then(serialize_response),
+            Err(request) if request.method == "unsubscribe" => {
+                RpcUnsubscribeRequest::parse(request.params)
+                    .map_err(RpcError::from)
+                    .and_then(|request| serialize_response(self.unsubscribe(request)))
+            }
+            Err(request) => {
+                // Regular requests may take a while (e.g. waiting for
+                // transaction execution) so they are processed concurrently
+                // and don't hold up the connection.
+                let handler = self.handler.clone();
+                let client_ip = self.client_ip;
+                let mut sender = self.sender.clone();
+                actix::spawn(async move {
+                    let result = handler.process_request(request, client_ip).await;
+                    send_message(&mut sender, &Message::response(id, result)).await;
+                });
+                return;
+            }
+        };
+        send_message(&mut self.sender, &Message::response(id, result)).await;
+    }
+
+    fn subscribe(
+        &mut self,
+        subscription: Subscription,
+    ) -> Result<RpcSubscribeResponse, RpcSubscriptionError> {
+        self.subscriptions.retain(|_, (method_name, task)| {
+            let finished = task.is_finished();
+            if finished {
+                metrics::WEBSOCKET_ACTIVE_SUBSCRIPTIONS.with_label_values(&[*method_name]).dec();
+            }
+            !finished
+        });
+        let limit = self.handler.limits_config.max_subscriptions_per_connection;
+        if self.subscriptions.len() >= limit {
+            return Err(RpcSubscriptionError::TooManySubscriptions { limit });
+        }
+
+        let id = self.next_subscription_id;
+        self.next_subscription_id += 1;
+        let method_name = subscription.method_name();
+        // The task is spawned on the current thread so it cannot push
+        // anything before the response to the subscribe request is queued.
+        let task = actix::spawn(run_subscription(
+            self.handler.clone(),
+            self.sender.clone(),
+            id,
+            subscription,
+        ));
+        metrics::WEBSOCKET_ACTIVE_SUBSCRIPTIONS.with_label_values(&[method_name]).inc();
+        self.subscriptions.insert(id, (method_name, task));
+        Ok(RpcSubscribeResponse { subscription: id })
+    }
+
+    fn unsubscribe(&mut self, request: RpcUnsubscribeRequest) -> RpcUnsubscribeResponse {
+        let unsubscribed = match self.subscriptions.remove(&request.subscription) {
+            Some((method_name, task)) => {
+                task.abort();
+                metrics::WEBSOCKET_ACTIVE_SUBSCRIPTIONS.with_label_values(&[method_name]).dec();
+                true
+            }
+            None => false,
+        };
+        RpcUnsubscribeResponse { unsubscribed }
+    }
+}
+
+/// Serialises `message` and queues it for sending.
+///
+/// Returns false if the connection has been closed.
+async fn send_message(sender: &mut mpsc::Sender<ws::Message>, message: &Message) -> bool {
+    match serde_json::to_string(message) {
+        Ok(text) => sender.send(ws::Message::Text(text.into())).await.is_ok(),
+        Err(err) => {
+            tracing::error!(target: "jsonrpc", ?err, "Failed to serialize WebSocket message");
+            true
+        }
+    }
+}
+
+/// Pushes a `subscription` notification with `result` to the client.
+///
+/// Returns false if the connection has been closed and the subscription
+/// should stop.
+async fn notify(
+    sender: &mut mpsc::Sender<ws::Message>,
+    subscription: SubscriptionId,
+    method_name: &'static str,
+    result: impl serde::Serialize,
+) -> bool {
+    let result = match serialize_response(result) {
+        Ok(result) => result,
+        Err(err) => {
+            tracing::error!(target: "jsonrpc", ?err, "Failed to serialize subscription result");
+            return true;
+        }
+    };
+    let params = match serialize_response(RpcSubscriptionNotification { subscription, result }) {
+        Ok(params) => params,
+        Err(err) => {
+            tracing::error!(target: "jsonrpc", ?err, "Failed to serialize subscription result");
+            return true;
+        }
+    };
+    metrics::WEBSOCKET_NOTIFICATIONS_SENT.with_label_values(&[method_name]).inc();
+    send_message(
+        sender,
+        &Message::notification(SUBSCRIPTION_NOTIFICATION_METHOD.to_owned(), params),
+    )
+    .await
+}
+
+async fn run_subscription(
+    handler: web::Data<JsonRpcHandler>,
+    mut sender: mpsc::Sender<ws::Message>,
+    id: SubscriptionId,
+    subscription: Subscription,
+) {
+    let method_name = subscription.method_name();
+    match subscription {
+        Subscription::Blocks(request) => {
+            let mut blocks = handler.block_feeds.subscribe(&handler, request.finality);
+            while let Some(block) = next_block(&mut blocks).await {
+                let response = RpcBlockResponse { block_view: BlockView::clone(&block) };
+                if !notify(&mut sender, id, method_name, response).await {
+                    return;
+                }
+            }
+        }
+        Subscription::Chunks(request) => {
+            let mut blocks = handler.block_feeds.subscribe(&handler, request.finality);
+            while let Some(block) = next_block(&mut blocks).await {
+                // Only chunks included at this height are new, the others are
+                // copies of chunks from previous blocks.
+                let new_chunks = block.chunks.iter().filter(|chunk| {
+                    chunk.height_included == block.header.height
+                        && request
+                            .shard_ids
+                            .as_ref()
+                            .map_or(true, |shard_ids| shard_ids.contains(&chunk.shard_id))
+                });
+                for chunk in new_chunks {
+                    let chunk_view: Result<_, RpcChunkError> = handler
+                        .view_client_send(GetChunk::ChunkHash(chunk.chunk_hash.into()))
+                        .await;
+                    let chunk_view = match chunk_view {
+                        Ok(chunk_view) => chunk_view,
+                        Err(err) => {
+                            tracing::debug!(target: "jsonrpc", ?err, chunk_hash = ?chunk.chunk_hash, "Failed to fetch chunk for subscription");
+                            continue;
+                        }
+                    };
+                    let response = RpcChunkResponse { chunk_view };
+                    if !notify(&mut sender, id, method_name, response).await {
+                        return;
+                    }
+                }
+            }
+        }
+        Subscription::AccountChanges(request) => {
+            let mut blocks = handler.block_feeds.subscribe(&handler, request.finality);
+            while let Some(block) = next_block(&mut blocks).await {
+                let block_hash = block.header.hash;
+                let changes: Result<_, RpcStateChangesError> = handler
+                    .view_client_send(GetStateChanges {
+                        block_hash,
+                        state_changes_request: StateChangesRequestView::AccountChanges {
+                            account_ids: request.account_ids.clone(),
+                        },
+                    })
+                    .await;
+                let changes = match changes {
+                    Ok(changes) => changes,
+                    Err(err) => {
+                        tracing::debug!(target: "jsonrpc", ?err, ?block_hash, "Failed to fetch state changes for subscription");
+                        continue;
+                    }
+                };
+                if changes.is_empty() {
+                    continue;
+                }
+                let response = RpcStateChangesInBlockResponse { block_hash, changes };
+                if !notify(&mut sender, id, method_name, response).await {
+                    return;
+                }
+            }
+        }
+        Subscription::TxStatus(request) => {
+            let (tx_hash, signer_account_id) = request.transaction_info.to_tx_hash_and_account();
+            // The status can only advance when a new block is processed, so
+            // it's checked once per block rather than on a timer.
+            let mut blocks = handler.block_feeds.subscribe(&handler, Finality::None);
+            let mut last_status = None;
+            loop {
+                let status: Result<_, RpcTransactionError> = handler
+                    .view_client_send(TxStatus {
+                        tx_hash,
+                        signer_account_id: signer_account_id.clone(),
+                        fetch_receipt: false,
+                    })
+                    .await;
+                match status {
+                    Ok(status) if last_status.as_ref() != Some(&status.status) => {
+                        last_status = Some(status.status.clone());
+                        let done = status.status >= request.wait_until;
+                        let response: RpcTransactionResponse = status.into();
+                        if !notify(&mut sender, id, method_name, response).await || done {
+                            return;
+                        }
+                    }
+                    Ok(_) | Err(RpcTransactionError::UnknownTransaction { .. }) => {}
+                    Err(err) => {
+                        tracing::debug!(target: "jsonrpc", ?err, ?tx_hash, "Failed to fetch transaction status for subscription");
+                    }
+                }
+                if next_block(&mut blocks).await.is_none() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Waits for the next block of a feed.
+///
+/// Returns `None` if the feed has stopped.  A subscription which fell too far
+/// behind skips the blocks it missed.
+async fn next_block(blocks: &mut broadcast::Receiver<Arc<BlockView>>) -> Option<Arc<BlockView>> {
+    loop {
+        match blocks.recv().await {
+            Ok(block) => return Some(block),
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                tracing::debug!(target: "jsonrpc", skipped, "Subscription fell behind the block feed");
+            }
+            Err(broadcast::error::RecvError::Closed) => return None,
+        }
+    }
+}
+
+/// Feeds of new blocks shared by all subscriptions of the server, one for
+/// each finality.
+///
+/// A feed is started by its first subscriber and stops once it has none left.
+#[derive(Default)]
+pub(crate) struct BlockFeeds {
+    feeds: Mutex<Vec<(Finality, broadcast::Sender<Arc<BlockView>>)>>,
+}
+
+impl BlockFeeds {
+    /// Returns a receiver of the blocks which become the head of `finality`
+    /// from now on.
+    fn subscribe(
+        &self,
+        handler: &web::Data<JsonRpcHandler>,
+        finality: Finality,
+    ) -> broadcast::Receiver<Arc<BlockView>> {
+        let mut feeds = self.feeds.lock().unwrap();
+        if let Some((_, sender)) =
+            feeds.iter().find(|(feed_finality, _)| feed_finality == &finality)
+        {
+            return sender.subscribe();
+        }
+        let (sender, receiver) = broadcast::channel(BLOCK_FEED_QUEUE_SIZE);
+        feeds.push((finality.clone(), sender.clone()));
+        actix::spawn(run_block_feed(handler.clone(), finality, sender));
+        receiver
+    }
+
+    /// Removes the feed of `finality` if nobody is subscribed to it anymore.
+    ///
+    /// Returns whether the feed was removed and should stop.
+    fn remove_if_unused(&self, finality: &Finality) -> bool {
+        let mut feeds = self.feeds.lock().unwrap();
+        let len = feeds.len();
+        feeds.retain(|(feed_finality, sender)| {
+            feed_finality != finality || sender.receiver_count() > 0
+        });
+        feeds.len() < len
+    }
+}
+
+/// Follows the head of given finality and publishes every block which
+/// becomes part of its chain, in increasing height order.
+async fn run_block_feed(
+    handler: web::Data<JsonRpcHandler>,
+    finality: Finality,
+    sender: broadcast::Sender<Arc<BlockView>>,
+) {
+    let mut last_head: Option<(CryptoHash, BlockHeight)> = None;
+    while !handler.block_feeds.remove_if_unused(&finality) {
+        let head: Result<BlockView, RpcBlockError> =
+            handler.view_client_send(GetBlock(BlockReference::Finality(finality.clone()))).await;
+        match head {
+            Ok(head) if last_head.map_or(true, |(hash, _)| hash != head.header.hash) => {
+                let new_head = (head.header.hash, head.header.height);
+                for block in blocks_since(&handler, head, last_head).await {
+                    // An error only means there are no subscribers at the
+                    // moment, which is checked at the top of the loop.
+                    let _ = sender.send(Arc::new(block));
+                }
+                last_head = Some(new_head);
+            }
+            Ok(_) => {}
+            Err(err) => {
+                tracing::debug!(target: "jsonrpc", ?err, "Failed to fetch head for block feed");
+            }
+        }
+        sleep(handler.polling_config.polling_interval).await;
+    }
+}
+
+/// Returns the blocks after `last_head` up to and including `head`, oldest
+/// first.
+///
+/// The blocks are found by following `prev_hash` back from `head` so that all
+/// of them are on the chain of `head` even if it's on a different fork than
+/// `last_head`.  Without a `last_head`, just `head` is returned.
+async fn blocks_since(
+    handler: &JsonRpcHandler,
+    head: BlockView,
+    last_head: Option<(CryptoHash, BlockHeight)>,
+) -> Vec<BlockView> {
+    let mut blocks = vec![head];
+    let Some((last_hash, last_height)) = last_head else {
+        return blocks;
+    };
+    while blocks.len() < MAX_CATCH_UP_BLOCKS {
+        let header = &blocks.last().unwrap().header;
+        if header.prev_hash == last_hash
+            || header.prev_height.map_or(true, |height| height <= last_height)
+        {
+            break;
+        }
+        let prev_hash = header.prev_hash;
+        let block: Result<BlockView, RpcBlockError> = handler
+            .view_client_send(GetBlock(BlockReference::BlockId(BlockId::Hash(prev_hash))))
+            .await;
+        match block {
+            Ok(block) => blocks.push(block),
+            Err(err) => {
+                tracing::debug!(target: "jsonrpc", ?err, ?prev_hash, "Failed to fetch block for block feed");
+                break;
+            }
+        }
+    }
+    blocks.reverse();
+    blocks
+}
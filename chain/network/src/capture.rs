@@ -0,0 +1,266 @@
+//! Capture of the network traffic of a node.
+//!
+//! When `NetworkConfig::capture_path` is set, every `PeerMessage` sent to or received
+//! from a peer is appended to a capture file, together with the time and the peer.
+//! The capture can be replayed later against another node, see
+//! `raw::Connection::send_captured`.
+//!
+//! Capture file consists of `MAGIC` followed by a zstd-compressed stream of
+//! borsh-encoded `Record`s. The stream is flushed regularly, so that a capture of a node
+//! which has crashed is readable up to (approximately) the time of the crash.
+use crate::network_protocol::{Encoding, PeerMessage};
+use crate::stats::metrics;
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_async::time;
+use near_primitives::network::PeerId;
+use std::io::{self, BufRead, Read, Write};
+use std::net::SocketAddr;
+use std::path::Path;
+
+/// Prefix of every capture file, identifying the format.
+const MAGIC: &[u8] = b"NEARCAP1";
+/// Maximal number of records waiting to be written.
+/// Records exceeding that limit are dropped, so that a slow disk doesn't slow down the node.
+const RECORD_QUEUE_CAPACITY: usize = 10000;
+const ZSTD_LEVEL: i32 = 3;
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// Message received from the peer.
+    Inbound,
+    /// Message sent to the peer.
+    Outbound,
+}
+
+/// Single captured message.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Record {
+    /// Time at which the message was sent or received, in nanoseconds since the UNIX epoch.
+    pub timestamp_nanos: i128,
+    pub direction: Direction,
+    /// None if the message was exchanged before the handshake has been completed.
+    pub peer_id: Option<PeerId>,
+    pub peer_addr: String,
+    /// Message encoded as a protobuf, regardless of the encoding used on the connection.
+    pub message: Vec<u8>,
+}
+
+impl Record {
+    pub fn timestamp(&self) -> time::Utc {
+        time::Utc::from_unix_timestamp_nanos(self.timestamp_nanos).unwrap_or(time::Utc::UNIX_EPOCH)
+    }
+
+    pub(crate) fn parse(&self) -> anyhow::Result<PeerMessage> {
+        Ok(PeerMessage::deserialize(Encoding::Proto, &self.message)?)
+    }
+
+    /// Name of the type of the captured message, or None if the message cannot be parsed.
+    pub fn message_type(&self) -> Option<&'static str> {
+        self.parse().ok().map(|msg| msg.msg_variant())
+    }
+}
+
+/// Writes records to a capture file.
+pub struct Writer<W: Write> {
+    encoder: zstd::Encoder<'static, W>,
+}
+
+impl<W: Write> Writer<W> {
+    pub fn new(mut w: W) -> io::Result<Self> {
+        w.write_all(MAGIC)?;
+        Ok(Self { encoder: zstd::Encoder::new(w, ZSTD_LEVEL)? })
+    }
+
+    pub fn write(&mut self, record: &Record) -> io::Result<()> {
+        record.serialize(&mut self.encoder)
+    }
+
+    /// Makes all the records written so far readable from the capture.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.encoder.flush()
+    }
+
+    pub fn finish(self) -> io::Result<W> {
+        self.encoder.finish()
+    }
+}
+
+/// Reads records from a capture file.
+pub struct Reader<R: Read> {
+    decoder: io::BufReader<zstd::Decoder<'static, io::BufReader<R>>>,
+}
+
+impl Reader<std::fs::File> {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        Self::new(std::fs::File::open(path)?)
+    }
+}
+
+impl<R: Read> Reader<R> {
+    pub fn new(mut r: R) -> anyhow::Result<Self> {
+        let mut magic = [0; MAGIC.len()];
+        r.read_exact(&mut magic)?;
+        anyhow::ensure!(magic == MAGIC, "not a network capture file");
+        Ok(Self { decoder: io::BufReader::new(zstd::Decoder::new(r)?) })
+    }
+
+    /// Returns the next record, or None at the end of the capture.
+    /// A capture of a node which hasn't been stopped cleanly ends with an error.
+    pub fn next(&mut self) -> io::Result<Option<Record>> {
+        if self.decoder.fill_buf()?.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Record::deserialize_reader(&mut self.decoder)?))
+    }
+}
+
+/// Captures the network traffic of the node in the background.
+pub(crate) struct Recorder {
+    send: Option<crossbeam_channel::Sender<Record>>,
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+impl Recorder {
+    /// Creates the capture file (overwriting it, if it exists) and starts a thread writing to it.
+    pub fn spawn(path: &Path) -> anyhow::Result<Self> {
+        let file = io::BufWriter::new(std::fs::File::create(path)?);
+        let mut writer = Writer::new(file)?;
+        let (send, recv) = crossbeam_channel::bounded::<Record>(RECORD_QUEUE_CAPACITY);
+        let thread = std::thread::spawn(move || {
+            let res = (|| -> io::Result<()> {
+                while let Ok(record) = recv.recv() {
+                    writer.write(&record)?;
+                    if recv.is_empty() {
+                        writer.flush()?;
+                    }
+                }
+                writer.finish()?.flush()
+            })();
+            if let Err(err) = res {
+                tracing::error!(target: "network", ?err, "failed to write the network capture");
+            }
+        });
+        tracing::info!(target: "network", ?path, "capturing the network traffic");
+        Ok(Self { send: Some(send), thread: Some(thread) })
+    }
+
+    pub fn record(
+        &self,
+        clock: &time::Clock,
+        direction: Direction,
+        peer_id: Option<&PeerId>,
+        peer_addr: SocketAddr,
+        msg: &PeerMessage,
+    ) {
+        let record = Record {
+            timestamp_nanos: clock.now_utc().unix_timestamp_nanos(),
+            direction,
+            peer_id: peer_id.cloned(),
+            peer_addr: peer_addr.to_string(),
+            message: msg.serialize(Encoding::Proto),
+        };
+        if self.send.as_ref().unwrap().try_send(record).is_err() {
+            metrics::NETWORK_CAPTURE_DROPPED_RECORDS.inc();
+        }
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        // Closing the channel makes the thread write the remaining records and stop.
+        self.send.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network_protocol::testonly as data;
+    use crate::testonly::make_rng;
+
+    fn make_record(rng: &mut impl rand::Rng, i: u64) -> Record {
+        let msg = PeerMessage::BlockRequest(data::make_hash(rng));
+        Record {
+            timestamp_nanos: i.into(),
+            direction: if i % 2 == 0 { Direction::Inbound } else { Direction::Outbound },
+            peer_id: Some(data::make_peer_id(rng)),
+            peer_addr: "127.0.0.1:24567".to_string(),
+            message: msg.serialize(Encoding::Proto),
+        }
+    }
+
+    #[test]
+    fn write_read() {
+        let mut rng = make_rng(921853233);
+        let records: Vec<_> = (0..100).map(|i| make_record(&mut rng, i)).collect();
+        let mut w = Writer::new(vec![]).unwrap();
+        for r in &records {
+            w.write(r).unwrap();
+        }
+        let buf = w.finish().unwrap();
+        let mut r = Reader::new(&buf[..]).unwrap();
+        for want in &records {
+            assert_eq!(want, &r.next().unwrap().unwrap());
+            assert_eq!(Some("BlockRequest"), want.message_type());
+        }
+        assert_eq!(None, r.next().unwrap());
+    }
+
+    #[test]
+    fn read_unfinished() {
+        let mut rng = make_rng(921853233);
+        let records: Vec<_> = (0..10).map(|i| make_record(&mut rng, i)).collect();
+        let mut w = Writer::new(vec![]).unwrap();
+        for r in &records {
+            w.write(r).unwrap();
+        }
+        w.flush().unwrap();
+        // Writer is not finished, as if the node has crashed.
+        let buf = w.encoder.get_ref().clone();
+        let mut r = Reader::new(&buf[..]).unwrap();
+        for want in &records {
+            assert_eq!(want, &r.next().unwrap().unwrap());
+        }
+        assert!(r.next().is_err());
+    }
+
+    #[test]
+    fn not_a_capture() {
+        assert!(Reader::new(&b"NEARCAP0"[..]).is_err());
+        assert!(Reader::new(&b""[..]).is_err());
+    }
+
+    #[test]
+    fn recorder() {
+        let mut rng = make_rng(921853233);
+        let clock = time::FakeClock::default();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("capture");
+        let peer_id = data::make_peer_id(&mut rng);
+        let addr: SocketAddr = "1.2.3.4:5".parse().unwrap();
+        let msgs: Vec<_> =
+            (0..10).map(|_| PeerMessage::BlockRequest(data::make_hash(&mut rng))).collect();
+        let recorder = Recorder::spawn(&path).unwrap();
+        for msg in &msgs {
+            recorder.record(&clock.clock(), Direction::Inbound, Some(&peer_id), addr, msg);
+            clock.advance(time::Duration::seconds(1));
+        }
+        drop(recorder);
+
+        let mut r = Reader::open(&path).unwrap();
+        let mut want_time = time::FakeClock::default().now_utc();
+        for msg in &msgs {
+            let got = r.next().unwrap().unwrap();
+            assert_eq!(got.timestamp(), want_time);
+            assert_eq!(got.direction, Direction::Inbound);
+            assert_eq!(got.peer_id.as_ref(), Some(&peer_id));
+            assert_eq!(got.peer_addr, "1.2.3.4:5");
+            assert_eq!(&got.parse().unwrap(), msg);
+            want_time += time::Duration::seconds(1);
+        }
+        assert_eq!(None, r.next().unwrap());
+    }
+}
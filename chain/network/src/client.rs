@@ -4,7 +4,7 @@ use crate::types::{NetworkInfo, ReasonForBan};
 
 use near_primitives::block::{Approval, Block, BlockHeader};
 use near_primitives::challenge::Challenge;
-use near_primitives::chunk_validation::ChunkStateWitness;
+use near_primitives::chunk_validation::EncodedChunkStateWitness;
 use near_primitives::hash::CryptoHash;
 use near_primitives::network::{AnnounceAccount, PeerId};
 use near_primitives::transaction::SignedTransaction;
@@ -64,7 +64,7 @@ pub trait Client: Send + Sync + 'static {
         accounts: Vec<(AnnounceAccount, Option<EpochId>)>,
     ) -> Result<Vec<AnnounceAccount>, ReasonForBan>;
 
-    async fn chunk_state_witness(&self, witness: ChunkStateWitness);
+    async fn chunk_state_witness(&self, witness: EncodedChunkStateWitness);
 }
 
 /// Implementation of Client which doesn't do anything and never returns errors.
@@ -133,5 +133,5 @@ impl Client for Noop {
         Ok(vec![])
     }
 
-    async fn chunk_state_witness(&self, _witness: ChunkStateWitness) {}
+    async fn chunk_state_witness(&self, _witness: EncodedChunkStateWitness) {}
 }
@@ -2,6 +2,7 @@ use crate::blacklist;
 use crate::concurrency::rate;
 use crate::network_protocol::PeerAddr;
 use crate::network_protocol::PeerInfo;
+use crate::peer::send_queue;
 use crate::peer_manager::peer_manager_actor::Event;
 use crate::peer_manager::peer_store;
 use crate::sink::Sink;
@@ -17,6 +18,7 @@ use near_primitives::test_utils::create_test_signer;
 use near_primitives::types::AccountId;
 use near_primitives::validator_signer::ValidatorSigner;
 use std::collections::HashSet;
+use std::path::PathBuf;
 use std::sync::Arc;
 
 /// How much height horizon to give to consider peer up to date.
@@ -98,6 +100,7 @@ pub struct NetworkConfig {
 
     pub peer_store: peer_store::Config,
     pub snapshot_hosts: snapshot_hosts::Config,
+    pub send_queue: send_queue::Config,
     pub whitelist_nodes: Vec<PeerInfo>,
     pub handshake_timeout: time::Duration,
 
@@ -165,6 +168,15 @@ pub struct NetworkConfig {
     //   * ignoring received deleted edges as well
     pub skip_tombstones: Option<time::Duration>,
 
+    /// Whether to encrypt connections to the peers which support it (see `peer::noise`).
+    /// Connections to the other peers stay in plaintext, except for the peers at
+    /// `ProtocolFeature::NoiseTransport` protocol version or above, which are rejected.
+    pub encrypted_transport: bool,
+
+    /// If set, all the messages exchanged with the peers are written to this file
+    /// (see `near_network::capture`).
+    pub capture_path: Option<PathBuf>,
+
     /// TEST-ONLY
     /// TODO(gprusak): make it pub(crate), once all integration tests
     /// are merged into near_network.
@@ -286,10 +298,34 @@ impl NetworkConfig {
                 connect_only_to_boot_nodes: cfg.experimental.connect_only_to_boot_nodes,
                 ban_window: cfg.ban_window.try_into()?,
                 peer_expiration_duration: cfg.peer_expiration_duration.try_into()?,
+                reputation: peer_store::ReputationConfig {
+                    invalid_message_penalty: cfg.peer_reputation.invalid_message_penalty,
+                    slow_response_penalty: cfg.peer_reputation.slow_response_penalty,
+                    request_timeout_penalty: cfg.peer_reputation.request_timeout_penalty,
+                    duplicate_routed_message_penalty: cfg
+                        .peer_reputation
+                        .duplicate_routed_message_penalty,
+                    useful_response_reward: cfg.peer_reputation.useful_response_reward,
+                    slow_response_threshold: cfg
+                        .peer_reputation
+                        .slow_response_threshold
+                        .try_into()?,
+                    request_timeout: cfg.peer_reputation.request_timeout.try_into()?,
+                    decay_period: cfg.peer_reputation.decay_period.try_into()?,
+                    deprioritize_threshold: cfg.peer_reputation.deprioritize_threshold,
+                    disconnect_threshold: cfg.peer_reputation.disconnect_threshold,
+                    ban_threshold: cfg.peer_reputation.ban_threshold,
+                },
             },
             snapshot_hosts: snapshot_hosts::Config {
                 snapshot_hosts_cache_size: cfg.snapshot_hosts_cache_size,
             },
+            send_queue: send_queue::Config {
+                consensus_weight: cfg.send_queue.consensus_weight,
+                regular_weight: cfg.send_queue.regular_weight,
+                bulk_weight: cfg.send_queue.bulk_weight,
+                max_bytes_per_second: cfg.send_queue.max_bytes_per_second,
+            },
             whitelist_nodes: if cfg.whitelist_nodes.is_empty() {
                 vec![]
             } else {
@@ -340,6 +376,8 @@ impl NetworkConfig {
             } else {
                 None
             },
+            encrypted_transport: cfg.experimental.encrypted_transport,
+            capture_path: cfg.experimental.capture_path,
             event_sink: Sink::null(),
         };
         this.override_config(cfg.experimental.network_config_overrides);
@@ -371,8 +409,10 @@ impl NetworkConfig {
                 ban_window: time::Duration::seconds(1),
                 peer_expiration_duration: time::Duration::seconds(60 * 60),
                 connect_only_to_boot_nodes: false,
+                reputation: Default::default(),
             },
             snapshot_hosts: snapshot_hosts::Config { snapshot_hosts_cache_size: 1000 },
+            send_queue: Default::default(),
             whitelist_nodes: vec![],
             handshake_timeout: time::Duration::seconds(5),
             connect_to_reliable_peers_on_startup: true,
@@ -407,6 +447,8 @@ impl NetworkConfig {
                 enable_outbound: true,
             }),
             skip_tombstones: None,
+            encrypted_transport: false,
+            capture_path: None,
             event_sink: Sink::null(),
         }
     }
@@ -450,6 +492,8 @@ impl NetworkConfig {
             );
         }
 
+        self.peer_store.reputation.validate().context("peer_store.reputation")?;
+        self.send_queue.validate().context("send_queue")?;
         self.accounts_data_broadcast_rate_limit
             .validate()
             .context("accounts_Data_broadcast_rate_limit")?;
@@ -1,5 +1,6 @@
 use crate::network_protocol::PeerAddr;
 use crate::stun;
+use std::path::PathBuf;
 use std::time::Duration;
 
 /// Time to persist Accounts Id in the router without removing them in seconds.
@@ -144,6 +145,15 @@ pub struct Config {
     // Remove peers that were not active for this amount of time.
     #[serde(default = "default_peer_expiration_duration")]
     pub peer_expiration_duration: Duration,
+    /// Policy for scoring peers based on their behaviour.
+    /// Peers with low reputation are deprioritized, disconnected and eventually banned.
+    #[serde(default)]
+    pub peer_reputation: PeerReputationConfig,
+    /// Scheduling of the messages sent to every peer: messages of different classes
+    /// share the bandwidth according to the weights, and the bandwidth used per peer
+    /// can be capped.
+    #[serde(default)]
+    pub send_queue: SendQueueConfig,
 
     /// List of the public addresses (in the format "<node public key>@<IP>:<port>") of trusted nodes,
     /// which are willing to route messages to this node. Useful only if this node is a validator.
@@ -196,6 +206,63 @@ pub struct Config {
     pub experimental: ExperimentalConfig,
 }
 
+/// See `near_network::peer_manager::peer_store::ReputationConfig`.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct PeerReputationConfig {
+    pub invalid_message_penalty: i32,
+    pub slow_response_penalty: i32,
+    pub request_timeout_penalty: i32,
+    pub duplicate_routed_message_penalty: i32,
+    pub useful_response_reward: i32,
+    pub slow_response_threshold: Duration,
+    pub request_timeout: Duration,
+    pub decay_period: Duration,
+    pub deprioritize_threshold: i32,
+    pub disconnect_threshold: i32,
+    pub ban_threshold: i32,
+}
+
+impl Default for PeerReputationConfig {
+    fn default() -> Self {
+        PeerReputationConfig {
+            invalid_message_penalty: 10,
+            slow_response_penalty: 2,
+            request_timeout_penalty: 5,
+            duplicate_routed_message_penalty: 1,
+            useful_response_reward: 1,
+            slow_response_threshold: Duration::from_secs(2),
+            request_timeout: Duration::from_secs(10),
+            decay_period: Duration::from_secs(10 * 60),
+            deprioritize_threshold: -20,
+            disconnect_threshold: -50,
+            ban_threshold: -80,
+        }
+    }
+}
+
+/// See `near_network::peer::send_queue::Config`.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct SendQueueConfig {
+    pub consensus_weight: u32,
+    pub regular_weight: u32,
+    pub bulk_weight: u32,
+    /// Maximal number of bytes per second sent to a single peer. Unlimited if not set.
+    pub max_bytes_per_second: Option<u64>,
+}
+
+impl Default for SendQueueConfig {
+    fn default() -> Self {
+        SendQueueConfig {
+            consensus_weight: 8,
+            regular_weight: 4,
+            bulk_weight: 1,
+            max_bytes_per_second: None,
+        }
+    }
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
 pub struct ExperimentalConfig {
     // If true - don't allow any inbound connections.
@@ -222,6 +289,15 @@ pub struct ExperimentalConfig {
     /// See `near_network::config::Tier1::new_connections_per_attempt`.
     pub tier1_new_connections_per_attempt: u64,
 
+    /// See `near_network::config::NetworkConfig::encrypted_transport`.
+    #[serde(default)]
+    pub encrypted_transport: bool,
+
+    /// See `near_network::config::NetworkConfig::capture_path`.
+    /// Relative paths are resolved against the working directory of the node.
+    #[serde(default)]
+    pub capture_path: Option<PathBuf>,
+
     /// See `NetworkConfig`.
     /// Fields set here will override the NetworkConfig fields.
     pub network_config_overrides: NetworkConfigOverrides,
@@ -254,6 +330,8 @@ impl Default for ExperimentalConfig {
             tier1_enable_outbound: true,
             tier1_connect_interval: Duration::from_secs(60),
             tier1_new_connections_per_attempt: 50,
+            encrypted_transport: false,
+            capture_path: None,
             network_config_overrides: Default::default(),
         }
     }
@@ -282,6 +360,8 @@ impl Default for Config {
             peer_stats_period: default_peer_stats_period(),
             monitor_peers_max_period: default_monitor_peers_max_period(),
             peer_expiration_duration: default_peer_expiration_duration(),
+            peer_reputation: Default::default(),
+            send_queue: Default::default(),
             public_addrs: vec![],
             allow_private_ip_in_public_addrs: false,
             trusted_stun_servers: default_trusted_stun_servers(),
@@ -13,6 +13,7 @@ mod stun;
 
 pub mod actix;
 pub mod blacklist;
+pub mod capture;
 pub mod client;
 pub mod concurrency;
 pub mod config;
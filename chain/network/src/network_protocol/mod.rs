@@ -8,7 +8,7 @@ mod proto_conv;
 mod state_sync;
 pub use edge::*;
 use near_primitives::chunk_validation::ChunkEndorsement;
-use near_primitives::chunk_validation::ChunkStateWitness;
+use near_primitives::chunk_validation::EncodedChunkStateWitness;
 pub use peer::*;
 pub use state_sync::*;
 
@@ -350,6 +350,8 @@ pub struct Handshake {
     pub(crate) partial_edge_info: PartialEdgeInfo,
     /// Account owned by the sender.
     pub(crate) owned_account: Option<SignedOwnedAccount>,
+    /// Message of the Noise handshake, see `peer::noise`.
+    pub(crate) noise_handshake: Option<Vec<u8>>,
 }
 
 #[derive(PartialEq, Eq, Clone, Debug, strum::IntoStaticStr)]
@@ -543,7 +545,7 @@ pub enum RoutedMessageBody {
     _UnusedVersionedStateResponse,
     PartialEncodedChunkForward(PartialEncodedChunkForwardMsg),
 
-    ChunkStateWitness(ChunkStateWitness),
+    ChunkStateWitness(EncodedChunkStateWitness),
     ChunkEndorsement(ChunkEndorsement),
 }
 
@@ -817,6 +819,13 @@ impl StateResponseInfo {
         }
     }
 
+    pub fn part_id(&self) -> Option<u64> {
+        match self {
+            Self::V1(info) => info.state_response.part_id(),
+            Self::V2(info) => info.state_response.part_id(),
+        }
+    }
+
     pub fn take_state_response(self) -> ShardStateSyncResponse {
         match self {
             Self::V1(info) => ShardStateSyncResponse::V1(info.state_response),
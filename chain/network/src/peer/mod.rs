@@ -1,4 +1,6 @@
+pub(crate) mod noise;
 pub(crate) mod peer_actor;
+pub(crate) mod send_queue;
 mod stream;
 mod tracker;
 mod transfer_stats;
@@ -0,0 +1,416 @@
+//! Noise_IK_25519_ChaChaPoly_SHA256 handshake and encryption of the peer connections.
+//! See https://noiseprotocol.org/noise.html for the specification.
+//!
+//! The static keys of the handshake are the node keys (ED25519) converted to X25519.
+//! Thanks to that the outbound side (initiator) knows the static key of the inbound
+//! side (responder) upfront from its PeerId, and the responder verifies that the static key
+//! sent by the initiator belongs to the `sender_peer_id` of the Handshake.
+//!
+//! The handshake messages are carried in the `noise_handshake` field of the Handshake:
+//!   -> e, es, s, ss  (Handshake sent by the outbound peer)
+//!   <- e, ee, se     (Handshake sent by the inbound peer in response)
+//! Once the handshake is completed, every frame sent over the connection is encrypted.
+//! Unlike the Noise spec, frames are not split into 64KiB transport messages, since
+//! ChaCha20-Poly1305 supports way larger messages than our NETWORK_MESSAGE_MAX_SIZE_BYTES.
+//!
+//! Peers which don't support encryption just ignore the `noise_handshake` field, in which
+//! case the connection stays in plaintext. To prevent an active attacker from downgrading
+//! the connection by stripping the field, a node with encryption enabled doesn't accept
+//! plaintext connections with peers at `ProtocolFeature::NoiseTransport` protocol version
+//! or above.
+use curve25519_dalek::montgomery::MontgomeryPoint;
+use curve25519_dalek::scalar::Scalar;
+use hkdf::Hkdf;
+use near_crypto::{key_conversion, PublicKey, SecretKey};
+use near_primitives::network::PeerId;
+use rand::RngCore as _;
+use ring::aead;
+use sha2::{Digest as _, Sha256};
+
+/// Name of the Noise protocol. It is exactly 32 bytes long, so it is used as the
+/// initial handshake hash directly.
+const PROTOCOL_NAME: &[u8; 32] = b"Noise_IK_25519_ChaChaPoly_SHA256";
+/// Binds the handshake to the NEAR network protocol.
+const PROLOGUE: &[u8] = b"near-network";
+
+const DH_LEN: usize = 32;
+/// Length of the authentication tag appended to every encrypted message.
+pub(crate) const TAG_LEN: usize = 16;
+/// e, encrypted s, encrypted empty payload.
+const INITIATOR_MESSAGE_LEN: usize = DH_LEN + DH_LEN + TAG_LEN + TAG_LEN;
+/// e, encrypted empty payload.
+const RESPONDER_MESSAGE_LEN: usize = DH_LEN + TAG_LEN;
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Error {
+    #[error("only ED25519 keys are supported")]
+    UnsupportedKeyType,
+    #[error("public key is not a valid curve point")]
+    InvalidPublicKey,
+    #[error("handshake message has length {got}, want {want}")]
+    InvalidMessageLength { got: usize, want: usize },
+    #[error("Diffie-Hellman key exchange with a low order point")]
+    LowOrderPoint,
+    #[error("static key of the peer doesn't match its PeerId")]
+    StaticKeyMismatch,
+    #[error("encryption failed")]
+    Encrypt,
+    #[error("decryption failed")]
+    Decrypt,
+    #[error("nonce exhausted")]
+    NonceExhausted,
+}
+
+struct KeyPair {
+    secret: Scalar,
+    public: MontgomeryPoint,
+}
+
+impl KeyPair {
+    fn new(secret: Scalar) -> Self {
+        Self { public: key_conversion::x25519_public_key(&secret), secret }
+    }
+
+    fn generate() -> Self {
+        let mut bytes = [0; 64];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        Self::new(Scalar::from_bytes_mod_order_wide(&bytes))
+    }
+
+    fn from_node_key(key: &SecretKey) -> Result<Self, Error> {
+        match key {
+            SecretKey::ED25519(key) => {
+                Ok(Self::new(key_conversion::convert_secret_key_to_x25519(key)))
+            }
+            SecretKey::SECP256K1(_) => Err(Error::UnsupportedKeyType),
+        }
+    }
+
+    fn dh(&self, public: &MontgomeryPoint) -> Result<[u8; DH_LEN], Error> {
+        let shared = (public * &self.secret).to_bytes();
+        if shared == [0; DH_LEN] {
+            return Err(Error::LowOrderPoint);
+        }
+        Ok(shared)
+    }
+}
+
+fn static_public_key(peer_id: &PeerId) -> Result<MontgomeryPoint, Error> {
+    match peer_id.public_key() {
+        PublicKey::ED25519(key) => {
+            key_conversion::convert_public_key_to_x25519(key).ok_or(Error::InvalidPublicKey)
+        }
+        PublicKey::SECP256K1(_) => Err(Error::UnsupportedKeyType),
+    }
+}
+
+fn read_public_key(msg: &[u8]) -> MontgomeryPoint {
+    MontgomeryPoint(msg[..DH_LEN].try_into().unwrap())
+}
+
+fn check_length(msg: &[u8], want: usize) -> Result<(), Error> {
+    if msg.len() != want {
+        return Err(Error::InvalidMessageLength { got: msg.len(), want });
+    }
+    Ok(())
+}
+
+/// ChaCha20-Poly1305 cipher with a counter-based nonce.
+pub(crate) struct CipherState {
+    key: aead::LessSafeKey,
+    nonce: u64,
+}
+
+impl CipherState {
+    fn new(key: &[u8; 32]) -> Self {
+        let key = aead::UnboundKey::new(&aead::CHACHA20_POLY1305, key).unwrap();
+        Self { key: aead::LessSafeKey::new(key), nonce: 0 }
+    }
+
+    fn next_nonce(&mut self) -> Result<aead::Nonce, Error> {
+        // The maximal nonce is reserved by the Noise spec.
+        if self.nonce == u64::MAX {
+            return Err(Error::NonceExhausted);
+        }
+        let mut nonce = [0; aead::NONCE_LEN];
+        nonce[4..].copy_from_slice(&self.nonce.to_le_bytes());
+        self.nonce += 1;
+        Ok(aead::Nonce::assume_unique_for_key(nonce))
+    }
+
+    fn encrypt_with_ad(&mut self, ad: &[u8], msg: &mut Vec<u8>) -> Result<(), Error> {
+        let nonce = self.next_nonce()?;
+        self.key
+            .seal_in_place_append_tag(nonce, aead::Aad::from(ad), msg)
+            .map_err(|_| Error::Encrypt)
+    }
+
+    fn decrypt_with_ad(&mut self, ad: &[u8], msg: &mut Vec<u8>) -> Result<(), Error> {
+        let nonce = self.next_nonce()?;
+        let len = self
+            .key
+            .open_in_place(nonce, aead::Aad::from(ad), &mut msg[..])
+            .map_err(|_| Error::Decrypt)?
+            .len();
+        msg.truncate(len);
+        Ok(())
+    }
+
+    /// Encrypts a frame in place, appending TAG_LEN bytes to it.
+    pub fn encrypt(&mut self, frame: &mut Vec<u8>) -> Result<(), Error> {
+        self.encrypt_with_ad(&[], frame)
+    }
+
+    /// Decrypts a frame in place.
+    pub fn decrypt(&mut self, frame: &mut Vec<u8>) -> Result<(), Error> {
+        self.decrypt_with_ad(&[], frame)
+    }
+}
+
+struct SymmetricState {
+    /// Chaining key.
+    ck: [u8; 32],
+    /// Handshake hash.
+    h: [u8; 32],
+    cipher: Option<CipherState>,
+}
+
+impl SymmetricState {
+    fn new() -> Self {
+        let mut s = Self { ck: *PROTOCOL_NAME, h: *PROTOCOL_NAME, cipher: None };
+        s.mix_hash(PROLOGUE);
+        s
+    }
+
+    fn mix_hash(&mut self, data: &[u8]) {
+        self.h = Sha256::new().chain_update(self.h).chain_update(data).finalize().into();
+    }
+
+    /// HKDF with 2 outputs, as defined by the Noise spec.
+    fn hkdf(&self, input_key_material: &[u8]) -> ([u8; 32], [u8; 32]) {
+        let mut out = [0; 64];
+        Hkdf::<Sha256>::new(Some(&self.ck[..]), input_key_material).expand(&[], &mut out).unwrap();
+        (out[..32].try_into().unwrap(), out[32..].try_into().unwrap())
+    }
+
+    fn mix_key(&mut self, input_key_material: &[u8]) {
+        let (ck, k) = self.hkdf(input_key_material);
+        self.ck = ck;
+        self.cipher = Some(CipherState::new(&k));
+    }
+
+    fn encrypt_and_hash(&mut self, plaintext: &[u8], out: &mut Vec<u8>) -> Result<(), Error> {
+        let mut msg = plaintext.to_vec();
+        if let Some(cipher) = &mut self.cipher {
+            cipher.encrypt_with_ad(&self.h, &mut msg)?;
+        }
+        self.mix_hash(&msg);
+        out.extend_from_slice(&msg);
+        Ok(())
+    }
+
+    fn decrypt_and_hash(&mut self, ciphertext: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut msg = ciphertext.to_vec();
+        if let Some(cipher) = &mut self.cipher {
+            cipher.decrypt_with_ad(&self.h, &mut msg)?;
+        }
+        self.mix_hash(ciphertext);
+        Ok(msg)
+    }
+
+    /// Returns the ciphers for the (initiator->responder, responder->initiator) directions.
+    fn split(&self) -> (CipherState, CipherState) {
+        let (k1, k2) = self.hkdf(&[]);
+        (CipherState::new(&k1), CipherState::new(&k2))
+    }
+}
+
+/// Ciphers of the connection, established by the handshake.
+pub(crate) struct Transport {
+    pub send: CipherState,
+    pub recv: CipherState,
+}
+
+/// Handshake state of the outbound side, awaiting the response of the inbound side.
+pub(crate) struct Initiator {
+    state: SymmetricState,
+    s: KeyPair,
+    e: KeyPair,
+}
+
+impl Initiator {
+    /// Starts the handshake with `peer_id`.
+    /// Returns the handshake state and the message to send to the peer.
+    pub fn start(node_key: &SecretKey, peer_id: &PeerId) -> Result<(Self, Vec<u8>), Error> {
+        let s = KeyPair::from_node_key(node_key)?;
+        let rs = static_public_key(peer_id)?;
+        let e = KeyPair::generate();
+        let mut state = SymmetricState::new();
+        state.mix_hash(rs.as_bytes());
+
+        let mut msg = Vec::with_capacity(INITIATOR_MESSAGE_LEN);
+        msg.extend_from_slice(e.public.as_bytes());
+        state.mix_hash(e.public.as_bytes());
+        state.mix_key(&e.dh(&rs)?);
+        state.encrypt_and_hash(s.public.as_bytes(), &mut msg)?;
+        state.mix_key(&s.dh(&rs)?);
+        state.encrypt_and_hash(&[], &mut msg)?;
+        Ok((Self { state, s, e }, msg))
+    }
+
+    /// Processes the response of the peer and completes the handshake.
+    pub fn finish(mut self, msg: &[u8]) -> Result<Transport, Error> {
+        check_length(msg, RESPONDER_MESSAGE_LEN)?;
+        let re = read_public_key(msg);
+        self.state.mix_hash(re.as_bytes());
+        self.state.mix_key(&self.e.dh(&re)?);
+        self.state.mix_key(&self.s.dh(&re)?);
+        self.state.decrypt_and_hash(&msg[DH_LEN..])?;
+        let (send, recv) = self.state.split();
+        Ok(Transport { send, recv })
+    }
+}
+
+/// Processes the handshake message received from `peer_id` and completes the handshake.
+/// Returns the established ciphers and the response to send to the peer.
+pub(crate) fn respond(
+    node_key: &SecretKey,
+    peer_id: &PeerId,
+    msg: &[u8],
+) -> Result<(Transport, Vec<u8>), Error> {
+    check_length(msg, INITIATOR_MESSAGE_LEN)?;
+    let s = KeyPair::from_node_key(node_key)?;
+    let want_rs = static_public_key(peer_id)?;
+    let mut state = SymmetricState::new();
+    state.mix_hash(s.public.as_bytes());
+
+    let re = read_public_key(msg);
+    state.mix_hash(re.as_bytes());
+    state.mix_key(&s.dh(&re)?);
+    let rs = state.decrypt_and_hash(&msg[DH_LEN..DH_LEN + DH_LEN + TAG_LEN])?;
+    let rs = MontgomeryPoint(rs.try_into().unwrap());
+    if rs != want_rs {
+        return Err(Error::StaticKeyMismatch);
+    }
+    state.mix_key(&s.dh(&rs)?);
+    state.decrypt_and_hash(&msg[DH_LEN + DH_LEN + TAG_LEN..])?;
+
+    let e = KeyPair::generate();
+    let mut resp = Vec::with_capacity(RESPONDER_MESSAGE_LEN);
+    resp.extend_from_slice(e.public.as_bytes());
+    state.mix_hash(e.public.as_bytes());
+    state.mix_key(&e.dh(&re)?);
+    state.mix_key(&e.dh(&rs)?);
+    state.encrypt_and_hash(&[], &mut resp)?;
+    let (recv, send) = state.split();
+    Ok((Transport { send, recv }, resp))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_crypto::KeyType;
+
+    fn make_key(seed: &str) -> (SecretKey, PeerId) {
+        let key = SecretKey::from_seed(KeyType::ED25519, seed);
+        let peer_id = PeerId::new(key.public_key());
+        (key, peer_id)
+    }
+
+    #[test]
+    fn handshake_and_transport() {
+        let (a_key, a_id) = make_key("a");
+        let (b_key, b_id) = make_key("b");
+        let (initiator, msg1) = Initiator::start(&a_key, &b_id).unwrap();
+        let (mut b, msg2) = respond(&b_key, &a_id, &msg1).unwrap();
+        let mut a = initiator.finish(&msg2).unwrap();
+
+        for i in 0..3 {
+            let want = format!("message {i}").into_bytes();
+            let mut frame = want.clone();
+            a.send.encrypt(&mut frame).unwrap();
+            assert_eq!(frame.len(), want.len() + TAG_LEN);
+            assert_ne!(frame[..want.len()], want[..]);
+            b.recv.decrypt(&mut frame).unwrap();
+            assert_eq!(frame, want);
+
+            let mut frame = want.clone();
+            b.send.encrypt(&mut frame).unwrap();
+            a.recv.decrypt(&mut frame).unwrap();
+            assert_eq!(frame, want);
+        }
+    }
+
+    #[test]
+    fn tampered_frame() {
+        let (a_key, a_id) = make_key("a");
+        let (b_key, b_id) = make_key("b");
+        let (initiator, msg1) = Initiator::start(&a_key, &b_id).unwrap();
+        let (mut b, msg2) = respond(&b_key, &a_id, &msg1).unwrap();
+        let mut a = initiator.finish(&msg2).unwrap();
+
+        let mut frame = b"hello".to_vec();
+        a.send.encrypt(&mut frame).unwrap();
+        frame[0] ^= 1;
+        assert_eq!(Err(Error::Decrypt), b.recv.decrypt(&mut frame));
+    }
+
+    #[test]
+    fn replayed_frame() {
+        let (a_key, a_id) = make_key("a");
+        let (b_key, b_id) = make_key("b");
+        let (initiator, msg1) = Initiator::start(&a_key, &b_id).unwrap();
+        let (mut b, msg2) = respond(&b_key, &a_id, &msg1).unwrap();
+        let mut a = initiator.finish(&msg2).unwrap();
+
+        let mut frame = b"hello".to_vec();
+        a.send.encrypt(&mut frame).unwrap();
+        let mut replay = frame.clone();
+        b.recv.decrypt(&mut frame).unwrap();
+        assert_eq!(Err(Error::Decrypt), b.recv.decrypt(&mut replay));
+    }
+
+    #[test]
+    fn wrong_responder() {
+        let (a_key, a_id) = make_key("a");
+        let (_, b_id) = make_key("b");
+        let (c_key, _) = make_key("c");
+        // The initiator expects to talk to b, but c responds.
+        let (_, msg1) = Initiator::start(&a_key, &b_id).unwrap();
+        assert_eq!(Some(Error::Decrypt), respond(&c_key, &a_id, &msg1).err());
+    }
+
+    #[test]
+    fn wrong_initiator() {
+        let (a_key, _) = make_key("a");
+        let (b_key, b_id) = make_key("b");
+        let (_, c_id) = make_key("c");
+        // a pretends to be c.
+        let (_, msg1) = Initiator::start(&a_key, &b_id).unwrap();
+        assert_eq!(Some(Error::StaticKeyMismatch), respond(&b_key, &c_id, &msg1).err());
+    }
+
+    #[test]
+    fn tampered_response() {
+        let (a_key, a_id) = make_key("a");
+        let (b_key, b_id) = make_key("b");
+        let (initiator, msg1) = Initiator::start(&a_key, &b_id).unwrap();
+        let (_, mut msg2) = respond(&b_key, &a_id, &msg1).unwrap();
+        *msg2.last_mut().unwrap() ^= 1;
+        assert_eq!(Some(Error::Decrypt), initiator.finish(&msg2).err());
+    }
+
+    #[test]
+    fn invalid_message_length() {
+        let (a_key, a_id) = make_key("a");
+        let (b_key, b_id) = make_key("b");
+        let (_, msg1) = Initiator::start(&a_key, &b_id).unwrap();
+        assert_eq!(
+            Some(Error::InvalidMessageLength {
+                got: INITIATOR_MESSAGE_LEN - 1,
+                want: INITIATOR_MESSAGE_LEN
+            }),
+            respond(&b_key, &a_id, &msg1[1..]).err()
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use crate::accounts_data::AccountDataError;
+use crate::capture;
 use crate::concurrency::atomic_cell::AtomicCell;
 use crate::concurrency::demux;
 use crate::config::PEERS_RESPONSE_MAX_PEERS;
@@ -9,12 +10,15 @@ use crate::network_protocol::{
     RawRoutedMessage, RoutedMessageBody, RoutingTableUpdate, StateResponseInfo, SyncAccountsData,
     SyncSnapshotHosts,
 };
+use crate::peer::noise;
+use crate::peer::send_queue;
 use crate::peer::stream;
-use crate::peer::tracker::Tracker;
+use crate::peer::tracker::{PendingRequest, Tracker};
 use crate::peer_manager::connection;
 use crate::peer_manager::network_state::{NetworkState, PRUNE_EDGES_AFTER};
 use crate::peer_manager::peer_manager_actor::Event;
 use crate::peer_manager::peer_manager_actor::MAX_TIER2_PEERS;
+use crate::peer_manager::peer_store::ReputationEvent;
 use crate::private_actix::{RegisterPeerError, SendMessage};
 use crate::routing::edge::verify_nonce;
 use crate::routing::NetworkTopologyChange;
@@ -37,7 +41,7 @@ use near_primitives::network::{AnnounceAccount, PeerId};
 use near_primitives::types::EpochId;
 use near_primitives::utils::DisplayOption;
 use near_primitives::version::{
-    ProtocolVersion, PEER_MIN_ALLOWED_PROTOCOL_VERSION, PROTOCOL_VERSION,
+    ProtocolFeature, ProtocolVersion, PEER_MIN_ALLOWED_PROTOCOL_VERSION, PROTOCOL_VERSION,
 };
 use parking_lot::Mutex;
 use rand::seq::IteratorRandom;
@@ -169,6 +173,9 @@ pub(crate) struct PeerActor {
     /// Whether the PeerActor should skip protobuf support detection and use
     /// a given encoding right away.
     force_encoding: Option<Encoding>,
+    /// State of the Noise handshake offered in the last outbound Handshake.
+    /// None, if no encryption was offered.
+    noise_initiator: Option<noise::Initiator>,
 
     /// Peer status.
     peer_status: PeerStatus,
@@ -303,7 +310,13 @@ impl PeerActor {
                 let peer_addr = stream.peer_addr;
                 let stream_type = stream.type_.clone();
                 let stats = Arc::new(connection::Stats::default());
-                let framed = stream::FramedStream::spawn(ctx, stream, stats.clone());
+                let framed = stream::FramedStream::spawn(
+                    ctx,
+                    &clock,
+                    stream,
+                    stats.clone(),
+                    network_state.config.send_queue.clone(),
+                );
                 Self {
                     closing_reason: None,
                     clock,
@@ -321,6 +334,7 @@ impl PeerActor {
                     routed_message_cache: LruCache::new(ROUTED_MESSAGE_CACHE_SIZE),
                     protocol_buffers_supported: false,
                     force_encoding,
+                    noise_initiator: None,
                     peer_info: match &stream_type {
                         tcp::StreamType::Inbound => None,
                         tcp::StreamType::Outbound { peer_id, .. } => Some(PeerInfo {
@@ -373,6 +387,7 @@ impl PeerActor {
         if let (PeerStatus::Ready(conn), PeerMessage::PeersRequest(_)) = (&self.peer_status, msg) {
             conn.last_time_peer_requested.store(Some(self.clock.now()));
         }
+        self.capture(capture::Direction::Outbound, msg);
         if let Some(enc) = self.encoding() {
             return self.send_message_with_encoding(msg, enc);
         }
@@ -380,6 +395,14 @@ impl PeerActor {
         self.send_message_with_encoding(msg, Encoding::Borsh);
     }
 
+    /// Appends the message to the network capture, if enabled.
+    fn capture(&self, direction: capture::Direction, msg: &PeerMessage) {
+        if let Some(capture) = &self.network_state.capture {
+            let peer_id = self.peer_info.0.as_ref().map(|p| &p.id);
+            capture.record(&self.clock, direction, peer_id, self.peer_addr, msg);
+        }
+    }
+
     fn send_message_with_encoding(&self, msg: &PeerMessage, enc: Encoding) {
         let msg_type: &str = msg.msg_variant();
         let _span = tracing::trace_span!(
@@ -394,7 +417,29 @@ impl PeerActor {
             // peers to update its height at the peer. In the future we will introduce a new
             // peer message type for that and then we can enable this check again.
             //PeerMessage::Block(b) if self.tracker.lock().has_received(b.hash()) => return,
-            PeerMessage::BlockRequest(h) => self.tracker.lock().push_request(*h),
+            PeerMessage::BlockRequest(h) => {
+                let mut tracker = self.tracker.lock();
+                tracker.push_request(*h);
+                // We don't know whether the peer has the block, and honest peers
+                // silently ignore requests for blocks they don't have, so only
+                // the response time counts.
+                tracker.start_request(PendingRequest::Block(*h), self.clock.now(), false);
+            }
+            PeerMessage::StateRequestPart(shard_id, sync_hash, part_id) => {
+                // Only penalise a timeout if the peer advertised that it serves this snapshot.
+                let penalize_timeout = self.other_peer_id().map_or(false, |peer_id| {
+                    self.network_state.snapshot_hosts.is_host(peer_id, sync_hash, *shard_id)
+                });
+                self.tracker.lock().start_request(
+                    PendingRequest::StatePart {
+                        shard_id: *shard_id,
+                        sync_hash: *sync_hash,
+                        part_id: *part_id,
+                    },
+                    self.clock.now(),
+                    penalize_timeout,
+                )
+            }
             PeerMessage::SyncAccountsData(d) => metrics::SYNC_ACCOUNTS_DATA
                 .with_label_values(&[
                     "sent",
@@ -412,7 +457,7 @@ impl PeerActor {
         self.tracker.lock().increment_sent(&self.clock, bytes.len() as u64);
         let bytes_len = bytes.len();
         tracing::trace!(target: "network", msg_len = bytes_len);
-        self.framed.send(stream::Frame(bytes));
+        self.framed.send(send_queue::MessageClass::of(msg), stream::Frame(bytes));
         metrics::PEER_DATA_SENT_BYTES.inc_by(bytes_len as u64);
         metrics::PEER_MESSAGE_SENT_BY_TYPE_TOTAL.with_label_values(&[msg_type]).inc();
         metrics::PEER_MESSAGE_SENT_BY_TYPE_BYTES
@@ -420,7 +465,41 @@ impl PeerActor {
             .inc_by(bytes_len as u64);
     }
 
-    fn send_handshake(&self, spec: HandshakeSpec) {
+    /// Whether a plaintext connection at the given protocol version has to be rejected.
+    /// See `peer::noise` for details.
+    fn encryption_required(&self, protocol_version: ProtocolVersion) -> bool {
+        self.network_state.config.encrypted_transport
+            && ProtocolFeature::NoiseTransport.protocol_version() <= protocol_version
+    }
+
+    /// Sends the Handshake of an outbound connection.
+    /// Offers an encrypted transport, if enabled. The `noise_handshake` field of the Handshake
+    /// serves as the capability flag: peers which don't support encryption ignore it and
+    /// respond without one, in which case the connection stays in plaintext unless
+    /// `encryption_required`.
+    fn send_outbound_handshake(&mut self, spec: HandshakeSpec) {
+        self.noise_initiator = None;
+        // Noise handshake cannot be expressed in Borsh.
+        let noise_handshake = if self.network_state.config.encrypted_transport
+            && self.encoding() != Some(Encoding::Borsh)
+        {
+            match noise::Initiator::start(&self.network_state.config.node_key, &spec.peer_id) {
+                Ok((initiator, msg)) => {
+                    self.noise_initiator = Some(initiator);
+                    Some(msg)
+                }
+                Err(err) => {
+                    tracing::debug!(target: "network", peer_id = ?spec.peer_id, "not offering encrypted transport: {err}");
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        self.send_handshake(spec, noise_handshake);
+    }
+
+    fn send_handshake(&self, spec: HandshakeSpec, noise_handshake: Option<Vec<u8>>) {
         let (height, tracked_shards) =
             if let Some(chain_info) = self.network_state.chain_info.load().as_ref() {
                 (chain_info.block.header().height(), chain_info.tracked_shards.clone())
@@ -449,12 +528,21 @@ impl PeerActor {
                 }
                 .sign(vc.signer.as_ref())
             }),
+            noise_handshake,
         };
+        let encrypted = handshake.noise_handshake.is_some();
         let msg = match spec.tier {
             tcp::Tier::T1 => PeerMessage::Tier1Handshake(handshake),
             tcp::Tier::T2 => PeerMessage::Tier2Handshake(handshake),
         };
-        self.send_message_or_log(&msg);
+        if encrypted {
+            // Peers supporting encryption support Proto encoding. We send the Handshake
+            // just once (rather than in every encoding), because the frame following it
+            // is expected to be encrypted already.
+            self.send_message_with_encoding(&msg, Encoding::Proto);
+        } else {
+            self.send_message_or_log(&msg);
+        }
     }
 
     fn stop(&mut self, ctx: &mut actix::Context<PeerActor>, reason: ClosingReason) {
@@ -486,6 +574,8 @@ impl PeerActor {
             PeerStatus::Connecting(_, it) => it,
             _ => panic!("process_handshake called in non-connecting state"),
         };
+        // Cipher and message to send to the peer in response to its Noise handshake.
+        let mut noise_response = None;
         match cs {
             ConnectingStatus::Outbound { handshake_spec: spec, .. } => {
                 if handshake.protocol_version != spec.protocol_version {
@@ -516,6 +606,33 @@ impl PeerActor {
                     self.stop(ctx, ClosingReason::HandshakeFailed);
                     return;
                 }
+                // If the peer doesn't respond to the Noise handshake, the connection stays in plaintext,
+                // unless encryption is required.
+                if handshake.noise_handshake.is_none()
+                    && self.encryption_required(spec.protocol_version)
+                {
+                    tracing::warn!(target: "network", "Encryption required but not negotiated. Disconnecting peer {}", handshake.sender_peer_id);
+                    self.stop(ctx, ClosingReason::HandshakeFailed);
+                    return;
+                }
+                if let Some(msg) = &handshake.noise_handshake {
+                    let Some(initiator) = self.noise_initiator.take() else {
+                        tracing::warn!(target: "network", "Unexpected noise handshake. Disconnecting peer {}", handshake.sender_peer_id);
+                        self.stop(ctx, ClosingReason::HandshakeFailed);
+                        return;
+                    };
+                    match initiator.finish(msg) {
+                        Ok(transport) => {
+                            self.framed.start_send_encryption(transport.send);
+                            self.framed.start_recv_encryption(transport.recv);
+                        }
+                        Err(err) => {
+                            tracing::warn!(target: "network", "Noise handshake failed: {err}. Disconnecting peer {}", handshake.sender_peer_id);
+                            self.stop(ctx, ClosingReason::HandshakeFailed);
+                            return;
+                        }
+                    }
+                }
             }
             ConnectingStatus::Inbound { .. } => {
                 if PEER_MIN_ALLOWED_PROTOCOL_VERSION > handshake.protocol_version
@@ -569,6 +686,35 @@ impl PeerActor {
                         return;
                     }
                 }
+                if handshake.noise_handshake.is_none()
+                    && self.encryption_required(handshake.protocol_version)
+                {
+                    tracing::debug!(target: "network", peer_id = ?handshake.sender_peer_id, "Encryption required but not offered, disconnecting");
+                    self.stop(ctx, ClosingReason::HandshakeFailed);
+                    return;
+                }
+                // Respond to the Noise handshake if encryption is enabled, otherwise
+                // the connection stays in plaintext.
+                if let Some(msg) = &handshake.noise_handshake {
+                    if self.network_state.config.encrypted_transport {
+                        match noise::respond(
+                            &self.network_state.config.node_key,
+                            &handshake.sender_peer_id,
+                            msg,
+                        ) {
+                            Ok((transport, response)) => {
+                                // The peer starts encrypting as soon as it receives our response.
+                                self.framed.start_recv_encryption(transport.recv);
+                                noise_response = Some((transport.send, response));
+                            }
+                            Err(err) => {
+                                tracing::debug!(target: "network", peer_id = ?handshake.sender_peer_id, "Noise handshake failed: {err}");
+                                self.stop(ctx, ClosingReason::HandshakeFailed);
+                                return;
+                            }
+                        }
+                    }
+                }
             }
         }
 
@@ -653,7 +799,9 @@ impl PeerActor {
             time::Interval::new(clock.now(), self.network_state.config.peer_stats_period);
         ctx.spawn({
             let conn = conn.clone();
+            let network_state = self.network_state.clone();
             wrap_future(async move {
+                let request_timeout = network_state.config.peer_store.reputation.request_timeout;
                 loop {
                     interval.tick(&clock).await;
                     let sent = tracker.lock().sent_bytes.minute_stats(&clock);
@@ -662,6 +810,15 @@ impl PeerActor {
                         .received_bytes_per_sec
                         .store(received.bytes_per_min / 60, Ordering::Relaxed);
                     conn.stats.sent_bytes_per_sec.store(sent.bytes_per_min / 60, Ordering::Relaxed);
+                    let expired =
+                        tracker.lock().take_expired_requests(clock.now(), request_timeout);
+                    for _ in 0..expired {
+                        network_state.record_reputation_event(
+                            &clock,
+                            &conn.peer_info.id,
+                            ReputationEvent::RequestTimeout,
+                        );
+                    }
                 }
             })
         });
@@ -689,12 +846,17 @@ impl PeerActor {
                         act.peer_status = PeerStatus::Ready(conn.clone());
                         // Respond to handshake if it's inbound and connection was consolidated.
                         if act.peer_type == PeerType::Inbound {
+                            let (send_cipher, noise_handshake) = noise_response.unzip();
                             act.send_handshake(HandshakeSpec{
                                 peer_id: handshake.sender_peer_id.clone(),
                                 tier,
                                 protocol_version: handshake.protocol_version,
                                 partial_edge_info: partial_edge_info,
-                            });
+                            }, noise_handshake);
+                            // Everything sent after the Handshake is encrypted.
+                            if let Some(cipher) = send_cipher {
+                                act.framed.start_send_encryption(cipher);
+                            }
                         }
                         // TIER1 is strictly reserved for BFT consensensus messages,
                         // so all kinds of periodical syncs happen only on TIER2 connections.
@@ -854,7 +1016,7 @@ impl PeerActor {
                         handshake_spec.protocol_version = common_version;
                         let spec = handshake_spec.clone();
                         ctx.wait(actix::fut::ready(()).then(move |_, act: &mut Self, _| {
-                            act.send_handshake(spec);
+                            act.send_outbound_handshake(spec);
                             actix::fut::ready(())
                         }));
                     }
@@ -900,7 +1062,7 @@ impl PeerActor {
                 );
                 let spec = handshake_spec.clone();
                 ctx.wait(actix::fut::ready(()).then(move |_, act: &mut Self, _| {
-                    act.send_handshake(spec);
+                    act.send_outbound_handshake(spec);
                     actix::fut::ready(())
                 }));
             }
@@ -1017,6 +1179,7 @@ impl PeerActor {
             .config
             .event_sink
             .delayed_push(|| Event::MessageProcessed(conn.tier, msg.clone()));
+        let mut response_time = None;
         let was_requested = match &msg {
             PeerMessage::Block(block) => {
                 self.network_state.txns_since_last_block.store(0, Ordering::Release);
@@ -1031,10 +1194,33 @@ impl PeerActor {
                 });
                 let mut tracker = self.tracker.lock();
                 tracker.push_received(hash);
+                response_time =
+                    tracker.finish_request(&PendingRequest::Block(hash), self.clock.now());
                 tracker.has_request(&hash)
             }
+            PeerMessage::VersionedStateResponse(info) => {
+                if let Some(part_id) = info.part_id() {
+                    let request = PendingRequest::StatePart {
+                        shard_id: info.shard_id(),
+                        sync_hash: info.sync_hash(),
+                        part_id,
+                    };
+                    response_time = self.tracker.lock().finish_request(&request, self.clock.now());
+                }
+                false
+            }
             _ => false,
         };
+        if let Some(response_time) = response_time {
+            let event = if response_time
+                > self.network_state.config.peer_store.reputation.slow_response_threshold
+            {
+                ReputationEvent::SlowResponse
+            } else {
+                ReputationEvent::UsefulResponse
+            };
+            self.network_state.record_reputation_event(&self.clock, &conn.peer_info.id, event);
+        }
         let clock = self.clock.clone();
         let network_state = self.network_state.clone();
         let peer_id = conn.peer_info.id.clone();
@@ -1331,6 +1517,11 @@ impl PeerActor {
                 if let Some(&t) = self.routed_message_cache.get(&key) {
                     if now <= t + DROP_DUPLICATED_MESSAGES_PERIOD {
                         metrics::MessageDropped::Duplicate.inc(&msg.body);
+                        self.network_state.record_reputation_event(
+                            &self.clock,
+                            &conn.peer_info.id,
+                            ReputationEvent::DuplicateRoutedMessage,
+                        );
                         self.network_state.config.event_sink.push(Event::RoutedMessageDropped);
                         tracing::debug!(target: "network", "Dropping duplicated message from {} to {:?}", msg.author, msg.target);
                         return;
@@ -1482,7 +1673,8 @@ impl actix::Actor for PeerActor {
         if let PeerStatus::Connecting(_, ConnectingStatus::Outbound { handshake_spec, .. }) =
             &self.peer_status
         {
-            self.send_handshake(handshake_spec.clone());
+            let spec = handshake_spec.clone();
+            self.send_outbound_handshake(spec);
         }
         self.network_state
             .config
@@ -1561,6 +1753,9 @@ impl actix::Handler<stream::Error> for PeerActor {
             }
             // It is expected in a sense that the peer might be just slow.
             stream::Error::Send(stream::SendError::QueueOverflow { .. }) => true,
+            // Either the peer is faulty or the traffic has been tampered with.
+            stream::Error::Recv(stream::RecvError::Decrypt(_))
+            | stream::Error::Send(stream::SendError::Encrypt(_)) => true,
             stream::Error::Recv(stream::RecvError::IO(err))
             | stream::Error::Send(stream::SendError::IO(err)) => match err.kind() {
                 // Connection has been closed.
@@ -1613,11 +1808,19 @@ impl actix::Handler<stream::Frame> for PeerActor {
             Ok(msg) => msg,
             Err(err) => {
                 tracing::debug!(target: "network", "Received invalid data {} from {}: {}", near_fmt::AbbrBytes(&msg), self.peer_info, err);
+                if let PeerStatus::Ready(conn) = &self.peer_status {
+                    self.network_state.record_reputation_event(
+                        &self.clock,
+                        &conn.peer_info.id,
+                        ReputationEvent::InvalidMessage,
+                    );
+                }
                 return;
             }
         };
 
         tracing::trace!(target: "network", "Received message: {}", peer_msg);
+        self.capture(capture::Direction::Inbound, &peer_msg);
 
         {
             let labels = [peer_msg.msg_variant()];
@@ -0,0 +1,349 @@
+//! Scheduling of the messages sent to a peer.
+//!
+//! Messages are split into classes (see `MessageClass`), with a separate queue per class.
+//! `Control` messages are always sent first, the remaining classes share the bandwidth
+//! of the connection proportionally to their weights (start-time fair queueing), so that
+//! a large `Block` doesn't delay the `BlockApproval`s queued after it.
+use crate::network_protocol::{PeerMessage, RoutedMessageBody};
+use near_async::time;
+use std::collections::VecDeque;
+
+/// Class of a message sent to a peer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MessageClass {
+    /// Handshakes and other messages managing the connection itself.
+    Control,
+    /// Small, latency-sensitive messages required for block and chunk production.
+    Consensus,
+    /// Messages which don't belong to any other class.
+    Regular,
+    /// Large messages which are not latency-sensitive: blocks, headers and state parts.
+    Bulk,
+}
+
+impl MessageClass {
+    const COUNT: usize = 4;
+
+    pub fn of(msg: &PeerMessage) -> Self {
+        match msg {
+            PeerMessage::Tier1Handshake(_)
+            | PeerMessage::Tier2Handshake(_)
+            | PeerMessage::HandshakeFailure(_, _)
+            | PeerMessage::LastEdge(_)
+            | PeerMessage::RequestUpdateNonce(_) => Self::Control,
+            PeerMessage::Block(_)
+            | PeerMessage::BlockHeaders(_)
+            | PeerMessage::VersionedStateResponse(_) => Self::Bulk,
+            PeerMessage::Routed(msg) => match &msg.body {
+                RoutedMessageBody::BlockApproval(_)
+                | RoutedMessageBody::ChunkEndorsement(_)
+                | RoutedMessageBody::PartialEncodedChunkRequest(_)
+                | RoutedMessageBody::PartialEncodedChunkResponse(_)
+                | RoutedMessageBody::VersionedPartialEncodedChunk(_)
+                | RoutedMessageBody::PartialEncodedChunkForward(_) => Self::Consensus,
+                RoutedMessageBody::StateResponse(_) => Self::Bulk,
+                _ => Self::Regular,
+            },
+            _ => Self::Regular,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Control => "control",
+            Self::Consensus => "consensus",
+            Self::Regular => "regular",
+            Self::Bulk => "bulk",
+        }
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// Configuration of the send queue of a connection.
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Shares of the bandwidth of the connection, assigned to the message classes
+    /// when there is more than one class of messages waiting to be sent.
+    /// `Control` messages are always sent first.
+    pub consensus_weight: u32,
+    pub regular_weight: u32,
+    pub bulk_weight: u32,
+    /// Maximal number of bytes per second sent to a single peer.
+    /// Unlimited if None.
+    pub max_bytes_per_second: Option<u64>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self { consensus_weight: 8, regular_weight: 4, bulk_weight: 1, max_bytes_per_second: None }
+    }
+}
+
+impl Config {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.consensus_weight == 0 || self.regular_weight == 0 || self.bulk_weight == 0 {
+            anyhow::bail!("weights have to be positive");
+        }
+        if self.max_bytes_per_second == Some(0) {
+            anyhow::bail!("max_bytes_per_second has to be positive");
+        }
+        Ok(())
+    }
+
+    fn weight(&self, class: MessageClass) -> u64 {
+        match class {
+            // Control messages are not weighted.
+            MessageClass::Control => 1,
+            MessageClass::Consensus => self.consensus_weight.into(),
+            MessageClass::Regular => self.regular_weight.into(),
+            MessageClass::Bulk => self.bulk_weight.into(),
+        }
+    }
+}
+
+/// Message waiting in the queue.
+pub(crate) struct Entry<T> {
+    pub class: MessageClass,
+    pub size: usize,
+    pub enqueued_at: time::Instant,
+    pub item: T,
+}
+
+/// Part of the queue between two consecutive barriers.
+struct Segment<M, B> {
+    /// Barrier preceding the messages of this segment.
+    barrier: Option<B>,
+    queues: [VecDeque<Entry<M>>; MessageClass::COUNT],
+}
+
+impl<M, B> Segment<M, B> {
+    fn new(barrier: Option<B>) -> Self {
+        Self { barrier, queues: Default::default() }
+    }
+}
+
+/// Item popped from the queue.
+pub(crate) enum Popped<M, B> {
+    Message(Entry<M>),
+    Barrier(B),
+}
+
+/// Multi-class queue of messages.
+///
+/// Apart from messages, the queue supports barriers: a barrier is popped after all the
+/// messages pushed before it and before all the messages pushed after it.
+pub(crate) struct Queue<M, B> {
+    config: Config,
+    segments: VecDeque<Segment<M, B>>,
+    /// Virtual time of the scheduler: the start tag of the last popped message.
+    vtime: u64,
+    /// Finish tag of the last popped message of each class.
+    finish: [u64; MessageClass::COUNT],
+}
+
+/// Scaling factor of the virtual time, so that small messages of classes with large weights
+/// have non-zero cost.
+const VTIME_SCALE: u64 = 1 << 16;
+
+impl<M, B> Queue<M, B> {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            segments: [Segment::new(None)].into(),
+            vtime: 0,
+            finish: [0; MessageClass::COUNT],
+        }
+    }
+
+    pub fn push(&mut self, entry: Entry<M>) {
+        let segment = self.segments.back_mut().unwrap();
+        segment.queues[entry.class.index()].push_back(entry);
+    }
+
+    pub fn push_barrier(&mut self, barrier: B) {
+        self.segments.push_back(Segment::new(Some(barrier)));
+    }
+
+    pub fn pop(&mut self) -> Option<Popped<M, B>> {
+        loop {
+            let segment = self.segments.front_mut().unwrap();
+            if let Some(barrier) = segment.barrier.take() {
+                return Some(Popped::Barrier(barrier));
+            }
+            if let Some(class) = self.next_class() {
+                return Some(Popped::Message(self.pop_class(class)));
+            }
+            if self.segments.len() == 1 {
+                return None;
+            }
+            self.segments.pop_front();
+        }
+    }
+
+    /// Selects the class of the next message of the front segment.
+    fn next_class(&self) -> Option<MessageClass> {
+        let queues = &self.segments.front().unwrap().queues;
+        if !queues[MessageClass::Control.index()].is_empty() {
+            return Some(MessageClass::Control);
+        }
+        [MessageClass::Consensus, MessageClass::Regular, MessageClass::Bulk]
+            .into_iter()
+            .filter(|c| !queues[c.index()].is_empty())
+            .min_by_key(|c| self.start_tag(*c))
+    }
+
+    fn start_tag(&self, class: MessageClass) -> u64 {
+        self.finish[class.index()].max(self.vtime)
+    }
+
+    fn pop_class(&mut self, class: MessageClass) -> Entry<M> {
+        let entry = self.segments.front_mut().unwrap().queues[class.index()].pop_front().unwrap();
+        if class != MessageClass::Control {
+            let start = self.start_tag(class);
+            let cost = (entry.size as u64).saturating_mul(VTIME_SCALE) / self.config.weight(class);
+            self.vtime = start;
+            self.finish[class.index()] = start.saturating_add(cost);
+        }
+        entry
+    }
+}
+
+/// Token bucket limiting the number of bytes sent per second.
+/// A message larger than the available budget is let through, and the
+/// subsequent messages wait until the debt is repaid.
+pub(crate) struct Throttle {
+    bytes_per_second: u64,
+    /// Available budget in bytes; negative if in debt.
+    budget: i64,
+    last_refill: time::Instant,
+}
+
+impl Throttle {
+    pub fn new(bytes_per_second: u64, now: time::Instant) -> Self {
+        Self { bytes_per_second, budget: 0, last_refill: now }
+    }
+
+    fn refill(&mut self, now: time::Instant) {
+        let elapsed = (now - self.last_refill).max(time::Duration::ZERO);
+        // At most 1s worth of bytes can be accumulated.
+        let refill = (elapsed.as_seconds_f64() * self.bytes_per_second as f64) as i64;
+        if refill > 0 {
+            self.budget = self.budget.saturating_add(refill).min(self.burst());
+            self.last_refill = now;
+        }
+    }
+
+    fn burst(&self) -> i64 {
+        self.bytes_per_second.try_into().unwrap_or(i64::MAX)
+    }
+
+    /// Returns how long the caller has to wait before sending the next message.
+    pub fn delay(&mut self, now: time::Instant) -> time::Duration {
+        self.refill(now);
+        if self.budget >= 0 {
+            return time::Duration::ZERO;
+        }
+        time::Duration::seconds_f64(-self.budget as f64 / self.bytes_per_second as f64)
+    }
+
+    /// Charges the budget for a sent message.
+    pub fn consume(&mut self, bytes: usize) {
+        self.budget = self.budget.saturating_sub(bytes.try_into().unwrap_or(i64::MAX));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(class: MessageClass, size: usize) -> Entry<usize> {
+        Entry { class, size, enqueued_at: time::FakeClock::default().now(), item: size }
+    }
+
+    fn pop_classes(q: &mut Queue<usize, usize>) -> Vec<MessageClass> {
+        let mut got = vec![];
+        while let Some(p) = q.pop() {
+            match p {
+                Popped::Message(e) => got.push(e.class),
+                Popped::Barrier(_) => panic!("unexpected barrier"),
+            }
+        }
+        got
+    }
+
+    #[test]
+    fn control_first() {
+        let mut q = Queue::new(Config::default());
+        q.push(entry(MessageClass::Bulk, 100));
+        q.push(entry(MessageClass::Consensus, 100));
+        q.push(entry(MessageClass::Control, 100));
+        assert_eq!(pop_classes(&mut q)[0], MessageClass::Control);
+        assert!(q.pop().is_none());
+    }
+
+    #[test]
+    fn weighted_share() {
+        let config = Config { consensus_weight: 3, regular_weight: 1, ..Config::default() };
+        let mut q = Queue::new(config);
+        for _ in 0..100 {
+            q.push(entry(MessageClass::Regular, 1000));
+        }
+        for _ in 0..100 {
+            q.push(entry(MessageClass::Consensus, 1000));
+        }
+        // While both classes are backlogged, consensus gets 3x more messages through.
+        let got = pop_classes(&mut q);
+        let consensus = got[..80].iter().filter(|c| **c == MessageClass::Consensus).count();
+        assert_eq!(consensus, 60);
+        assert_eq!(got.len(), 200);
+    }
+
+    #[test]
+    fn small_message_not_blocked_by_large() {
+        let mut q = Queue::new(Config::default());
+        q.push(entry(MessageClass::Bulk, 10_000_000));
+        q.push(entry(MessageClass::Bulk, 10_000_000));
+        assert!(matches!(q.pop(), Some(Popped::Message(e)) if e.class == MessageClass::Bulk));
+        // A consensus message arriving while a large message is being sent
+        // goes before the remaining bulk messages.
+        q.push(entry(MessageClass::Consensus, 100));
+        let got = pop_classes(&mut q);
+        assert_eq!(got, vec![MessageClass::Consensus, MessageClass::Bulk]);
+    }
+
+    #[test]
+    fn barrier() {
+        let mut q = Queue::new(Config::default());
+        q.push(entry(MessageClass::Bulk, 1));
+        q.push_barrier(2);
+        q.push(entry(MessageClass::Control, 3));
+        let mut got = vec![];
+        while let Some(p) = q.pop() {
+            got.push(match p {
+                Popped::Message(e) => e.item,
+                Popped::Barrier(b) => b,
+            });
+        }
+        assert_eq!(got, vec![1, 2, 3]);
+        assert!(q.pop().is_none());
+    }
+
+    #[test]
+    fn throttle() {
+        let clock = time::FakeClock::default();
+        let mut t = Throttle::new(1000, clock.now());
+        assert_eq!(t.delay(clock.now()), time::Duration::ZERO);
+        t.consume(2000);
+        assert_eq!(t.delay(clock.now()), time::Duration::seconds(2));
+        clock.advance(time::Duration::seconds(1));
+        assert_eq!(t.delay(clock.now()), time::Duration::seconds(1));
+        clock.advance(time::Duration::seconds(5));
+        assert_eq!(t.delay(clock.now()), time::Duration::ZERO);
+        // Budget accumulated while idle is capped at 1s worth of bytes.
+        t.consume(1500);
+        assert_eq!(t.delay(clock.now()), time::Duration::milliseconds(500));
+    }
+}
@@ -1,9 +1,13 @@
+use crate::peer::noise;
+use crate::peer::send_queue;
 use crate::peer_manager::connection;
 use crate::stats::metrics;
 use crate::tcp;
 use actix::fut::future::wrap_future;
 use actix::AsyncContext as _;
 use bytesize::{GIB, MIB};
+use near_async::time;
+use parking_lot::Mutex;
 use std::io;
 use std::net::SocketAddr;
 use std::sync::atomic::Ordering;
@@ -26,6 +30,8 @@ pub(crate) enum SendError {
     IO(#[source] io::Error),
     #[error("queue is full, got {got_bytes}B, max capacity is {want_max_bytes}")]
     QueueOverflow { got_bytes: usize, want_max_bytes: usize },
+    #[error("encryption: {0}")]
+    Encrypt(#[source] noise::Error),
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -34,12 +40,21 @@ pub(crate) enum RecvError {
     IO(#[source] io::Error),
     #[error("message too large: got {got_bytes}B, want <={want_max_bytes}B")]
     MessageTooLarge { got_bytes: usize, want_max_bytes: usize },
+    #[error("decryption: {0}")]
+    Decrypt(#[source] noise::Error),
 }
 
 #[derive(actix::Message, PartialEq, Eq, Clone, Debug)]
 #[rtype(result = "()")]
 pub(crate) struct Frame(pub Vec<u8>);
 
+/// Item of the send queue.
+enum SendItem {
+    Frame(send_queue::Entry<Frame>),
+    /// Frames following this item in the queue are encrypted.
+    StartEncryption(noise::CipherState),
+}
+
 /// Stream critical error.
 /// Actor is responsible for calling ctx.stop() after receiving stream::Error.
 /// Actor might receive more than 1 stream::Error, but should call ctx.stop() just after the
@@ -57,10 +72,13 @@ pub(crate) enum Error {
 }
 
 pub(crate) struct FramedStream<Actor: actix::Actor> {
-    queue_send: tokio::sync::mpsc::UnboundedSender<Frame>,
+    queue_send: tokio::sync::mpsc::UnboundedSender<SendItem>,
+    /// Cipher to decrypt the received frames with, to be picked up by run_recv_loop.
+    recv_cipher: Arc<Mutex<Option<noise::CipherState>>>,
     stats: Arc<connection::Stats>,
     send_buf_size_metric: Arc<metrics::IntGaugeGuard>,
     addr: actix::Addr<Actor>,
+    clock: time::Clock,
 }
 
 impl<Actor> FramedStream<Actor>
@@ -71,44 +89,77 @@ where
 {
     pub fn spawn(
         ctx: &mut actix::Context<Actor>,
+        clock: &time::Clock,
         stream: tcp::Stream,
         stats: Arc<connection::Stats>,
+        send_queue_config: send_queue::Config,
     ) -> Self {
         let (tcp_recv, tcp_send) = tokio::io::split(stream.stream);
         let (queue_send, queue_recv) = tokio::sync::mpsc::unbounded_channel();
+        let recv_cipher = Arc::new(Mutex::new(None));
         let send_buf_size_metric = Arc::new(metrics::MetricGuard::new(
             &*metrics::PEER_DATA_WRITE_BUFFER_SIZE,
             vec![stream.peer_addr.to_string()],
         ));
         ctx.spawn(wrap_future({
             let addr = ctx.address();
+            let clock = clock.clone();
             let stats = stats.clone();
             let m = send_buf_size_metric.clone();
             async move {
-                if let Err(err) = Self::run_send_loop(tcp_send, queue_recv, stats, m).await {
-                    addr.do_send(Error::Send(SendError::IO(err)));
+                if let Err(err) =
+                    Self::run_send_loop(&clock, tcp_send, queue_recv, send_queue_config, stats, m)
+                        .await
+                {
+                    addr.do_send(Error::Send(err));
                 }
             }
         }));
         ctx.spawn(wrap_future({
             let addr = ctx.address();
             let stats = stats.clone();
+            let recv_cipher = recv_cipher.clone();
             async move {
-                if let Err(err) =
-                    Self::run_recv_loop(stream.peer_addr, tcp_recv, addr.clone(), stats).await
+                if let Err(err) = Self::run_recv_loop(
+                    stream.peer_addr,
+                    tcp_recv,
+                    addr.clone(),
+                    stats,
+                    recv_cipher,
+                )
+                .await
                 {
                     addr.do_send(Error::Recv(err));
                 }
             }
         }));
-        Self { queue_send, stats, send_buf_size_metric, addr: ctx.address() }
+        Self {
+            queue_send,
+            recv_cipher,
+            stats,
+            send_buf_size_metric,
+            addr: ctx.address(),
+            clock: clock.clone(),
+        }
+    }
+
+    /// Encrypts all the frames pushed to the send queue after this call.
+    pub fn start_send_encryption(&self, cipher: noise::CipherState) {
+        let _ = self.queue_send.send(SendItem::StartEncryption(cipher));
+    }
+
+    /// Decrypts all the frames which haven't been received yet.
+    /// When called from the Frame handler, the first decrypted frame is the one
+    /// following the frame being handled.
+    pub fn start_recv_encryption(&self, cipher: noise::CipherState) {
+        *self.recv_cipher.lock() = Some(cipher);
     }
 
-    /// Pushes `msg` to the send queue.
+    /// Pushes `msg` to the send queue of the given class.
     /// Silently drops message if the connection has been closed.
     /// If the message is too large, it will be silently dropped inside run_send_loop.
     /// Emits a critical error to Actor if send queue is full.
-    pub fn send(&self, frame: Frame) {
+    pub fn send(&self, class: send_queue::MessageClass, frame: Frame) {
         let msg = &frame.0;
         let mut buf_size =
             self.stats.bytes_to_send.fetch_add(msg.len() as u64, Ordering::Acquire) as usize;
@@ -125,7 +176,12 @@ where
                 want_max_bytes: MAX_WRITE_BUFFER_CAPACITY_BYTES,
             }));
         }
-        let _ = self.queue_send.send(frame);
+        let _ = self.queue_send.send(SendItem::Frame(send_queue::Entry {
+            class,
+            size: frame.0.len(),
+            enqueued_at: self.clock.now(),
+            item: frame,
+        }));
     }
 
     /// Event loop receiving and processing messages.
@@ -134,6 +190,7 @@ where
     /// then the loop will start reading the next message before the subhandler returns.
     /// Loop uses a fixed small buffer allocated by BufReader.
     /// For each message it allocates a Vec with exact size of the message.
+    /// Once a cipher is set in `recv_cipher`, all the subsequent messages are decrypted.
     // TODO(gprusak): once borsh support is dropped, we can parse a proto
     // directly from the stream.
     async fn run_recv_loop(
@@ -141,6 +198,7 @@ where
         read: ReadHalf,
         addr: actix::Addr<Actor>,
         stats: Arc<connection::Stats>,
+        recv_cipher: Arc<Mutex<Option<noise::CipherState>>>,
     ) -> Result<(), RecvError> {
         const READ_BUFFER_CAPACITY: usize = 8 * 1024;
         let mut read = tokio::io::BufReader::with_capacity(READ_BUFFER_CAPACITY, read);
@@ -151,13 +209,18 @@ where
             &metrics::PEER_DATA_READ_BUFFER_SIZE,
             vec![peer_addr.to_string()],
         );
+        let mut cipher: Option<noise::CipherState> = None;
         loop {
             let n = read.read_u32_le().await.map_err(RecvError::IO)? as usize;
-            if n > NETWORK_MESSAGE_MAX_SIZE_BYTES {
-                return Err(RecvError::MessageTooLarge {
-                    got_bytes: n,
-                    want_max_bytes: NETWORK_MESSAGE_MAX_SIZE_BYTES,
-                });
+            if let Some(c) = recv_cipher.lock().take() {
+                cipher = Some(c);
+            }
+            let max_size = match cipher {
+                Some(_) => NETWORK_MESSAGE_MAX_SIZE_BYTES + noise::TAG_LEN,
+                None => NETWORK_MESSAGE_MAX_SIZE_BYTES,
+            };
+            if n > max_size {
+                return Err(RecvError::MessageTooLarge { got_bytes: n, want_max_bytes: max_size });
             }
             msg_size_metric.observe(n as f64);
             buf_size_metric.set(n as i64);
@@ -168,6 +231,9 @@ where
             buf_size_metric.set(0);
             stats.received_messages.fetch_add(1, Ordering::Relaxed);
             stats.received_bytes.fetch_add(n as u64, Ordering::Relaxed);
+            if let Some(cipher) = &mut cipher {
+                cipher.decrypt(&mut buf).map_err(RecvError::Decrypt)?;
+            }
             if let Err(_) = addr.send(Frame(buf)).await {
                 // We got mailbox error, which means that Actor has stopped,
                 // so we should just close the stream.
@@ -175,41 +241,87 @@ where
             }
         }
     }
+    /// Event loop sending the queued messages.
+    /// Messages are sent in the order determined by `send_queue::Queue`, and at most at
+    /// `send_queue::Config::max_bytes_per_second`. Before every message, all the items
+    /// pending in `queue_recv` are moved to the scheduler, so that a latency-sensitive
+    /// message can overtake the messages queued before it.
     async fn run_send_loop(
+        clock: &time::Clock,
         tcp_send: WriteHalf,
-        mut queue_recv: tokio::sync::mpsc::UnboundedReceiver<Frame>,
+        mut queue_recv: tokio::sync::mpsc::UnboundedReceiver<SendItem>,
+        config: send_queue::Config,
         stats: Arc<connection::Stats>,
         buf_size_metric: Arc<metrics::IntGaugeGuard>,
-    ) -> io::Result<()> {
+    ) -> Result<(), SendError> {
         const WRITE_BUFFER_CAPACITY: usize = 8 * 1024;
         let mut writer = tokio::io::BufWriter::with_capacity(WRITE_BUFFER_CAPACITY, tcp_send);
-        while let Some(Frame(mut msg)) = queue_recv.recv().await {
-            // Try writing a batch of messages and flush once at the end.
-            loop {
-                // TODO(gprusak): sending a too large message should probably be treated as a bug,
-                // since dropping messages may lead to hard-to-debug high-level issues.
-                if msg.len() > NETWORK_MESSAGE_MAX_SIZE_BYTES {
-                    metrics::MessageDropped::InputTooLong.inc_unknown_msg();
-                } else {
-                    writer.write_u32_le(msg.len() as u32).await?;
-                    writer.write_all(&msg[..]).await?;
+        let mut cipher: Option<noise::CipherState> = None;
+        let mut throttle =
+            config.max_bytes_per_second.map(|b| send_queue::Throttle::new(b, clock.now()));
+        let mut queue = send_queue::Queue::new(config);
+        let schedule = |queue: &mut send_queue::Queue<_, _>, item| match item {
+            SendItem::Frame(entry) => queue.push(entry),
+            SendItem::StartEncryption(c) => queue.push_barrier(c),
+        };
+        loop {
+            while let Ok(item) = queue_recv.try_recv() {
+                schedule(&mut queue, item);
+            }
+            let Some(popped) = queue.pop() else {
+                // Flush once the queue is empty, so that the messages are written in batches.
+                // This is an unconditional flush, which means that even if new messages
+                // will be added to the queue in the meantime, we will wait for the buffer
+                // to be flushed before sending them. This is suboptimal in case messages are small
+                // and added to the queue at a rate similar to flush latency. To fix that
+                // we would need to put writer.flush() and queue_recv.recv() into a tokio::select
+                // and make sure that both are cancellation-safe.
+                writer.flush().await.map_err(SendError::IO)?;
+                match queue_recv.recv().await {
+                    Some(item) => schedule(&mut queue, item),
+                    None => return Ok(()),
+                }
+                continue;
+            };
+            let entry = match popped {
+                send_queue::Popped::Barrier(c) => {
+                    cipher = Some(c);
+                    continue;
+                }
+                send_queue::Popped::Message(entry) => entry,
+            };
+            let class = entry.class.as_str();
+            metrics::PEER_MESSAGE_QUEUEING_DELAY
+                .with_label_values(&[class])
+                .observe((clock.now() - entry.enqueued_at).as_seconds_f64());
+            let Frame(mut msg) = entry.item;
+            let msg_len = msg.len();
+            // TODO(gprusak): sending a too large message should probably be treated as a bug,
+            // since dropping messages may lead to hard-to-debug high-level issues.
+            if msg_len > NETWORK_MESSAGE_MAX_SIZE_BYTES {
+                metrics::MessageDropped::InputTooLong.inc_unknown_msg();
+            } else {
+                if let Some(throttle) = &mut throttle {
+                    let delay = throttle.delay(clock.now());
+                    if delay > time::Duration::ZERO {
+                        // Don't keep the already written messages in the buffer while waiting.
+                        writer.flush().await.map_err(SendError::IO)?;
+                        clock.sleep(delay).await;
+                    }
+                    throttle.consume(msg_len);
+                }
+                if let Some(cipher) = &mut cipher {
+                    cipher.encrypt(&mut msg).map_err(SendError::Encrypt)?;
                 }
-                stats.messages_to_send.fetch_sub(1, Ordering::Release);
-                stats.bytes_to_send.fetch_sub(msg.len() as u64, Ordering::Release);
-                buf_size_metric.sub(msg.len() as i64);
-                msg = match queue_recv.try_recv() {
-                    Ok(Frame(it)) => it,
-                    Err(_) => break,
-                };
+                writer.write_u32_le(msg.len() as u32).await.map_err(SendError::IO)?;
+                writer.write_all(&msg[..]).await.map_err(SendError::IO)?;
+                metrics::PEER_MESSAGE_SENT_BY_CLASS_BYTES
+                    .with_label_values(&[class])
+                    .inc_by(msg_len as u64);
             }
-            // This is an unconditional flush, which means that even if new messages
-            // will be added to the queue in the meantime, we will wait for the buffer
-            // to be flushed before sending them. This is suboptimal in case messages are small
-            // and added to the queue at a rate similar to flush latency. To fix that
-            // we would need to put writer.flush() and queue_recv.recv() into a tokio::select
-            // and make sure that both are cancellation-safe.
-            writer.flush().await?;
+            stats.messages_to_send.fetch_sub(1, Ordering::Release);
+            stats.bytes_to_send.fetch_sub(msg_len as u64, Ordering::Release);
+            buf_size_metric.sub(msg_len as i64);
         }
-        Ok(())
     }
 }
@@ -3,6 +3,8 @@ use crate::network_protocol::{
     Encoding, Handshake, HandshakeFailureReason, PartialEdgeInfo, PeerMessage, PeersRequest,
     PeersResponse, RoutedMessageBody,
 };
+use crate::peer::noise;
+use crate::peer::peer_actor::ClosingReason;
 use crate::peer::testonly::{Event, PeerConfig, PeerHandle};
 use crate::peer_manager::peer_manager_actor::Event as PME;
 use crate::tcp;
@@ -13,29 +15,35 @@ use anyhow::Context as _;
 use assert_matches::assert_matches;
 use near_async::time;
 use near_o11y::testonly::init_test_logger;
-use near_primitives::version::{PEER_MIN_ALLOWED_PROTOCOL_VERSION, PROTOCOL_VERSION};
+use near_primitives::version::{
+    ProtocolFeature, PEER_MIN_ALLOWED_PROTOCOL_VERSION, PROTOCOL_VERSION,
+};
 use std::sync::Arc;
 
 async fn test_peer_communication(
     outbound_encoding: Option<Encoding>,
     inbound_encoding: Option<Encoding>,
+    outbound_encrypted_transport: bool,
+    inbound_encrypted_transport: bool,
 ) -> anyhow::Result<()> {
-    tracing::info!("test_peer_communication({outbound_encoding:?},{inbound_encoding:?})");
+    tracing::info!("test_peer_communication({outbound_encoding:?},{inbound_encoding:?},{outbound_encrypted_transport},{inbound_encrypted_transport})");
 
     let mut rng = make_rng(89028037453);
     let mut clock = time::FakeClock::default();
 
     let chain = Arc::new(data::Chain::make(&mut clock, &mut rng, 12));
-    let inbound_cfg = PeerConfig {
+    let mut inbound_cfg = PeerConfig {
         chain: chain.clone(),
         network: chain.make_config(&mut rng),
         force_encoding: inbound_encoding,
     };
-    let outbound_cfg = PeerConfig {
+    inbound_cfg.network.encrypted_transport = inbound_encrypted_transport;
+    let mut outbound_cfg = PeerConfig {
         chain: chain.clone(),
         network: chain.make_config(&mut rng),
         force_encoding: outbound_encoding,
     };
+    outbound_cfg.network.encrypted_transport = outbound_encrypted_transport;
     let (outbound_stream, inbound_stream) =
         tcp::Stream::loopback(inbound_cfg.id(), tcp::Tier::T2).await;
     let mut inbound = PeerHandle::start_endpoint(clock.clock(), inbound_cfg, inbound_stream).await;
@@ -171,7 +179,7 @@ async fn peer_communication() -> anyhow::Result<()> {
                     continue;
                 }
             }
-            test_peer_communication(*outbound, *inbound)
+            test_peer_communication(*outbound, *inbound, false, false)
                 .await
                 .with_context(|| format!("(outbound={outbound:?},inbound={inbound:?})"))?;
         }
@@ -179,6 +187,27 @@ async fn peer_communication() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+// Verifies that peers are able to communicate over an encrypted transport
+// and that they fall back to plaintext if only one of them wants encryption
+// and the protocol version doesn't require it.
+async fn peer_communication_encrypted() -> anyhow::Result<()> {
+    init_test_logger();
+    let encodings = [None, Some(Encoding::Proto)];
+    let mut encrypted = vec![(true, true)];
+    if ProtocolFeature::NoiseTransport.protocol_version() > PROTOCOL_VERSION {
+        encrypted.extend([(true, false), (false, true)]);
+    }
+    for encoding in &encodings {
+        for (outbound, inbound) in &encrypted {
+            test_peer_communication(*encoding, *encoding, *outbound, *inbound).await.with_context(
+                || format!("(encoding={encoding:?},outbound={outbound},inbound={inbound})"),
+            )?;
+        }
+    }
+    Ok(())
+}
+
 async fn test_handshake(outbound_encoding: Option<Encoding>, inbound_encoding: Option<Encoding>) {
     let mut rng = make_rng(89028037453);
     let mut clock = time::FakeClock::default();
@@ -210,6 +239,7 @@ async fn test_handshake(outbound_encoding: Option<Encoding>, inbound_encoding: O
         sender_chain_info: outbound_cfg.chain.get_peer_chain_info(),
         partial_edge_info: outbound_cfg.partial_edge_info(&inbound.cfg.id(), 1),
         owned_account: None,
+        noise_handshake: None,
     };
     // We will also introduce chain_id mismatch, but ProtocolVersionMismatch is expected to take priority.
     handshake.sender_chain_info.genesis_id.chain_id = "unknown_chain".to_string();
@@ -266,3 +296,167 @@ async fn handshake() -> anyhow::Result<()> {
     }
     Ok(())
 }
+
+#[tokio::test]
+// Verifies that the inbound peer responds to the Noise handshake and encrypts the connection.
+async fn encrypted_handshake() {
+    init_test_logger();
+    let mut rng = make_rng(89028037453);
+    let mut clock = time::FakeClock::default();
+
+    let chain = Arc::new(data::Chain::make(&mut clock, &mut rng, 12));
+    let mut inbound_cfg = PeerConfig {
+        network: chain.make_config(&mut rng),
+        chain: chain.clone(),
+        force_encoding: None,
+    };
+    inbound_cfg.network.encrypted_transport = true;
+    let outbound_cfg = PeerConfig {
+        network: chain.make_config(&mut rng),
+        chain: chain.clone(),
+        force_encoding: Some(Encoding::Proto),
+    };
+    let (outbound_stream, inbound_stream) =
+        tcp::Stream::loopback(inbound_cfg.id(), tcp::Tier::T2).await;
+    let inbound = PeerHandle::start_endpoint(clock.clock(), inbound_cfg, inbound_stream).await;
+    let outbound_port = outbound_stream.local_addr.port();
+    let mut outbound = Stream::new(Some(Encoding::Proto), outbound_stream);
+
+    let (initiator, msg) =
+        noise::Initiator::start(&outbound_cfg.network.node_key, &inbound.cfg.id()).unwrap();
+    let handshake = Handshake {
+        protocol_version: PROTOCOL_VERSION,
+        oldest_supported_version: PROTOCOL_VERSION,
+        sender_peer_id: outbound_cfg.id(),
+        target_peer_id: inbound.cfg.id(),
+        sender_listen_port: Some(outbound_port),
+        sender_chain_info: outbound_cfg.chain.get_peer_chain_info(),
+        partial_edge_info: outbound_cfg.partial_edge_info(&inbound.cfg.id(), 1),
+        owned_account: None,
+        noise_handshake: Some(msg),
+    };
+    outbound.write(&PeerMessage::Tier2Handshake(handshake)).await;
+    let resp = match outbound.read().await.unwrap() {
+        PeerMessage::Tier2Handshake(resp) => resp,
+        msg => panic!("unexpected message {msg}"),
+    };
+    outbound.start_encryption(initiator.finish(&resp.noise_handshake.unwrap()).unwrap());
+
+    // Send an encrypted request and wait for the encrypted response.
+    let req = PeerMessage::PeersRequest(PeersRequest { max_peers: None, max_direct_peers: None });
+    outbound.write(&req).await;
+    loop {
+        if let PeerMessage::PeersResponse(_) = outbound.read().await.unwrap() {
+            break;
+        }
+    }
+}
+
+#[tokio::test]
+// Verifies that the inbound peer rejects a Noise handshake made with a key
+// not matching the sender_peer_id.
+async fn encrypted_handshake_key_mismatch() {
+    init_test_logger();
+    let mut rng = make_rng(89028037453);
+    let mut clock = time::FakeClock::default();
+
+    let chain = Arc::new(data::Chain::make(&mut clock, &mut rng, 12));
+    let mut inbound_cfg = PeerConfig {
+        network: chain.make_config(&mut rng),
+        chain: chain.clone(),
+        force_encoding: None,
+    };
+    inbound_cfg.network.encrypted_transport = true;
+    let outbound_cfg = PeerConfig {
+        network: chain.make_config(&mut rng),
+        chain: chain.clone(),
+        force_encoding: Some(Encoding::Proto),
+    };
+    let (outbound_stream, inbound_stream) =
+        tcp::Stream::loopback(inbound_cfg.id(), tcp::Tier::T2).await;
+    let inbound = PeerHandle::start_endpoint(clock.clock(), inbound_cfg, inbound_stream).await;
+    let outbound_port = outbound_stream.local_addr.port();
+    let mut outbound = Stream::new(Some(Encoding::Proto), outbound_stream);
+
+    let mut events = inbound.events.from_now();
+    let other_key = data::make_secret_key(&mut rng);
+    let (_, msg) = noise::Initiator::start(&other_key, &inbound.cfg.id()).unwrap();
+    let handshake = Handshake {
+        protocol_version: PROTOCOL_VERSION,
+        oldest_supported_version: PROTOCOL_VERSION,
+        sender_peer_id: outbound_cfg.id(),
+        target_peer_id: inbound.cfg.id(),
+        sender_listen_port: Some(outbound_port),
+        sender_chain_info: outbound_cfg.chain.get_peer_chain_info(),
+        partial_edge_info: outbound_cfg.partial_edge_info(&inbound.cfg.id(), 1),
+        owned_account: None,
+        noise_handshake: Some(msg),
+    };
+    outbound.write(&PeerMessage::Tier2Handshake(handshake)).await;
+    let reason = events
+        .recv_until(|ev| match ev {
+            Event::Network(PME::ConnectionClosed(ev)) => Some(ev.reason),
+            _ => None,
+        })
+        .await;
+    assert_eq!(ClosingReason::HandshakeFailed, reason);
+}
+
+#[tokio::test]
+// Verifies that a peer with encryption enabled accepts a plaintext Handshake only
+// from peers below the protocol version requiring encryption.
+async fn encrypted_handshake_required() {
+    init_test_logger();
+    let mut rng = make_rng(89028037453);
+    let mut clock = time::FakeClock::default();
+
+    let chain = Arc::new(data::Chain::make(&mut clock, &mut rng, 12));
+    let noise_version = ProtocolFeature::NoiseTransport.protocol_version();
+    for protocol_version in [PEER_MIN_ALLOWED_PROTOCOL_VERSION, PROTOCOL_VERSION] {
+        let mut inbound_cfg = PeerConfig {
+            network: chain.make_config(&mut rng),
+            chain: chain.clone(),
+            force_encoding: None,
+        };
+        inbound_cfg.network.encrypted_transport = true;
+        let outbound_cfg = PeerConfig {
+            network: chain.make_config(&mut rng),
+            chain: chain.clone(),
+            force_encoding: Some(Encoding::Proto),
+        };
+        let (outbound_stream, inbound_stream) =
+            tcp::Stream::loopback(inbound_cfg.id(), tcp::Tier::T2).await;
+        let inbound = PeerHandle::start_endpoint(clock.clock(), inbound_cfg, inbound_stream).await;
+        let outbound_port = outbound_stream.local_addr.port();
+        let mut outbound = Stream::new(Some(Encoding::Proto), outbound_stream);
+
+        let mut events = inbound.events.from_now();
+        let handshake = Handshake {
+            protocol_version,
+            oldest_supported_version: PEER_MIN_ALLOWED_PROTOCOL_VERSION,
+            sender_peer_id: outbound_cfg.id(),
+            target_peer_id: inbound.cfg.id(),
+            sender_listen_port: Some(outbound_port),
+            sender_chain_info: outbound_cfg.chain.get_peer_chain_info(),
+            partial_edge_info: outbound_cfg.partial_edge_info(&inbound.cfg.id(), 1),
+            owned_account: None,
+            noise_handshake: None,
+        };
+        outbound.write(&PeerMessage::Tier2Handshake(handshake)).await;
+        if protocol_version < noise_version {
+            let resp = match outbound.read().await.unwrap() {
+                PeerMessage::Tier2Handshake(resp) => resp,
+                msg => panic!("unexpected message {msg}"),
+            };
+            assert_eq!(None, resp.noise_handshake);
+        } else {
+            let reason = events
+                .recv_until(|ev| match ev {
+                    Event::Network(PME::ConnectionClosed(ev)) => Some(ev.reason),
+                    _ => None,
+                })
+                .await;
+            assert_eq!(ClosingReason::HandshakeFailed, reason);
+        }
+    }
+}
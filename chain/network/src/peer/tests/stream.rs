@@ -1,10 +1,14 @@
 use crate::actix::ActixSystem;
 use crate::network_protocol::testonly as data;
+use crate::peer::noise;
+use crate::peer::send_queue::MessageClass;
 use crate::peer::stream;
 use crate::tcp;
 use crate::testonly::make_rng;
 use actix::Actor as _;
 use actix::ActorContext as _;
+use near_async::time;
+use near_primitives::network::PeerId;
 use rand::Rng as _;
 use std::sync::Arc;
 use tokio::sync::mpsc;
@@ -20,12 +24,24 @@ impl actix::Actor for Actor {
 
 #[derive(actix::Message)]
 #[rtype("()")]
-struct SendFrame(stream::Frame);
+struct SendFrame(MessageClass, stream::Frame);
 
 impl actix::Handler<SendFrame> for Actor {
     type Result = ();
-    fn handle(&mut self, SendFrame(frame): SendFrame, _ctx: &mut Self::Context) {
-        self.stream.send(frame);
+    fn handle(&mut self, SendFrame(class, frame): SendFrame, _ctx: &mut Self::Context) {
+        self.stream.send(class, frame);
+    }
+}
+
+#[derive(actix::Message)]
+#[rtype("()")]
+struct StartEncryption(noise::Transport);
+
+impl actix::Handler<StartEncryption> for Actor {
+    type Result = ();
+    fn handle(&mut self, StartEncryption(t): StartEncryption, _ctx: &mut Self::Context) {
+        self.stream.start_send_encryption(t.send);
+        self.stream.start_recv_encryption(t.recv);
     }
 }
 
@@ -55,7 +71,13 @@ impl Actor {
             queue_recv,
             system: ActixSystem::spawn(|| {
                 Actor::create(|ctx| {
-                    let stream = stream::FramedStream::spawn(ctx, s, Arc::default());
+                    let stream = stream::FramedStream::spawn(
+                        ctx,
+                        &time::Clock::real(),
+                        s,
+                        Arc::default(),
+                        Default::default(),
+                    );
                     Self { stream, queue_send }
                 })
             })
@@ -82,7 +104,7 @@ async fn send_recv() {
             })
             .collect();
         for msg in &msgs {
-            a1.system.addr.send(SendFrame(msg.clone())).await.unwrap();
+            a1.system.addr.send(SendFrame(MessageClass::Regular, msg.clone())).await.unwrap();
         }
         for want in &msgs {
             let got = a2.queue_recv.recv().await.unwrap();
@@ -90,3 +112,76 @@ async fn send_recv() {
         }
     }
 }
+
+#[tokio::test]
+async fn send_recv_encrypted() {
+    let mut rng = make_rng(98324532);
+    let key1 = data::make_secret_key(&mut rng);
+    let key2 = data::make_secret_key(&mut rng);
+    let id1 = PeerId::new(key1.public_key());
+    let id2 = PeerId::new(key2.public_key());
+    let (s1, s2) = tcp::Stream::loopback(id2.clone(), tcp::Tier::T2).await;
+    let mut a1 = Actor::spawn(s1).await;
+    let mut a2 = Actor::spawn(s2).await;
+
+    let (initiator, msg) = noise::Initiator::start(&key1, &id2).unwrap();
+    let (t2, msg) = noise::respond(&key2, &id1, &msg).unwrap();
+    let t1 = initiator.finish(&msg).unwrap();
+    a1.system.addr.send(StartEncryption(t1)).await.unwrap();
+    a2.system.addr.send(StartEncryption(t2)).await.unwrap();
+
+    for _ in 0..5 {
+        let n = rng.gen_range(1..10);
+        let msgs: Vec<_> = (0..n)
+            .map(|_| {
+                let size = rng.gen_range(0..10000);
+                let mut msg = vec![0; size];
+                rng.fill(&mut msg[..]);
+                stream::Frame(msg)
+            })
+            .collect();
+        for msg in &msgs {
+            a1.system.addr.send(SendFrame(MessageClass::Regular, msg.clone())).await.unwrap();
+            a2.system.addr.send(SendFrame(MessageClass::Regular, msg.clone())).await.unwrap();
+        }
+        for want in &msgs {
+            assert_eq!(&a2.queue_recv.recv().await.unwrap(), want);
+            assert_eq!(&a1.queue_recv.recv().await.unwrap(), want);
+        }
+    }
+}
+
+#[tokio::test]
+async fn send_recv_classes() {
+    let mut rng = make_rng(98324532);
+    let (s1, s2) = tcp::Stream::loopback(data::make_peer_id(&mut rng), tcp::Tier::T2).await;
+    let a1 = Actor::spawn(s1).await;
+    let mut a2 = Actor::spawn(s2).await;
+
+    let classes =
+        [MessageClass::Control, MessageClass::Consensus, MessageClass::Regular, MessageClass::Bulk];
+    let msgs: Vec<_> = (0..50)
+        .map(|i| {
+            let size = rng.gen_range(0..10000);
+            let mut msg = vec![0; size];
+            rng.fill(&mut msg[..]);
+            // Prefix the message with its index, so that the messages are distinct.
+            msg.insert(0, i as u8);
+            (classes[rng.gen_range(0..classes.len())], stream::Frame(msg))
+        })
+        .collect();
+    for (class, msg) in &msgs {
+        a1.system.addr.send(SendFrame(*class, msg.clone())).await.unwrap();
+    }
+    let mut got = vec![];
+    for _ in &msgs {
+        got.push(a2.queue_recv.recv().await.unwrap());
+    }
+    // Messages of different classes may be reordered, but messages within a class are not.
+    for class in classes {
+        let want: Vec<_> =
+            msgs.iter().filter(|(c, _)| *c == class).map(|(_, m)| m.clone()).collect();
+        let got: Vec<_> = got.iter().filter(|m| want.contains(m)).cloned().collect();
+        assert_eq!(got, want);
+    }
+}
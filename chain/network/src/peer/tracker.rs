@@ -1,9 +1,20 @@
 use crate::peer::transfer_stats::TransferStats;
 use near_async::time;
 use near_primitives::hash::CryptoHash;
+use near_primitives::types::ShardId;
+use std::collections::HashMap;
 
 /// Maximum number of requests and responses to track.
 const MAX_TRACK_SIZE: usize = 30;
+/// Maximum number of requests awaiting a response to track.
+const MAX_PENDING_REQUESTS: usize = 100;
+
+/// A request sent to the peer, whose response time affects the peer reputation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum PendingRequest {
+    Block(CryptoHash),
+    StatePart { shard_id: ShardId, sync_hash: CryptoHash, part_id: u64 },
+}
 
 /// Internal structure to keep a circular queue within a tracker with unique hashes.
 struct CircularUniqueQueue {
@@ -49,6 +60,9 @@ pub(crate) struct Tracker {
     requested: CircularUniqueQueue,
     /// Received elements.
     received: CircularUniqueQueue,
+    /// Requests awaiting a response, with the time they were sent at and whether
+    /// the peer should be penalised if the request times out.
+    pending: HashMap<PendingRequest, (time::Instant, bool)>,
 }
 
 impl Default for Tracker {
@@ -58,6 +72,7 @@ impl Default for Tracker {
             received_bytes: TransferStats::default(),
             requested: CircularUniqueQueue::new(MAX_TRACK_SIZE),
             received: CircularUniqueQueue::new(MAX_TRACK_SIZE),
+            pending: HashMap::new(),
         }
     }
 }
@@ -89,6 +104,50 @@ impl Tracker {
     pub(crate) fn push_request(&mut self, hash: CryptoHash) {
         self.requested.push(hash);
     }
+
+    /// Records that the request has been sent. If the request is already pending,
+    /// the original send time is kept.
+    /// `penalize_timeout` should be set only if the peer advertised that it can serve
+    /// the request: honest peers silently ignore requests they can't serve.
+    pub(crate) fn start_request(
+        &mut self,
+        request: PendingRequest,
+        now: time::Instant,
+        penalize_timeout: bool,
+    ) {
+        if self.pending.len() >= MAX_PENDING_REQUESTS && !self.pending.contains_key(&request) {
+            return;
+        }
+        self.pending.entry(request).or_insert((now, penalize_timeout));
+    }
+
+    /// Marks the request as responded to and returns how long the response took.
+    /// Returns None if the request wasn't pending.
+    pub(crate) fn finish_request(
+        &mut self,
+        request: &PendingRequest,
+        now: time::Instant,
+    ) -> Option<time::Duration> {
+        self.pending.remove(request).map(|(sent, _)| now - sent)
+    }
+
+    /// Drops the requests which were sent more than `timeout` ago and returns how many
+    /// of the dropped requests should be penalised.
+    pub(crate) fn take_expired_requests(
+        &mut self,
+        now: time::Instant,
+        timeout: time::Duration,
+    ) -> usize {
+        let mut penalized = 0;
+        self.pending.retain(|_, (sent, penalize_timeout)| {
+            let expired = now - *sent > timeout;
+            if expired && *penalize_timeout {
+                penalized += 1;
+            }
+            !expired
+        });
+        penalized
+    }
 }
 
 #[cfg(test)]
@@ -97,6 +156,38 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_pending_requests() {
+        let clock = time::FakeClock::default();
+        let mut tracker = Tracker::default();
+        let block = PendingRequest::Block(hash(&[1]));
+        let part = PendingRequest::StatePart { shard_id: 0, sync_hash: hash(&[2]), part_id: 3 };
+
+        tracker.start_request(block.clone(), clock.now(), false);
+        clock.advance(time::Duration::seconds(1));
+        tracker.start_request(block.clone(), clock.now(), false);
+        tracker.start_request(part.clone(), clock.now(), true);
+        clock.advance(time::Duration::seconds(1));
+        assert_eq!(Some(time::Duration::seconds(2)), tracker.finish_request(&block, clock.now()));
+        assert_eq!(None, tracker.finish_request(&block, clock.now()));
+
+        clock.advance(time::Duration::seconds(10));
+        assert_eq!(1, tracker.take_expired_requests(clock.now(), time::Duration::seconds(5)));
+        assert_eq!(None, tracker.finish_request(&part, clock.now()));
+    }
+
+    #[test]
+    fn test_unpenalized_request_timeout() {
+        let clock = time::FakeClock::default();
+        let mut tracker = Tracker::default();
+        let block = PendingRequest::Block(hash(&[1]));
+
+        tracker.start_request(block.clone(), clock.now(), false);
+        clock.advance(time::Duration::seconds(10));
+        assert_eq!(0, tracker.take_expired_requests(clock.now(), time::Duration::seconds(5)));
+        assert_eq!(None, tracker.finish_request(&block, clock.now()));
+    }
+
     #[test]
     #[should_panic]
     fn test_circular_queue_zero_capacity() {
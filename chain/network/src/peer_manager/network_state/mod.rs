@@ -1,5 +1,6 @@
 use crate::accounts_data::{AccountDataCache, AccountDataError};
 use crate::announce_accounts::AnnounceAccountCache;
+use crate::capture;
 use crate::client;
 use crate::concurrency::demux;
 use crate::concurrency::runtime::Runtime;
@@ -98,6 +99,8 @@ pub(crate) struct NetworkState {
     pub created_at: time::Instant,
     /// GenesisId of the chain.
     pub genesis_id: GenesisId,
+    /// Capture of the network traffic, enabled by `config.capture_path`.
+    pub capture: Option<capture::Recorder>,
     pub client: Arc<dyn client::Client>,
     pub shards_manager_adapter: Sender<ShardsManagerRequestFromNetwork>,
 
@@ -169,6 +172,7 @@ impl NetworkState {
         client: Arc<dyn client::Client>,
         shards_manager_adapter: Sender<ShardsManagerRequestFromNetwork>,
         whitelist_nodes: Vec<WhitelistNode>,
+        capture: Option<capture::Recorder>,
     ) -> Self {
         Self {
             runtime: Runtime::new(),
@@ -185,6 +189,7 @@ impl NetworkState {
                 prune_edges_after: Some(PRUNE_EDGES_AFTER),
             })),
             genesis_id,
+            capture,
             client,
             shards_manager_adapter,
             chain_info: Default::default(),
@@ -246,6 +251,31 @@ impl NetworkState {
         }
     }
 
+    /// Updates the reputation of the peer in the peer store and disconnects
+    /// or bans the peer if its reputation dropped too low.
+    pub fn record_reputation_event(
+        &self,
+        clock: &time::Clock,
+        peer_id: &PeerId,
+        event: peer_store::ReputationEvent,
+    ) {
+        metrics::PEER_REPUTATION_EVENTS.with_label_values(&[event.as_str()]).inc();
+        match self.peer_store.record_reputation_event(peer_id, event) {
+            peer_store::ReputationAction::None => {}
+            peer_store::ReputationAction::Disconnect => {
+                if let Some(peer) = self.tier2.load().ready.get(peer_id) {
+                    metrics::PEER_REPUTATION_ACTIONS.with_label_values(&["disconnect"]).inc();
+                    tracing::info!(target: "network", ?peer_id, "Disconnecting peer with low reputation");
+                    peer.stop(None);
+                }
+            }
+            peer_store::ReputationAction::Ban => {
+                metrics::PEER_REPUTATION_ACTIONS.with_label_values(&["ban"]).inc();
+                self.disconnect_and_ban(clock, peer_id, ReasonForBan::LowReputation);
+            }
+        }
+    }
+
     /// is_peer_whitelisted checks whether a peer is a whitelisted node.
     /// whitelisted nodes are allowed to connect, even if the inbound connections limit has
     /// been reached. This predicate should be evaluated AFTER the Handshake.
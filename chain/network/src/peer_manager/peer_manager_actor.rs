@@ -1,3 +1,4 @@
+use crate::capture;
 use crate::config;
 use crate::debug::{DebugStatus, GetDebugStatus};
 use crate::network_protocol::SyncSnapshotHosts;
@@ -209,8 +210,9 @@ impl PeerManagerActor {
     ) -> anyhow::Result<actix::Addr<Self>> {
         let config = config.verify().context("config")?;
         let store = store::Store::from(store);
-        let peer_store = peer_store::PeerStore::new(&clock, config.peer_store.clone())
-            .context("PeerStore::new")?;
+        let peer_store =
+            peer_store::PeerStore::new(&clock, config.peer_store.clone(), store.clone())
+                .context("PeerStore::new")?;
         tracing::debug!(target: "network",
                len = peer_store.len(),
                boot_nodes = config.peer_store.boot_nodes.len(),
@@ -224,6 +226,12 @@ impl PeerManagerActor {
             }
             v
         };
+        let capture = config
+            .capture_path
+            .as_deref()
+            .map(capture::Recorder::spawn)
+            .transpose()
+            .context("capture")?;
         let my_peer_id = config.node_id();
         let arbiter = actix::Arbiter::new().handle();
         let clock = clock;
@@ -236,6 +244,7 @@ impl PeerManagerActor {
             client,
             shards_manager_adapter,
             whitelist_nodes,
+            capture,
         ));
         arbiter.spawn({
             let arbiter = arbiter.clone();
@@ -664,6 +673,7 @@ impl PeerManagerActor {
                 Some(e) => e.nonce(),
                 None => 0,
             },
+            reputation: self.state.peer_store.reputation(&cp.peer_info.id),
         };
         NetworkInfo {
             connected_peers: tier2.ready.values().map(connected_peer).collect(),
@@ -1074,6 +1084,7 @@ impl actix::Handler<GetDebugStatus> for PeerManagerActor {
                         addr: format!("{:?}", known_peer_state.peer_info.addr),
                         first_seen: known_peer_state.first_seen.unix_timestamp(),
                         last_seen: known_peer_state.last_seen.unix_timestamp(),
+                        reputation: self.state.peer_store.reputation(peer_id),
                         last_attempt: known_peer_state.last_outbound_attempt.clone().map(
                             |(attempt_time, attempt_result)| {
                                 let foo = match attempt_result {
@@ -1,5 +1,6 @@
 use crate::blacklist;
 use crate::network_protocol::PeerInfo;
+use crate::store;
 use crate::types::{KnownPeerState, KnownPeerStatus, ReasonForBan};
 use anyhow::bail;
 use im::hashmap::Entry;
@@ -13,11 +14,15 @@ use rand::thread_rng;
 use std::net::SocketAddr;
 use std::ops::Not;
 
+mod reputation;
 #[cfg(test)]
 mod testonly;
 #[cfg(test)]
 mod tests;
 
+pub use reputation::ReputationConfig;
+pub(crate) use reputation::{ReputationAction, ReputationEvent};
+
 /// The PeerStore is an in-memory cache of known peer states. It is used to:
 ///     - Store information about known peers in the network. Peers may be discovered
 ///       by connecting to them directly or by learning about them from other peers.
@@ -26,6 +31,8 @@ mod tests;
 ///
 /// Contents of the PeerStore are not persisted to the database. Upon starting a node,
 /// the PeerStore is initialized from the boot nodes in its config.
+/// The only exception are the reputation scores of the peers (see ReputationConfig),
+/// which are loaded from the database and saved back periodically.
 
 /// Level of trust we have about a new (PeerId, Addr) pair.
 #[derive(Eq, PartialEq, Debug, Clone, Copy)]
@@ -71,6 +78,8 @@ pub struct Config {
     pub peer_expiration_duration: time::Duration,
     /// Duration of the ban for misbehaving peers.
     pub ban_window: time::Duration,
+    /// Policy for scoring peers based on their behaviour.
+    pub reputation: ReputationConfig,
 }
 
 /// Known peers store, maintaining cache of known peers
@@ -84,6 +93,8 @@ struct Inner {
     // It can happens that some peers don't have known address, so
     // they will not be present in this list, otherwise they will be present.
     addr_peers: HashMap<SocketAddr, VerifiedPeer>,
+    reputations: reputation::Reputations,
+    store: store::Store,
 }
 
 impl Inner {
@@ -252,6 +263,7 @@ impl Inner {
             if let Err(err) = self.peer_unban(&peer_id) {
                 tracing::error!(target: "network", ?peer_id, ?err, "Failed to unban a peer");
             }
+            self.reputations.unban(peer_id);
         }
     }
 
@@ -271,23 +283,35 @@ impl Inner {
         }
     }
 
+    /// Saves the reputation scores to the DB if they have changed.
+    fn save_reputations(&mut self) {
+        if let Some(reputations) = self.reputations.take_changed() {
+            if let Err(err) = self.store.set_peer_reputations(&reputations) {
+                tracing::error!(target: "network", ?err, "Failed to save peer reputations");
+            }
+        }
+    }
+
     /// Cleans up the state of the PeerStore, due to passing time.
     /// * it unbans a peer if config.ban_window has passed
     /// * it updates KnownPeerStatus.last_seen of the connected peers
     /// * it removes peers which were not seen for config.peer_expiration_duration
+    /// * it decays the reputation scores and persists them
     /// This function should be called periodically.
     pub fn update(&mut self, clock: &time::Clock) {
         let now = clock.now_utc();
         self.unban(now);
         self.update_last_seen(now);
         self.remove_expired(now);
+        self.reputations.decay(clock.now());
+        self.save_reputations();
     }
 }
 
 pub(crate) struct PeerStore(Mutex<Inner>);
 
 impl PeerStore {
-    pub fn new(clock: &time::Clock, config: Config) -> anyhow::Result<Self> {
+    pub fn new(clock: &time::Clock, config: Config, store: store::Store) -> anyhow::Result<Self> {
         let boot_nodes: HashSet<_> = config.boot_nodes.iter().map(|p| p.id.clone()).collect();
         // A mapping from `PeerId` to `KnownPeerState`.
         let mut peerid_2_state = LruCache::new(config.peer_states_cache_size as usize);
@@ -334,8 +358,16 @@ impl PeerStore {
             }
         }
 
-        let inner =
-            Inner { config, boot_nodes, peer_states: peerid_2_state, addr_peers: addr_2_peer };
+        let reputations =
+            reputation::Reputations::new(config.reputation.clone(), store.get_peer_reputations());
+        let inner = Inner {
+            config,
+            boot_nodes,
+            peer_states: peerid_2_state,
+            addr_peers: addr_2_peer,
+            reputations,
+            store,
+        };
         Ok(PeerStore(Mutex::new(inner)))
     }
 
@@ -359,6 +391,26 @@ impl PeerStore {
         self.0.lock().update(clock)
     }
 
+    /// Returns the reputation score of the peer.
+    pub fn reputation(&self, peer_id: &PeerId) -> i32 {
+        self.0.lock().reputations.get(peer_id)
+    }
+
+    /// Updates the reputation of the peer and returns what should be done with
+    /// the peer according to the reputation policy.
+    pub fn record_reputation_event(
+        &self,
+        peer_id: &PeerId,
+        event: ReputationEvent,
+    ) -> ReputationAction {
+        let mut inner = self.0.lock();
+        let action = inner.reputations.record(peer_id, event);
+        if action != ReputationAction::None {
+            tracing::debug!(target: "network", ?peer_id, ?event, reputation = inner.reputations.get(peer_id), ?action, "Peer reputation dropped");
+        }
+        action
+    }
+
     #[allow(dead_code)]
     /// Returns the state of the current peer in memory.
     pub fn get_peer_state(&self, peer_id: &PeerId) -> Option<KnownPeerState> {
@@ -428,43 +480,55 @@ impl PeerStore {
 
     /// Return unconnected or peers with unknown status that we can try to connect to.
     /// Peers with unknown addresses are filtered out.
+    /// Peers with reputation at or below `deprioritize_threshold` are returned only
+    /// if there are no other candidates.
     pub fn unconnected_peer(
         &self,
         ignore_fn: impl Fn(&KnownPeerState) -> bool,
         prefer_previously_connected_peer: bool,
     ) -> Option<PeerInfo> {
         let inner = self.0.lock();
-        if prefer_previously_connected_peer {
-            let preferred_peer = inner.find_peers(
+        for skip_deprioritized in [true, false] {
+            let ignore_fn = |p: &KnownPeerState| {
+                ignore_fn(p)
+                    || (skip_deprioritized && inner.reputations.is_deprioritized(&p.peer_info.id))
+            };
+            if prefer_previously_connected_peer {
+                let preferred_peer = inner.find_peers(
+                    |p| {
+                        (p.status == KnownPeerStatus::NotConnected)
+                            && !ignore_fn(p)
+                            && p.peer_info.addr.is_some()
+                            // if we're connecting only to the boot nodes - filter out the nodes that are not bootnodes.
+                            && (!inner.config.connect_only_to_boot_nodes || inner.boot_nodes.contains(&p.peer_info.id))
+                    },
+                    1,
+                )
+                .get(0)
+                .cloned();
+                // If we found a preferred peer - return it.
+                if preferred_peer.is_some() {
+                    return preferred_peer;
+                };
+                // otherwise, pick a peer from the wider pool below.
+            }
+            let peer = inner.find_peers(
                 |p| {
-                    (p.status == KnownPeerStatus::NotConnected)
+                    (p.status == KnownPeerStatus::NotConnected || p.status == KnownPeerStatus::Unknown)
                         && !ignore_fn(p)
                         && p.peer_info.addr.is_some()
-                        // if we're connecting only to the boot nodes - filter out the nodes that are not bootnodes.
+                        // If we're connecting only to the boot nodes - filter out the nodes that are not boot nodes.
                         && (!inner.config.connect_only_to_boot_nodes || inner.boot_nodes.contains(&p.peer_info.id))
                 },
                 1,
             )
             .get(0)
             .cloned();
-            // If we found a preferred peer - return it.
-            if preferred_peer.is_some() {
-                return preferred_peer;
-            };
-            // otherwise, pick a peer from the wider pool below.
+            if peer.is_some() {
+                return peer;
+            }
         }
-        inner.find_peers(
-            |p| {
-                (p.status == KnownPeerStatus::NotConnected || p.status == KnownPeerStatus::Unknown)
-                    && !ignore_fn(p)
-                    && p.peer_info.addr.is_some()
-                    // If we're connecting only to the boot nodes - filter out the nodes that are not boot nodes.
-                    && (!inner.config.connect_only_to_boot_nodes || inner.boot_nodes.contains(&p.peer_info.id))
-            },
-            1,
-        )
-        .get(0)
-        .cloned()
+        None
     }
 
     /// Return healthy known peers up to given amount.
@@ -0,0 +1,231 @@
+use near_async::time;
+use near_primitives::network::PeerId;
+use std::collections::HashMap;
+
+/// Lowest reputation a peer can have.
+pub const MIN_REPUTATION: i32 = -100;
+/// Highest reputation a peer can have.
+pub const MAX_REPUTATION: i32 = 100;
+
+/// Observed behaviour of a peer which affects its reputation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ReputationEvent {
+    /// The peer sent us a message which we couldn't decode.
+    InvalidMessage,
+    /// The peer responded to a BlockRequest or StateRequestPart, but it took
+    /// longer than `ReputationConfig::slow_response_threshold`.
+    SlowResponse,
+    /// The peer didn't respond within `ReputationConfig::request_timeout` to a
+    /// StateRequestPart for a snapshot it advertised as a snapshot host.
+    RequestTimeout,
+    /// The peer sent us the same routed message more than once.
+    DuplicateRoutedMessage,
+    /// The peer responded promptly to a BlockRequest or StateRequestPart.
+    UsefulResponse,
+}
+
+impl ReputationEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::InvalidMessage => "invalid_message",
+            Self::SlowResponse => "slow_response",
+            Self::RequestTimeout => "request_timeout",
+            Self::DuplicateRoutedMessage => "duplicate_routed_message",
+            Self::UsefulResponse => "useful_response",
+        }
+    }
+}
+
+/// What the caller should do with the peer after its reputation has been updated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ReputationAction {
+    None,
+    /// Reputation dropped to `disconnect_threshold`: close the connection.
+    Disconnect,
+    /// Reputation dropped to `ban_threshold`: ban the peer.
+    Ban,
+}
+
+/// Parameters of the peer reputation policy.
+///
+/// Every peer starts with reputation 0. Events reported about the peer move its
+/// reputation within [MIN_REPUTATION, MAX_REPUTATION] and the reputation slowly
+/// decays back towards 0 over time.
+#[derive(Clone, Debug)]
+pub struct ReputationConfig {
+    pub invalid_message_penalty: i32,
+    pub slow_response_penalty: i32,
+    pub request_timeout_penalty: i32,
+    pub duplicate_routed_message_penalty: i32,
+    pub useful_response_reward: i32,
+    /// Responses which took longer than that are considered slow.
+    pub slow_response_threshold: time::Duration,
+    /// Requests which were not responded to within that time are considered timed out.
+    pub request_timeout: time::Duration,
+    /// Every `decay_period` the reputation of every peer moves by 1 towards 0.
+    pub decay_period: time::Duration,
+    /// Peers with reputation at or below this value are picked for outbound
+    /// connections only if there are no other candidates.
+    pub deprioritize_threshold: i32,
+    /// Peers with reputation at or below this value are disconnected.
+    pub disconnect_threshold: i32,
+    /// Peers with reputation at or below this value are banned.
+    pub ban_threshold: i32,
+}
+
+impl Default for ReputationConfig {
+    fn default() -> Self {
+        Self {
+            invalid_message_penalty: 10,
+            slow_response_penalty: 2,
+            request_timeout_penalty: 5,
+            duplicate_routed_message_penalty: 1,
+            useful_response_reward: 1,
+            slow_response_threshold: time::Duration::seconds(2),
+            request_timeout: time::Duration::seconds(10),
+            decay_period: time::Duration::minutes(10),
+            deprioritize_threshold: -20,
+            disconnect_threshold: -50,
+            ban_threshold: -80,
+        }
+    }
+}
+
+impl ReputationConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if !(MIN_REPUTATION <= self.ban_threshold
+            && self.ban_threshold < self.disconnect_threshold
+            && self.disconnect_threshold < self.deprioritize_threshold
+            && self.deprioritize_threshold < 0)
+        {
+            anyhow::bail!(
+                "thresholds have to satisfy {} <= ban_threshold({}) < disconnect_threshold({}) < deprioritize_threshold({}) < 0",
+                MIN_REPUTATION,
+                self.ban_threshold,
+                self.disconnect_threshold,
+                self.deprioritize_threshold
+            );
+        }
+        let weights = [
+            self.invalid_message_penalty,
+            self.slow_response_penalty,
+            self.request_timeout_penalty,
+            self.duplicate_routed_message_penalty,
+            self.useful_response_reward,
+        ];
+        if weights.iter().any(|w| *w < 0) {
+            anyhow::bail!("penalties and rewards have to be non-negative");
+        }
+        if self.decay_period <= time::Duration::ZERO {
+            anyhow::bail!("decay_period has to be positive");
+        }
+        Ok(())
+    }
+
+    fn delta(&self, event: ReputationEvent) -> i32 {
+        match event {
+            ReputationEvent::InvalidMessage => -self.invalid_message_penalty,
+            ReputationEvent::SlowResponse => -self.slow_response_penalty,
+            ReputationEvent::RequestTimeout => -self.request_timeout_penalty,
+            ReputationEvent::DuplicateRoutedMessage => -self.duplicate_routed_message_penalty,
+            ReputationEvent::UsefulResponse => self.useful_response_reward,
+        }
+    }
+}
+
+/// Reputation of the peers.
+///
+/// It is kept separately from the LRU cache of known peer states, so that a
+/// misbehaving peer cannot reset its reputation by getting evicted from the cache.
+/// Peers with reputation 0 are not stored.
+pub(crate) struct Reputations {
+    config: ReputationConfig,
+    scores: HashMap<PeerId, i32>,
+    last_decay: Option<time::Instant>,
+    /// Whether `scores` changed since the last call to `take_changed()`.
+    changed: bool,
+}
+
+impl Reputations {
+    pub fn new(config: ReputationConfig, scores: impl IntoIterator<Item = (PeerId, i32)>) -> Self {
+        let scores = scores
+            .into_iter()
+            .map(|(peer_id, score)| (peer_id, score.clamp(MIN_REPUTATION, MAX_REPUTATION)))
+            .filter(|(_, score)| *score != 0)
+            .collect();
+        Self { config, scores, last_decay: None, changed: false }
+    }
+
+    pub fn get(&self, peer_id: &PeerId) -> i32 {
+        self.scores.get(peer_id).copied().unwrap_or(0)
+    }
+
+    fn set(&mut self, peer_id: &PeerId, score: i32) {
+        let score = score.clamp(MIN_REPUTATION, MAX_REPUTATION);
+        if score == self.get(peer_id) {
+            return;
+        }
+        if score == 0 {
+            self.scores.remove(peer_id);
+        } else {
+            self.scores.insert(peer_id.clone(), score);
+        }
+        self.changed = true;
+    }
+
+    pub fn is_deprioritized(&self, peer_id: &PeerId) -> bool {
+        self.get(peer_id) <= self.config.deprioritize_threshold
+    }
+
+    /// Applies the event to the reputation of the peer and returns the action
+    /// required by the policy.
+    pub fn record(&mut self, peer_id: &PeerId, event: ReputationEvent) -> ReputationAction {
+        let delta = self.config.delta(event);
+        let score = self.get(peer_id).saturating_add(delta);
+        self.set(peer_id, score);
+        let score = self.get(peer_id);
+        if delta >= 0 {
+            ReputationAction::None
+        } else if score <= self.config.ban_threshold {
+            ReputationAction::Ban
+        } else if score <= self.config.disconnect_threshold {
+            ReputationAction::Disconnect
+        } else {
+            ReputationAction::None
+        }
+    }
+
+    /// Gives the peer another chance after its ban has expired: the reputation is
+    /// raised to `deprioritize_threshold`, so that the peer is not banned again
+    /// right away, but it is still not preferred.
+    pub fn unban(&mut self, peer_id: &PeerId) {
+        let score = self.get(peer_id).max(self.config.deprioritize_threshold);
+        self.set(peer_id, score);
+    }
+
+    /// Moves reputation of every peer by 1 towards 0 for every `decay_period`
+    /// which passed since the last decay.
+    pub fn decay(&mut self, now: time::Instant) {
+        let last_decay = *self.last_decay.get_or_insert(now);
+        // Float to int conversion saturates, so `step` can't overflow.
+        let step = ((now - last_decay) / self.config.decay_period).floor() as i32;
+        if step < 1 {
+            return;
+        }
+        self.last_decay = Some(last_decay + self.config.decay_period * step);
+        let peer_ids: Vec<_> = self.scores.keys().cloned().collect();
+        for peer_id in peer_ids {
+            let score = self.get(&peer_id);
+            let decayed = if score > 0 { (score - step).max(0) } else { (score + step).min(0) };
+            self.set(&peer_id, decayed);
+        }
+    }
+
+    /// Returns the scores if they changed since the last call.
+    pub fn take_changed(&mut self) -> Option<Vec<(PeerId, i32)>> {
+        if !std::mem::take(&mut self.changed) {
+            return None;
+        }
+        Some(self.scores.iter().map(|(peer_id, score)| (peer_id.clone(), *score)).collect())
+    }
+}
@@ -5,6 +5,10 @@ use near_crypto::{KeyType, SecretKey};
 use std::collections::HashSet;
 use std::net::{Ipv4Addr, SocketAddrV4};
 
+fn make_store() -> store::Store {
+    store::Store::from(near_store::db::TestDB::new())
+}
+
 fn get_peer_id(seed: String) -> PeerId {
     PeerId::new(SecretKey::from_seed(KeyType::ED25519, seed.as_str()).public_key())
 }
@@ -37,6 +41,7 @@ fn make_config(
         connect_only_to_boot_nodes,
         ban_window: time::Duration::seconds(1),
         peer_expiration_duration: time::Duration::days(1000),
+        reputation: ReputationConfig::default(),
     }
 }
 
@@ -47,9 +52,12 @@ fn ban_store() {
     let peer_info_to_ban = gen_peer_info(1);
     let boot_nodes = vec![peer_info_a, peer_info_to_ban.clone()];
 
-    let peer_store =
-        PeerStore::new(&clock.clock(), make_config(&boot_nodes, Blacklist::default(), false))
-            .unwrap();
+    let peer_store = PeerStore::new(
+        &clock.clock(),
+        make_config(&boot_nodes, Blacklist::default(), false),
+        make_store(),
+    )
+    .unwrap();
     assert_eq!(peer_store.healthy_peers(3).len(), 2);
     peer_store.peer_ban(&clock.clock(), &peer_info_to_ban.id, ReasonForBan::Abusive).unwrap();
     assert_eq!(peer_store.healthy_peers(3).len(), 1);
@@ -62,9 +70,12 @@ fn test_unconnected_peer() {
     let peer_info_to_ban = gen_peer_info(1);
     let boot_nodes = vec![peer_info_a, peer_info_to_ban];
 
-    let peer_store =
-        PeerStore::new(&clock.clock(), make_config(&boot_nodes, Blacklist::default(), false))
-            .unwrap();
+    let peer_store = PeerStore::new(
+        &clock.clock(),
+        make_config(&boot_nodes, Blacklist::default(), false),
+        make_store(),
+    )
+    .unwrap();
 
     assert!(peer_store.unconnected_peer(|_| false, false).is_some());
     assert!(peer_store.unconnected_peer(|_| true, false).is_none());
@@ -85,9 +96,12 @@ fn test_unknown_vs_not_connected() {
         nodes.map(|peer| peer_store.get_peer_state(&peer.id).map(|known_state| known_state.status))
     };
 
-    let peer_store =
-        PeerStore::new(&clock.clock(), make_config(&boot_nodes, Blacklist::default(), false))
-            .unwrap();
+    let peer_store = PeerStore::new(
+        &clock.clock(),
+        make_config(&boot_nodes, Blacklist::default(), false),
+        make_store(),
+    )
+    .unwrap();
 
     // Check the status of the in-memory store.
     // Boot node should be marked as not-connected, as we've verified it.
@@ -159,9 +173,12 @@ fn test_unconnected_peer_only_boot_nodes() {
     // 1 non-boot (peer_in_store) node peer that is in the store.
     // we should connect to peer_in_store
     {
-        let peer_store =
-            PeerStore::new(&clock.clock(), make_config(&boot_nodes, Blacklist::default(), false))
-                .unwrap();
+        let peer_store = PeerStore::new(
+            &clock.clock(),
+            make_config(&boot_nodes, Blacklist::default(), false),
+            make_store(),
+        )
+        .unwrap();
         peer_store.add_direct_peer(&clock.clock(), peer_in_store.clone());
         peer_store.peer_connected(&clock.clock(), &peer_info_a);
         assert_eq!(peer_store.unconnected_peer(|_| false, false), Some(peer_in_store.clone()));
@@ -171,9 +188,12 @@ fn test_unconnected_peer_only_boot_nodes() {
     // 1 non-boot (peer_in_store) node peer that is in the store.
     // connect to only boot nodes is enabled - we should not find any peer to connect to.
     {
-        let peer_store =
-            PeerStore::new(&clock.clock(), make_config(&boot_nodes, Default::default(), true))
-                .unwrap();
+        let peer_store = PeerStore::new(
+            &clock.clock(),
+            make_config(&boot_nodes, Default::default(), true),
+            make_store(),
+        )
+        .unwrap();
         peer_store.add_direct_peer(&clock.clock(), peer_in_store);
         peer_store.peer_connected(&clock.clock(), &peer_info_a);
         assert_eq!(peer_store.unconnected_peer(|_| false, false), None);
@@ -185,6 +205,7 @@ fn test_unconnected_peer_only_boot_nodes() {
         let peer_store = PeerStore::new(
             &clock.clock(),
             make_config(&boot_nodes, Default::default(), connect_to_boot_nodes),
+            make_store(),
         )
         .unwrap();
         peer_store.add_direct_peer(&clock.clock(), peer_info_a.clone());
@@ -237,7 +258,8 @@ fn check_integrity(peer_store: &PeerStore) -> bool {
 fn handle_peer_id_change() {
     let clock = time::FakeClock::default();
     let peer_store =
-        PeerStore::new(&clock.clock(), make_config(&[], Default::default(), false)).unwrap();
+        PeerStore::new(&clock.clock(), make_config(&[], Default::default(), false), make_store())
+            .unwrap();
 
     let peers_id = (0..2).map(|ix| get_peer_id(format!("node{}", ix))).collect::<Vec<_>>();
     let addr = get_addr(0);
@@ -261,7 +283,8 @@ fn handle_peer_id_change() {
 fn dont_handle_address_change() {
     let clock = time::FakeClock::default();
     let peer_store =
-        PeerStore::new(&clock.clock(), make_config(&[], Default::default(), false)).unwrap();
+        PeerStore::new(&clock.clock(), make_config(&[], Default::default(), false), make_store())
+            .unwrap();
 
     let peers_id = (0..1).map(|ix| get_peer_id(format!("node{}", ix))).collect::<Vec<_>>();
     let addrs = (0..2).map(get_addr).collect::<Vec<_>>();
@@ -280,7 +303,8 @@ fn dont_handle_address_change() {
 fn check_add_peers_overriding() {
     let clock = time::FakeClock::default();
     let peer_store =
-        PeerStore::new(&clock.clock(), make_config(&[], Default::default(), false)).unwrap();
+        PeerStore::new(&clock.clock(), make_config(&[], Default::default(), false), make_store())
+            .unwrap();
 
     // Five peers: A, B, C, D, X, T
     let peers_id = (0..6).map(|ix| get_peer_id(format!("node{}", ix))).collect::<Vec<_>>();
@@ -372,7 +396,8 @@ fn check_ignore_blacklisted_peers() {
     let blacklist: blacklist::Blacklist =
         ["127.0.0.1:1"].iter().map(|e| e.parse().unwrap()).collect();
 
-    let peer_store = PeerStore::new(&clock.clock(), make_config(&[], blacklist, false)).unwrap();
+    let peer_store =
+        PeerStore::new(&clock.clock(), make_config(&[], blacklist, false), make_store()).unwrap();
 
     peer_store.add_indirect_peers(
         &clock.clock(),
@@ -417,7 +442,8 @@ fn test_delete_peers() {
     let peer_addresses = peer_infos.iter().map(|info| info.addr.unwrap()).collect::<Vec<_>>();
 
     let peer_store =
-        PeerStore::new(&clock.clock(), make_config(&[], Default::default(), false)).unwrap();
+        PeerStore::new(&clock.clock(), make_config(&[], Default::default(), false), make_store())
+            .unwrap();
 
     peer_store.add_indirect_peers(&clock.clock(), peer_infos.into_iter());
     assert_peers_in_cache(&peer_store, &peer_ids, &peer_addresses);
@@ -431,7 +457,7 @@ fn test_lru_eviction() {
     let clock = time::FakeClock::default();
     let mut config = make_config(&[], Default::default(), false);
     config.peer_states_cache_size = 10;
-    let peer_store = PeerStore::new(&clock.clock(), config).unwrap();
+    let peer_store = PeerStore::new(&clock.clock(), config, make_store()).unwrap();
 
     let (peer_ids, peer_infos): (Vec<_>, Vec<_>) = (0..15)
         .map(|i| {
@@ -462,7 +488,7 @@ fn test_lru_ignore_duplicate_peers() {
     let clock = time::FakeClock::default();
     let mut config = make_config(&[], Default::default(), false);
     config.peer_states_cache_size = 10;
-    let peer_store = PeerStore::new(&clock.clock(), config).unwrap();
+    let peer_store = PeerStore::new(&clock.clock(), config, make_store()).unwrap();
 
     let (peer_ids, peer_infos): (Vec<_>, Vec<_>) = (0..15)
         .map(|i| {
@@ -485,3 +511,71 @@ fn test_lru_ignore_duplicate_peers() {
     peer_store.add_indirect_peers(&clock.clock(), peer_infos[10..].iter().cloned());
     assert_peers_in_cache(&peer_store, &peer_ids[5..], &peer_addresses[5..]);
 }
+
+/// Tests that negative events lead to disconnecting and then banning the peer,
+/// and that peers with low reputation are picked for outbound connections last.
+#[test]
+fn test_reputation_policy() {
+    let clock = time::FakeClock::default();
+    let good_peer = gen_peer_info(0);
+    let bad_peer = gen_peer_info(1);
+    let config = make_config(&[good_peer.clone(), bad_peer.clone()], Default::default(), false);
+    let reputation = config.reputation.clone();
+    let peer_store = PeerStore::new(&clock.clock(), config, make_store()).unwrap();
+
+    assert_eq!(
+        ReputationAction::None,
+        peer_store.record_reputation_event(&good_peer.id, ReputationEvent::UsefulResponse)
+    );
+    assert_eq!(reputation.useful_response_reward, peer_store.reputation(&good_peer.id));
+
+    let mut actions = vec![];
+    while peer_store.reputation(&bad_peer.id) > reputation.ban_threshold {
+        actions.push(
+            peer_store.record_reputation_event(&bad_peer.id, ReputationEvent::InvalidMessage),
+        );
+    }
+    assert_eq!(Some(&ReputationAction::Ban), actions.last());
+    assert!(actions.contains(&ReputationAction::Disconnect));
+
+    // The bad peer is returned only if the good one is ignored.
+    for _ in 0..10 {
+        assert_eq!(Some(good_peer.clone()), peer_store.unconnected_peer(|_| false, false));
+    }
+    assert_eq!(
+        Some(bad_peer.clone()),
+        peer_store.unconnected_peer(|p| p.peer_info.id == good_peer.id, false)
+    );
+
+    // Positive events never trigger disconnecting.
+    assert_eq!(
+        ReputationAction::None,
+        peer_store.record_reputation_event(&bad_peer.id, ReputationEvent::UsefulResponse)
+    );
+}
+
+/// Tests that reputation decays towards 0 and is persisted in the DB.
+#[test]
+fn test_reputation_decay_and_persistence() {
+    let clock = time::FakeClock::default();
+    let store = make_store();
+    let peer = gen_peer_info(0);
+    let config = make_config(&[], Default::default(), false);
+    let reputation = config.reputation.clone();
+    let peer_store = PeerStore::new(&clock.clock(), config.clone(), store.clone()).unwrap();
+
+    peer_store.record_reputation_event(&peer.id, ReputationEvent::RequestTimeout);
+    peer_store.record_reputation_event(&peer.id, ReputationEvent::RequestTimeout);
+    let score = -2 * reputation.request_timeout_penalty;
+    assert_eq!(score, peer_store.reputation(&peer.id));
+
+    // The first update only starts the decay timer.
+    peer_store.update(&clock.clock());
+    clock.advance(reputation.decay_period * 3);
+    peer_store.update(&clock.clock());
+    assert_eq!(score + 3, peer_store.reputation(&peer.id));
+
+    // A new PeerStore loads the scores saved by update().
+    let peer_store = PeerStore::new(&clock.clock(), config, store).unwrap();
+    assert_eq!(score + 3, peer_store.reputation(&peer.id));
+}
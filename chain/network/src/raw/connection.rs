@@ -1,3 +1,4 @@
+use crate::capture;
 use crate::network_protocol::{
     Encoding, Handshake, HandshakeFailureReason, PartialEdgeInfo, PeerChainInfoV2, PeerIdOrHash,
     PeerMessage, Ping, Pong, RawRoutedMessage, RoutedMessageBody, RoutingTableUpdate,
@@ -214,6 +215,7 @@ fn new_handshake(
         },
         partial_edge_info: PartialEdgeInfo::new(my_peer_id, target_peer_id, nonce, secret_key),
         owned_account: None,
+        noise_handshake: None,
     })
 }
 
@@ -415,6 +417,33 @@ impl Connection {
         self.stream.write_message(&PeerMessage::Routed(Box::new(msg))).await
     }
 
+    /// Sends the message from the capture record to the peer, unless it is specific to the
+    /// connection it was captured on (handshakes, edge updates, disconnects), in which case
+    /// it is skipped. Returns whether the message has been sent.
+    /// Routed messages are addressed to the captured node and signed by their original
+    /// authors, so they are re-targeted to the peer and re-signed with our key.
+    pub async fn send_captured(&mut self, record: &capture::Record) -> anyhow::Result<bool> {
+        let msg = match record.parse()? {
+            PeerMessage::Tier1Handshake(_)
+            | PeerMessage::Tier2Handshake(_)
+            | PeerMessage::HandshakeFailure(_, _)
+            | PeerMessage::LastEdge(_)
+            | PeerMessage::RequestUpdateNonce(_)
+            | PeerMessage::Disconnect(_) => return Ok(false),
+            PeerMessage::Routed(routed) => {
+                let routed = RawRoutedMessage {
+                    target: PeerIdOrHash::PeerId(self.peer_id.clone()),
+                    body: routed.msg.body,
+                }
+                .sign(&self.secret_key, routed.msg.ttl, Some(Utc::now_utc()));
+                PeerMessage::Routed(Box::new(routed))
+            }
+            msg => msg,
+        };
+        self.stream.write_message(&msg).await?;
+        Ok(true)
+    }
+
     fn target_is_for_me(&mut self, target: &PeerIdOrHash) -> bool {
         match target {
             PeerIdOrHash::PeerId(peer_id) => peer_id == &self.my_peer_id,
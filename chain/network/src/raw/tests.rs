@@ -1,7 +1,10 @@
+use crate::capture;
 use crate::network_protocol::testonly as data;
+use crate::network_protocol::{Disconnect, Encoding, PeerMessage, RoutedMessageBody};
+use crate::peer_manager::testonly::Event;
 use crate::raw;
 use crate::tcp;
-use crate::testonly;
+use crate::testonly::{self, fake_client};
 use crate::types::PeerInfo;
 use near_async::time;
 use near_crypto::{KeyType, SecretKey};
@@ -184,3 +187,102 @@ async fn test_listener() {
     // at least the handshake logic has gotten exercised somewhat
     let _ = conn.recv().await.unwrap();
 }
+
+#[tokio::test]
+async fn test_raw_conn_replay() {
+    init_test_logger();
+    let mut rng = testonly::make_rng(33955575545);
+    let rng = &mut rng;
+    let mut clock = time::FakeClock::default();
+    let chain = Arc::new(data::Chain::make(&mut clock, rng, 10));
+
+    // Capture of the traffic of some other node.
+    let block_hash = *chain.blocks[5].hash();
+    let tx = data::make_signed_transaction(rng);
+    // Routed to the captured node and signed by some other peer.
+    let forwarded_tx = data::make_signed_transaction(rng);
+    let routed = data::make_routed_message(rng, RoutedMessageBody::ForwardTx(forwarded_tx.clone()));
+    let msgs = [
+        (
+            capture::Direction::Inbound,
+            PeerMessage::Tier2Handshake(data::make_handshake(rng, &chain)),
+        ),
+        (capture::Direction::Inbound, PeerMessage::BlockRequest(block_hash)),
+        (capture::Direction::Outbound, PeerMessage::BlockRequest(data::make_hash(rng))),
+        (capture::Direction::Inbound, PeerMessage::Transaction(tx.clone())),
+        (capture::Direction::Inbound, PeerMessage::Routed(Box::new(routed))),
+        (
+            capture::Direction::Inbound,
+            PeerMessage::Disconnect(Disconnect { remove_from_connection_store: false }),
+        ),
+    ];
+    let mut w = capture::Writer::new(vec![]).unwrap();
+    for (i, (direction, msg)) in msgs.iter().enumerate() {
+        w.write(&capture::Record {
+            timestamp_nanos: i as i128,
+            direction: *direction,
+            peer_id: Some(data::make_peer_id(rng)),
+            peer_addr: "127.0.0.1:24567".to_string(),
+            message: msg.serialize(Encoding::Proto),
+        })
+        .unwrap();
+    }
+    let capture = w.finish().unwrap();
+
+    let cfg = chain.make_config(rng);
+    let peer_id = cfg.node_id();
+    let addr = **cfg.node_addr.as_ref().unwrap();
+    let genesis_id = chain.genesis_id.clone();
+    let pm = crate::peer_manager::testonly::start(
+        clock.clock(),
+        near_store::db::TestDB::new(),
+        cfg,
+        chain,
+    )
+    .await;
+    let mut events = pm.events.clone();
+
+    let mut conn = raw::Connection::connect(
+        addr,
+        peer_id.clone(),
+        None,
+        &genesis_id.chain_id,
+        genesis_id.hash,
+        0,
+        vec![0],
+        time::Duration::SECOND,
+    )
+    .await
+    .unwrap();
+
+    // Replay the messages received by the captured node.
+    let mut reader = capture::Reader::new(&capture[..]).unwrap();
+    let mut sent = vec![];
+    while let Some(record) = reader.next().unwrap() {
+        if record.direction == capture::Direction::Inbound {
+            sent.push(conn.send_captured(&record).await.unwrap());
+        }
+    }
+    // Handshake and Disconnect are specific to the captured connection.
+    assert_eq!(sent, vec![false, true, true, true, false]);
+
+    events
+        .recv_until(|ev| match ev {
+            Event::Client(fake_client::Event::BlockRequest(hash)) if hash == block_hash => Some(()),
+            _ => None,
+        })
+        .await;
+    events
+        .recv_until(|ev| match ev {
+            Event::Client(fake_client::Event::Transaction(got)) if got == tx => Some(()),
+            _ => None,
+        })
+        .await;
+    // The routed message has been re-targeted to the peer, so it is delivered to its client.
+    events
+        .recv_until(|ev| match ev {
+            Event::Client(fake_client::Event::Transaction(got)) if got == forwarded_tx => Some(()),
+            _ => None,
+        })
+        .await;
+}
@@ -8,7 +8,9 @@ use crate::concurrency;
 use crate::network_protocol::SnapshotHostInfo;
 use crate::network_protocol::SnapshotHostInfoVerificationError;
 use lru::LruCache;
+use near_primitives::hash::CryptoHash;
 use near_primitives::network::PeerId;
+use near_primitives::types::ShardId;
 use parking_lot::Mutex;
 use rayon::iter::ParallelBridge;
 use std::collections::HashMap;
@@ -130,6 +132,14 @@ impl SnapshotHostsCache {
         (newly_inserted_data, err)
     }
 
+    /// Whether the peer advertised a snapshot of the given shard at the given sync hash.
+    pub fn is_host(&self, peer_id: &PeerId, sync_hash: &CryptoHash, shard_id: ShardId) -> bool {
+        match self.0.lock().hosts.peek(peer_id) {
+            Some(info) => &info.sync_hash == sync_hash && info.shards.contains(&shard_id),
+            None => false,
+        }
+    }
+
     pub fn get_hosts(&self) -> Vec<Arc<SnapshotHostInfo>> {
         self.0.lock().hosts.iter().map(|(_, v)| v.clone()).collect()
     }
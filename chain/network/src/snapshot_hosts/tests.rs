@@ -200,3 +200,32 @@ async fn test_lru_eviction() {
     // check that the oldest data was evicted
     assert_eq!([&info1, &info2].as_set(), cache.get_hosts().iter().collect::<HashSet<_>>());
 }
+
+#[tokio::test]
+async fn is_host() {
+    init_test_logger();
+    let mut rng = make_rng(2947294234);
+    let rng = &mut rng;
+
+    let key0 = data::make_secret_key(rng);
+    let key1 = data::make_secret_key(rng);
+
+    let peer0 = PeerId::new(key0.public_key());
+    let peer1 = PeerId::new(key1.public_key());
+
+    let config = Config { snapshot_hosts_cache_size: 100 };
+    let cache = SnapshotHostsCache::new(config);
+
+    let info0 = Arc::new(make_snapshot_host_info(&peer0, 123, vec![0, 2], &key0));
+    let res = cache.insert(vec![info0.clone()]).await;
+    assert_eq!([&info0].as_set(), unwrap(&res).as_set());
+
+    assert!(cache.is_host(&peer0, &info0.sync_hash, 0));
+    assert!(cache.is_host(&peer0, &info0.sync_hash, 2));
+    // shard not in the snapshot
+    assert!(!cache.is_host(&peer0, &info0.sync_hash, 1));
+    // different sync hash
+    assert!(!cache.is_host(&peer0, &CryptoHash::hash_borsh(124u64), 0));
+    // unknown peer
+    assert!(!cache.is_host(&peer1, &info0.sync_hash, 0));
+}
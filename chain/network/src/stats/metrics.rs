@@ -118,6 +118,23 @@ pub(crate) static PEER_DATA_RECEIVED_BYTES: Lazy<IntCounter> = Lazy::new(|| {
         .unwrap()
 });
 
+pub(crate) static PEER_REPUTATION_EVENTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "near_peer_reputation_events_total",
+        "Number of events affecting the reputation of peers, by event type",
+        &["event"],
+    )
+    .unwrap()
+});
+pub(crate) static PEER_REPUTATION_ACTIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "near_peer_reputation_actions_total",
+        "Number of peers disconnected or banned because of low reputation",
+        &["action"],
+    )
+    .unwrap()
+});
+
 pub(crate) static PEER_MSG_SIZE_BYTES: Lazy<HistogramVec> = Lazy::new(|| {
     try_create_histogram_vec(
         "near_peer_msg_size_bytes",
@@ -160,6 +177,30 @@ pub(crate) static PEER_DATA_WRITE_BUFFER_SIZE: Lazy<IntGaugeVec> = Lazy::new(||
     )
     .unwrap()
 });
+pub(crate) static PEER_MESSAGE_QUEUEING_DELAY: Lazy<HistogramVec> = Lazy::new(|| {
+    try_create_histogram_vec(
+        "near_peer_message_queueing_delay",
+        "Time that messages spend in the send queue of a peer, by message class",
+        &["class"],
+        Some(exponential_buckets(0.0001, 2., 18).unwrap()),
+    )
+    .unwrap()
+});
+pub(crate) static PEER_MESSAGE_SENT_BY_CLASS_BYTES: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "near_peer_message_sent_by_class_bytes",
+        "Total data sent to peers, by message class",
+        &["class"],
+    )
+    .unwrap()
+});
+pub(crate) static NETWORK_CAPTURE_DROPPED_RECORDS: Lazy<IntCounter> = Lazy::new(|| {
+    try_create_int_counter(
+        "near_network_capture_dropped_records_total",
+        "Number of messages which were not written to the network capture, because the capture queue was full",
+    )
+    .unwrap()
+});
 pub(crate) static PEER_MESSAGE_RECEIVED_BY_TYPE_BYTES: Lazy<IntCounterVec> = Lazy::new(|| {
     try_create_int_counter_vec(
         "near_peer_message_received_by_type_bytes",
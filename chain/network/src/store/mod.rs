@@ -137,6 +137,19 @@ impl Store {
     }
 }
 
+// PeerStore storage.
+impl Store {
+    pub fn set_peer_reputations(&mut self, reputations: &Vec<(PeerId, i32)>) -> Result<(), Error> {
+        let mut update = self.0.new_update();
+        update.set::<schema::PeerReputations>(&(), reputations);
+        self.0.commit(update).map_err(Error)
+    }
+
+    pub fn get_peer_reputations(&self) -> Vec<(PeerId, i32)> {
+        self.0.get::<schema::PeerReputations>(&()).unwrap_or(Some(vec![])).unwrap_or(vec![])
+    }
+}
+
 impl From<Arc<dyn near_store::db::Database>> for Store {
     fn from(store: Arc<dyn near_store::db::Database>) -> Self {
         Self(schema::Store::from(store))
@@ -103,6 +103,13 @@ impl Column for RecentOutboundConnections {
     type Value = Vec<ConnectionInfoRepr>;
 }
 
+pub(super) struct PeerReputations;
+impl Column for PeerReputations {
+    const COL: DBCol = DBCol::PeerReputations;
+    type Key = Borsh<()>;
+    type Value = Borsh<Vec<(PeerId, i32)>>;
+}
+
 pub(super) struct PeerComponent;
 impl Column for PeerComponent {
     const COL: DBCol = DBCol::PeerComponent;
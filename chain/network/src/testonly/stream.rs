@@ -1,20 +1,26 @@
 //! Stream wrapper, which allows for custom interactions with the network protocol.
 //! We might want to turn it into a fuzz testing framework for the network protocol.
-use bytes::BytesMut;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
 use crate::network_protocol::{Encoding, PeerMessage};
+use crate::peer::noise;
 use crate::tcp;
 
 pub struct Stream {
     stream: tcp::Stream,
     force_encoding: Option<Encoding>,
     protocol_buffers_supported: bool,
+    transport: Option<noise::Transport>,
 }
 
 impl Stream {
     pub fn new(force_encoding: Option<Encoding>, stream: tcp::Stream) -> Self {
-        Self { stream, force_encoding, protocol_buffers_supported: false }
+        Self { stream, force_encoding, protocol_buffers_supported: false, transport: None }
+    }
+
+    /// Encrypts/decrypts all the subsequent messages.
+    pub(crate) fn start_encryption(&mut self, transport: noise::Transport) {
+        self.transport = Some(transport);
     }
 
     fn encoding(&self) -> Option<Encoding> {
@@ -30,9 +36,11 @@ impl Stream {
     pub async fn read(&mut self) -> Result<PeerMessage, std::io::Error> {
         'read: loop {
             let n = self.stream.stream.read_u32_le().await? as usize;
-            let mut buf = BytesMut::new();
-            buf.resize(n, 0);
+            let mut buf = vec![0; n];
             self.stream.stream.read_exact(&mut buf[..]).await?;
+            if let Some(t) = &mut self.transport {
+                t.recv.decrypt(&mut buf).unwrap();
+            }
             for enc in [Encoding::Proto, Encoding::Borsh] {
                 if let Ok(msg) = PeerMessage::deserialize(enc, &buf[..]) {
                     // If deserialize() succeeded but we expected different encoding, ignore the
@@ -61,8 +69,12 @@ impl Stream {
     }
 
     async fn write_encoded(&mut self, msg: &[u8]) {
+        let mut msg = msg.to_vec();
+        if let Some(t) = &mut self.transport {
+            t.send.encrypt(&mut msg).unwrap();
+        }
         self.stream.stream.write_u32_le(msg.len() as u32).await.unwrap();
-        self.stream.stream.write_all(msg).await.unwrap();
+        self.stream.stream.write_all(&msg).await.unwrap();
         self.stream.stream.flush().await.unwrap();
     }
 }
@@ -18,7 +18,7 @@ use near_async::time;
 use near_crypto::PublicKey;
 use near_primitives::block::{ApprovalMessage, Block, GenesisId};
 use near_primitives::challenge::Challenge;
-use near_primitives::chunk_validation::{ChunkEndorsementMessage, ChunkStateWitness};
+use near_primitives::chunk_validation::{ChunkEndorsementMessage, EncodedChunkStateWitness};
 use near_primitives::hash::CryptoHash;
 use near_primitives::network::{AnnounceAccount, PeerId};
 use near_primitives::sharding::PartialEncodedChunkWithArcReceipts;
@@ -69,6 +69,7 @@ pub enum ReasonForBan {
     InvalidDistanceVector = 11,
     Blacklisted = 14,
     ProvidedNotEnoughHeaders = 15,
+    LowReputation = 16,
 }
 
 /// Banning signal sent from Peer instance to PeerManager
@@ -258,8 +259,8 @@ pub enum NetworkRequests {
     TxStatus(AccountId, AccountId, CryptoHash),
     /// A challenge to invalidate a block.
     Challenge(Challenge),
-    /// A chunk's state witness.
-    ChunkStateWitness(Vec<AccountId>, ChunkStateWitness),
+    /// A chunk's state witness, sent to the given chunk validators.
+    ChunkStateWitness(Vec<AccountId>, EncodedChunkStateWitness),
     /// Message for a chunk endorsement, sent by a chunk validator to
     /// the block producer.
     ChunkEndorsement(ChunkEndorsementMessage),
@@ -345,6 +346,8 @@ pub struct ConnectedPeerInfo {
     pub peer_type: PeerType,
     /// Nonce used for the connection with the peer.
     pub nonce: u64,
+    /// Reputation score of the peer, see `peer_store::ReputationConfig`.
+    pub reputation: i32,
 }
 
 #[derive(Debug, Clone, actix::MessageResponse)]
@@ -1,10 +1,12 @@
 use std::collections::btree_map::Entry;
 use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::Arc;
 
+use crate::policy::{Priority, RoundRobinPolicy, TransactionOrderingPolicy};
 use crate::types::{PoolIterator, PoolKey, TransactionGroup};
 
-use near_crypto::PublicKey;
 use near_o11y::metrics::prometheus::core::{AtomicI64, GenericGauge};
+use near_o11y::metrics::IntCounter;
 use near_primitives::epoch_manager::RngSeed;
 use near_primitives::hash::{hash, CryptoHash};
 use near_primitives::transaction::SignedTransaction;
@@ -12,6 +14,7 @@ use near_primitives::types::AccountId;
 use std::ops::Bound;
 
 mod metrics;
+pub mod policy;
 pub mod types;
 
 #[derive(Debug, PartialEq)]
@@ -22,6 +25,24 @@ pub enum InsertTransactionResult {
     Duplicate,
     /// Not enough space to fit the transaction.
     NoSpaceLeft,
+    /// The signer of the transaction has used up its quota in the pool.
+    SignerQuotaExceeded,
+}
+
+/// Limits on the transactions of a single signer account held in the pool.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SignerQuota {
+    /// Max number of transactions of a signer.
+    pub count_limit: Option<u64>,
+    /// Max total size of transactions of a signer measured in bytes.
+    pub size_limit: Option<u64>,
+}
+
+/// Number and total size of transactions of a signer in the pool.
+#[derive(Default)]
+struct SignerUsage {
+    count: u64,
+    size: u64,
 }
 
 /// Transaction pool: keeps track of transactions that were not yet accepted into the block chain.
@@ -36,13 +57,29 @@ pub struct TransactionPool {
     key_seed: RngSeed,
     /// The key after which the pool iterator starts. Doesn't have to be present in the pool.
     last_used_key: PoolKey,
-    /// If set, new transactions that bring the size of the pool over this limit will be rejected.
+    /// If set, new transactions that bring the size of the pool over this limit will be rejected,
+    /// unless enough space can be freed by evicting groups of the same or lower priority.
     total_transaction_size_limit: Option<u64>,
     /// Total size of transactions in the pool measured in bytes.
     total_transaction_size: u64,
+    /// Decides the priority of transaction groups.
+    ordering_policy: Arc<dyn TransactionOrderingPolicy>,
+    /// Limits on the transactions of a single signer.
+    signer_quota: SignerQuota,
+    /// Usage of the pool by each signer with transactions in it.
+    signer_usage: HashMap<AccountId, SignerUsage>,
+    /// Groups in the order of eviction: the lowest priority first and then the oldest first.
+    /// Groups pulled by a pool iterator stay here until they run out of transactions.
+    eviction_queue: BTreeMap<(Priority, u64), PoolKey>,
+    /// The key and the sequence number of each group in `eviction_queue` by the hash of its key.
+    /// The priority of a group is decided when it's created, so this is how later transactions
+    /// find the key of their group.
+    groups: HashMap<CryptoHash, (PoolKey, u64)>,
+    next_group_seq_number: u64,
     /// Metrics tracked for transaction pool.
     transaction_pool_count_metric: GenericGauge<AtomicI64>,
     transaction_pool_size_metric: GenericGauge<AtomicI64>,
+    transaction_pool_evicted_metric: IntCounter,
 }
 
 impl TransactionPool {
@@ -55,27 +92,57 @@ impl TransactionPool {
             metrics::TRANSACTION_POOL_COUNT.with_label_values(&[metrics_label]);
         let transaction_pool_size_metric =
             metrics::TRANSACTION_POOL_SIZE.with_label_values(&[metrics_label]);
+        let transaction_pool_evicted_metric =
+            metrics::TRANSACTION_POOL_EVICTED.with_label_values(&[metrics_label]);
         // A `get()` call initializes a metric even if its value is zero.
         transaction_pool_count_metric.get();
         transaction_pool_size_metric.get();
+        transaction_pool_evicted_metric.get();
 
         Self {
             key_seed,
             transactions: BTreeMap::new(),
             unique_transactions: HashSet::new(),
-            last_used_key: CryptoHash::default(),
+            last_used_key: PoolKey { priority: Priority::MAX, hash: CryptoHash::default() },
             total_transaction_size_limit,
             total_transaction_size: 0,
+            ordering_policy: Arc::new(RoundRobinPolicy),
+            signer_quota: SignerQuota::default(),
+            signer_usage: HashMap::new(),
+            eviction_queue: BTreeMap::new(),
+            groups: HashMap::new(),
+            next_group_seq_number: 0,
             transaction_pool_count_metric,
             transaction_pool_size_metric,
+            transaction_pool_evicted_metric,
         }
     }
 
-    fn key(&self, account_id: &AccountId, public_key: &PublicKey) -> PoolKey {
-        let mut v = borsh::to_vec(&public_key).unwrap();
+    /// Sets the policy ordering the transaction groups. Round robin is used by default.
+    pub fn with_ordering_policy(
+        mut self,
+        ordering_policy: Arc<dyn TransactionOrderingPolicy>,
+    ) -> Self {
+        assert!(self.transactions.is_empty(), "can't change the ordering of a non-empty pool");
+        self.ordering_policy = ordering_policy;
+        self
+    }
+
+    /// Sets the limits on transactions of a single signer. Signers are unlimited by default.
+    pub fn with_signer_quota(mut self, signer_quota: SignerQuota) -> Self {
+        self.signer_quota = signer_quota;
+        self
+    }
+
+    fn key(&self, transaction: &SignedTransaction) -> PoolKey {
+        let mut v = borsh::to_vec(&transaction.transaction.public_key).unwrap();
         v.extend_from_slice(&self.key_seed);
-        v.extend_from_slice(account_id.as_bytes());
-        hash(&v)
+        v.extend_from_slice(transaction.transaction.signer_id.as_bytes());
+        let hash = hash(&v);
+        match self.groups.get(&hash) {
+            Some((key, _)) => *key,
+            None => PoolKey { priority: self.ordering_policy.priority(transaction), hash },
+        }
     }
 
     /// Inserts a signed transaction that passed validation into the pool.
@@ -84,37 +151,132 @@ impl TransactionPool {
         &mut self,
         signed_transaction: SignedTransaction,
     ) -> InsertTransactionResult {
-        if !self.unique_transactions.insert(signed_transaction.get_hash()) {
+        if self.unique_transactions.contains(&signed_transaction.get_hash()) {
             // The hash of this transaction was already seen, skip it.
             return InsertTransactionResult::Duplicate;
         }
+        let transaction_size = signed_transaction.get_size();
+        let signer_id = &signed_transaction.transaction.signer_id;
+        let (signer_count, signer_size) =
+            self.signer_usage.get(signer_id).map_or((0, 0), |usage| (usage.count, usage.size));
+        if self.signer_quota.count_limit.map_or(false, |limit| signer_count >= limit)
+            || self
+                .signer_quota
+                .size_limit
+                .map_or(false, |limit| signer_size + transaction_size > limit)
+        {
+            return InsertTransactionResult::SignerQuotaExceeded;
+        }
+
+        let key = self.key(&signed_transaction);
         // We never expect the total size to go over `u64` during real operation as that would
         // be more than 10^9 GiB of RAM consumed for transaction pool, so panicing here is intended
         // to catch a logic error in estimation of transaction size.
         let new_total_transaction_size = self
             .total_transaction_size
-            .checked_add(signed_transaction.get_size())
+            .checked_add(transaction_size)
             .expect("Total transaction size is too large");
         if let Some(limit) = self.total_transaction_size_limit {
-            if new_total_transaction_size > limit {
+            if new_total_transaction_size > limit
+                && !self.evict_groups(key, new_total_transaction_size - limit)
+            {
                 return InsertTransactionResult::NoSpaceLeft;
             }
         }
 
         // At this point transaction is accepted to the pool.
-        self.total_transaction_size = new_total_transaction_size;
-        let signer_id = &signed_transaction.transaction.signer_id;
-        let signer_public_key = &signed_transaction.transaction.public_key;
-        self.transactions
-            .entry(self.key(signer_id, signer_public_key))
-            .or_insert_with(Vec::new)
-            .push(signed_transaction);
+        self.unique_transactions.insert(signed_transaction.get_hash());
+        self.total_transaction_size += transaction_size;
+        let usage = self.signer_usage.entry(signer_id.clone()).or_default();
+        usage.count += 1;
+        usage.size += transaction_size;
+        if !self.groups.contains_key(&key.hash) {
+            let seq_number = self.next_group_seq_number;
+            self.next_group_seq_number += 1;
+            self.groups.insert(key.hash, (key, seq_number));
+            self.eviction_queue.insert((key.priority, seq_number), key);
+        }
+        self.transactions.entry(key).or_insert_with(Vec::new).push(signed_transaction);
 
         self.transaction_pool_count_metric.inc();
         self.transaction_pool_size_metric.set(self.total_transaction_size as i64);
         InsertTransactionResult::Success
     }
 
+    /// Evicts whole transaction groups to free at least `size` bytes, starting with the groups
+    /// of the lowest priority and the oldest ones. Only groups of the same or lower priority than
+    /// `key` are evicted, and never the group of `key` itself.
+    ///
+    /// Returns false without evicting anything if not enough space can be freed.
+    fn evict_groups(&mut self, key: PoolKey, size: u64) -> bool {
+        let mut victims = vec![];
+        let mut freed_size = 0;
+        for (&(priority, _), victim) in &self.eviction_queue {
+            if freed_size >= size || priority > key.priority {
+                break;
+            }
+            if *victim == key {
+                continue;
+            }
+            freed_size += self.transactions[victim].iter().map(|tx| tx.get_size()).sum::<u64>();
+            victims.push(*victim);
+        }
+        if freed_size < size {
+            return false;
+        }
+        for victim in victims {
+            let transactions = self.transactions.remove(&victim).expect("victims are in the pool");
+            for tx in &transactions {
+                self.unique_transactions.remove(&tx.get_hash());
+                release_signer_usage(
+                    &mut self.signer_usage,
+                    &tx.transaction.signer_id,
+                    1,
+                    tx.get_size(),
+                );
+            }
+            tracing::debug!(target: "client", signer_id = ?transactions[0].transaction.signer_id, num_transactions = transactions.len(), "evicting transactions from the pool");
+            self.transaction_pool_evicted_metric.inc_by(transactions.len() as u64);
+            self.forget_group(victim);
+        }
+        // See the comment in `insert_transaction` where we increase the size for reasoning
+        // why panicing here catches a logic error.
+        self.total_transaction_size = self
+            .total_transaction_size
+            .checked_sub(freed_size)
+            .expect("Total transaction size dropped below zero");
+        true
+    }
+
+    /// Removes a group which ran out of transactions from the eviction queue.
+    fn forget_group(&mut self, key: PoolKey) {
+        if let Some((_, seq_number)) = self.groups.remove(&key.hash) {
+            self.eviction_queue.remove(&(key.priority, seq_number));
+        }
+    }
+
+    /// Accounts for the transactions pulled from a group by a pool iterator.
+    fn release_pulled_transactions(&mut self, group: &TransactionGroup) {
+        for hash in &group.removed_transaction_hashes {
+            self.unique_transactions.remove(hash);
+        }
+        // See the comment in `insert_transaction` where we increase the size for reasoning
+        // why panicing here catches a logic error.
+        self.total_transaction_size = self
+            .total_transaction_size
+            .checked_sub(group.removed_transaction_size)
+            .expect("Total transaction size dropped below zero");
+        release_signer_usage(
+            &mut self.signer_usage,
+            &group.signer_id,
+            group.removed_transaction_hashes.len() as u64,
+            group.removed_transaction_size,
+        );
+        if group.transactions.is_empty() {
+            self.forget_group(group.key);
+        }
+    }
+
     /// Returns a pool iterator wrapper that implements an iterator-like trait to iterate over
     /// transaction groups in the proper order defined by the protocol.
     /// When the iterator is dropped, all remaining groups are inserted back into the pool.
@@ -134,10 +296,8 @@ impl TransactionPool {
                 continue;
             }
 
-            let signer_id = &tx.transaction.signer_id;
-            let signer_public_key = &tx.transaction.public_key;
             grouped_transactions
-                .entry(self.key(signer_id, signer_public_key))
+                .entry(self.key(tx))
                 .or_insert_with(HashSet::new)
                 .insert(tx.get_hash());
         }
@@ -153,10 +313,17 @@ impl TransactionPool {
                         .total_transaction_size
                         .checked_sub(tx.get_size())
                         .expect("Total transaction size dropped below zero");
+                    release_signer_usage(
+                        &mut self.signer_usage,
+                        &tx.transaction.signer_id,
+                        1,
+                        tx.get_size(),
+                    );
                     false
                 });
                 if entry.get().is_empty() {
                     entry.remove_entry();
+                    self.forget_group(key);
                 }
             }
         }
@@ -175,6 +342,33 @@ impl TransactionPool {
     pub fn transaction_size(&self) -> u64 {
         self.total_transaction_size
     }
+
+    /// Returns the number of distinct signers of the transactions in the pool.
+    pub fn signer_count(&self) -> usize {
+        self.signer_usage.len()
+    }
+
+    /// Returns all transactions in the pool in no particular order.
+    pub fn transactions(&self) -> impl Iterator<Item = &SignedTransaction> {
+        self.transactions.values().flatten()
+    }
+}
+
+fn release_signer_usage(
+    signer_usage: &mut HashMap<AccountId, SignerUsage>,
+    signer_id: &AccountId,
+    count: u64,
+    size: u64,
+) {
+    if count == 0 {
+        return;
+    }
+    let usage = signer_usage.get_mut(signer_id).expect("signer has transactions in the pool");
+    usage.count -= count;
+    usage.size -= size;
+    if usage.count == 0 {
+        signer_usage.remove(signer_id);
+    }
 }
 
 /// PoolIterator is a structure to pull transactions from the pool.
@@ -184,8 +378,8 @@ pub struct PoolIteratorWrapper<'a> {
     /// Mutable reference to the pool, to avoid exposing it while the iterator exists.
     pool: &'a mut TransactionPool,
 
-    /// Queue of transaction groups. Each group there is sorted by nonce.
-    sorted_groups: VecDeque<TransactionGroup>,
+    /// Queues of transaction groups by their priority. Each group there is sorted by nonce.
+    sorted_groups: BTreeMap<Priority, VecDeque<TransactionGroup>>,
 }
 
 impl<'a> PoolIteratorWrapper<'a> {
@@ -195,74 +389,73 @@ impl<'a> PoolIteratorWrapper<'a> {
 }
 
 /// The iterator works with the following algorithm:
-/// On next(), the iterator tries to get a transaction group from the pool, sorts transactions in
-/// it, and add it to the back of the sorted groups queue.
-/// Remembers the last used key, so it can continue from the next key.
+/// On next(), the iterator tries to get a transaction group of the highest priority from the
+/// pool, sorts transactions in it, and add it to the back of the sorted groups queue of its
+/// priority. Remembers the last used key, so it can continue from the next key.
 ///
-/// If the pool is empty, the iterator gets the group from the front of the sorted groups queue.
+/// If the pool is empty or has only groups of a lower priority than some sorted groups queue,
+/// the iterator gets the group from the front of the highest priority sorted groups queue
+/// instead.
 ///
 /// If this group is empty (no transactions left inside), then the iterator discards it and
 /// updates `unique_transactions` in the pool. Then gets the next one.
 ///
-/// Once a non-empty group is found, this group is pushed to the back of the sorted groups queue
+/// Once a non-empty group is found, this group is pushed to the back of its sorted groups queue
 /// and the iterator returns a mutable reference to this group.
 ///
-/// If the sorted groups queue is empty, the iterator returns None.
+/// If all sorted groups queues are empty, the iterator returns None.
 ///
 /// When the iterator is dropped, `unique_transactions` in the pool is updated for every group.
-/// And all non-empty group from the sorted groups queue are inserted back into the pool.
+/// And all non-empty group from the sorted groups queues are inserted back into the pool.
+///
+/// With the default round robin ordering all groups have the same priority, so all groups are
+/// pulled from the pool first and then visited in a round robin.
 impl<'a> PoolIterator for PoolIteratorWrapper<'a> {
     fn next(&mut self) -> Option<&mut TransactionGroup> {
-        if !self.pool.transactions.is_empty() {
-            let key = *self
-                .pool
-                .transactions
-                .range((Bound::Excluded(self.pool.last_used_key), Bound::Unbounded))
-                .next()
-                .map(|(k, _v)| k)
-                .unwrap_or_else(|| {
-                    self.pool
-                        .transactions
-                        .keys()
-                        .next()
-                        .expect("we've just checked that the map is not empty")
-                });
-            self.pool.last_used_key = key;
-            let mut transactions =
-                self.pool.transactions.remove(&key).expect("just checked existence");
-            transactions.sort_by_key(|st| std::cmp::Reverse(st.transaction.nonce));
-            self.sorted_groups.push_back(TransactionGroup {
-                key,
-                transactions,
-                removed_transaction_hashes: vec![],
-                removed_transaction_size: 0,
-            });
-            Some(self.sorted_groups.back_mut().expect("just pushed"))
-        } else {
-            while let Some(sorted_group) = self.sorted_groups.pop_front() {
-                if sorted_group.transactions.is_empty() {
-                    for hash in sorted_group.removed_transaction_hashes {
-                        self.pool.unique_transactions.remove(&hash);
-                    }
-                    // See the comment in `insert_transaction` where we increase the size for reasoning
-                    // why panicing here catches a logic error.
-                    self.pool.total_transaction_size = self
-                        .pool
-                        .total_transaction_size
-                        .checked_sub(sorted_group.removed_transaction_size)
-                        .expect("Total transaction size dropped below zero");
-
-                    self.pool
-                        .transaction_pool_count_metric
-                        .set(self.pool.unique_transactions.len() as i64);
-                    self.pool.transaction_pool_size_metric.set(self.pool.transaction_size() as i64);
-                } else {
-                    self.sorted_groups.push_back(sorted_group);
-                    return Some(self.sorted_groups.back_mut().expect("just pushed"));
-                }
+        let pool_priority = self.pool.transactions.keys().next().map(|key| key.priority);
+        while let Some((&priority, queue)) = self.sorted_groups.iter_mut().next_back() {
+            if pool_priority.map_or(false, |pool_priority| pool_priority >= priority) {
+                break;
+            }
+            let Some(sorted_group) = queue.pop_front() else {
+                self.sorted_groups.remove(&priority);
+                continue;
+            };
+            if sorted_group.transactions.is_empty() {
+                self.pool.release_pulled_transactions(&sorted_group);
+                self.pool
+                    .transaction_pool_count_metric
+                    .set(self.pool.unique_transactions.len() as i64);
+                self.pool.transaction_pool_size_metric.set(self.pool.transaction_size() as i64);
+            } else {
+                queue.push_back(sorted_group);
+                return self.sorted_groups.get_mut(&priority).and_then(|queue| queue.back_mut());
             }
-            None
         }
+
+        let first_key = *self.pool.transactions.keys().next()?;
+        // Continue after the last used key, unless it's the last group of its priority.
+        let key = self
+            .pool
+            .transactions
+            .range((Bound::Excluded(self.pool.last_used_key), Bound::Unbounded))
+            .next()
+            .map(|(k, _v)| *k)
+            .filter(|k| k.priority == first_key.priority)
+            .unwrap_or(first_key);
+        self.pool.last_used_key = key;
+        let mut transactions = self.pool.transactions.remove(&key).expect("just checked existence");
+        transactions.sort_by_key(|st| std::cmp::Reverse(st.transaction.nonce));
+        let signer_id = transactions[0].transaction.signer_id.clone();
+        let queue = self.sorted_groups.entry(key.priority).or_default();
+        queue.push_back(TransactionGroup {
+            key,
+            signer_id,
+            transactions,
+            removed_transaction_hashes: vec![],
+            removed_transaction_size: 0,
+        });
+        Some(queue.back_mut().expect("just pushed"))
     }
 }
 
@@ -271,18 +464,8 @@ impl<'a> PoolIterator for PoolIteratorWrapper<'a> {
 /// removed from the pool's unique_transactions.
 impl<'a> Drop for PoolIteratorWrapper<'a> {
     fn drop(&mut self) {
-        for group in self.sorted_groups.drain(..) {
-            for hash in group.removed_transaction_hashes {
-                self.pool.unique_transactions.remove(&hash);
-            }
-            // See the comment in `insert_transaction` where we increase the size for reasoning
-            // why panicing here catches a logic error.
-            self.pool.total_transaction_size = self
-                .pool
-                .total_transaction_size
-                .checked_sub(group.removed_transaction_size)
-                .expect("Total transaction size dropped below zero");
-
+        for group in std::mem::take(&mut self.sorted_groups).into_values().flatten() {
+            self.pool.release_pulled_transactions(&group);
             if !group.transactions.is_empty() {
                 self.pool.transactions.insert(group.key, group.transactions);
             }
@@ -296,7 +479,7 @@ impl<'a> Drop for PoolIteratorWrapper<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::sync::Arc;
+    use crate::policy::{PrepaidGasPolicy, PrioritizedAccountsPolicy};
 
     use rand::seq::SliceRandom;
     use rand::thread_rng;
@@ -408,6 +591,21 @@ mod tests {
         assert_eq!(nonces, (1..=5).map(|a| vec![a, a + 20]).flatten().collect::<Vec<u64>>());
     }
 
+    /// Transactions pulled by the pool iterator are no longer listed by `transactions()`.
+    #[test]
+    fn test_transactions() {
+        let transactions = generate_transactions("alice.near", "alice.near", 1, 10);
+
+        let (nonces, pool) = process_txs_to_nonces(transactions.clone(), 4);
+        let mut remaining: Vec<_> = pool.transactions().cloned().collect();
+        remaining.sort_by_key(|tx| tx.transaction.nonce);
+        let expected: Vec<_> =
+            transactions.into_iter().filter(|tx| !nonces.contains(&tx.transaction.nonce)).collect();
+        assert_eq!(remaining.len(), pool.len());
+        assert_eq!(remaining, expected);
+        assert_eq!(pool.signer_count(), 1);
+    }
+
     /// Add transactions of nonce from 1..=3 and transactions with nonce 21..=31. Pull 10.
     /// Then try to get another 10.
     #[test]
@@ -594,4 +792,172 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_signer_quota() {
+        let transactions = generate_transactions("alice.near", "alice.near", 1, 10);
+        let mut pool = TransactionPool::new(TEST_SEED, None, "")
+            .with_signer_quota(SignerQuota { count_limit: Some(5), size_limit: None });
+        for (i, tx) in transactions.iter().cloned().enumerate() {
+            let expected = if i < 5 {
+                InsertTransactionResult::Success
+            } else {
+                InsertTransactionResult::SignerQuotaExceeded
+            };
+            assert_eq!(pool.insert_transaction(tx), expected);
+        }
+        // The quota is per signer account, not per access key.
+        let tx = generate_transactions("alice.near", "bob.near", 1, 1).pop().unwrap();
+        assert_eq!(pool.insert_transaction(tx), InsertTransactionResult::SignerQuotaExceeded);
+        let tx = generate_transactions("bob.near", "bob.near", 1, 1).pop().unwrap();
+        assert_eq!(pool.insert_transaction(tx), InsertTransactionResult::Success);
+
+        // Pulling transactions from the pool releases the quota.
+        assert_eq!(prepare_transactions(&mut pool, 2).len(), 2);
+        let tx = transactions[5].clone();
+        assert_eq!(pool.insert_transaction(tx), InsertTransactionResult::Success);
+
+        let size_limit = transactions[0].get_size() * 2;
+        let mut pool = TransactionPool::new(TEST_SEED, None, "")
+            .with_signer_quota(SignerQuota { count_limit: None, size_limit: Some(size_limit) });
+        let results: Vec<_> =
+            transactions.into_iter().take(3).map(|tx| pool.insert_transaction(tx)).collect();
+        assert_eq!(
+            results,
+            vec![
+                InsertTransactionResult::Success,
+                InsertTransactionResult::Success,
+                InsertTransactionResult::SignerQuotaExceeded
+            ]
+        );
+        assert_eq!(pool.len(), 2);
+    }
+
+    #[test]
+    fn test_eviction_of_oldest_group() {
+        let old_transactions = generate_transactions("alice.near", "alice.near", 1, 5);
+        let new_transactions = generate_transactions("bob.near", "bob.near", 1, 5);
+        let pool_size_limit = old_transactions.iter().map(|tx| tx.get_size()).sum::<u64>();
+        let mut pool = TransactionPool::new(TEST_SEED, Some(pool_size_limit), "");
+        for tx in old_transactions {
+            assert_eq!(pool.insert_transaction(tx), InsertTransactionResult::Success);
+        }
+        // The group of alice is evicted as a whole to make space for bob.
+        for tx in new_transactions.iter().cloned() {
+            assert_eq!(pool.insert_transaction(tx), InsertTransactionResult::Success);
+        }
+        assert_eq!(pool.len(), 5);
+        assert!(pool.transaction_size() <= pool_size_limit);
+        // A group never evicts itself.
+        let tx = generate_transactions("bob.near", "bob.near", 6, 6).pop().unwrap();
+        assert_eq!(pool.insert_transaction(tx), InsertTransactionResult::NoSpaceLeft);
+
+        let mut pool_txs = prepare_transactions(&mut pool, 10);
+        pool_txs.sort_by_key(|tx| tx.transaction.nonce);
+        assert_eq!(pool_txs, new_transactions);
+        assert_eq!(pool.len(), 0);
+        assert_eq!(pool.transaction_size(), 0);
+    }
+
+    fn prioritized_pool(total_transaction_size_limit: Option<u64>) -> TransactionPool {
+        let policy = PrioritizedAccountsPolicy::new([("relayer.near".parse().unwrap(), 1)]);
+        TransactionPool::new(TEST_SEED, total_transaction_size_limit, "")
+            .with_ordering_policy(Arc::new(policy))
+    }
+
+    #[test]
+    fn test_prioritized_ordering() {
+        let mut transactions = generate_transactions("alice.near", "alice.near", 1, 5);
+        transactions.extend(generate_transactions("relayer.near", "relayer.near", 11, 15));
+        transactions.extend(generate_transactions("relayer.near", "relayer_2.near", 21, 25));
+        transactions.extend(generate_transactions("bob.near", "bob.near", 31, 35));
+        transactions.shuffle(&mut thread_rng());
+        let mut pool = prioritized_pool(None);
+        for tx in transactions {
+            assert_eq!(pool.insert_transaction(tx), InsertTransactionResult::Success);
+        }
+
+        // Both keys of the relayer are served in a round robin before anyone else.
+        let mut nonces: Vec<u64> =
+            prepare_transactions(&mut pool, 12).iter().map(|tx| tx.transaction.nonce).collect();
+        sort_pairs(&mut nonces[..]);
+        assert_eq!(
+            nonces[..10],
+            (11..=15).map(|a| vec![a, a + 10]).flatten().collect::<Vec<u64>>()
+        );
+        sort_pairs(&mut nonces[10..]);
+        assert_eq!(nonces[10..], [1, 31]);
+        assert_eq!(pool.len(), 8);
+    }
+
+    #[test]
+    fn test_eviction_respects_priority() {
+        let relayer_transactions = generate_transactions("relayer.near", "relayer.near", 1, 5);
+        let pool_size_limit = relayer_transactions.iter().map(|tx| tx.get_size()).sum::<u64>();
+        let mut pool = prioritized_pool(Some(pool_size_limit));
+        for tx in relayer_transactions.iter().cloned() {
+            assert_eq!(pool.insert_transaction(tx), InsertTransactionResult::Success);
+        }
+        // Transactions of a lower priority can't evict the relayer.
+        let tx = generate_transactions("alice.near", "alice.near", 1, 1).pop().unwrap();
+        assert_eq!(pool.insert_transaction(tx), InsertTransactionResult::NoSpaceLeft);
+        assert_eq!(pool.len(), 5);
+
+        let mut pool = prioritized_pool(Some(pool_size_limit));
+        for tx in generate_transactions("alice.near", "alice.near", 1, 5) {
+            assert_eq!(pool.insert_transaction(tx), InsertTransactionResult::Success);
+        }
+        // But the relayer can evict them.
+        for tx in relayer_transactions.iter().cloned() {
+            assert_eq!(pool.insert_transaction(tx), InsertTransactionResult::Success);
+        }
+        let mut pool_txs = prepare_transactions(&mut pool, 10);
+        pool_txs.sort_by_key(|tx| tx.transaction.nonce);
+        assert_eq!(pool_txs, relayer_transactions);
+    }
+
+    fn function_call(signer_id: &str, nonce: u64, gas: u64) -> SignedTransaction {
+        let signer_id: AccountId = signer_id.parse().unwrap();
+        let signer = InMemorySigner::from_seed(signer_id.clone(), KeyType::ED25519, "seed");
+        SignedTransaction::call(
+            nonce,
+            signer_id,
+            "contract.near".parse().unwrap(),
+            &signer,
+            0,
+            "method".to_string(),
+            vec![],
+            gas,
+            CryptoHash::default(),
+        )
+    }
+
+    #[test]
+    fn test_prepaid_gas_ordering() {
+        let mut pool = TransactionPool::new(TEST_SEED, None, "")
+            .with_ordering_policy(Arc::new(PrepaidGasPolicy));
+        let transactions = vec![
+            function_call("alice.near", 1, 10_000_000_000_000),
+            function_call("bob.near", 1, 300_000_000_000_000),
+            generate_transactions("carol.near", "carol.near", 1, 1).pop().unwrap(),
+            // Joins the group of alice despite its higher prepaid gas.
+            function_call("alice.near", 2, 300_000_000_000_000),
+        ];
+        for tx in transactions {
+            assert_eq!(pool.insert_transaction(tx), InsertTransactionResult::Success);
+        }
+        let pulled: Vec<(String, u64)> = prepare_transactions(&mut pool, 4)
+            .iter()
+            .map(|tx| (tx.transaction.signer_id.to_string(), tx.transaction.nonce))
+            .collect();
+        assert_eq!(
+            pulled,
+            [
+                ("bob.near".to_string(), 1),
+                ("alice.near".to_string(), 1),
+                ("alice.near".to_string(), 2),
+                ("carol.near".to_string(), 1),
+            ]
+        );
+    }
 }
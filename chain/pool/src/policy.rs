@@ -0,0 +1,80 @@
+use near_primitives::action::Action;
+use near_primitives::transaction::SignedTransaction;
+use near_primitives::types::{AccountId, Gas};
+use std::collections::HashMap;
+
+/// Priority of a transaction group. Groups of a higher priority are pulled from the pool first
+/// and are evicted from it last.
+pub type Priority = u32;
+
+/// Decides the order in which transaction groups are pulled from the pool and evicted from it.
+///
+/// A group takes the priority of the transaction that created it, later transactions of the same
+/// (AccountId, PublicKey) join the group whatever their priority, so that they are still pulled
+/// in the order of their nonces. Groups of the same priority are visited in a random round-robin
+/// order.
+pub trait TransactionOrderingPolicy: Send + Sync {
+    fn priority(&self, transaction: &SignedTransaction) -> Priority;
+}
+
+/// All groups have the same priority, so every signer gets a fair share of the chunk space.
+#[derive(Clone, Debug, Default)]
+pub struct RoundRobinPolicy;
+
+impl TransactionOrderingPolicy for RoundRobinPolicy {
+    fn priority(&self, _transaction: &SignedTransaction) -> Priority {
+        0
+    }
+}
+
+/// Transactions of the configured signers are served before the transactions of everyone else,
+/// which have priority 0.
+#[derive(Clone, Debug, Default)]
+pub struct PrioritizedAccountsPolicy {
+    priorities: HashMap<AccountId, Priority>,
+}
+
+impl PrioritizedAccountsPolicy {
+    pub fn new(priorities: impl IntoIterator<Item = (AccountId, Priority)>) -> Self {
+        Self { priorities: priorities.into_iter().collect() }
+    }
+}
+
+impl TransactionOrderingPolicy for PrioritizedAccountsPolicy {
+    fn priority(&self, transaction: &SignedTransaction) -> Priority {
+        self.priorities.get(&transaction.transaction.signer_id).copied().unwrap_or_default()
+    }
+}
+
+/// Transactions are served by the gas they prepay per byte of their size, the highest first.
+///
+/// Transactions don't carry their own gas price or priority fee: all of them are charged the gas
+/// price of the block they are included in. The prepaid gas, bought upfront at that price, is
+/// what a transaction offers to pay, so transactions offering more per byte of chunk space go
+/// first. Transactions without function calls have priority 0.
+#[derive(Clone, Debug, Default)]
+pub struct PrepaidGasPolicy;
+
+/// Priorities of `PrepaidGasPolicy` are measured in Ggas per byte, which keeps them within
+/// `Priority` for any transaction allowed by the protocol.
+const GAS_PER_PRIORITY_UNIT: Gas = 1_000_000_000;
+
+fn total_prepaid_gas(actions: &[Action]) -> Gas {
+    actions
+        .iter()
+        .map(|action| match action {
+            Action::Delegate(signed_delegate_action) => {
+                total_prepaid_gas(&signed_delegate_action.delegate_action.get_actions())
+            }
+            action => action.get_prepaid_gas(),
+        })
+        .fold(0, Gas::saturating_add)
+}
+
+impl TransactionOrderingPolicy for PrepaidGasPolicy {
+    fn priority(&self, transaction: &SignedTransaction) -> Priority {
+        let prepaid_gas = total_prepaid_gas(&transaction.transaction.actions);
+        let gas_per_byte = prepaid_gas / transaction.get_size().max(1);
+        (gas_per_byte / GAS_PER_PRIORITY_UNIT).try_into().unwrap_or(Priority::MAX)
+    }
+}
@@ -1,22 +1,45 @@
+use crate::policy::Priority;
 use near_primitives::hash::CryptoHash;
 use near_primitives::transaction::SignedTransaction;
+use near_primitives::types::AccountId;
+use std::cmp::Ordering;
 
 /// Trait acts like an iterator. It iterates over transactions groups by returning mutable
 /// references to them. Each transaction group implements a draining iterator to pull transactions.
-/// The order of the transaction groups is round robin scheduling.
+/// Groups of the same priority are visited using round robin scheduling, and groups of a lower
+/// priority only once all groups of a higher priority ran out of transactions.
 /// When this iterator is dropped the remaining transactions are returned back to the pool.
 pub trait PoolIterator {
     fn next(&mut self) -> Option<&mut TransactionGroup>;
 }
 
-/// A hash of (an AccountId, a PublicKey and a seed).
-/// Used to randomize the order of the keys.
-pub(crate) type PoolKey = CryptoHash;
+/// Identifies a group of transactions with the same (AccountId, PublicKey).
+/// Keys are ordered by the priority of the group, the highest first, and then by a hash of
+/// (the AccountId, the PublicKey and a seed) to randomize the order of groups of the same priority.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct PoolKey {
+    pub(crate) priority: Priority,
+    pub(crate) hash: CryptoHash,
+}
+
+impl Ord for PoolKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.priority.cmp(&self.priority).then_with(|| self.hash.cmp(&other.hash))
+    }
+}
+
+impl PartialOrd for PoolKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
 
 /// Represents a group of transactions with the same key.
 pub struct TransactionGroup {
     /// The key of the group.
     pub(crate) key: PoolKey,
+    /// The signer of all transactions in the group.
+    pub(crate) signer_id: AccountId,
     /// Ordered transactions by nonce in non-increasing order (e.g. 3, 2, 2).
     pub(crate) transactions: Vec<SignedTransaction>,
     /// Hashes of the transactions that were pulled from the group using `.next()`.
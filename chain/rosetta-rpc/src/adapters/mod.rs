@@ -483,6 +483,52 @@ impl From<NearActions> for Vec<crate::models::Operation> {
 
                     operations.extend(delegated_operations);
                 } // TODO(#8469): Implement delegate action support, for now they are ignored.
+                near_primitives::transaction::Action::DeployContractWithMigration(action) => {
+                    // Represented as a deployment followed by a function call without a
+                    // deposit, so converting the operations back yields these two actions.
+                    let initiate_deploy_contract_operation_id =
+                        crate::models::OperationIdentifier::new(&operations);
+                    operations.push(
+                        validated_operations::InitiateDeployContractOperation {
+                            sender_account: sender_account_identifier.clone(),
+                        }
+                        .into_operation(initiate_deploy_contract_operation_id.clone()),
+                    );
+
+                    operations.push(
+                        validated_operations::DeployContractOperation {
+                            account: receiver_account_identifier.clone(),
+                            code: action.code,
+                        }
+                        .into_related_operation(
+                            crate::models::OperationIdentifier::new(&operations),
+                            vec![initiate_deploy_contract_operation_id],
+                        ),
+                    );
+
+                    let initiate_function_call_operation_id =
+                        crate::models::OperationIdentifier::new(&operations);
+                    operations.push(
+                        validated_operations::InitiateFunctionCallOperation {
+                            sender_account: sender_account_identifier.clone(),
+                        }
+                        .into_operation(initiate_function_call_operation_id.clone()),
+                    );
+
+                    operations.push(
+                        validated_operations::FunctionCallOperation {
+                            account: receiver_account_identifier.clone(),
+                            method_name: action.method_name,
+                            args: action.args,
+                            attached_gas: action.gas,
+                            attached_amount: 0,
+                        }
+                        .into_related_operation(
+                            crate::models::OperationIdentifier::new(&operations),
+                            vec![initiate_function_call_operation_id],
+                        ),
+                    );
+                }
             }
         }
         operations
@@ -6,6 +6,7 @@ use near_primitives::types::{
 };
 use near_primitives::version::Version;
 use std::cmp::{max, min};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
@@ -83,6 +84,10 @@ pub struct ExternalStorageConfig {
     /// to reduce the performance impact of state sync.
     #[serde(default = "default_num_concurrent_requests_during_catchup")]
     pub num_concurrent_requests_during_catchup: u32,
+    /// Location of a json file with credentials allowing read access to the
+    /// storage. Only used by `AzureBlob`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credentials_file: Option<PathBuf>,
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
@@ -99,6 +104,23 @@ pub enum ExternalStorageLocation {
     GCS {
         bucket: String,
     },
+    /// Read-only mirror of state parts served by any HTTP(S) file server.
+    /// Parts are expected under `base_url` with the same layout as in the
+    /// other locations, and directories need to have listing enabled.
+    Http {
+        base_url: String,
+        /// If enabled, every state part must be accompanied by a
+        /// `<part>.sha256` file in the `sha256sum` format, and downloaded
+        /// parts are verified against it.
+        #[serde(default)]
+        verify_checksums: bool,
+    },
+    /// Azure Blob Storage or any service implementing its REST API.
+    AzureBlob {
+        /// Account URL, e.g. `https://<account>.blob.core.windows.net`.
+        endpoint: String,
+        container: String,
+    },
 }
 
 /// Configures how to dump state to external storage.
@@ -261,6 +283,7 @@ pub fn default_state_sync() -> Option<StateSyncConfig> {
             num_concurrent_requests: DEFAULT_STATE_SYNC_NUM_CONCURRENT_REQUESTS_EXTERNAL,
             num_concurrent_requests_during_catchup:
                 DEFAULT_STATE_SYNC_NUM_CONCURRENT_REQUESTS_ON_CATCHUP_EXTERNAL,
+            credentials_file: None,
         }),
     })
 }
@@ -289,6 +312,20 @@ pub fn default_transaction_pool_size_limit() -> Option<u64> {
     Some(100_000_000) // 100 MB.
 }
 
+/// Order in which transactions are taken from the transaction pool when producing chunks.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub enum TransactionPoolOrdering {
+    /// All signers take turns in a random round robin order.
+    #[default]
+    RoundRobin,
+    /// Transactions of the listed signers are taken first, the highest priority first, and are
+    /// evicted last when the pool is full. All other signers have priority 0.
+    Prioritized { priorities: BTreeMap<AccountId, u32> },
+    /// Transactions prepaying more gas per byte of their size are taken first and are evicted
+    /// last when the pool is full.
+    PrepaidGas,
+}
+
 pub fn default_tx_routing_height_horizon() -> BlockHeightDelta {
     4
 }
@@ -301,6 +338,10 @@ pub fn default_produce_chunk_add_transactions_time_limit() -> Option<Duration> {
     Some(Duration::from_millis(200))
 }
 
+pub fn default_max_chunk_state_witness_size() -> u64 {
+    16 * 1024 * 1024 // 16 MiB.
+}
+
 /// ClientConfig where some fields can be updated at runtime.
 #[derive(Clone, serde::Serialize)]
 pub struct ClientConfig {
@@ -407,6 +448,14 @@ pub struct ClientConfig {
     /// Limit of the size of per-shard transaction pool measured in bytes. If not set, the size
     /// will be unbounded.
     pub transaction_pool_size_limit: Option<u64>,
+    /// Limit of the number of transactions of a single signer account in a per-shard
+    /// transaction pool. If not set, the number is unbounded.
+    pub transaction_pool_signer_count_limit: Option<u64>,
+    /// Limit of the size of transactions of a single signer account in a per-shard transaction
+    /// pool measured in bytes. If not set, the size is unbounded.
+    pub transaction_pool_signer_size_limit: Option<u64>,
+    /// Order in which transactions are taken from the transaction pool.
+    pub transaction_pool_ordering: TransactionPoolOrdering,
     // Allows more detailed logging, for example a list of orphaned blocks.
     pub enable_multiline_logging: bool,
     // Configuration for resharding.
@@ -419,6 +468,14 @@ pub struct ClientConfig {
     /// some limit is reached. This time limit ensures that adding transactions won't take
     /// longer than the specified duration, which helps to produce the chunk quickly.
     pub produce_chunk_add_transactions_time_limit: MutableConfigValue<Option<Duration>>,
+    /// Limit of the uncompressed size of the state witness of a produced chunk in bytes.
+    /// Transactions which would bring the witness over the limit are left in the pool.
+    pub max_chunk_state_witness_size: u64,
+    /// Save chunk state witnesses which fail validation to the database, so that they can be
+    /// replayed with `neard view-state replay-chunk-state-witness`.
+    pub save_invalid_witnesses: bool,
+    /// Save chunk state witnesses validated at the last few heights to the database.
+    pub save_latest_witnesses: bool,
 }
 
 impl ClientConfig {
@@ -492,6 +549,9 @@ impl ClientConfig {
             state_sync_enabled,
             state_sync: StateSyncConfig::default(),
             transaction_pool_size_limit: None,
+            transaction_pool_signer_count_limit: None,
+            transaction_pool_signer_size_limit: None,
+            transaction_pool_ordering: TransactionPoolOrdering::default(),
             enable_multiline_logging: false,
             state_split_config: MutableConfigValue::new(
                 StateSplitConfig::default(),
@@ -502,6 +562,9 @@ impl ClientConfig {
                 default_produce_chunk_add_transactions_time_limit(),
                 "produce_chunk_add_transactions_time_limit",
             ),
+            max_chunk_state_witness_size: default_max_chunk_state_witness_size(),
+            save_invalid_witnesses: false,
+            save_latest_witnesses: false,
         }
     }
 }
@@ -1,6 +1,8 @@
 use crate::{signature, vrf, PublicKey};
 use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::montgomery::MontgomeryPoint;
 use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
 use std::mem::transmute;
 
 pub fn is_valid_staking_key(public_key: &PublicKey) -> bool {
@@ -28,6 +30,25 @@ pub fn convert_secret_key(key: &signature::ED25519SecretKey) -> vrf::SecretKey {
     vrf::SecretKey::from_scalar(s)
 }
 
+/// Converts an ED25519 public key to the X25519 public key of the same key pair,
+/// so that it can be used for a Diffie-Hellman key exchange.
+pub fn convert_public_key_to_x25519(key: &signature::ED25519PublicKey) -> Option<MontgomeryPoint> {
+    let ep: EdwardsPoint = CompressedEdwardsY::from_slice(&key.0).ok()?.decompress()?;
+    Some(ep.to_montgomery())
+}
+
+/// Converts an ED25519 secret key to the X25519 secret scalar of the same key pair.
+/// `x25519_public_key(convert_secret_key_to_x25519(sk)) == convert_public_key_to_x25519(pk)`.
+pub fn convert_secret_key_to_x25519(key: &signature::ED25519SecretKey) -> Scalar {
+    let b = <&[u8; 32]>::try_from(&key.0[..32]).unwrap();
+    ed25519_dalek::hazmat::ExpandedSecretKey::from(b).scalar
+}
+
+/// Computes the X25519 public key of the given secret scalar.
+pub fn x25519_public_key(secret: &Scalar) -> MontgomeryPoint {
+    EdwardsPoint::mul_base(secret).to_montgomery()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -50,4 +71,23 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_x25519_conversion() {
+        for _ in 0..10 {
+            let kk = signature::SecretKey::from_random(signature::KeyType::ED25519);
+            let pk = match kk.public_key() {
+                signature::PublicKey::ED25519(k) => k,
+                _ => unreachable!(),
+            };
+            let sk = match kk {
+                signature::SecretKey::ED25519(k) => k,
+                _ => unreachable!(),
+            };
+            assert_eq!(
+                x25519_public_key(&convert_secret_key_to_x25519(&sk)),
+                convert_public_key_to_x25519(&pk).unwrap()
+            );
+        }
+    }
 }
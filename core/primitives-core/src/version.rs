@@ -130,6 +130,12 @@ pub enum ProtocolFeature {
     /// NEP: https://github.com/near/NEPs/pull/509
     ChunkValidation,
     EthImplicitAccounts,
+    /// Peers with encrypted transport enabled require a Noise handshake from
+    /// the peers at this version, instead of falling back to plaintext.
+    NoiseTransport,
+    /// Adds `DeployContractWithMigration` action which deploys a contract and
+    /// calls a migration method of the new code in the same receipt.
+    DeployContractWithMigration,
 }
 
 impl ProtocolFeature {
@@ -186,6 +192,8 @@ impl ProtocolFeature {
             ProtocolFeature::RejectBlocksWithOutdatedProtocolVersions => 132,
             ProtocolFeature::ChunkValidation => 137,
             ProtocolFeature::EthImplicitAccounts => 138,
+            ProtocolFeature::NoiseTransport => 140,
+            ProtocolFeature::DeployContractWithMigration => 141,
         }
     }
 }
@@ -198,7 +206,7 @@ const STABLE_PROTOCOL_VERSION: ProtocolVersion = 64;
 /// Largest protocol version supported by the current binary.
 pub const PROTOCOL_VERSION: ProtocolVersion = if cfg!(feature = "nightly_protocol") {
     // On nightly, pick big enough version to support all features.
-    139
+    141
 } else {
     // Enable all stable features.
     STABLE_PROTOCOL_VERSION
@@ -94,6 +94,48 @@ impl fmt::Debug for DeployContractAction {
     }
 }
 
+/// Deploy contract action which atomically migrates the contract state.
+///
+/// After the code is deployed, `method_name` of the new code is called in the
+/// same receipt. If the call fails, the whole receipt fails and the previous
+/// code is kept.
+#[serde_as]
+#[derive(
+    BorshSerialize, BorshDeserialize, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone,
+)]
+pub struct DeployContractWithMigrationAction {
+    /// WebAssembly binary
+    #[serde_as(as = "Base64")]
+    pub code: Vec<u8>,
+    pub method_name: String,
+    #[serde_as(as = "Base64")]
+    pub args: Vec<u8>,
+    pub gas: Gas,
+}
+
+impl DeployContractWithMigrationAction {
+    /// The call of the migration method, which is done without a deposit.
+    pub fn function_call(&self) -> FunctionCallAction {
+        FunctionCallAction {
+            method_name: self.method_name.clone(),
+            args: self.args.clone(),
+            gas: self.gas,
+            deposit: 0,
+        }
+    }
+}
+
+impl fmt::Debug for DeployContractWithMigrationAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeployContractWithMigrationAction")
+            .field("code", &format_args!("{}", base64(&self.code)))
+            .field("method_name", &format_args!("{}", &self.method_name))
+            .field("args", &format_args!("{}", base64(&self.args)))
+            .field("gas", &format_args!("{}", &self.gas))
+            .finish()
+    }
+}
+
 #[serde_as]
 #[derive(
     BorshSerialize, BorshDeserialize, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone,
@@ -177,6 +219,8 @@ pub enum Action {
     DeleteKey(Box<DeleteKeyAction>),
     DeleteAccount(DeleteAccountAction),
     Delegate(Box<delegate::SignedDelegateAction>),
+    /// Sets a Wasm code to a receiver_id and calls a migration method of it
+    DeployContractWithMigration(Box<DeployContractWithMigrationAction>),
 }
 const _: () = assert!(
     cfg!(not(target_pointer_width = "64")) || std::mem::size_of::<Action>() == 32,
@@ -187,6 +231,7 @@ impl Action {
     pub fn get_prepaid_gas(&self) -> Gas {
         match self {
             Action::FunctionCall(a) => a.gas,
+            Action::DeployContractWithMigration(a) => a.gas,
             _ => 0,
         }
     }
@@ -211,6 +256,12 @@ impl From<DeployContractAction> for Action {
     }
 }
 
+impl From<DeployContractWithMigrationAction> for Action {
+    fn from(deploy_contract_with_migration_action: DeployContractWithMigrationAction) -> Self {
+        Self::DeployContractWithMigration(Box::new(deploy_contract_with_migration_action))
+    }
+}
+
 impl From<FunctionCallAction> for Action {
     fn from(function_call_action: FunctionCallAction) -> Self {
         Self::FunctionCall(Box::new(function_call_action))
@@ -1,9 +1,22 @@
-use crate::sharding::{ChunkHash, ShardChunkHeader};
+use std::collections::HashMap;
+use std::io::{self, Read};
+
+use crate::challenge::PartialState;
+use crate::sharding::{ChunkHash, ReceiptProof, ShardChunkHeader};
+use crate::transaction::SignedTransaction;
 use crate::types::StateRoot;
 use borsh::{BorshDeserialize, BorshSerialize};
 use near_crypto::Signature;
+use near_primitives_core::hash::CryptoHash;
 use near_primitives_core::types::AccountId;
 
+/// Upper bound of the size of a serialized `ChunkStateWitness`. Witnesses
+/// which decompress to more than this are rejected without being parsed.
+pub const MAX_CHUNK_STATE_WITNESS_SIZE: usize = 64 * 1024 * 1024;
+
+/// Compression level used for state witnesses sent over the network.
+const WITNESS_COMPRESSION_LEVEL: i32 = 3;
+
 /// The state witness for a chunk; proves the state transition that the
 /// chunk attests to.
 #[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
@@ -12,6 +25,90 @@ pub struct ChunkStateWitness {
     pub chunk_header: ShardChunkHeader,
     // TODO(#10265): Replace this with fields for the actual witness.
     pub state_root: StateRoot,
+    /// Transition of the shard state by the last chunk of the shard.
+    // TODO(#10265): Record the state accessed when applying the chunk.
+    pub main_state_transition: ChunkStateTransition,
+    /// Transitions of the shard state in the blocks after the last chunk
+    /// which are missing a chunk for the shard.
+    pub implicit_transitions: Vec<ChunkStateTransition>,
+    /// Receipts applied with the last chunk, by the chunk which sent them.
+    pub source_receipt_proofs: HashMap<ChunkHash, ReceiptProof>,
+    /// Transactions included in the chunk.
+    pub new_transactions: Vec<SignedTransaction>,
+}
+
+/// Proof of a state transition of a shard in a single block.
+#[derive(Debug, Clone, PartialEq, Eq, Default, BorshSerialize, BorshDeserialize)]
+pub struct ChunkStateTransition {
+    /// Block in which the transition happened.
+    pub block_hash: CryptoHash,
+    /// Part of the state before the transition which is accessed by it.
+    pub base_state: PartialState,
+    /// State root after the transition.
+    pub post_state_root: StateRoot,
+}
+
+/// Sizes of the components of a `ChunkStateWitness` once serialized, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ChunkStateWitnessSize {
+    pub main_state_transition: usize,
+    pub implicit_transitions: usize,
+    pub source_receipt_proofs: usize,
+    pub new_transactions: usize,
+    /// Size of the whole witness, including the chunk header.
+    pub total: usize,
+}
+
+impl ChunkStateWitness {
+    pub fn size(&self) -> ChunkStateWitnessSize {
+        ChunkStateWitnessSize {
+            main_state_transition: borsh_size(&self.main_state_transition),
+            implicit_transitions: borsh_size(&self.implicit_transitions),
+            source_receipt_proofs: borsh_size(&self.source_receipt_proofs),
+            new_transactions: borsh_size(&self.new_transactions),
+            total: borsh_size(self),
+        }
+    }
+}
+
+fn borsh_size(value: &impl BorshSerialize) -> usize {
+    borsh::object_length(value).expect("serializing to a counter never fails")
+}
+
+/// A `ChunkStateWitness` serialized with borsh and compressed with zstd, as
+/// sent to chunk validators.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct EncodedChunkStateWitness(Box<[u8]>);
+
+impl EncodedChunkStateWitness {
+    /// Returns the encoded witness together with its uncompressed size.
+    pub fn encode(witness: &ChunkStateWitness) -> io::Result<(Self, usize)> {
+        let raw = borsh::to_vec(witness)?;
+        let compressed = zstd::stream::encode_all(raw.as_slice(), WITNESS_COMPRESSION_LEVEL)?;
+        Ok((Self(compressed.into_boxed_slice()), raw.len()))
+    }
+
+    /// Decompresses and parses the witness, together with its uncompressed
+    /// size. Fails without decompressing further once the uncompressed data
+    /// exceeds `max_size` bytes.
+    pub fn decode(&self, max_size: usize) -> io::Result<(ChunkStateWitness, usize)> {
+        let decoder = zstd::stream::read::Decoder::new(&self.0[..])?;
+        let mut raw = Vec::new();
+        decoder.take(max_size as u64 + 1).read_to_end(&mut raw)?;
+        if raw.len() > max_size {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("chunk state witness exceeds {max_size} bytes when decompressed"),
+            ));
+        }
+        let witness = ChunkStateWitness::try_from_slice(&raw)?;
+        Ok((witness, raw.len()))
+    }
+
+    /// Size of the compressed witness in bytes.
+    pub fn size_bytes(&self) -> usize {
+        self.0.len()
+    }
 }
 
 /// The endorsement of a chunk by a chunk validator. By providing this, a
@@ -49,3 +146,72 @@ pub struct ChunkEndorsementMessage {
     pub endorsement: ChunkEndorsement,
     pub target: AccountId,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ChunkStateTransition, ChunkStateWitness, EncodedChunkStateWitness};
+    use crate::hash::CryptoHash;
+    use crate::sharding::{ShardChunkHeader, ShardChunkHeaderV3};
+    use crate::transaction::SignedTransaction;
+    use crate::validator_signer::EmptyValidatorSigner;
+    use near_crypto::{InMemorySigner, KeyType};
+
+    fn witness(num_transactions: u64) -> ChunkStateWitness {
+        let header = ShardChunkHeaderV3::new(
+            CryptoHash::default(),
+            CryptoHash::default(),
+            CryptoHash::default(),
+            CryptoHash::default(),
+            0,
+            1,
+            0,
+            0,
+            0,
+            0,
+            CryptoHash::default(),
+            CryptoHash::default(),
+            vec![],
+            &EmptyValidatorSigner::default(),
+        );
+        let signer = InMemorySigner::from_seed("test".parse().unwrap(), KeyType::ED25519, "test");
+        let new_transactions = (0..num_transactions)
+            .map(|nonce| {
+                SignedTransaction::send_money(
+                    nonce,
+                    "test".parse().unwrap(),
+                    "other".parse().unwrap(),
+                    &signer,
+                    1,
+                    CryptoHash::default(),
+                )
+            })
+            .collect();
+        ChunkStateWitness {
+            chunk_header: ShardChunkHeader::V3(header),
+            state_root: CryptoHash::default(),
+            main_state_transition: ChunkStateTransition::default(),
+            implicit_transitions: vec![],
+            source_receipt_proofs: Default::default(),
+            new_transactions,
+        }
+    }
+
+    #[test]
+    fn test_encode_decode_witness() {
+        let witness = witness(100);
+        let size = witness.size();
+        assert_eq!(size.new_transactions, borsh::object_length(&witness.new_transactions).unwrap());
+        assert!(size.total > size.new_transactions + size.main_state_transition);
+
+        let (encoded, raw_size) = EncodedChunkStateWitness::encode(&witness).unwrap();
+        assert_eq!(raw_size, size.total);
+        // Transactions of a single signer compress well.
+        assert!(encoded.size_bytes() < raw_size);
+
+        let (decoded, decoded_size) = encoded.decode(raw_size).unwrap();
+        assert_eq!(decoded, witness);
+        assert_eq!(decoded_size, raw_size);
+        // Decompression stops at the limit.
+        assert!(encoded.decode(raw_size - 1).is_err());
+    }
+}
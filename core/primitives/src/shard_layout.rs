@@ -216,6 +216,14 @@ impl ShardLayout {
         }
     }
 
+    /// Returns the boundary accounts of a V1 shard layout, or `None` for a V0 one.
+    pub fn boundary_accounts(&self) -> Option<&[AccountId]> {
+        match self {
+            Self::V0(_) => None,
+            Self::V1(v1) => Some(&v1.boundary_accounts),
+        }
+    }
+
     fn num_shards(&self) -> NumShards {
         match self {
             Self::V0(v0) => v0.num_shards,
@@ -16,7 +16,8 @@ use std::hash::{Hash, Hasher};
 
 pub use crate::action::{
     Action, AddKeyAction, CreateAccountAction, DeleteAccountAction, DeleteKeyAction,
-    DeployContractAction, FunctionCallAction, StakeAction, TransferAction,
+    DeployContractAction, DeployContractWithMigrationAction, FunctionCallAction, StakeAction,
+    TransferAction,
 };
 
 pub type LogEntry = String;
@@ -240,6 +241,52 @@ pub enum ExecutionMetadata {
     V2(ProfileDataV2),
     /// V3: With ProfileData by gas parameters
     V3(ProfileDataV3),
+    /// V4: With ProfileData by gas parameters, gas usage per action and refunds
+    V4(ExecutionMetadataV4),
+}
+
+impl ExecutionMetadata {
+    /// Gas profile by gas parameters, if recorded.
+    pub fn profile(&self) -> Option<&ProfileDataV3> {
+        match self {
+            ExecutionMetadata::V1 | ExecutionMetadata::V2(_) => None,
+            ExecutionMetadata::V3(profile) => Some(profile),
+            ExecutionMetadata::V4(metadata) => Some(&metadata.profile),
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Clone, Eq, Debug, Default)]
+pub struct ExecutionMetadataV4 {
+    pub profile: ProfileDataV3,
+    /// Usage of each executed action of the receipt, in order.
+    /// Actions following a failed action are not executed and have no entry.
+    pub actions: Vec<ActionUsage>,
+    pub refund: RefundInfo,
+}
+
+/// Gas and compute used by a single action.
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Clone, Copy, Eq, Debug, Default)]
+pub struct ActionUsage {
+    pub gas_burnt: Gas,
+    pub gas_used: Gas,
+    pub compute_usage: Compute,
+}
+
+/// Refunds related to the execution of a receipt.
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Clone, Eq, Debug, Default)]
+pub struct RefundInfo {
+    /// Whether the executed receipt is itself a refund receipt.
+    pub is_refund: bool,
+    /// Prepaid gas which hasn't been used and is refunded to the signer.
+    pub gas_refund: Gas,
+    /// Tokens refunded to the signer for the unused gas, adjusted by the difference between
+    /// the purchase and the current gas price.
+    pub gas_balance_refund: Balance,
+    /// Deposit refunded to the predecessor because the receipt has failed.
+    pub deposit_refund: Balance,
+    /// Ids of the refund receipts produced by the execution.
+    pub refund_receipt_ids: Vec<CryptoHash>,
 }
 
 impl fmt::Debug for ExecutionOutcome {
@@ -10,8 +10,9 @@ use serde;
 
 use crate::hash::{hash, CryptoHash};
 use crate::receipt::Receipt;
+use crate::sharding::ChunkHash;
 use crate::transaction::SignedTransaction;
-use crate::types::{NumSeats, NumShards, ShardId};
+use crate::types::{BlockHeight, NumSeats, NumShards, ShardId};
 use crate::version::{
     ProtocolVersion, CORRECT_RANDOM_VALUE_PROTOCOL_VERSION, CREATE_HASH_PROTOCOL_VERSION,
     CREATE_RECEIPT_ID_SWITCH_TO_CURRENT_BLOCK_VERSION,
@@ -222,6 +223,21 @@ pub fn get_outcome_id_block_hash_rev(key: &[u8]) -> std::io::Result<(CryptoHash,
     Ok((outcome_id, block_hash))
 }
 
+/// Key of a chunk state witness in `DBCol::InvalidChunkStateWitnesses` and
+/// `DBCol::LatestChunkStateWitnesses`. The height is big-endian so that the
+/// witnesses are ordered by height.
+pub fn get_chunk_state_witness_key(
+    height_created: BlockHeight,
+    shard_id: ShardId,
+    chunk_hash: &ChunkHash,
+) -> Vec<u8> {
+    let mut res = Vec::with_capacity(48);
+    res.extend_from_slice(&height_created.to_be_bytes());
+    res.extend_from_slice(&shard_id.to_be_bytes());
+    res.extend_from_slice(chunk_hash.as_ref());
+    res
+}
+
 /// Creates a new Receipt ID from a given signed transaction and a block hash.
 /// This method is backward compatible, so it takes the current protocol version.
 pub fn create_receipt_id_from_transaction(
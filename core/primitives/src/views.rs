@@ -25,16 +25,17 @@ use crate::sharding::{
     ShardChunkHeaderV3,
 };
 use crate::transaction::{
-    Action, AddKeyAction, CreateAccountAction, DeleteAccountAction, DeleteKeyAction,
-    DeployContractAction, ExecutionMetadata, ExecutionOutcome, ExecutionOutcomeWithIdAndProof,
-    ExecutionStatus, FunctionCallAction, PartialExecutionOutcome, PartialExecutionStatus,
-    SignedTransaction, StakeAction, TransferAction,
+    Action, ActionUsage, AddKeyAction, CreateAccountAction, DeleteAccountAction, DeleteKeyAction,
+    DeployContractAction, DeployContractWithMigrationAction, ExecutionMetadata, ExecutionOutcome,
+    ExecutionOutcomeWithId, ExecutionOutcomeWithIdAndProof, ExecutionStatus, FunctionCallAction,
+    PartialExecutionOutcome, PartialExecutionStatus, SignedTransaction, StakeAction,
+    TransferAction,
 };
 use crate::types::{
-    AccountId, AccountWithPublicKey, Balance, BlockHeight, EpochHeight, EpochId, FunctionArgs, Gas,
-    Nonce, NumBlocks, ShardId, StateChangeCause, StateChangeKind, StateChangeValue,
-    StateChangeWithCause, StateChangesRequest, StateRoot, StorageUsage, StoreKey, StoreValue,
-    ValidatorKickoutReason,
+    AccountId, AccountWithPublicKey, Balance, BlockHeight, Compute, EpochHeight, EpochId,
+    FunctionArgs, Gas, Nonce, NumBlocks, ShardId, StateChangeCause, StateChangeKind,
+    StateChangeValue, StateChangeWithCause, StateChangesRequest, StateRoot, StorageUsage, StoreKey,
+    StoreValue, ValidatorKickoutReason,
 };
 use crate::version::{ProtocolVersion, Version};
 use borsh::{BorshDeserialize, BorshSerialize};
@@ -45,6 +46,7 @@ use near_primitives_core::config::{ActionCosts, ExtCosts, ParameterCost};
 use near_primitives_core::runtime::fees::Fee;
 use near_vm_runner::logic::CompiledContractCache;
 use near_vm_runner::ContractCode;
+use near_vm_runner::ProfileDataV3;
 use num_rational::Rational32;
 use serde_with::base64::Base64;
 use serde_with::serde_as;
@@ -235,6 +237,43 @@ pub struct ViewStateResult {
     pub proof: Vec<Arc<[u8]>>,
 }
 
+/// Breakdown of the storage used by contract data of a single account.
+///
+/// Byte counts are computed the same way as `storage_usage` of an account:
+/// each record is charged for its key, its value and a fixed per-record
+/// overhead.
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct ContractStorageReportView {
+    pub account_id: AccountId,
+    pub block_hash: CryptoHash,
+    pub block_height: BlockHeight,
+    pub total_keys: u64,
+    pub total_bytes: u64,
+    /// Usage grouped by key prefix, sorted by bytes in descending order.
+    pub prefixes: Vec<StoragePrefixUsageView>,
+    /// Usage of keys which don't match any of the requested prefixes.
+    pub other_keys: u64,
+    pub other_bytes: u64,
+    /// Largest records, sorted by bytes in descending order.
+    pub largest_keys: Vec<StorageKeyUsageView>,
+    /// Set if the scan stopped at the limit of keys, in which case the report
+    /// covers only the keys before it. Pass it as `start_key` to continue.
+    pub next_key: Option<StoreKey>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct StoragePrefixUsageView {
+    pub prefix: StoreKey,
+    pub keys: u64,
+    pub bytes: u64,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct StorageKeyUsageView {
+    pub key: StoreKey,
+    pub bytes: u64,
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq, Clone, Default)]
 pub struct CallResult {
     pub result: Vec<u8>,
@@ -273,6 +312,7 @@ pub struct KnownPeerStateView {
     pub first_seen: i64,
     pub last_seen: i64,
     pub last_attempt: Option<(i64, String)>,
+    pub reputation: i32,
 }
 
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
@@ -385,6 +425,8 @@ pub struct PeerInfoView {
     pub is_outbound_peer: bool,
     /// Connection nonce.
     pub nonce: u64,
+    /// Reputation score of the peer.
+    pub reputation: i32,
 }
 
 /// Information about a Producer: its account name, peer_id and a list of connected peers that
@@ -1207,6 +1249,13 @@ pub enum ActionView {
         delegate_action: DelegateAction,
         signature: Signature,
     },
+    DeployContractWithMigration {
+        #[serde_as(as = "Base64")]
+        code: Vec<u8>,
+        method_name: String,
+        args: FunctionArgs,
+        gas: Gas,
+    },
 }
 
 impl From<Action> for ActionView {
@@ -1239,6 +1288,14 @@ impl From<Action> for ActionView {
                 delegate_action: action.delegate_action,
                 signature: action.signature,
             },
+            Action::DeployContractWithMigration(action) => {
+                ActionView::DeployContractWithMigration {
+                    code: hash(&action.code).as_ref().to_vec(),
+                    method_name: action.method_name,
+                    args: action.args.into(),
+                    gas: action.gas,
+                }
+            }
         }
     }
 }
@@ -1276,6 +1333,14 @@ impl TryFrom<ActionView> for Action {
             ActionView::Delegate { delegate_action, signature } => {
                 Action::Delegate(Box::new(SignedDelegateAction { delegate_action, signature }))
             }
+            ActionView::DeployContractWithMigration { code, method_name, args, gas } => {
+                Action::DeployContractWithMigration(Box::new(DeployContractWithMigrationAction {
+                    code,
+                    method_name,
+                    args: args.into(),
+                    gas,
+                }))
+            }
         })
     }
 }
@@ -1446,6 +1511,50 @@ pub struct CostGasUsed {
 pub struct ExecutionMetadataView {
     pub version: u32,
     pub gas_profile: Option<Vec<CostGasUsed>>,
+    /// Gas and compute used by each executed action of the receipt, in order.
+    /// Present since version 4.
+    /// Not included in Borsh, which is used on the wire (e.g. `TxStatusResponse`),
+    /// to keep the layout compatible with older nodes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[borsh(skip)]
+    pub action_gas_usage: Option<Vec<ActionGasUsageView>>,
+    /// Present since version 4. Not included in Borsh, like `action_gas_usage`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[borsh(skip)]
+    pub refund: Option<RefundView>,
+}
+
+#[derive(PartialEq, Clone, Eq, Debug, serde::Serialize, serde::Deserialize)]
+pub struct ActionGasUsageView {
+    pub gas_burnt: Gas,
+    pub gas_used: Gas,
+    pub compute_usage: Compute,
+}
+
+impl From<ActionUsage> for ActionGasUsageView {
+    fn from(usage: ActionUsage) -> Self {
+        Self {
+            gas_burnt: usage.gas_burnt,
+            gas_used: usage.gas_used,
+            compute_usage: usage.compute_usage,
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Eq, Debug, serde::Serialize, serde::Deserialize)]
+pub struct RefundView {
+    /// Whether the receipt is a refund receipt.
+    pub is_refund: bool,
+    /// Unused prepaid gas refunded to the signer.
+    pub gas_refund: Gas,
+    /// Tokens refunded to the signer for the unused gas.
+    #[serde(with = "dec_format")]
+    pub gas_balance_refund: Balance,
+    /// Deposit refunded to the predecessor after a failure.
+    #[serde(with = "dec_format")]
+    pub deposit_refund: Balance,
+    /// Refund receipts among the `receipt_ids` of the outcome.
+    pub refund_receipt_ids: Vec<CryptoHash>,
 }
 
 impl Default for ExecutionMetadataView {
@@ -1460,8 +1569,9 @@ impl From<ExecutionMetadata> for ExecutionMetadataView {
             ExecutionMetadata::V1 => 1,
             ExecutionMetadata::V2(_) => 2,
             ExecutionMetadata::V3(_) => 3,
+            ExecutionMetadata::V4(_) => 4,
         };
-        let mut gas_profile = match metadata {
+        let mut gas_profile = match &metadata {
             ExecutionMetadata::V1 => None,
             ExecutionMetadata::V2(profile_data) => {
                 // Add actions, wasm op, and ext costs in groups.
@@ -1492,43 +1602,8 @@ impl From<ExecutionMetadata> for ExecutionMetadataView {
 
                 Some(costs)
             }
-            ExecutionMetadata::V3(profile) => {
-                // Add actions, wasm op, and ext costs in groups.
-                // actions costs are 1-to-1
-                let mut costs: Vec<CostGasUsed> = ActionCosts::iter()
-                    .flat_map(|cost| {
-                        let gas_used = profile.get_action_cost(cost);
-                        (gas_used > 0).then(|| {
-                            CostGasUsed::action(
-                                format!("{:?}", cost).to_ascii_uppercase(),
-                                gas_used,
-                            )
-                        })
-                    })
-                    .collect();
-
-                // wasm op is a single cost, for historical reasons it is inaccurately displayed as "wasm host"
-                let wasm_gas_used = profile.get_wasm_cost();
-                if wasm_gas_used > 0 {
-                    costs.push(CostGasUsed::wasm_host(
-                        "WASM_INSTRUCTION".to_string(),
-                        wasm_gas_used,
-                    ));
-                }
-
-                // ext costs are 1-to-1
-                for ext_cost in ExtCosts::iter() {
-                    let gas_used = profile.get_ext_cost(ext_cost);
-                    if gas_used > 0 {
-                        costs.push(CostGasUsed::wasm_host(
-                            format!("{:?}", ext_cost).to_ascii_uppercase(),
-                            gas_used,
-                        ));
-                    }
-                }
-
-                Some(costs)
-            }
+            ExecutionMetadata::V3(profile) => Some(CostGasUsed::from_profile(profile)),
+            ExecutionMetadata::V4(v4) => Some(CostGasUsed::from_profile(&v4.profile)),
         };
         if let Some(ref mut costs) = gas_profile {
             // The order doesn't really matter, but the default one is just
@@ -1541,7 +1616,23 @@ impl From<ExecutionMetadata> for ExecutionMetadataView {
                 lhs.cost_category.cmp(&rhs.cost_category).then_with(|| lhs.cost.cmp(&rhs.cost))
             });
         }
-        ExecutionMetadataView { version, gas_profile }
+        let (action_gas_usage, refund) = match metadata {
+            ExecutionMetadata::V4(metadata) => {
+                let refund = metadata.refund;
+                (
+                    Some(metadata.actions.into_iter().map(Into::into).collect()),
+                    Some(RefundView {
+                        is_refund: refund.is_refund,
+                        gas_refund: refund.gas_refund,
+                        gas_balance_refund: refund.gas_balance_refund,
+                        deposit_refund: refund.deposit_refund,
+                        refund_receipt_ids: refund.refund_receipt_ids,
+                    }),
+                )
+            }
+            _ => (None, None),
+        };
+        ExecutionMetadataView { version, gas_profile, action_gas_usage, refund }
     }
 }
 
@@ -1553,6 +1644,38 @@ impl CostGasUsed {
     pub fn wasm_host(cost: String, gas_used: Gas) -> Self {
         Self { cost_category: "WASM_HOST_COST".to_string(), cost, gas_used }
     }
+
+    /// Non-zero costs of a profile by gas parameters.
+    fn from_profile(profile: &ProfileDataV3) -> Vec<Self> {
+        // Add actions, wasm op, and ext costs in groups.
+        // actions costs are 1-to-1
+        let mut costs: Vec<CostGasUsed> = ActionCosts::iter()
+            .flat_map(|cost| {
+                let gas_used = profile.get_action_cost(cost);
+                (gas_used > 0).then(|| {
+                    CostGasUsed::action(format!("{:?}", cost).to_ascii_uppercase(), gas_used)
+                })
+            })
+            .collect();
+
+        // wasm op is a single cost, for historical reasons it is inaccurately displayed as "wasm host"
+        let wasm_gas_used = profile.get_wasm_cost();
+        if wasm_gas_used > 0 {
+            costs.push(CostGasUsed::wasm_host("WASM_INSTRUCTION".to_string(), wasm_gas_used));
+        }
+
+        // ext costs are 1-to-1
+        for ext_cost in ExtCosts::iter() {
+            let gas_used = profile.get_ext_cost(ext_cost);
+            if gas_used > 0 {
+                costs.push(CostGasUsed::wasm_host(
+                    format!("{:?}", ext_cost).to_ascii_uppercase(),
+                    gas_used,
+                ));
+            }
+        }
+        costs
+    }
 }
 
 #[derive(
@@ -1785,6 +1908,103 @@ pub struct FinalExecutionOutcomeWithReceiptView {
     pub receipts: Vec<ReceiptView>,
 }
 
+/// Result of executing a transaction and all receipts it produced on top of
+/// the state of some block, without committing anything.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct TransactionSimulationView {
+    /// Block on top of which the transaction was executed.
+    pub block_hash: CryptoHash,
+    pub block_height: BlockHeight,
+    /// Computed the same way as in `FinalExecutionOutcomeView`.
+    pub status: FinalExecutionStatus,
+    pub transaction_outcome: ExecutionOutcomeWithIdView,
+    /// Outcomes of the receipts in the order they were executed.
+    pub receipts_outcome: Vec<ExecutionOutcomeWithIdView>,
+    /// All receipts produced during the execution, including refunds.
+    pub receipts: Vec<ReceiptView>,
+    /// Gas used by all the receipts combined, per cost.
+    pub gas_profile: Vec<CostGasUsed>,
+    /// Gas burnt by the transaction and all of its receipts.
+    pub gas_burnt: Gas,
+    #[serde(with = "dec_format")]
+    pub tokens_burnt: Balance,
+    /// Changes the execution would make to the state.
+    pub state_changes: StateChangesView,
+}
+
+impl TransactionSimulationView {
+    pub fn new(
+        block_hash: CryptoHash,
+        block_height: BlockHeight,
+        transaction_outcome: ExecutionOutcomeWithId,
+        receipt_outcomes: Vec<ExecutionOutcomeWithId>,
+        receipts: Vec<Receipt>,
+        state_changes: Vec<StateChangeWithCause>,
+    ) -> Self {
+        let mut gas_burnt = transaction_outcome.outcome.gas_burnt;
+        let mut tokens_burnt = transaction_outcome.outcome.tokens_burnt;
+        let mut profile = ProfileDataV3::new();
+        for outcome_with_id in &receipt_outcomes {
+            let outcome = &outcome_with_id.outcome;
+            gas_burnt = gas_burnt.saturating_add(outcome.gas_burnt);
+            tokens_burnt = tokens_burnt.saturating_add(outcome.tokens_burnt);
+            if let Some(receipt_profile) = outcome.metadata.profile() {
+                profile.merge(receipt_profile);
+            }
+        }
+        let gas_profile = ExecutionMetadataView::from(ExecutionMetadata::V3(profile))
+            .gas_profile
+            .unwrap_or_default();
+
+        let into_view = |outcome_with_id| {
+            ExecutionOutcomeWithIdView::from(ExecutionOutcomeWithIdAndProof {
+                proof: vec![],
+                block_hash,
+                outcome_with_id,
+            })
+        };
+        let transaction_outcome = into_view(transaction_outcome);
+        let receipts_outcome: Vec<_> = receipt_outcomes.into_iter().map(into_view).collect();
+        let status = Self::final_status(&transaction_outcome, &receipts_outcome);
+        Self {
+            block_hash,
+            block_height,
+            status,
+            transaction_outcome,
+            receipts_outcome,
+            receipts: receipts.into_iter().map(Into::into).collect(),
+            gas_profile,
+            gas_burnt,
+            tokens_burnt,
+            state_changes: state_changes.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Follows the chain of `SuccessReceiptId` statuses from the transaction,
+    /// like `Chain::get_final_transaction_result` does.
+    fn final_status(
+        transaction_outcome: &ExecutionOutcomeWithIdView,
+        receipts_outcome: &[ExecutionOutcomeWithIdView],
+    ) -> FinalExecutionStatus {
+        let mut outcome = transaction_outcome;
+        loop {
+            match &outcome.outcome.status {
+                ExecutionStatusView::Unknown => return FinalExecutionStatus::Started,
+                ExecutionStatusView::Failure(e) => return FinalExecutionStatus::Failure(e.clone()),
+                ExecutionStatusView::SuccessValue(v) => {
+                    return FinalExecutionStatus::SuccessValue(v.clone())
+                }
+                ExecutionStatusView::SuccessReceiptId(id) => {
+                    match receipts_outcome.iter().find(|outcome| &outcome.id == id) {
+                        Some(next) => outcome = next,
+                        None => return FinalExecutionStatus::Started,
+                    }
+                }
+            }
+        }
+    }
+}
+
 pub mod validator_stake_view {
     pub use super::ValidatorStakeViewV1;
     use crate::types::validator_stake::ValidatorStake;
@@ -2069,6 +2289,112 @@ pub struct NextEpochValidatorInfo {
     pub shards: Vec<ShardId>,
 }
 
+/// Block and chunk production of a single validator over a range of heights
+/// of an epoch.
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct ValidatorPerformanceView {
+    pub account_id: AccountId,
+    pub epoch: ValidatorEpochPerformanceView,
+    /// Projection for the current epoch. `None` if the account doesn't
+    /// produce blocks or chunks in the current epoch.
+    pub kickout_risk: Option<KickoutRiskView>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct ValidatorEpochPerformanceView {
+    pub epoch_id: EpochId,
+    pub epoch_height: EpochHeight,
+    /// First height covered.
+    pub start_height: BlockHeight,
+    /// Last height covered. For the current epoch this is at most the head
+    /// height.
+    pub end_height: BlockHeight,
+    /// Height to continue from if the range was cut short before the end of
+    /// the epoch. The counts below only cover `start_height..=end_height`.
+    pub next_start_height: Option<BlockHeight>,
+    pub num_produced_blocks: NumBlocks,
+    pub num_expected_blocks: NumBlocks,
+    pub num_produced_chunks: NumBlocks,
+    pub num_expected_chunks: NumBlocks,
+    pub blocks: Vec<BlockSlotView>,
+    pub chunks: Vec<ChunkSlotView>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct BlockSlotView {
+    pub height: BlockHeight,
+    pub status: BlockSlotStatus,
+    /// Hash of the canonical block, or of one of the orphaned blocks.
+    pub block_hash: Option<CryptoHash>,
+    /// Block producers whose approvals are missing in the canonical block.
+    pub missing_approvals: Vec<AccountId>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BlockSlotStatus {
+    /// The block is on the canonical chain.
+    Produced,
+    /// The block was produced but the next block producer built on top of
+    /// a different block, so it didn't make it to the canonical chain.
+    Orphaned,
+    /// No block was seen at this height.
+    Missed,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct ChunkSlotView {
+    /// Height right after the previous block, which determines the chunk
+    /// producer of the slot.
+    pub height: BlockHeight,
+    /// Height of the block which was expected to include the chunk.
+    pub block_height: BlockHeight,
+    pub shard_id: ShardId,
+    pub status: ChunkSlotStatus,
+    pub chunk_hash: Option<ChunkHash>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ChunkSlotStatus {
+    /// The chunk was included in the block at its height.
+    Produced,
+    /// The chunk was received by this node but it wasn't included in the
+    /// block at its height, usually because it arrived after the block was
+    /// produced. Only detected for shards tracked by this node.
+    Late,
+    /// The chunk wasn't seen by this node.
+    Missed,
+}
+
+/// Whether a validator is on track to be kicked out at the end of the current
+/// epoch, based on the kickout thresholds of the epoch config. Exemptions
+/// limiting the total kicked out stake are not taken into account.
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct KickoutRiskView {
+    pub epoch_id: EpochId,
+    pub estimated_epoch_end_height: BlockHeight,
+    pub block_producer_kickout_threshold: u8,
+    pub chunk_producer_kickout_threshold: u8,
+    pub blocks: ProductionProjectionView,
+    pub chunks: ProductionProjectionView,
+    /// Production so far is below one of the thresholds.
+    pub below_threshold: bool,
+    /// The thresholds can't be reached anymore even if every remaining block
+    /// and chunk is produced.
+    pub kickout_unavoidable: bool,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct ProductionProjectionView {
+    pub produced: NumBlocks,
+    pub expected: NumBlocks,
+    /// Slots left until the estimated end of the epoch, assuming no skipped
+    /// heights.
+    pub remaining_expected: NumBlocks,
+    /// How many of the remaining slots must be produced to stay above the
+    /// threshold. `None` if the threshold can't be reached anymore.
+    pub min_remaining_to_produce: Option<NumBlocks>,
+}
+
 #[derive(
     PartialEq,
     Eq,
@@ -2472,6 +2798,25 @@ pub struct SplitStorageInfoView {
     pub hot_db_kind: Option<String>,
 }
 
+/// Transactions waiting in the transaction pool of a shard.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ShardPendingTransactionsView {
+    pub shard_id: ShardId,
+    pub transactions: Vec<SignedTransactionView>,
+}
+
+/// Contents of the transaction pool of a shard.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TransactionPoolShardStatsView {
+    pub shard_id: ShardId,
+    /// Number of transactions in the pool.
+    pub transaction_count: u64,
+    /// Total size of the transactions in the pool in bytes.
+    pub transaction_size: u64,
+    /// Number of distinct signers of the transactions in the pool.
+    pub signer_count: u64,
+}
+
 impl From<RuntimeConfig> for RuntimeConfigView {
     fn from(config: RuntimeConfig) -> Self {
         Self {
@@ -2934,7 +3279,8 @@ impl From<ExtCostsConfigView> for near_primitives_core::config::ExtCostsConfig {
 #[cfg(test)]
 mod tests {
     use super::ExecutionMetadataView;
-    use crate::transaction::ExecutionMetadata;
+    use crate::hash::CryptoHash;
+    use crate::transaction::{ActionUsage, ExecutionMetadata, ExecutionMetadataV4, RefundInfo};
     use near_vm_runner::{ProfileDataV2, ProfileDataV3};
 
     /// The JSON representation used in RPC responses must not remove or rename
@@ -2979,4 +3325,57 @@ mod tests {
         let view = ExecutionMetadataView::from(metadata);
         insta::assert_json_snapshot!(view);
     }
+
+    /// `ExecutionMetadataView` with metadata V4 displayed on the RPC should not change.
+    #[test]
+    #[cfg_attr(feature = "nightly", ignore)]
+    fn test_exec_metadata_v4_view() {
+        let metadata = ExecutionMetadata::V4(ExecutionMetadataV4 {
+            profile: ProfileDataV3::test(),
+            actions: vec![
+                ActionUsage { gas_burnt: 100, gas_used: 100, compute_usage: 100 },
+                ActionUsage { gas_burnt: 200, gas_used: 1200, compute_usage: 300 },
+            ],
+            refund: RefundInfo {
+                is_refund: false,
+                gas_refund: 500,
+                gas_balance_refund: 50000,
+                deposit_refund: 7,
+                refund_receipt_ids: vec![CryptoHash::default()],
+            },
+        });
+        let view = ExecutionMetadataView::from(metadata);
+        insta::assert_json_snapshot!(view);
+    }
+
+    /// The Borsh layout of `ExecutionMetadataView` is used on the wire and must not change.
+    #[test]
+    fn test_exec_metadata_view_borsh_layout() {
+        #[derive(borsh::BorshSerialize)]
+        struct ExecutionMetadataViewV3 {
+            version: u32,
+            gas_profile: Option<Vec<super::CostGasUsed>>,
+        }
+
+        let metadata = ExecutionMetadata::V4(ExecutionMetadataV4 {
+            profile: ProfileDataV3::test(),
+            actions: vec![ActionUsage { gas_burnt: 100, gas_used: 100, compute_usage: 100 }],
+            refund: RefundInfo {
+                is_refund: false,
+                gas_refund: 500,
+                gas_balance_refund: 50000,
+                deposit_refund: 7,
+                refund_receipt_ids: vec![],
+            },
+        });
+        let view = ExecutionMetadataView::from(metadata);
+        let old = ExecutionMetadataViewV3 { version: 4, gas_profile: view.gas_profile.clone() };
+        let bytes = borsh::to_vec(&view).unwrap();
+        assert_eq!(borsh::to_vec(&old).unwrap(), bytes);
+
+        let decoded: ExecutionMetadataView = borsh::from_slice(&bytes).unwrap();
+        assert_eq!(view.gas_profile, decoded.gas_profile);
+        assert_eq!(None, decoded.action_gas_usage);
+        assert_eq!(None, decoded.refund);
+    }
 }
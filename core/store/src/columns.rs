@@ -277,6 +277,24 @@ pub enum DBCol {
     /// - *Rows*: arbitrary string, see `crate::db::FLAT_STATE_VALUES_INLINING_MIGRATION_STATUS_KEY` for example
     /// - *Column type*: arbitrary bytes
     Misc,
+    /// Reputation scores of the network peers, see `near_network::peer_manager::peer_store`.
+    /// - *Rows*: single row (empty row name)
+    /// - *Content type*: Vec of (PeerId, i32)
+    PeerReputations,
+    /// Transactions of the transaction pool, saved so that they survive a restart of the node.
+    /// - *Rows*: `shard_uid` + transaction hash
+    /// - *Column type*: `SignedTransaction`
+    TransactionPool,
+    /// Chunk state witnesses which failed validation, saved if `save_invalid_witnesses` is set.
+    /// Only one witness per height and shard is kept, for the last few heights before the head.
+    /// - *Rows*: height_created (big-endian u64) + shard_id (big-endian u64) + chunk hash
+    /// - *Column type*: `ChunkStateWitness`
+    InvalidChunkStateWitnesses,
+    /// Chunk state witnesses validated recently, saved if `save_latest_witnesses` is set.
+    /// Only witnesses of the last few heights are kept.
+    /// - *Rows*: height_created (big-endian u64) + shard_id (big-endian u64) + chunk hash
+    /// - *Column type*: `ChunkStateWitness`
+    LatestChunkStateWitnesses,
     /// Column to store data for Epoch Sync.
     /// Does not contain data for genesis epoch.
     /// - *Rows*: `epoch_id`
@@ -292,7 +310,8 @@ pub enum DBCol {
 /// Currently only used in cold storage continuous migration.
 #[derive(PartialEq, Copy, Clone, Debug, Hash, Eq, strum::EnumIter)]
 pub enum DBKeyType {
-    /// Empty row name. Used in DBCol::LastComponentNonce and DBCol::RecentOutboundConnections
+    /// Empty row name. Used in DBCol::LastComponentNonce, DBCol::RecentOutboundConnections
+    /// and DBCol::PeerReputations
     Empty,
     /// Set of predetermined strings. Used, for example, in DBCol::BlockMisc
     StringLiteral,
@@ -433,6 +452,11 @@ impl DBCol {
             // TODO
             DBCol::ChallengedBlocks => false,
             DBCol::Misc => false,
+            // TransactionPool is only needed to restore the pool after a restart.
+            DBCol::TransactionPool => false,
+            // Saved chunk state witnesses are only needed to debug chunk validation.
+            DBCol::InvalidChunkStateWitnesses => false,
+            DBCol::LatestChunkStateWitnesses => false,
             // BlockToCatchup is only needed while syncing and it is not immutable.
             DBCol::BlocksToCatchup => false,
             // BlockRefCount is only needed when handling forks and it is not immutable.
@@ -456,6 +480,7 @@ impl DBCol {
             | DBCol::BlockHeight
             | DBCol::_Peers
             | DBCol::RecentOutboundConnections
+            | DBCol::PeerReputations
             | DBCol::BlockMerkleTree
             | DBCol::AccountAnnouncements
             | DBCol::EpochLightClientBlocks
@@ -503,6 +528,7 @@ impl DBCol {
             DBCol::IncomingReceipts => &[DBKeyType::BlockHash, DBKeyType::ShardId],
             DBCol::_Peers => &[DBKeyType::PeerId],
             DBCol::RecentOutboundConnections => &[DBKeyType::Empty],
+            DBCol::PeerReputations => &[DBKeyType::Empty],
             DBCol::EpochInfo => &[DBKeyType::EpochId],
             DBCol::BlockInfo => &[DBKeyType::BlockHash],
             DBCol::Chunks => &[DBKeyType::ChunkHash],
@@ -547,6 +573,10 @@ impl DBCol {
             DBCol::FlatStateChanges => &[DBKeyType::ShardUId, DBKeyType::BlockHash],
             DBCol::FlatStateDeltaMetadata => &[DBKeyType::ShardUId, DBKeyType::BlockHash],
             DBCol::FlatStorageStatus => &[DBKeyType::ShardUId],
+            DBCol::TransactionPool => &[DBKeyType::ShardUId, DBKeyType::TransactionHash],
+            DBCol::InvalidChunkStateWitnesses | DBCol::LatestChunkStateWitnesses => {
+                &[DBKeyType::BlockHeight, DBKeyType::ShardId, DBKeyType::ChunkHash]
+            }
             #[cfg(feature = "new_epoch_sync")]
             DBCol::EpochSyncInfo => &[DBKeyType::EpochId],
         }
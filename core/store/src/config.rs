@@ -115,6 +115,44 @@ pub struct StoreConfig {
 
     // TODO (#9989): To be phased out in favor of state_snapshot_config
     pub state_snapshot_compaction_enabled: bool,
+
+    /// Configuration of the background job removing unreachable `State`
+    /// entries, see [`crate::state_garbage_auditor`].
+    pub state_garbage_auditor: StateGarbageAuditorConfig,
+}
+
+/// Config of the state garbage auditor, which removes trie nodes and values
+/// that can no longer be reached from any state root of the chain.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
+#[serde(default)]
+pub struct StateGarbageAuditorConfig {
+    /// Whether the auditor runs periodically in the background.  Only
+    /// non-archival nodes support it.
+    pub enabled: bool,
+    /// How long to wait between audits.
+    pub period: Duration,
+    /// Number of unreachable entries removed in one database write.
+    pub batch_size: usize,
+    /// Pause between two batches, so that removal doesn't compete with block
+    /// processing for IO.
+    pub batch_delay: Duration,
+    /// Size of the filter of reachable nodes, in bits per `State` entry of the
+    /// audited shard.  A bigger filter takes more memory but lets fewer
+    /// unreachable entries stay in the database.
+    pub filter_bits_per_entry: u32,
+}
+
+impl Default for StateGarbageAuditorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            period: Duration::from_secs(24 * 60 * 60),
+            batch_size: 10_000,
+            batch_delay: Duration::from_millis(100),
+            // Gives about 1% of unreachable entries kept by mistake.
+            filter_bits_per_entry: 10,
+        }
+    }
 }
 
 /// Config used to control state snapshot creation. This is used for state sync and resharding.
@@ -297,6 +335,8 @@ impl Default for StoreConfig {
 
             // TODO: To be phased out in favor of state_snapshot_config
             state_snapshot_compaction_enabled: false,
+
+            state_garbage_auditor: Default::default(),
         }
     }
 }
@@ -28,6 +28,15 @@ impl SplitDB {
         return Arc::new(SplitDB { hot, cold });
     }
 
+    /// Updates metrics of reads which missed the hot database and had to be
+    /// served by the cold one.
+    fn record_cold_read(col: DBCol, found: bool) {
+        let col: &str = col.into();
+        crate::metrics::SPLIT_DB_COLD_READS
+            .with_label_values(&[col, if found { "found" } else { "missing" }])
+            .inc();
+    }
+
     /// The cmp function for the DBIteratorItems.
     ///
     /// Note that this does not implement total ordering because there isn't a
@@ -81,7 +90,9 @@ impl Database for SplitDB {
             return Ok(Some(hot_result));
         }
         if col.is_cold() {
-            return self.cold.get_raw_bytes(col, key);
+            let cold_result = self.cold.get_raw_bytes(col, key)?;
+            Self::record_cold_read(col, cold_result.is_some());
+            return Ok(cold_result);
         }
         Ok(None)
     }
@@ -99,7 +110,9 @@ impl Database for SplitDB {
             return Ok(Some(hot_result));
         }
         if col.is_cold() {
-            return self.cold.get_with_rc_stripped(col, key);
+            let cold_result = self.cold.get_with_rc_stripped(col, key)?;
+            Self::record_cold_read(col, cold_result.is_some());
+            return Ok(cold_result);
         }
         Ok(None)
     }
@@ -314,6 +327,38 @@ mod test {
         // Test 3: nothing, there aren't any non-cold reference counted columns.
     }
 
+    #[test]
+    fn test_cold_reads_metric() {
+        let hot = create_hot();
+        let cold = create_cold();
+        let split = SplitDB::new(hot.clone(), cold.clone());
+
+        // Use a column which no other test reads from so that the counters
+        // aren't affected by tests running in parallel.
+        let col = DBCol::NextBlockHashes;
+        let counter = |result| {
+            crate::metrics::SPLIT_DB_COLD_READS
+                .with_label_values(&[<&str>::from(col), result])
+                .get()
+        };
+        let (found, missing) = (counter("found"), counter("missing"));
+
+        set(&hot, col, FOO, FOO_VALUE);
+        set(&cold, col, BAR, BAR_VALUE);
+
+        // Served by the hot db, the cold db is not consulted.
+        assert_eq!(split.get_raw_bytes(col, FOO).unwrap().as_deref(), Some(FOO_VALUE));
+        assert_eq!((counter("found"), counter("missing")), (found, missing));
+
+        // Served by the cold db.
+        assert_eq!(split.get_raw_bytes(col, BAR).unwrap().as_deref(), Some(BAR_VALUE));
+        assert_eq!((counter("found"), counter("missing")), (found + 1, missing));
+
+        // Missing in both.
+        assert_eq!(split.get_raw_bytes(col, BAZ).unwrap(), None);
+        assert_eq!((counter("found"), counter("missing")), (found + 1, missing + 1));
+    }
+
     #[test]
     fn test_iter() {
         let hot = create_hot();
@@ -1,11 +1,13 @@
 use crate::flat::store_helper;
+use itertools::EitherOrBoth;
 use near_primitives::hash::CryptoHash;
 use near_primitives::shard_layout::ShardUId;
 use near_primitives::state::FlatStateValue;
+use std::collections::BTreeMap;
 
 use crate::Store;
 
-use super::types::FlatStateIterator;
+use super::types::{FlatStateIterator, FlatStorageError, FlatStorageResult};
 use super::FlatStorage;
 
 /// Struct for getting value references from the flat storage, corresponding
@@ -53,6 +55,49 @@ impl FlatStorageChunkView {
         store_helper::iter_flat_state_entries(self.flat_storage.shard_uid(), &self.store, from, to)
     }
 
+    /// Returns iterator over entries in the given range of keys, taken from
+    /// the state corresponding to `FlatStorageChunkView::block_hash`.
+    ///
+    /// Unlike `iter_flat_state_entries`, which only reads the state at flat
+    /// head, this applies deltas of all blocks between flat head and the
+    /// block on top of it. Deltas are read from disk, so this is meant for
+    /// view requests and tools rather than for chunk processing.
+    pub fn iter_range<'a>(
+        &'a self,
+        from: Option<&[u8]>,
+        to: Option<&[u8]>,
+    ) -> FlatStorageResult<FlatStateIterator<'a>> {
+        let shard_uid = self.flat_storage.shard_uid();
+        let in_range =
+            |key: &[u8]| from.map_or(true, |from| key >= from) && to.map_or(true, |to| key < to);
+        // Blocks are returned in backwards chain order, so apply them in
+        // reverse to let the most recent change of each key win.
+        let mut overlay = BTreeMap::new();
+        for block_hash in self.flat_storage.get_blocks_to_head(&self.block_hash)?.iter().rev() {
+            let changes = store_helper::get_delta_changes(&self.store, shard_uid, *block_hash)?
+                .ok_or_else(|| {
+                    FlatStorageError::StorageInternalError(format!(
+                        "delta for block {block_hash} is missing"
+                    ))
+                })?;
+            overlay.extend(changes.0.into_iter().filter(|(key, _)| in_range(key.as_slice())));
+        }
+
+        let entries = self.iter_flat_state_entries(from, to);
+        let iter = itertools::merge_join_by(entries, overlay, |entry, (key, _)| match entry {
+            Ok((entry_key, _)) => entry_key.cmp(key),
+            // Surface errors as early as possible.
+            Err(_) => std::cmp::Ordering::Less,
+        })
+        .filter_map(|item| match item {
+            EitherOrBoth::Left(entry) => Some(entry),
+            EitherOrBoth::Right((key, value)) | EitherOrBoth::Both(_, (key, value)) => {
+                value.map(|value| Ok((key, value)))
+            }
+        });
+        Ok(Box::new(iter))
+    }
+
     pub fn get_head_hash(&self) -> CryptoHash {
         self.flat_storage.get_head_hash()
     }
@@ -0,0 +1,329 @@
+//! Aggregation of storage used by contract data of a single account, based on
+//! flat storage.
+//!
+//! Contract frameworks such as near-sdk store each collection under its own
+//! key prefix, so grouping keys by prefix shows which collections consume the
+//! storage of a contract.
+
+use super::types::{FlatStorageError, FlatStorageResult};
+use super::FlatStorageChunkView;
+use near_primitives::hash::CryptoHash;
+use near_primitives::trie_key::trie_key_parsers;
+use near_primitives::types::{AccountId, BlockHeight};
+use near_primitives::views::{
+    ContractStorageReportView, StorageKeyUsageView, StoragePrefixUsageView,
+};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap};
+
+/// Which keys to scan and how to group them in `contract_storage_report`.
+pub struct ContractStorageReportOptions<'a> {
+    /// Prefixes to group keys by. Keys are grouped by their first byte if empty.
+    pub prefixes: &'a [Vec<u8>],
+    /// Number of largest records to report individually.
+    pub top: usize,
+    /// Key to start the scan from, usually `next_key` of the previous report.
+    pub start_key: Option<&'a [u8]>,
+    /// Maximum number of keys to scan.
+    pub max_keys: usize,
+}
+
+/// Walks the `ContractData` keys of `account_id` in the state visible from
+/// `chunk_view` and aggregates their sizes by key prefix.
+///
+/// Each key is attributed to the longest of `options.prefixes` it starts with.
+/// If no prefixes are given, keys are grouped by their first byte instead. The
+/// `options.top` largest records are reported individually.
+///
+/// At most `options.max_keys` keys are scanned. If there are more, the report
+/// covers only the scanned keys and `next_key` is set to the key the next scan
+/// should start from.
+pub fn contract_storage_report(
+    chunk_view: &FlatStorageChunkView,
+    block_hash: CryptoHash,
+    block_height: BlockHeight,
+    account_id: &AccountId,
+    options: &ContractStorageReportOptions,
+    num_extra_bytes_record: u64,
+) -> FlatStorageResult<ContractStorageReportView> {
+    let from = trie_key_parsers::get_raw_prefix_for_contract_data(
+        account_id,
+        options.start_key.unwrap_or_default(),
+    );
+    // The raw prefix ends with the account data separator, so incrementing
+    // it gives the smallest key past all contract data of the account.
+    let mut to = trie_key_parsers::get_raw_prefix_for_contract_data(account_id, &[]);
+    *to.last_mut().unwrap() += 1;
+
+    let mut builder = ReportBuilder::new(options.prefixes, options.top, num_extra_bytes_record);
+    let mut next_key = None;
+    for (scanned, entry) in chunk_view.iter_range(Some(&from), Some(&to))?.enumerate() {
+        let (raw_key, value) = entry?;
+        let key = trie_key_parsers::parse_data_key_from_contract_data_key(&raw_key, account_id)
+            .map_err(|err| {
+                FlatStorageError::StorageInternalError(format!(
+                    "invalid ContractData key format: {err}"
+                ))
+            })?;
+        if scanned == options.max_keys {
+            next_key = Some(key.to_vec());
+            break;
+        }
+        builder.add(key, value.value_len() as u64);
+    }
+    Ok(builder.finish(account_id.clone(), block_hash, block_height, next_key))
+}
+
+#[derive(Default)]
+struct Usage {
+    keys: u64,
+    bytes: u64,
+}
+
+impl Usage {
+    fn add(&mut self, bytes: u64) {
+        self.keys += 1;
+        self.bytes += bytes;
+    }
+}
+
+struct ReportBuilder {
+    /// Configured prefixes. Empty if keys are grouped by their first byte.
+    prefixes: Vec<Vec<u8>>,
+    num_extra_bytes_record: u64,
+    top: usize,
+    total: Usage,
+    by_prefix: BTreeMap<Vec<u8>, Usage>,
+    other: Usage,
+    /// Min-heap holding at most `top` largest records.
+    largest: BinaryHeap<Reverse<(u64, Vec<u8>)>>,
+}
+
+impl ReportBuilder {
+    fn new(prefixes: &[Vec<u8>], top: usize, num_extra_bytes_record: u64) -> Self {
+        // Configured prefixes are always reported, even if no key matches them.
+        let by_prefix = prefixes.iter().map(|prefix| (prefix.clone(), Usage::default())).collect();
+        Self {
+            prefixes: prefixes.to_vec(),
+            num_extra_bytes_record,
+            top,
+            total: Usage::default(),
+            by_prefix,
+            other: Usage::default(),
+            largest: BinaryHeap::new(),
+        }
+    }
+
+    fn add(&mut self, key: &[u8], value_len: u64) {
+        let bytes = key.len() as u64 + value_len + self.num_extra_bytes_record;
+        self.total.add(bytes);
+
+        let prefix = if self.prefixes.is_empty() {
+            key.get(..1)
+        } else {
+            self.prefixes
+                .iter()
+                .filter(|prefix| key.starts_with(prefix))
+                .max_by_key(|prefix| prefix.len())
+                .map(|prefix| prefix.as_slice())
+        };
+        match prefix {
+            Some(prefix) => self.by_prefix.entry(prefix.to_vec()).or_default().add(bytes),
+            None => self.other.add(bytes),
+        }
+
+        if self.top > 0 {
+            self.largest.push(Reverse((bytes, key.to_vec())));
+            if self.largest.len() > self.top {
+                self.largest.pop();
+            }
+        }
+    }
+
+    fn finish(
+        self,
+        account_id: AccountId,
+        block_hash: CryptoHash,
+        block_height: BlockHeight,
+        next_key: Option<Vec<u8>>,
+    ) -> ContractStorageReportView {
+        let mut prefixes: Vec<_> = self
+            .by_prefix
+            .into_iter()
+            .map(|(prefix, usage)| StoragePrefixUsageView {
+                prefix: prefix.into(),
+                keys: usage.keys,
+                bytes: usage.bytes,
+            })
+            .collect();
+        // Stable sort keeps prefixes with equal usage in lexicographic order.
+        prefixes.sort_by_key(|usage| Reverse(usage.bytes));
+        let largest_keys = self
+            .largest
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((bytes, key))| StorageKeyUsageView { key: key.into(), bytes })
+            .collect();
+        ContractStorageReportView {
+            account_id,
+            block_hash,
+            block_height,
+            total_keys: self.total.keys,
+            total_bytes: self.total.bytes,
+            prefixes,
+            other_keys: self.other.keys,
+            other_bytes: self.other.bytes,
+            largest_keys,
+            next_key: next_key.map(Into::into),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{contract_storage_report, ContractStorageReportOptions};
+    use crate::flat::delta::{FlatStateChanges, FlatStateDelta, FlatStateDeltaMetadata};
+    use crate::flat::test_utils::MockChain;
+    use crate::flat::{
+        store_helper, FlatStorageManager, FlatStorageReadyStatus, FlatStorageStatus,
+    };
+    use crate::test_utils::create_test_store;
+    use near_primitives::shard_layout::ShardUId;
+    use near_primitives::state::FlatStateValue;
+    use near_primitives::trie_key::TrieKey;
+    use near_primitives::types::AccountId;
+    use near_primitives::views::{StorageKeyUsageView, StoragePrefixUsageView};
+
+    fn data_key(account_id: &AccountId, key: &[u8]) -> Vec<u8> {
+        TrieKey::ContractData { account_id: account_id.clone(), key: key.to_vec() }.to_vec()
+    }
+
+    #[test]
+    fn contract_storage_report_applies_deltas() {
+        let chain = MockChain::linear_chain(3);
+        let shard_uid = ShardUId::single_shard();
+        let account: AccountId = "alice.near".parse().unwrap();
+        // Account whose id is a prefix of the reported one must not be counted.
+        let other_account: AccountId = "alice.nea".parse().unwrap();
+        let store = create_test_store();
+
+        let mut store_update = store.store_update();
+        store_helper::set_flat_storage_status(
+            &mut store_update,
+            shard_uid,
+            FlatStorageStatus::Ready(FlatStorageReadyStatus { flat_head: chain.get_block(0) }),
+        );
+        for (key, value) in [
+            (data_key(&account, b"mA"), &b"1234"[..]),
+            (data_key(&account, b"mB"), &b"1"[..]),
+            (data_key(&account, b"v\x00"), &b"12345678"[..]),
+            (data_key(&account, b"STATE"), &b"12"[..]),
+            (data_key(&other_account, b"mC"), &b"123"[..]),
+        ] {
+            store_helper::set_flat_state_value(
+                &mut store_update,
+                shard_uid,
+                key,
+                Some(FlatStateValue::inlined(value)),
+            );
+        }
+        // Block 1 overwrites one key and adds another, block 2 removes a key.
+        let deltas = [
+            vec![
+                (data_key(&account, b"mA"), Some(FlatStateValue::inlined(b"12"))),
+                (data_key(&account, b"mD"), Some(FlatStateValue::inlined(b"123456"))),
+            ],
+            vec![(data_key(&account, b"v\x00"), None)],
+        ];
+        for (i, changes) in deltas.into_iter().enumerate() {
+            let delta = FlatStateDelta {
+                changes: FlatStateChanges::from(changes),
+                metadata: FlatStateDeltaMetadata {
+                    block: chain.get_block(i as u64 + 1),
+                    prev_block_with_changes: None,
+                },
+            };
+            store_helper::set_delta(&mut store_update, shard_uid, &delta);
+        }
+        store_update.commit().unwrap();
+
+        let flat_storage_manager = FlatStorageManager::new(store);
+        flat_storage_manager.create_flat_storage_for_shard(shard_uid).unwrap();
+        let block_hash = chain.get_block_hash(2);
+        let chunk_view = flat_storage_manager.chunk_view(shard_uid, block_hash).unwrap();
+
+        let prefixes = vec![b"m".to_vec(), b"mD".to_vec(), b"x".to_vec()];
+        let options = ContractStorageReportOptions {
+            prefixes: &prefixes,
+            top: 2,
+            start_key: None,
+            max_keys: 10,
+        };
+        let report =
+            contract_storage_report(&chunk_view, block_hash, 2, &account, &options, 40).unwrap();
+        assert_eq!(report.total_keys, 4);
+        assert_eq!(report.total_bytes, (2 + 2) + (2 + 1) + (2 + 6) + (5 + 2) + 4 * 40);
+        assert_eq!(
+            report.prefixes,
+            vec![
+                StoragePrefixUsageView { prefix: b"m".to_vec().into(), keys: 2, bytes: 87 },
+                StoragePrefixUsageView { prefix: b"mD".to_vec().into(), keys: 1, bytes: 48 },
+                StoragePrefixUsageView { prefix: b"x".to_vec().into(), keys: 0, bytes: 0 },
+            ]
+        );
+        assert_eq!((report.other_keys, report.other_bytes), (1, 47));
+        assert_eq!(
+            report.largest_keys,
+            vec![
+                StorageKeyUsageView { key: b"mD".to_vec().into(), bytes: 48 },
+                StorageKeyUsageView { key: b"STATE".to_vec().into(), bytes: 47 },
+            ]
+        );
+
+        assert_eq!(report.next_key, None);
+
+        // Without prefixes, keys are grouped by their first byte.
+        let options =
+            ContractStorageReportOptions { prefixes: &[], top: 0, start_key: None, max_keys: 10 };
+        let report =
+            contract_storage_report(&chunk_view, block_hash, 2, &account, &options, 40).unwrap();
+        assert_eq!(
+            report.prefixes,
+            vec![
+                StoragePrefixUsageView { prefix: b"m".to_vec().into(), keys: 3, bytes: 135 },
+                StoragePrefixUsageView { prefix: b"S".to_vec().into(), keys: 1, bytes: 47 },
+            ]
+        );
+        assert!(report.largest_keys.is_empty());
+
+        // The scan stops after `max_keys` keys and continues from `next_key`.
+        let options =
+            ContractStorageReportOptions { prefixes: &[], top: 0, start_key: None, max_keys: 2 };
+        let report =
+            contract_storage_report(&chunk_view, block_hash, 2, &account, &options, 40).unwrap();
+        assert_eq!(report.total_keys, 2);
+        assert_eq!(report.total_bytes, (5 + 2) + (2 + 2) + 2 * 40);
+        assert_eq!(report.next_key, Some(b"mB".to_vec().into()));
+        let options = ContractStorageReportOptions {
+            prefixes: &[],
+            top: 0,
+            start_key: Some(b"mB"),
+            max_keys: 2,
+        };
+        let report =
+            contract_storage_report(&chunk_view, block_hash, 2, &account, &options, 40).unwrap();
+        assert_eq!(report.total_keys, 2);
+        assert_eq!(report.total_bytes, (2 + 1) + (2 + 6) + 2 * 40);
+        assert_eq!(report.next_key, None);
+
+        // State at flat head doesn't include deltas.
+        let block_hash = chain.get_block_hash(0);
+        let chunk_view = flat_storage_manager.chunk_view(shard_uid, block_hash).unwrap();
+        let options =
+            ContractStorageReportOptions { prefixes: &[], top: 0, start_key: None, max_keys: 10 };
+        let report =
+            contract_storage_report(&chunk_view, block_hash, 0, &account, &options, 40).unwrap();
+        assert_eq!(report.total_keys, 4);
+        assert_eq!(report.total_bytes, (2 + 4) + (2 + 1) + (2 + 8) + (5 + 2) + 4 * 40);
+    }
+}
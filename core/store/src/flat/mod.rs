@@ -26,6 +26,7 @@
 //!                     inside flat storage).
 
 mod chunk_view;
+mod contract_storage;
 pub mod delta;
 mod inlining_migration;
 mod manager;
@@ -37,6 +38,7 @@ pub mod test_utils;
 mod types;
 
 pub use chunk_view::FlatStorageChunkView;
+pub use contract_storage::{contract_storage_report, ContractStorageReportOptions};
 pub use delta::{FlatStateChanges, FlatStateDelta, FlatStateDeltaMetadata};
 pub use inlining_migration::{inline_flat_state_values, FlatStateValuesInliningMigrationHandle};
 pub use manager::FlatStorageManager;
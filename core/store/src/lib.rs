@@ -50,6 +50,7 @@ pub mod metrics;
 pub mod migrations;
 mod opener;
 mod rocksdb_metrics;
+pub mod state_garbage_auditor;
 mod sync_utils;
 pub mod test_utils;
 pub mod trie;
@@ -287,6 +288,15 @@ impl Store {
         Ok(value)
     }
 
+    /// Fetches the value including the refcount of reference counted columns.
+    pub(crate) fn get_raw_bytes(
+        &self,
+        column: DBCol,
+        key: &[u8],
+    ) -> io::Result<Option<DBSlice<'_>>> {
+        self.storage.get_raw_bytes(column, key)
+    }
+
     pub fn get_ser<T: BorshDeserialize>(&self, column: DBCol, key: &[u8]) -> io::Result<Option<T>> {
         self.get(column, key)?.as_deref().map(T::try_from_slice).transpose()
     }
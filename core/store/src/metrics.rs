@@ -228,6 +228,14 @@ pub static COLD_MIGRATION_READS: Lazy<IntCounterVec> = Lazy::new(|| {
     )
     .unwrap()
 });
+pub static SPLIT_DB_COLD_READS: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "near_split_db_cold_reads",
+        "Number of reads from the split storage which missed the hot store and were forwarded to the cold store.",
+        &["col", "result"],
+    )
+    .unwrap()
+});
 pub static COLD_HEAD_HEIGHT: Lazy<IntGauge> = Lazy::new(|| {
     try_create_int_gauge("near_cold_head_height", "Height of the head of cold storage").unwrap()
 });
@@ -545,6 +553,30 @@ pub static COLD_STORE_MIGRATION_BATCH_WRITE_TIME: Lazy<HistogramVec> = Lazy::new
     )
     .unwrap()
 });
+pub(crate) static STATE_GARBAGE_AUDITOR_LIVE_NODES: Lazy<IntGaugeVec> = Lazy::new(|| {
+    try_create_int_gauge_vec(
+        "near_state_garbage_auditor_live_nodes",
+        "Number of trie nodes and values reachable from the head state root found by the last audit of the shard.",
+        &["shard_uid"],
+    )
+    .unwrap()
+});
+pub(crate) static STATE_GARBAGE_AUDITOR_REMOVED_ENTRIES: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "near_state_garbage_auditor_removed_entries_total",
+        "Number of unreachable State entries removed by the state garbage auditor.",
+        &["shard_uid"],
+    )
+    .unwrap()
+});
+pub(crate) static STATE_GARBAGE_AUDITOR_RECLAIMED_BYTES: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "near_state_garbage_auditor_reclaimed_bytes_total",
+        "Total size of keys and values of the State entries removed by the state garbage auditor.",
+        &["shard_uid"],
+    )
+    .unwrap()
+});
 
 fn export_store_stats(store: &Store, temperature: Temperature) {
     if let Some(stats) = store.get_store_statistics() {
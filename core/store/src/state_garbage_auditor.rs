@@ -0,0 +1,492 @@
+//! Removal of `State` entries which can't be reached from the chain anymore.
+//!
+//! Trie nodes and values in [`DBCol::State`] are reference counted and garbage
+//! collection removes them once their refcount drops to zero.  Refcounting
+//! bugs leave behind entries whose refcount never gets there.  The auditor
+//! finds such entries of a shard and removes them:
+//!
+//! 1. It records every node and value reachable from the state root of the
+//!    shard at the head of the chain, together with everything mentioned by
+//!    the [`DBCol::TrieChanges`] of the shard.  Trie changes are kept for all
+//!    blocks which are not garbage collected yet, so they cover the states of
+//!    older blocks and forks, as well as entries which garbage collection is
+//!    yet to decrement.
+//! 2. It scans the `State` entries of the shard and removes the ones which
+//!    were not recorded, in batches.  Before each batch it records the trie
+//!    changes of the blocks processed in the meantime.
+//!
+//! Reachable entries are recorded in a bloom filter to bound memory usage.  A
+//! false positive only means that an unreachable entry stays in the database.
+//! Entries are removed by decrementing their refcount to zero, so an entry
+//! which gets inserted again concurrently is kept.
+//!
+//! State sync, catchup and resharding write `State` entries without trie
+//! changes, so the audit is aborted if any of them could have happened since
+//! it started.
+
+use std::collections::{HashMap, HashSet};
+use std::num::NonZeroU32;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+use borsh::BorshDeserialize;
+use near_primitives::block::Tip;
+use near_primitives::hash::CryptoHash;
+use near_primitives::shard_layout::get_block_shard_uid;
+use near_primitives::types::{BlockHeight, EpochId, StateRoot};
+use near_primitives::utils::index_to_bytes;
+
+use crate::config::StateGarbageAuditorConfig;
+use crate::db::refcount::decode_value_with_rc;
+use crate::metadata::DbKind;
+use crate::metrics::{
+    STATE_GARBAGE_AUDITOR_LIVE_NODES, STATE_GARBAGE_AUDITOR_RECLAIMED_BYTES,
+    STATE_GARBAGE_AUDITOR_REMOVED_ENTRIES,
+};
+use crate::trie::{RawTrieNode, RawTrieNodeWithSize, TrieChanges};
+use crate::{DBCol, ShardUId, Store, FINAL_HEAD_KEY, HEAD_KEY};
+
+#[derive(thiserror::Error, Debug)]
+pub enum StateGarbageAuditError {
+    #[error("state garbage audit is not supported: {0}")]
+    Unsupported(String),
+    #[error("state garbage audit was interrupted")]
+    Interrupted,
+    #[error("state garbage audit was aborted: {0}")]
+    Aborted(String),
+    #[error("trie node {0} reachable from the state root is missing")]
+    MissingNode(CryptoHash),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// Whether unreachable entries are removed or only counted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuditMode {
+    DryRun,
+    Remove,
+}
+
+/// Outcome of the audit of a single shard.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StateGarbageAuditReport {
+    /// Number of `State` entries of the shard when the audit started.
+    pub total_entries: u64,
+    /// Number of trie nodes and values reachable from the head state root.
+    pub live_nodes: u64,
+    /// Number of unreachable entries found.  In [`AuditMode::Remove`] mode all
+    /// of them were removed.
+    pub unreachable_entries: u64,
+    /// Total size of keys and values of the unreachable entries.
+    pub unreachable_bytes: u64,
+}
+
+/// Audits the `State` entries of `shard_uid`, see the module documentation.
+///
+/// `state_root` must be the state root of the shard after the chunk of the
+/// head block, as the node saw it right before calling this function.
+pub fn audit_state_garbage(
+    store: &Store,
+    shard_uid: ShardUId,
+    state_root: StateRoot,
+    config: &StateGarbageAuditorConfig,
+    mode: AuditMode,
+    keep_going: &AtomicBool,
+) -> Result<StateGarbageAuditReport, StateGarbageAuditError> {
+    let kind = store.get_db_kind()?;
+    if kind != Some(DbKind::RPC) {
+        return Err(StateGarbageAuditError::Unsupported(format!(
+            "database kind is {kind:?}, only RPC databases are supported"
+        )));
+    }
+    let start = Instant::now();
+    let head = get_tip(store, HEAD_KEY)?;
+    let final_head = get_tip(store, FINAL_HEAD_KEY)?;
+    check_no_state_sync(store, &head.epoch_id)?;
+    let prefix = shard_uid.to_bytes();
+
+    let mut report = StateGarbageAuditReport::default();
+    for item in store.iter_prefix(DBCol::State, &prefix) {
+        item?;
+        report.total_entries += 1;
+    }
+    tracing::info!(target: "state_garbage_auditor", %shard_uid, total_entries = report.total_entries, "Starting the audit");
+
+    let mut live = LiveNodeFilter::new(report.total_entries, config.filter_bits_per_entry);
+    for item in store.iter(DBCol::TrieChanges) {
+        let (key, value) = item?;
+        if key[CryptoHash::LENGTH..] == prefix {
+            record_trie_changes(&mut live, &value)?;
+        }
+    }
+    let mut trie_changes_tracker = TrieChangesTracker::new(final_head.height);
+
+    report.live_nodes =
+        record_reachable_nodes(store, shard_uid, state_root, &mut live, keep_going)?;
+    STATE_GARBAGE_AUDITOR_LIVE_NODES
+        .with_label_values(&[&shard_uid.to_string()])
+        .set(report.live_nodes as i64);
+    tracing::info!(target: "state_garbage_auditor", %shard_uid, live_nodes = report.live_nodes, elapsed = ?start.elapsed(), "Recorded reachable nodes");
+
+    let mut batch = vec![];
+    for item in store.iter_prefix(DBCol::State, &prefix) {
+        if !keep_going.load(Ordering::Relaxed) {
+            return Err(StateGarbageAuditError::Interrupted);
+        }
+        let (key, value) = item?;
+        let hash = CryptoHash::try_from(&key[prefix.len()..]).map_err(|_| {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, "malformed State key")
+        })?;
+        if live.contains(&hash) {
+            continue;
+        }
+        batch.push((key, value.len()));
+        if batch.len() >= config.batch_size {
+            remove_batch(
+                store,
+                shard_uid,
+                &head.epoch_id,
+                &mut live,
+                &mut trie_changes_tracker,
+                std::mem::take(&mut batch),
+                mode,
+                &mut report,
+            )?;
+            std::thread::sleep(config.batch_delay);
+        }
+    }
+    remove_batch(
+        store,
+        shard_uid,
+        &head.epoch_id,
+        &mut live,
+        &mut trie_changes_tracker,
+        batch,
+        mode,
+        &mut report,
+    )?;
+    tracing::info!(target: "state_garbage_auditor", %shard_uid, ?mode, ?report, elapsed = ?start.elapsed(), "Finished the audit");
+    Ok(report)
+}
+
+fn get_tip(store: &Store, key: &[u8]) -> Result<Tip, StateGarbageAuditError> {
+    store.get_ser::<Tip>(DBCol::BlockMisc, key)?.ok_or_else(|| {
+        StateGarbageAuditError::Unsupported(format!("{} is not set", String::from_utf8_lossy(key)))
+    })
+}
+
+/// Makes sure that nothing could have written `State` entries without trie
+/// changes since the audit started in `epoch_id`.
+fn check_no_state_sync(store: &Store, epoch_id: &EpochId) -> Result<(), StateGarbageAuditError> {
+    let head = get_tip(store, HEAD_KEY)?;
+    if &head.epoch_id != epoch_id {
+        return Err(StateGarbageAuditError::Aborted("a new epoch started".to_string()));
+    }
+    if store.iter(DBCol::BlocksToCatchup).next().is_some()
+        || store.iter(DBCol::StateDlInfos).next().is_some()
+    {
+        return Err(StateGarbageAuditError::Aborted("state sync is in progress".to_string()));
+    }
+    Ok(())
+}
+
+fn record_trie_changes(
+    live: &mut LiveNodeFilter,
+    value: &[u8],
+) -> Result<(), StateGarbageAuditError> {
+    let trie_changes = TrieChanges::try_from_slice(value)?;
+    for insertion in trie_changes.insertions() {
+        live.insert(insertion.hash());
+    }
+    for deletion in trie_changes.deletions() {
+        live.insert(deletion.hash());
+    }
+    Ok(())
+}
+
+/// Walks the trie from `state_root` and records every node and value in it.
+/// Returns the number of recorded nodes and values.
+fn record_reachable_nodes(
+    store: &Store,
+    shard_uid: ShardUId,
+    state_root: StateRoot,
+    live: &mut LiveNodeFilter,
+    keep_going: &AtomicBool,
+) -> Result<u64, StateGarbageAuditError> {
+    if state_root == StateRoot::default() {
+        return Ok(0);
+    }
+    let mut count = 0;
+    let mut stack = vec![state_root];
+    let prefix = shard_uid.to_bytes();
+    let mut key = prefix.to_vec();
+    while let Some(hash) = stack.pop() {
+        if !keep_going.load(Ordering::Relaxed) {
+            return Err(StateGarbageAuditError::Interrupted);
+        }
+        live.insert(&hash);
+        count += 1;
+        key.truncate(prefix.len());
+        key.extend_from_slice(hash.as_ref());
+        let Some(bytes) = store.get(DBCol::State, &key)? else {
+            return Err(StateGarbageAuditError::MissingNode(hash));
+        };
+        let node = RawTrieNodeWithSize::try_from_slice(&bytes)?.node;
+        let (value, children) = match node {
+            RawTrieNode::Leaf(_, value) => (Some(value), None),
+            RawTrieNode::BranchNoValue(children) => (None, Some(children)),
+            RawTrieNode::BranchWithValue(value, children) => (Some(value), Some(children)),
+            RawTrieNode::Extension(_, child) => {
+                stack.push(child);
+                (None, None)
+            }
+        };
+        if let Some(value) = value {
+            live.insert(&value.hash);
+            count += 1;
+        }
+        if let Some(children) = children {
+            stack.extend(children.iter().map(|(_, child)| *child));
+        }
+    }
+    Ok(count)
+}
+
+fn remove_batch(
+    store: &Store,
+    shard_uid: ShardUId,
+    epoch_id: &EpochId,
+    live: &mut LiveNodeFilter,
+    trie_changes_tracker: &mut TrieChangesTracker,
+    batch: Vec<(Box<[u8]>, usize)>,
+    mode: AuditMode,
+    report: &mut StateGarbageAuditReport,
+) -> Result<(), StateGarbageAuditError> {
+    if batch.is_empty() {
+        return Ok(());
+    }
+    check_no_state_sync(store, epoch_id)?;
+    trie_changes_tracker.record_new_blocks(store, shard_uid, live)?;
+
+    let mut store_update = store.store_update();
+    let mut removed_entries = 0;
+    let mut removed_bytes = 0;
+    for (key, value_len) in batch {
+        let hash = CryptoHash::try_from(&key[key.len() - CryptoHash::LENGTH..]).unwrap();
+        if live.contains(&hash) {
+            continue;
+        }
+        if mode == AuditMode::Remove {
+            let Some(raw_value) = store.get_raw_bytes(DBCol::State, &key)? else {
+                continue;
+            };
+            let (_, rc) = decode_value_with_rc(&raw_value);
+            let Some(rc) = u32::try_from(rc).ok().and_then(NonZeroU32::new) else {
+                continue;
+            };
+            store_update.decrement_refcount_by(DBCol::State, &key, rc);
+        }
+        removed_entries += 1;
+        removed_bytes += (key.len() + value_len) as u64;
+    }
+    if mode == AuditMode::Remove {
+        store_update.commit()?;
+        let shard_uid = shard_uid.to_string();
+        STATE_GARBAGE_AUDITOR_REMOVED_ENTRIES
+            .with_label_values(&[&shard_uid])
+            .inc_by(removed_entries);
+        STATE_GARBAGE_AUDITOR_RECLAIMED_BYTES
+            .with_label_values(&[&shard_uid])
+            .inc_by(removed_bytes);
+    }
+    report.unreachable_entries += removed_entries;
+    report.unreachable_bytes += removed_bytes;
+    Ok(())
+}
+
+/// Records the trie changes of the blocks processed while the audit runs.
+struct TrieChangesTracker {
+    /// Blocks at heights below this one were already accounted for.
+    from_height: BlockHeight,
+    seen_blocks: HashSet<CryptoHash>,
+}
+
+impl TrieChangesTracker {
+    fn new(final_height: BlockHeight) -> Self {
+        Self { from_height: final_height, seen_blocks: HashSet::new() }
+    }
+
+    fn record_new_blocks(
+        &mut self,
+        store: &Store,
+        shard_uid: ShardUId,
+        live: &mut LiveNodeFilter,
+    ) -> Result<(), StateGarbageAuditError> {
+        // Blocks can still be added at heights above the final head, so those
+        // heights are checked again on the next call.
+        let final_height = get_tip(store, FINAL_HEAD_KEY)?.height;
+        let head_height = get_tip(store, HEAD_KEY)?.height;
+        for height in self.from_height..=head_height {
+            let Some(blocks) = store.get_ser::<HashMap<EpochId, HashSet<CryptoHash>>>(
+                DBCol::BlockPerHeight,
+                &index_to_bytes(height),
+            )?
+            else {
+                continue;
+            };
+            for block_hash in blocks.into_values().flatten() {
+                if !self.seen_blocks.insert(block_hash) {
+                    continue;
+                }
+                let key = get_block_shard_uid(&block_hash, &shard_uid);
+                if let Some(value) = store.get(DBCol::TrieChanges, &key)? {
+                    record_trie_changes(live, &value)?;
+                }
+            }
+        }
+        self.from_height = self.from_height.max(final_height);
+        Ok(())
+    }
+}
+
+/// Bloom filter of trie node and value hashes.
+struct LiveNodeFilter {
+    bits: Vec<u64>,
+    num_hashes: u32,
+}
+
+impl LiveNodeFilter {
+    fn new(expected_items: u64, bits_per_item: u32) -> Self {
+        let num_bits = (expected_items.max(1) * u64::from(bits_per_item.max(1))).max(64);
+        // The optimal number of hash functions is bits per item * ln(2).
+        let num_hashes =
+            ((f64::from(bits_per_item) * std::f64::consts::LN_2).round() as u32).max(1);
+        Self { bits: vec![0; ((num_bits + 63) / 64) as usize], num_hashes }
+    }
+
+    /// Hashes are uniformly distributed already, so the bit positions are
+    /// derived from them directly with double hashing.
+    fn positions<'a>(&'a self, hash: &CryptoHash) -> impl Iterator<Item = usize> + 'a {
+        let bytes = hash.as_bytes();
+        let h1 = u64::from_le_bytes(bytes[0..8].try_into().unwrap());
+        let h2 = u64::from_le_bytes(bytes[8..16].try_into().unwrap()) | 1;
+        let num_bits = self.bits.len() as u64 * 64;
+        (0..u64::from(self.num_hashes))
+            .map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % num_bits) as usize)
+    }
+
+    fn insert(&mut self, hash: &CryptoHash) {
+        let positions: Vec<_> = self.positions(hash).collect();
+        for pos in positions {
+            self.bits[pos / 64] |= 1 << (pos % 64);
+        }
+    }
+
+    fn contains(&self, hash: &CryptoHash) -> bool {
+        self.positions(hash).all(|pos| self.bits[pos / 64] & (1 << (pos % 64)) != 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicBool;
+
+    use near_primitives::block::Tip;
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::shard_layout::{get_block_shard_uid, ShardUId};
+    use near_primitives::types::{EpochId, StateRoot};
+
+    use super::{audit_state_garbage, AuditMode, StateGarbageAuditReport};
+    use crate::config::StateGarbageAuditorConfig;
+    use crate::test_utils::{create_test_store, test_populate_trie, TestTriesBuilder};
+    use crate::trie::TrieChanges;
+    use crate::{DBCol, ShardTries, Store, FINAL_HEAD_KEY, HEAD_KEY};
+
+    fn set_head(store: &Store) {
+        let tip = Tip {
+            height: 1,
+            last_block_hash: CryptoHash::hash_bytes(b"head"),
+            prev_block_hash: CryptoHash::default(),
+            epoch_id: EpochId::default(),
+            next_epoch_id: EpochId::default(),
+        };
+        let mut store_update = store.store_update();
+        store_update.set_ser(DBCol::BlockMisc, HEAD_KEY, &tip).unwrap();
+        store_update.set_ser(DBCol::BlockMisc, FINAL_HEAD_KEY, &tip).unwrap();
+        store_update.commit().unwrap();
+    }
+
+    /// Populates a trie and then overwrites one of its values without
+    /// decrementing the refcounts of the replaced nodes, the way a refcounting
+    /// bug would.  Returns the new root and the changes which replaced them.
+    fn populate_with_garbage(tries: &ShardTries, shard_uid: ShardUId) -> (StateRoot, TrieChanges) {
+        let changes = (0..16u8).map(|i| (vec![i, 1, 2], Some(vec![i; 40]))).collect();
+        let root = test_populate_trie(tries, &StateRoot::default(), shard_uid, changes);
+        let trie = tries.get_trie_for_shard(shard_uid, root);
+        let trie_changes = trie.update(vec![(vec![3, 1, 2], Some(vec![42; 40]))]).unwrap();
+        let mut store_update = tries.store_update();
+        tries.apply_insertions(&trie_changes, shard_uid, &mut store_update);
+        store_update.commit().unwrap();
+        (trie_changes.new_root, trie_changes)
+    }
+
+    fn audit(
+        store: &Store,
+        shard_uid: ShardUId,
+        root: StateRoot,
+        mode: AuditMode,
+    ) -> StateGarbageAuditReport {
+        let config = StateGarbageAuditorConfig {
+            enabled: true,
+            batch_size: 2,
+            batch_delay: std::time::Duration::ZERO,
+            // Makes false positives practically impossible in these tiny tries.
+            filter_bits_per_entry: 64,
+            ..Default::default()
+        };
+        let keep_going = AtomicBool::new(true);
+        audit_state_garbage(store, shard_uid, root, &config, mode, &keep_going).unwrap()
+    }
+
+    #[test]
+    fn test_removes_unreachable_entries() {
+        let store = create_test_store();
+        set_head(&store);
+        let tries = TestTriesBuilder::new().with_store(store.clone()).build();
+        let shard_uid = ShardUId::single_shard();
+        let (root, _) = populate_with_garbage(&tries, shard_uid);
+
+        let dry_run = audit(&store, shard_uid, root, AuditMode::DryRun);
+        assert!(dry_run.unreachable_entries > 0);
+        assert_eq!(dry_run.total_entries, dry_run.live_nodes + dry_run.unreachable_entries);
+
+        let removed = audit(&store, shard_uid, root, AuditMode::Remove);
+        assert_eq!(removed, dry_run);
+
+        // Every node reachable from the root is still there, otherwise the
+        // audit would fail with `MissingNode`.
+        let after = audit(&store, shard_uid, root, AuditMode::DryRun);
+        assert_eq!(after.unreachable_entries, 0);
+        assert_eq!(after.total_entries, dry_run.live_nodes);
+    }
+
+    #[test]
+    fn test_keeps_entries_of_trie_changes() {
+        let store = create_test_store();
+        set_head(&store);
+        let tries = TestTriesBuilder::new().with_store(store.clone()).build();
+        let shard_uid = ShardUId::single_shard();
+        let (root, trie_changes) = populate_with_garbage(&tries, shard_uid);
+
+        // Garbage collection is yet to decrement the deletions of a block
+        // which isn't collected, so those entries must be left alone.
+        let mut store_update = store.store_update();
+        let key = get_block_shard_uid(&CryptoHash::hash_bytes(b"head"), &shard_uid);
+        store_update.set_ser(DBCol::TrieChanges, &key, &trie_changes).unwrap();
+        store_update.commit().unwrap();
+
+        let report = audit(&store, shard_uid, root, AuditMode::Remove);
+        assert_eq!(report.unreachable_entries, 0);
+        assert!(report.total_entries > report.live_nodes);
+    }
+}
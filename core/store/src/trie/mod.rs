@@ -434,6 +434,10 @@ impl TrieRefcountAddition {
 }
 
 impl TrieRefcountSubtraction {
+    pub fn hash(&self) -> &CryptoHash {
+        &self.trie_node_or_value_hash
+    }
+
     pub fn new(trie_node_or_value_hash: CryptoHash, rc: std::num::NonZeroU32) -> Self {
         Self { trie_node_or_value_hash, _ignored: Default::default(), rc }
     }
@@ -476,6 +476,10 @@ impl WrappedTrieChanges {
         &self.state_changes
     }
 
+    pub fn trie_changes(&self) -> &TrieChanges {
+        &self.trie_changes
+    }
+
     pub fn apply_mem_changes(&self) {
         self.tries.apply_memtrie_changes(&self.trie_changes, self.shard_uid, self.block_height);
     }
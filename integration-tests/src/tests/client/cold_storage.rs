@@ -1,16 +1,22 @@
 use borsh::BorshDeserialize;
+use near_actix_test_utils::run_actix;
 use near_chain::{ChainGenesis, Provenance};
 use near_chain_configs::Genesis;
 use near_client::test_utils::TestEnv;
-use near_client::ProcessTxResponse;
+use near_client::{start_view_client, ProcessTxResponse, Query};
 use near_crypto::{InMemorySigner, KeyType};
+use near_epoch_manager::shard_tracker::{ShardTracker, TrackedConfig};
 use near_epoch_manager::EpochManager;
+use near_network::test_utils::MockPeerManagerAdapter;
 use near_o11y::testonly::init_test_logger;
+use near_o11y::WithSpanContextExt;
 use near_primitives::block::Tip;
 use near_primitives::sharding::{PartialEncodedChunk, ShardChunk};
 use near_primitives::transaction::{
     Action, DeployContractAction, FunctionCallAction, SignedTransaction,
 };
+use near_primitives::types::{BlockId, BlockReference};
+use near_primitives::views::{QueryRequest, QueryResponseKind};
 use near_primitives_core::types::AccountId;
 use near_store::cold_storage::{
     copy_all_data_to_cold, test_cold_genesis_update, test_get_store_initial_writes,
@@ -22,9 +28,10 @@ use near_store::test_utils::create_test_node_storage_with_cold;
 use near_store::{DBCol, Store, COLD_HEAD_KEY, HEAD_KEY};
 use nearcore::config::GenesisExt;
 use nearcore::test_utils::TestEnvNightshadeSetupExt;
-use nearcore::{cold_storage::spawn_cold_store_loop, NearConfig};
+use nearcore::{cold_storage::spawn_cold_store_loop, NearConfig, NightshadeRuntime};
 use std::collections::HashSet;
 use std::str::FromStr;
+use std::sync::Arc;
 use strum::IntoEnumIterator;
 
 fn check_key(first_store: &Store, second_store: &Store, col: DBCol, key: &[u8]) {
@@ -585,3 +592,90 @@ fn test_cold_loop_on_gc_boundary() {
         end_cold_head
     );
 }
+
+/// Checks that the view client of a split storage archival node serves state
+/// queries for blocks which have been garbage collected from the hot storage
+/// by falling back to the cold storage.
+/// Test flow:
+/// - Produce a few blocks and copy them to the cold storage.
+/// - Produce enough blocks for the hot storage to garbage collect them.
+/// - Query an account at height 1 through a view client running on the hot
+///   storage only, which fails, and through one running on the split storage,
+///   which succeeds.
+#[test]
+fn test_split_storage_view_client_query() {
+    init_test_logger();
+
+    let epoch_length = 5;
+
+    let mut genesis = Genesis::test(vec!["test0".parse().unwrap(), "test1".parse().unwrap()], 1);
+    genesis.config.epoch_length = epoch_length;
+    let mut chain_genesis = ChainGenesis::test();
+    chain_genesis.epoch_length = epoch_length;
+
+    let (storage, ..) = create_test_node_storage_with_cold(DB_VERSION, DbKind::Hot);
+    let hot_store = storage.get_hot_store();
+    let mut env = TestEnv::builder(chain_genesis.clone())
+        .archive(true)
+        .save_trie_changes(true)
+        .stores(vec![hot_store.clone()])
+        .real_epoch_managers(&genesis.config)
+        .nightshade_runtimes(&genesis)
+        .build();
+
+    let gc_height = env.clients[0].config.gc.gc_num_epochs_to_keep * epoch_length * 2;
+    for height in 1..gc_height {
+        env.produce_block(0, height);
+    }
+    let keep_going = Arc::new(std::sync::atomic::AtomicBool::new(true));
+    copy_all_data_to_cold((*storage.cold_db().unwrap()).clone(), &hot_store, 1000000, &keep_going)
+        .unwrap();
+    update_cold_head(&*storage.cold_db().unwrap(), &hot_store, &(gc_height - 1)).unwrap();
+    for height in gc_height..gc_height * 2 {
+        env.produce_block(0, height);
+    }
+    assert!(env.clients[0].chain.tail().unwrap() > 1);
+
+    let client_config = env.clients[0].config.clone();
+    let split_store = storage.get_split_store().unwrap();
+    let home_dir = tempfile::tempdir().unwrap();
+    run_actix(async move {
+        let view_client = |store: Store| {
+            let epoch_manager = EpochManager::new_arc_handle(store.clone(), &genesis.config);
+            let shard_tracker = ShardTracker::new(TrackedConfig::AllShards, epoch_manager.clone());
+            let runtime = NightshadeRuntime::test(
+                home_dir.path(),
+                store,
+                &genesis.config,
+                epoch_manager.clone(),
+            );
+            start_view_client(
+                None,
+                chain_genesis.clone(),
+                epoch_manager,
+                shard_tracker,
+                runtime,
+                Arc::new(MockPeerManagerAdapter::default()).into(),
+                client_config.clone(),
+                Default::default(),
+            )
+        };
+        let query = || {
+            Query::new(
+                BlockReference::BlockId(BlockId::Height(1)),
+                QueryRequest::ViewAccount { account_id: "test0".parse().unwrap() },
+            )
+            .with_span_context()
+        };
+
+        let hot_view_client = view_client(hot_store);
+        assert!(hot_view_client.send(query()).await.unwrap().is_err());
+
+        let split_view_client = view_client(split_store);
+        let response = split_view_client.send(query()).await.unwrap().unwrap();
+        assert_eq!(response.block_height, 1);
+        assert!(matches!(response.kind, QueryResponseKind::ViewAccount(_)));
+
+        actix::System::current().stop();
+    });
+}
@@ -10,7 +10,7 @@ use near_primitives::hash::CryptoHash;
 use near_primitives::runtime::config_store::RuntimeConfigStore;
 use near_primitives::test_utils::encode;
 use near_primitives::transaction::{
-    Action, ExecutionMetadata, FunctionCallAction, SignedTransaction,
+    Action, ExecutionMetadata, ExecutionMetadataV4, FunctionCallAction, SignedTransaction,
 };
 use near_primitives::types::{BlockHeightDelta, Gas, TrieNodesCount};
 use near_primitives::version::{ProtocolFeature, ProtocolVersion};
@@ -130,18 +130,21 @@ fn compare_node_counts() {
             match metadata {
                 ExecutionMetadata::V1 => panic!("ExecutionMetadata cannot be empty"),
                 ExecutionMetadata::V2(_profile_data) => panic!("expected newest ExecutionMetadata"),
-                ExecutionMetadata::V3(profile_data) => TrieNodesCount {
-                    db_reads: {
-                        let cost = profile_data.get_ext_cost(ExtCosts::touching_trie_node);
-                        assert_eq!(cost % touching_trie_node_cost, 0);
-                        cost / touching_trie_node_cost
-                    },
-                    mem_reads: {
-                        let cost = profile_data.get_ext_cost(ExtCosts::read_cached_trie_node);
-                        assert_eq!(cost % read_cached_trie_node_cost, 0);
-                        cost / read_cached_trie_node_cost
-                    },
-                },
+                ExecutionMetadata::V3(_profile_data) => panic!("expected newest ExecutionMetadata"),
+                ExecutionMetadata::V4(ExecutionMetadataV4 { profile: profile_data, .. }) => {
+                    TrieNodesCount {
+                        db_reads: {
+                            let cost = profile_data.get_ext_cost(ExtCosts::touching_trie_node);
+                            assert_eq!(cost % touching_trie_node_cost, 0);
+                            cost / touching_trie_node_cost
+                        },
+                        mem_reads: {
+                            let cost = profile_data.get_ext_cost(ExtCosts::read_cached_trie_node);
+                            assert_eq!(cost % read_cached_trie_node_cost, 0);
+                            cost / read_cached_trie_node_cost
+                        },
+                    }
+                }
             }
         })
         .collect();
@@ -1,11 +1,15 @@
 use near_chain::{ChainGenesis, Provenance};
 use near_chain_configs::{Genesis, GenesisConfig, GenesisRecords};
 use near_client::test_utils::TestEnv;
+use near_client::ProcessTxResponse;
+use near_crypto::{InMemorySigner, KeyType};
 use near_o11y::testonly::init_test_logger;
 use near_primitives::block::Tip;
+use near_primitives::chunk_validation::ChunkStateWitness;
 use near_primitives::shard_layout::ShardLayout;
 use near_primitives::state_record::StateRecord;
 use near_primitives::test_utils::create_test_signer;
+use near_primitives::transaction::SignedTransaction;
 use near_primitives::types::AccountInfo;
 use near_primitives_core::account::Account;
 use near_primitives_core::checked_feature;
@@ -131,6 +135,75 @@ fn test_chunk_validation_basic() {
     assert!(!approvals.is_empty());
 }
 
+/// Transactions which would make the chunk state witness larger than
+/// `max_chunk_state_witness_size` are left out of the chunk and stay in the
+/// pool until a later chunk includes them.
+#[test]
+fn test_chunk_state_witness_size_limit() {
+    init_test_logger();
+
+    if !checked_feature!("stable", ChunkValidation, PROTOCOL_VERSION) {
+        println!("Test not applicable without ChunkValidation enabled");
+        return;
+    }
+
+    let genesis = Genesis::test(vec!["test0".parse().unwrap(), "test1".parse().unwrap()], 1);
+    let mut env = TestEnv::builder(ChainGenesis::new(&genesis))
+        .real_epoch_managers(&genesis.config)
+        .nightshade_runtimes(&genesis)
+        .build();
+    let genesis_block = env.clients[0].chain.get_block_by_height(0).unwrap();
+    let signer = InMemorySigner::from_seed("test0".parse().unwrap(), KeyType::ED25519, "test0");
+    let transactions: Vec<SignedTransaction> = (1..=5)
+        .map(|nonce| {
+            SignedTransaction::send_money(
+                nonce,
+                "test0".parse().unwrap(),
+                "test1".parse().unwrap(),
+                &signer,
+                1,
+                *genesis_block.hash(),
+            )
+        })
+        .collect();
+    for tx in &transactions {
+        assert_eq!(env.clients[0].process_tx(tx.clone(), false, false), ProcessTxResponse::ValidTx);
+    }
+
+    // Leave room in the witness for two and a half transactions.
+    let last_header = genesis_block.chunks()[0].clone();
+    let empty_witness = ChunkStateWitness {
+        state_root: last_header.prev_state_root(),
+        chunk_header: last_header,
+        main_state_transition: Default::default(),
+        implicit_transitions: vec![],
+        source_receipt_proofs: Default::default(),
+        new_transactions: vec![],
+    };
+    let tx_size = transactions[0].get_size();
+    env.clients[0].config.max_chunk_state_witness_size =
+        empty_witness.size().total as u64 + 2 * tx_size + tx_size / 2;
+
+    let mut num_included = vec![];
+    for height in 1..=10 {
+        env.produce_block(0, height);
+        let block = env.clients[0].chain.get_block_by_height(height).unwrap();
+        let chunks = block.chunks();
+        let chunk_header = &chunks[0];
+        if chunk_header.height_included() != height {
+            continue;
+        }
+        let chunk = env.clients[0].chain.get_chunk(&chunk_header.chunk_hash()).unwrap();
+        if chunk.transactions().is_empty() {
+            continue;
+        }
+        num_included.push(chunk.transactions().len());
+        let num_in_pool = env.clients[0].sharded_tx_pool.transactions().count();
+        assert_eq!(num_in_pool, transactions.len() - num_included.iter().sum::<usize>());
+    }
+    assert_eq!(num_included, vec![2, 2, 1]);
+}
+
 // Returns the block producer for the height of head + height_offset.
 fn get_block_producer(env: &TestEnv, head: &Tip, height_offset: u64) -> AccountId {
     let client = &env.clients[0];
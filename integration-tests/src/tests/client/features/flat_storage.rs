@@ -131,8 +131,8 @@ fn test_flat_storage_upgrade() {
                 .outcome_with_id
                 .outcome
                 .metadata;
-            if let ExecutionMetadata::V3(profile_data) = metadata {
-                profile_data.get_ext_cost(ExtCosts::touching_trie_node)
+            if let ExecutionMetadata::V4(metadata) = metadata {
+                metadata.profile.get_ext_cost(ExtCosts::touching_trie_node)
             } else {
                 panic!("Too old version of metadata: {metadata:?}");
             }
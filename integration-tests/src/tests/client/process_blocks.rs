@@ -989,6 +989,7 @@ fn client_sync_headers() {
                     connection_established_time: near_async::time::Instant::now(),
                     peer_type: PeerType::Outbound,
                     nonce: 1,
+                    reputation: 0,
                 }],
                 num_connected_peers: 1,
                 peer_max_count: 1,
@@ -1059,6 +1060,48 @@ fn test_process_invalid_tx() {
     );
 }
 
+/// Transactions saved from the pool are restored after the node restarts on the same database.
+#[test]
+fn test_transaction_pool_persistence() {
+    init_test_logger();
+    let genesis = Genesis::test(vec!["test0".parse().unwrap(), "test1".parse().unwrap()], 1);
+    let chain_genesis = ChainGenesis::new(&genesis);
+    let mut env = TestEnv::builder(chain_genesis.clone())
+        .real_epoch_managers(&genesis.config)
+        .nightshade_runtimes(&genesis)
+        .build();
+    let genesis_hash = *env.clients[0].chain.genesis().hash();
+    let signer = InMemorySigner::from_seed("test0".parse().unwrap(), KeyType::ED25519, "test0");
+    let transactions: Vec<SignedTransaction> = (1..=3)
+        .map(|nonce| {
+            SignedTransaction::send_money(
+                nonce,
+                "test0".parse().unwrap(),
+                "test1".parse().unwrap(),
+                &signer,
+                1,
+                genesis_hash,
+            )
+        })
+        .collect();
+    for tx in &transactions {
+        assert_eq!(env.clients[0].process_tx(tx.clone(), false, false), ProcessTxResponse::ValidTx);
+    }
+    env.clients[0].save_transaction_pool().unwrap();
+
+    let mut env = TestEnv::builder(chain_genesis)
+        .stores(vec![env.clients[0].chain.store().store().clone()])
+        .real_epoch_managers(&genesis.config)
+        .nightshade_runtimes(&genesis)
+        .build();
+    assert_eq!(env.clients[0].sharded_tx_pool.transactions().count(), 0);
+    env.clients[0].restore_transaction_pool().unwrap();
+    let mut restored: Vec<SignedTransaction> =
+        env.clients[0].sharded_tx_pool.transactions().map(|(_, tx)| tx.clone()).collect();
+    restored.sort_by_key(|tx| tx.transaction.nonce);
+    assert_eq!(restored, transactions);
+}
+
 /// If someone produce a block with Utc::now() + 1 min, we should produce a block with valid timestamp
 #[test]
 fn test_time_attack() {
@@ -2813,7 +2856,29 @@ fn test_execution_metadata() {
             .map(|it| it.gas_used)
             .sum::<u64>();
 
-    assert_eq!(expected_receipt_cost, actual_receipt_cost)
+    assert_eq!(expected_receipt_cost, actual_receipt_cost);
+
+    // The function call is the only action, it burnt everything but the receipt creation cost.
+    assert_eq!(metadata.version, 4);
+    let action_gas_usage = metadata.action_gas_usage.as_ref().unwrap();
+    assert_eq!(action_gas_usage.len(), 1);
+    assert_eq!(
+        action_gas_usage[0].gas_burnt + config.fees.fee(ActionCosts::new_action_receipt).execution,
+        outcome.gas_burnt
+    );
+
+    // Unused prepaid gas is refunded in a separate receipt.
+    let refund = metadata.refund.as_ref().unwrap();
+    assert!(!refund.is_refund);
+    assert!(refund.gas_refund > 0);
+    assert_eq!(refund.deposit_refund, 0);
+    assert_eq!(refund.refund_receipt_ids.len(), 1);
+    let refund_outcome = execution_outcome
+        .receipts_outcome
+        .iter()
+        .find(|outcome| outcome.id == refund.refund_receipt_ids[0])
+        .unwrap();
+    assert!(refund_outcome.outcome.metadata.refund.as_ref().unwrap().is_refund);
 }
 
 #[test]
@@ -502,6 +502,7 @@ fn sync_state_dump() {
                                         },
                                         num_concurrent_requests: 1,
                                         num_concurrent_requests_during_catchup: 1,
+                                        credentials_file: None,
                                     });
 
                                 let nearcore::NearNode {
@@ -3,6 +3,11 @@ use std::{collections::HashMap, io, sync::Arc};
 use borsh::BorshDeserialize;
 
 use crate::runtime_utils::{get_runtime_and_trie, get_test_trie_viewer, TEST_SHARD_UID};
+use near_crypto::{InMemorySigner, KeyType, Signature};
+use near_primitives::errors::InvalidTxError;
+use near_primitives::runtime::config::RuntimeConfig;
+use near_primitives::shard_layout::ShardLayout;
+use near_primitives::transaction::{ExecutionStatus, SignedTransaction};
 use near_primitives::{
     account::Account,
     hash::hash as sha256,
@@ -21,7 +26,8 @@ use near_primitives::{
 use near_store::{set_account, NibbleSlice, RawTrieNode, RawTrieNodeWithSize};
 use node_runtime::state_viewer::errors;
 use node_runtime::state_viewer::*;
-use testlib::runtime_utils::alice_account;
+use std::collections::BTreeMap;
+use testlib::runtime_utils::{alice_account, bob_account};
 
 struct ProofVerifier {
     nodes: HashMap<CryptoHash, RawTrieNodeWithSize>,
@@ -411,3 +417,79 @@ fn test_log_when_panic() {
 
     assert_eq!(logs, vec!["hello".to_string()]);
 }
+
+fn simulate_transaction(
+    transaction: &SignedTransaction,
+    verify_signature: bool,
+) -> Result<TransactionSimulation, errors::SimulateTransactionError> {
+    let (_, tries, root) = get_runtime_and_trie();
+    let viewer = TrieViewer::default();
+    let view_state = ViewApplyState {
+        block_height: 1,
+        prev_block_hash: CryptoHash::default(),
+        block_hash: CryptoHash::default(),
+        epoch_id: EpochId::default(),
+        epoch_height: 0,
+        block_timestamp: 1,
+        current_protocol_version: PROTOCOL_VERSION,
+        cache: None,
+    };
+    let state_updates = BTreeMap::from([(0, tries.new_trie_update_view(TEST_SHARD_UID, root))]);
+    viewer.simulate_transaction(
+        state_updates,
+        &ShardLayout::v0(1, 1),
+        view_state,
+        Arc::new(RuntimeConfig::test()),
+        100,
+        transaction,
+        verify_signature,
+        &MockEpochInfoProvider::default(),
+    )
+}
+
+fn send_money_transaction() -> SignedTransaction {
+    let signer =
+        InMemorySigner::from_seed(alice_account(), KeyType::ED25519, alice_account().as_ref());
+    SignedTransaction::send_money(
+        1,
+        alice_account(),
+        bob_account(),
+        &signer,
+        1_000,
+        CryptoHash::default(),
+    )
+}
+
+#[test]
+fn test_simulate_transaction() {
+    let transaction = send_money_transaction();
+    let simulation = simulate_transaction(&transaction, true).unwrap();
+
+    assert_eq!(simulation.transaction_outcome.id, transaction.get_hash());
+    // The transfer itself comes first, possibly followed by a gas refund.
+    assert_eq!(simulation.receipts[0].receiver_id, bob_account());
+    assert_eq!(simulation.receipt_outcomes.len(), simulation.receipts.len());
+    assert!(matches!(
+        simulation.receipt_outcomes[0].outcome.status,
+        ExecutionStatus::SuccessValue(_)
+    ));
+    for account_id in [alice_account(), bob_account()] {
+        let key = TrieKey::Account { account_id };
+        assert!(simulation.state_changes.iter().any(|change| change.trie_key == key));
+    }
+}
+
+#[test]
+fn test_simulate_transaction_without_signature() {
+    let mut transaction = send_money_transaction();
+    transaction.signature = Signature::empty(KeyType::ED25519);
+
+    let err = simulate_transaction(&transaction, true).err().unwrap();
+    assert!(matches!(
+        err,
+        errors::SimulateTransactionError::InvalidTransaction {
+            error: InvalidTxError::InvalidSignature
+        }
+    ));
+    simulate_transaction(&transaction, false).unwrap();
+}
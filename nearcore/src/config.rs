@@ -5,14 +5,14 @@ use near_chain_configs::{
     default_enable_multiline_logging, default_epoch_sync_enabled,
     default_header_sync_expected_height_per_second, default_header_sync_initial_timeout,
     default_header_sync_progress_timeout, default_header_sync_stall_ban_timeout,
-    default_log_summary_period, default_produce_chunk_add_transactions_time_limit,
-    default_state_sync, default_state_sync_enabled, default_state_sync_timeout,
-    default_sync_check_period, default_sync_height_threshold, default_sync_step_period,
-    default_transaction_pool_size_limit, default_trie_viewer_state_size_limit,
-    default_tx_routing_height_horizon, default_view_client_threads,
-    default_view_client_throttle_period, get_initial_supply, ClientConfig, GCConfig, Genesis,
-    GenesisConfig, GenesisValidationMode, LogSummaryStyle, MutableConfigValue, StateSplitConfig,
-    StateSyncConfig,
+    default_log_summary_period, default_max_chunk_state_witness_size,
+    default_produce_chunk_add_transactions_time_limit, default_state_sync,
+    default_state_sync_enabled, default_state_sync_timeout, default_sync_check_period,
+    default_sync_height_threshold, default_sync_step_period, default_transaction_pool_size_limit,
+    default_trie_viewer_state_size_limit, default_tx_routing_height_horizon,
+    default_view_client_threads, default_view_client_throttle_period, get_initial_supply,
+    ClientConfig, GCConfig, Genesis, GenesisConfig, GenesisValidationMode, LogSummaryStyle,
+    MutableConfigValue, StateSplitConfig, StateSyncConfig, TransactionPoolOrdering,
 };
 use near_config_utils::{ValidationError, ValidationErrors};
 use near_crypto::{InMemorySigner, KeyFile, KeyType, PublicKey, Signer};
@@ -306,6 +306,20 @@ pub struct Config {
     /// Setting this value too low (<1MB) on the validator might lead to production of smaller
     /// chunks and underutilizing the capacity of the network.
     pub transaction_pool_size_limit: Option<u64>,
+    /// Limit of the number of transactions of a single signer account in a per-shard
+    /// transaction pool. If not set, the number will be unbounded.
+    /// Transactions of a signer over the limit are rejected, so that a single account can't
+    /// fill the pool.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction_pool_signer_count_limit: Option<u64>,
+    /// Limit of the size of transactions of a single signer account in a per-shard transaction
+    /// pool measured in bytes. If not set, the size will be unbounded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction_pool_signer_size_limit: Option<u64>,
+    /// Order in which transactions are taken from the transaction pool when producing chunks.
+    /// When the pool is full, groups of transactions of the lowest priority and then the oldest
+    /// ones are evicted to make space for new transactions.
+    pub transaction_pool_ordering: TransactionPoolOrdering,
     // Configuration for resharding.
     pub state_split_config: StateSplitConfig,
     /// If the node is not a chunk producer within that many blocks, then route
@@ -316,6 +330,17 @@ pub struct Config {
     /// some limit is reached. This time limit ensures that adding transactions won't take
     /// longer than the specified duration, which helps to produce the chunk quickly.
     pub produce_chunk_add_transactions_time_limit: Option<Duration>,
+    /// Limit of the uncompressed size of the state witness of a produced chunk in bytes.
+    /// Once the witness reaches it, no more transactions are added to the chunk. Chunk
+    /// validators reject witnesses larger than 64 MiB, so the limit can't exceed that.
+    pub max_chunk_state_witness_size: u64,
+    /// Save chunk state witnesses which fail validation to the database for debugging.
+    #[serde(skip_serializing_if = "is_false")]
+    pub save_invalid_witnesses: bool,
+    /// Save chunk state witnesses validated at the last few heights to the database for
+    /// debugging.
+    #[serde(skip_serializing_if = "is_false")]
+    pub save_latest_witnesses: bool,
 }
 
 fn is_false(value: &bool) -> bool {
@@ -355,11 +380,17 @@ impl Default for Config {
             state_sync: default_state_sync(),
             state_sync_enabled: default_state_sync_enabled(),
             transaction_pool_size_limit: default_transaction_pool_size_limit(),
+            transaction_pool_signer_count_limit: None,
+            transaction_pool_signer_size_limit: None,
+            transaction_pool_ordering: TransactionPoolOrdering::default(),
             enable_multiline_logging: default_enable_multiline_logging(),
             state_split_config: StateSplitConfig::default(),
             tx_routing_height_horizon: default_tx_routing_height_horizon(),
             produce_chunk_add_transactions_time_limit:
                 default_produce_chunk_add_transactions_time_limit(),
+            max_chunk_state_witness_size: default_max_chunk_state_witness_size(),
+            save_invalid_witnesses: false,
+            save_latest_witnesses: false,
         }
     }
 }
@@ -382,6 +413,11 @@ fn default_cold_store_loop_sleep_duration() -> Duration {
 
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
 pub struct SplitStorageConfig {
+    /// If true, the view client of an archival node reads from both hot and
+    /// cold storage, falling back to cold for data which has already been
+    /// garbage collected from hot.  This allows state queries for any block
+    /// since genesis.  If false, queries are only served for blocks which are
+    /// still in the hot storage.
     #[serde(default = "default_enable_split_storage_view_client")]
     pub enable_split_storage_view_client: bool,
 
@@ -655,6 +691,9 @@ impl NearConfig {
                 state_sync_enabled: config.state_sync_enabled,
                 state_sync: config.state_sync.unwrap_or_default(),
                 transaction_pool_size_limit: config.transaction_pool_size_limit,
+                transaction_pool_signer_count_limit: config.transaction_pool_signer_count_limit,
+                transaction_pool_signer_size_limit: config.transaction_pool_signer_size_limit,
+                transaction_pool_ordering: config.transaction_pool_ordering,
                 enable_multiline_logging: config.enable_multiline_logging.unwrap_or(true),
                 state_split_config: MutableConfigValue::new(
                     config.state_split_config,
@@ -665,6 +704,9 @@ impl NearConfig {
                     config.produce_chunk_add_transactions_time_limit,
                     "produce_chunk_add_transactions_time_limit",
                 ),
+                max_chunk_state_witness_size: config.max_chunk_state_witness_size,
+                save_invalid_witnesses: config.save_invalid_witnesses,
+                save_latest_witnesses: config.save_latest_witnesses,
             },
             network_config: NetworkConfig::new(
                 config.network,
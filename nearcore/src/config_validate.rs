@@ -1,5 +1,6 @@
 use near_chain_configs::{ExternalStorageLocation, SyncConfig};
 use near_config_utils::{ValidationError, ValidationErrors};
+use near_primitives::chunk_validation::MAX_CHUNK_STATE_WITNESS_SIZE;
 use std::collections::HashSet;
 use std::path::Path;
 
@@ -43,6 +44,13 @@ impl<'a> ConfigValidator<'a> {
             self.validation_errors.push_config_semantics_error(error_message);
         }
 
+        let state_garbage_auditor = &self.config.store.state_garbage_auditor;
+        if state_garbage_auditor.enabled && state_garbage_auditor.batch_size == 0 {
+            let error_message =
+                "store.state_garbage_auditor.batch_size should not be 0".to_string();
+            self.validation_errors.push_config_semantics_error(error_message);
+        }
+
         if self.config.consensus.min_block_production_delay
             > self.config.consensus.max_block_production_delay
         {
@@ -71,6 +79,14 @@ impl<'a> ConfigValidator<'a> {
             self.validation_errors.push_config_semantics_error(error_message);
         }
 
+        if self.config.max_chunk_state_witness_size > MAX_CHUNK_STATE_WITNESS_SIZE as u64 {
+            let error_message = format!(
+                "max_chunk_state_witness_size: {} is greater than the {} bytes accepted by chunk validators",
+                self.config.max_chunk_state_witness_size, MAX_CHUNK_STATE_WITNESS_SIZE
+            );
+            self.validation_errors.push_config_semantics_error(error_message);
+        }
+
         if self.config.gc.gc_blocks_limit == 0
             || self.config.gc.gc_fork_clean_step == 0
             || self.config.gc.gc_num_epochs_to_keep == 0
@@ -108,6 +124,16 @@ impl<'a> ConfigValidator<'a> {
                             self.validation_errors.push_config_semantics_error(error_message);
                         }
                     }
+                    ExternalStorageLocation::Http { .. } => {
+                        let error_message = format!("'config.state_sync.dump.location.Http' is not supported, HTTP mirrors are read-only.");
+                        self.validation_errors.push_config_semantics_error(error_message);
+                    }
+                    ExternalStorageLocation::AzureBlob { endpoint, container } => {
+                        if endpoint.is_empty() || container.is_empty() {
+                            let error_message = format!("'config.state_sync.dump.location.AzureBlob.endpoint' and 'config.state_sync.dump.location.AzureBlob.container' need to be specified when 'config.state_sync.dump.location.AzureBlob' is present.");
+                            self.validation_errors.push_config_semantics_error(error_message);
+                        }
+                    }
                 }
 
                 if let Some(credentials_file) = &dump_config.credentials_file {
@@ -139,11 +165,55 @@ impl<'a> ConfigValidator<'a> {
                                 self.validation_errors.push_config_semantics_error(error_message);
                             }
                         }
+                        ExternalStorageLocation::Http { base_url, .. } => {
+                            if !base_url.starts_with("http://") && !base_url.starts_with("https://")
+                            {
+                                let error_message = format!("'config.state_sync.sync.ExternalStorage.location.Http.base_url' needs to be an http:// or https:// URL when 'config.state_sync.sync.ExternalStorage.location.Http' is present.");
+                                self.validation_errors.push_config_semantics_error(error_message);
+                            }
+                        }
+                        ExternalStorageLocation::AzureBlob { endpoint, container } => {
+                            if endpoint.is_empty() || container.is_empty() {
+                                let error_message = format!("'config.state_sync.sync.ExternalStorage.location.AzureBlob.endpoint' and 'config.state_sync.sync.ExternalStorage.location.AzureBlob.container' need to be specified when 'config.state_sync.sync.ExternalStorage.location.AzureBlob' is present.");
+                                self.validation_errors.push_config_semantics_error(error_message);
+                            }
+                        }
                     }
                     if config.num_concurrent_requests == 0 {
                         let error_message = format!("'config.state_sync.sync.ExternalStorage.num_concurrent_requests' needs to be greater than 0");
                         self.validation_errors.push_config_semantics_error(error_message);
                     }
+                    if let Some(credentials_file) = &config.credentials_file {
+                        if !credentials_file.exists() || !credentials_file.is_file() {
+                            let error_message = format!("'config.state_sync.sync.ExternalStorage.credentials_file' is provided but the specified file does not exist or is not a file.");
+                            self.validation_errors.push_config_semantics_error(error_message);
+                        }
+                    }
+                }
+            }
+        }
+
+        #[cfg(feature = "json_rpc")]
+        if let Some(rpc) = &self.config.rpc {
+            let limits_config = &rpc.limits_config;
+            if limits_config.max_batch_size == 0 {
+                let error_message =
+                    "'config.rpc.limits_config.max_batch_size' needs to be greater than 0"
+                        .to_string();
+                self.validation_errors.push_config_semantics_error(error_message);
+            }
+            let rate_limits = &limits_config.rate_limits;
+            let limits =
+                rate_limits.default.iter().map(|limit| ("default".to_string(), limit)).chain(
+                    rate_limits
+                        .per_method
+                        .iter()
+                        .map(|(method, limit)| (format!("per_method.{method}"), limit)),
+                );
+            for (name, limit) in limits {
+                if limit.burst == 0 || limit.qps <= 0. {
+                    let error_message = format!("'config.rpc.limits_config.rate_limits.{name}' needs to have burst and qps greater than 0, got burst {} and qps {}.", limit.burst, limit.qps);
+                    self.validation_errors.push_config_semantics_error(error_message);
                 }
             }
         }
@@ -183,6 +253,14 @@ mod test {
         validate_config(&config).unwrap();
     }
 
+    #[test]
+    #[should_panic(expected = "max_chunk_state_witness_size: 134217728 is greater than")]
+    fn test_max_chunk_state_witness_size_over_limit() {
+        let mut config = Config::default();
+        config.max_chunk_state_witness_size = 128 * 1024 * 1024;
+        validate_config(&config).unwrap();
+    }
+
     #[test]
     #[should_panic(
         expected = "Configuration with archive = false and save_trie_changes = false is not supported"
@@ -250,4 +328,22 @@ mod test {
         config.tx_routing_height_horizon = 1_000_000_000;
         validate_config(&config).unwrap();
     }
+
+    #[test]
+    #[cfg(feature = "json_rpc")]
+    #[should_panic(
+        expected = "\\nconfig.json semantic issue: 'config.rpc.limits_config.rate_limits.per_method.query' needs to have burst and qps greater than 0, got burst 0 and qps 1."
+    )]
+    fn test_rpc_rate_limit_zero_burst() {
+        let mut config = Config::default();
+        config
+            .rpc
+            .as_mut()
+            .unwrap()
+            .limits_config
+            .rate_limits
+            .per_method
+            .insert("query".to_string(), near_jsonrpc::RpcRateLimit { burst: 0, qps: 1. });
+        validate_config(&config).unwrap();
+    }
 }
@@ -4,6 +4,7 @@ use crate::metrics::spawn_trie_metrics_loop;
 pub use crate::runtime::NightshadeRuntime;
 
 use crate::cold_storage::spawn_cold_store_loop;
+use crate::state_garbage_auditor::{spawn_state_garbage_auditor, StateGarbageAuditorHandle};
 use crate::state_sync::{spawn_state_sync_dump, StateSyncDumpHandle};
 use actix::{Actor, Addr};
 use actix_rt::ArbiterHandle;
@@ -49,6 +50,7 @@ mod entity_debug_serializer;
 mod metrics;
 pub mod migrations;
 mod runtime;
+pub mod state_garbage_auditor;
 pub mod state_sync;
 pub mod test_utils;
 
@@ -211,6 +213,8 @@ pub struct NearNode {
     pub cold_store_loop_handle: Option<ColdStoreLoopHandle>,
     /// Contains handles to background threads that may be dumping state to S3.
     pub state_sync_dump_handle: Option<StateSyncDumpHandle>,
+    /// Only set if the state garbage auditor is enabled in the store config.
+    pub state_garbage_auditor_handle: Option<StateGarbageAuditorHandle>,
     /// A handle to control background flat state values inlining migration.
     /// Needed temporarily, will be removed after the migration is completed.
     pub flat_state_migration_handle: FlatStateValuesInliningMigrationHandle,
@@ -285,6 +289,12 @@ pub fn start_with_config_and_synchronization(
         };
 
     let cold_store_loop_handle = spawn_cold_store_loop(&config, &storage, epoch_manager.clone())?;
+    let state_garbage_auditor_handle = spawn_state_garbage_auditor(
+        &config,
+        &storage,
+        epoch_manager.clone(),
+        shard_tracker.clone(),
+    )?;
 
     let telemetry = TelemetryActor::new(config.telemetry_config.clone()).start();
     let chain_genesis = ChainGenesis::new(&config.genesis);
@@ -445,6 +455,7 @@ pub fn start_with_config_and_synchronization(
         arbiters,
         cold_store_loop_handle,
         state_sync_dump_handle,
+        state_garbage_auditor_handle,
         flat_state_migration_handle,
         state_split_handle,
     })
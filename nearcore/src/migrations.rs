@@ -115,6 +115,12 @@ impl<'a> near_store::StoreMigrator for Migrator<'a> {
             }
             36 => near_store::migrations::migrate_36_to_37(store),
             37 => near_store::migrations::migrate_37_to_38(store),
+            38 => {
+                // The TransactionPool, PeerReputations, InvalidChunkStateWitnesses and
+                // LatestChunkStateWitnesses columns are created empty when the database is opened.
+                tracing::info!(target: "migrations", "Migrating DB version from 38 to 39. Transaction pool and peer reputations will be persisted across restarts, chunk state witnesses can be saved for debugging.");
+                Ok(())
+            }
             DB_VERSION.. => unreachable!(),
         }
     }
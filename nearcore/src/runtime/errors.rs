@@ -1,4 +1,4 @@
-use near_chain::near_chain_primitives::error::QueryError;
+use near_chain::near_chain_primitives::error::{QueryError, SimulateTransactionError};
 
 #[easy_ext::ext(FromStateViewerErrors)]
 impl QueryError {
@@ -109,3 +109,25 @@ impl QueryError {
         Self::InternalError { error_message: error.to_string(), block_height, block_hash }
     }
 }
+
+#[easy_ext::ext(FromSimulateTransactionErrors)]
+impl SimulateTransactionError {
+    pub fn from_trie_viewer_error(
+        error: node_runtime::state_viewer::errors::SimulateTransactionError,
+    ) -> Self {
+        match error {
+            node_runtime::state_viewer::errors::SimulateTransactionError::InvalidTransaction {
+                error,
+            } => Self::InvalidTransaction { error },
+            node_runtime::state_viewer::errors::SimulateTransactionError::UnavailableShard {
+                shard_id,
+            } => Self::UnavailableShard { shard_id },
+            node_runtime::state_viewer::errors::SimulateTransactionError::TooManyReceipts {
+                limit,
+            } => Self::TooManyReceipts { limit },
+            node_runtime::state_viewer::errors::SimulateTransactionError::InternalError {
+                error_message,
+            } => Self::InternalError { error_message },
+        }
+    }
+}
@@ -3,7 +3,8 @@ use crate::migrations::load_migration_data;
 use crate::NearConfig;
 
 use borsh::BorshDeserialize;
-use errors::FromStateViewerErrors;
+use errors::{FromSimulateTransactionErrors, FromStateViewerErrors};
+use near_chain::near_chain_primitives::error::SimulateTransactionError;
 use near_chain::types::{
     ApplySplitStateResult, ApplyTransactionResult, ApplyTransactionsBlockContext,
     ApplyTransactionsChunkContext, RuntimeAdapter, RuntimeStorageConfig, StorageDataSource, Tip,
@@ -32,12 +33,13 @@ use near_primitives::transaction::SignedTransaction;
 use near_primitives::trie_key::TrieKey;
 use near_primitives::types::{
     AccountId, Balance, BlockHeight, EpochHeight, EpochId, EpochInfoProvider, Gas, MerkleHash,
-    ShardId, StateChangeCause, StateChangesForSplitStates, StateRoot, StateRootNode,
+    ShardId, StateChangeCause, StateChanges, StateChangesExt, StateChangesForSplitStates,
+    StateRoot, StateRootNode,
 };
 use near_primitives::version::ProtocolVersion;
 use near_primitives::views::{
-    AccessKeyInfoView, CallResult, QueryRequest, QueryResponse, QueryResponseKind, ViewApplyState,
-    ViewStateResult,
+    AccessKeyInfoView, CallResult, QueryRequest, QueryResponse, QueryResponseKind,
+    TransactionSimulationView, ViewApplyState, ViewStateResult,
 };
 use near_store::config::StateSnapshotType;
 use near_store::flat::FlatStorageManager;
@@ -1040,6 +1042,72 @@ impl RuntimeAdapter for NightshadeRuntime {
         }
     }
 
+    fn simulate_transaction(
+        &self,
+        state_roots: &HashMap<ShardUId, StateRoot>,
+        block_height: BlockHeight,
+        block_timestamp: u64,
+        prev_block_hash: &CryptoHash,
+        block_hash: &CryptoHash,
+        epoch_id: &EpochId,
+        gas_price: Balance,
+        transaction: &SignedTransaction,
+        verify_signature: bool,
+    ) -> Result<TransactionSimulationView, SimulateTransactionError> {
+        let internal_error = |err: &dyn std::fmt::Display| {
+            SimulateTransactionError::InternalError { error_message: err.to_string() }
+        };
+        let (epoch_height, current_protocol_version) = {
+            let epoch_manager = self.epoch_manager.read();
+            let epoch_info =
+                epoch_manager.get_epoch_info(epoch_id).map_err(|err| internal_error(&err))?;
+            (epoch_info.epoch_height(), epoch_info.protocol_version())
+        };
+        let shard_layout =
+            self.epoch_manager.get_shard_layout(epoch_id).map_err(|err| internal_error(&err))?;
+        let state_updates = state_roots
+            .iter()
+            .map(|(shard_uid, state_root)| {
+                let shard_id = ShardId::from(shard_uid.shard_id);
+                (shard_id, self.tries.new_trie_update_view(*shard_uid, *state_root))
+            })
+            .collect();
+        let view_state = ViewApplyState {
+            block_height,
+            prev_block_hash: *prev_block_hash,
+            block_hash: *block_hash,
+            epoch_id: epoch_id.clone(),
+            epoch_height,
+            block_timestamp,
+            current_protocol_version,
+            cache: Some(Box::new(StoreCompiledContractCache::new(&self.tries.get_store()))),
+        };
+        let simulation = self
+            .trie_viewer
+            .simulate_transaction(
+                state_updates,
+                &shard_layout,
+                view_state,
+                self.runtime_config_store.get_config(current_protocol_version).clone(),
+                gas_price,
+                transaction,
+                verify_signature,
+                self.epoch_manager.as_ref(),
+            )
+            .map_err(SimulateTransactionError::from_trie_viewer_error)?;
+        let state_changes =
+            StateChanges::from_changes(simulation.state_changes.into_iter().map(Ok))
+                .map_err(|err| internal_error(&err))?;
+        Ok(TransactionSimulationView::new(
+            *block_hash,
+            block_height,
+            simulation.transaction_outcome,
+            simulation.receipt_outcomes,
+            simulation.receipts,
+            state_changes,
+        ))
+    }
+
     // Wrapper to get the metrics.
     fn obtain_state_part(
         &self,
@@ -0,0 +1,164 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use near_chain::types::Tip;
+use near_epoch_manager::shard_tracker::ShardTracker;
+use near_epoch_manager::{EpochManagerAdapter, EpochManagerHandle};
+use near_primitives::shard_layout::{get_block_shard_uid, ShardUId};
+use near_primitives::types::chunk_extra::ChunkExtra;
+use near_primitives::types::{AccountId, StateRoot};
+use near_store::config::StateGarbageAuditorConfig;
+use near_store::metadata::DbKind;
+use near_store::state_garbage_auditor::{audit_state_garbage, AuditMode, StateGarbageAuditError};
+use near_store::{DBCol, NodeStorage, Store, HEAD_KEY};
+
+use crate::NearConfig;
+
+/// How often the auditor thread checks whether it should stop while waiting
+/// for the next audit.
+const STOP_CHECK_PERIOD: Duration = Duration::from_secs(1);
+
+/// A handle to the background thread running the state garbage auditor.
+pub struct StateGarbageAuditorHandle {
+    join_handle: std::thread::JoinHandle<()>,
+    keep_going: Arc<AtomicBool>,
+}
+
+impl StateGarbageAuditorHandle {
+    pub fn stop(self) {
+        self.keep_going.store(false, Ordering::Relaxed);
+        match self.join_handle.join() {
+            Ok(_) => {
+                tracing::debug!(target: "state_garbage_auditor", "Joined the state garbage auditor thread");
+            }
+            Err(_) => {
+                tracing::error!(target: "state_garbage_auditor", "Failed to join the state garbage auditor thread");
+            }
+        }
+    }
+}
+
+/// Returns the state roots of all shards after the chunks of the head block.
+///
+/// The state root is `None` for the shards the node didn't track at the head,
+/// as it doesn't apply their chunks and has no state root for them.  Their
+/// state must not be audited.
+pub fn head_state_roots(
+    store: &Store,
+    epoch_manager: &dyn EpochManagerAdapter,
+    shard_tracker: &ShardTracker,
+    me: Option<&AccountId>,
+) -> anyhow::Result<Vec<(ShardUId, Option<StateRoot>)>> {
+    let head = store.get_ser::<Tip>(DBCol::BlockMisc, HEAD_KEY)?.context("HEAD is not set")?;
+    let shard_layout = epoch_manager.get_shard_layout(&head.epoch_id)?;
+    let mut state_roots = vec![];
+    for shard_uid in shard_layout.shard_uids() {
+        if !shard_tracker.care_about_shard(me, &head.prev_block_hash, shard_uid.shard_id(), true) {
+            state_roots.push((shard_uid, None));
+            continue;
+        }
+        let chunk_extra = store
+            .get_ser::<ChunkExtra>(
+                DBCol::ChunkExtra,
+                &get_block_shard_uid(&head.last_block_hash, &shard_uid),
+            )?
+            .with_context(|| format!("no chunk extra for {shard_uid} at the head"))?;
+        state_roots.push((shard_uid, Some(*chunk_extra.state_root())));
+    }
+    Ok(state_roots)
+}
+
+/// Audits every shard once, removing the unreachable `State` entries.
+fn run_audit(
+    store: &Store,
+    epoch_manager: &EpochManagerHandle,
+    shard_tracker: &ShardTracker,
+    me: Option<&AccountId>,
+    config: &StateGarbageAuditorConfig,
+    keep_going: &AtomicBool,
+) -> anyhow::Result<()> {
+    for (shard_uid, state_root) in head_state_roots(store, epoch_manager, shard_tracker, me)? {
+        let Some(state_root) = state_root else {
+            tracing::info!(target: "state_garbage_auditor", %shard_uid, "Skipping a shard which is not tracked");
+            continue;
+        };
+        match audit_state_garbage(
+            store,
+            shard_uid,
+            state_root,
+            config,
+            AuditMode::Remove,
+            keep_going,
+        ) {
+            Ok(_) => {}
+            // Another audit will be attempted after the next period.
+            Err(StateGarbageAuditError::Aborted(reason)) => {
+                tracing::info!(target: "state_garbage_auditor", %shard_uid, %reason, "Audit aborted");
+            }
+            Err(StateGarbageAuditError::Interrupted) => return Ok(()),
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(())
+}
+
+fn state_garbage_auditor_loop(
+    config: &StateGarbageAuditorConfig,
+    keep_going: &AtomicBool,
+    store: Store,
+    epoch_manager: &EpochManagerHandle,
+    shard_tracker: &ShardTracker,
+    me: Option<&AccountId>,
+) {
+    tracing::info!(target: "state_garbage_auditor", "Starting the state garbage auditor");
+    let mut next_audit = Instant::now() + config.period;
+    while keep_going.load(Ordering::Relaxed) {
+        if Instant::now() < next_audit {
+            std::thread::sleep(STOP_CHECK_PERIOD);
+            continue;
+        }
+        if let Err(err) = run_audit(&store, epoch_manager, shard_tracker, me, config, keep_going) {
+            tracing::error!(target: "state_garbage_auditor", ?err, "State garbage audit failed");
+        }
+        next_audit = Instant::now() + config.period;
+    }
+    tracing::debug!(target: "state_garbage_auditor", "Stopping the state garbage auditor");
+}
+
+/// Spawns the state garbage auditor in a background thread if it's enabled in
+/// the config.  Only RPC databases are audited, archival nodes never remove
+/// state.
+pub fn spawn_state_garbage_auditor(
+    config: &NearConfig,
+    storage: &NodeStorage,
+    epoch_manager: Arc<EpochManagerHandle>,
+    shard_tracker: ShardTracker,
+) -> anyhow::Result<Option<StateGarbageAuditorHandle>> {
+    let auditor_config = config.config.store.state_garbage_auditor.clone();
+    if !auditor_config.enabled {
+        return Ok(None);
+    }
+    let store = storage.get_hot_store();
+    if config.config.archive || store.get_db_kind()? != Some(DbKind::RPC) {
+        tracing::warn!(target: "state_garbage_auditor", "Not spawning the state garbage auditor because the database is not an RPC one");
+        return Ok(None);
+    }
+
+    let me = config.validator_signer.as_ref().map(|signer| signer.validator_id().clone());
+    let keep_going = Arc::new(AtomicBool::new(true));
+    let keep_going_clone = keep_going.clone();
+    let join_handle =
+        std::thread::Builder::new().name("state_garbage_auditor".to_string()).spawn(move || {
+            state_garbage_auditor_loop(
+                &auditor_config,
+                &keep_going_clone,
+                store,
+                epoch_manager.as_ref(),
+                &shard_tracker,
+                me.as_ref(),
+            )
+        })?;
+    Ok(Some(StateGarbageAuditorHandle { join_handle, keep_going }))
+}
@@ -3,7 +3,9 @@ use crate::metrics;
 use near_chain::types::RuntimeAdapter;
 use near_chain::{Chain, ChainGenesis, ChainStoreAccess, DoomslugThresholdMode, Error};
 use near_chain_configs::{ClientConfig, ExternalStorageLocation};
-use near_client::sync::external::{create_bucket_readwrite, external_storage_location};
+use near_client::sync::external::{
+    azure_sas_token, create_bucket_readwrite, create_http_client, external_storage_location,
+};
 use near_client::sync::external::{
     external_storage_location_directory, get_part_id_from_filename, is_part_filename,
     ExternalConnection,
@@ -62,6 +64,16 @@ pub fn spawn_state_sync_dump(
                 bucket
             }
         },
+        ExternalStorageLocation::Http { .. } => {
+            anyhow::bail!("State parts can't be dumped to an HTTP mirror, it is read-only.")
+        },
+        ExternalStorageLocation::AzureBlob { endpoint, container } => ExternalConnection::AzureBlob {
+            reqwest_client: Arc::new(create_http_client(Duration::from_secs(30))),
+            endpoint,
+            container,
+            sas_token: Some(azure_sas_token(dump_config.credentials_file)?.ok_or_else(|| anyhow::anyhow!(
+                "Writing to Azure Blob Storage requires a shared access signature. Please either provide AZURE_STORAGE_SAS_TOKEN in the environment, or create a credentials file and link it in config.json as 'credentials_file'."))?),
+        },
     };
 
     // Determine how many threads to start.
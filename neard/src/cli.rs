@@ -6,6 +6,7 @@ use near_client::ConfigUpdater;
 use near_cold_store_tool::ColdStoreCommand;
 use near_database_tool::commands::DatabaseCommand;
 use near_dyn_configs::{UpdateableConfigLoader, UpdateableConfigLoaderError, UpdateableConfigs};
+use near_epoch_simulator::cli::EpochSimulatorCommand;
 #[cfg(feature = "new_epoch_sync")]
 use near_epoch_sync_tool::EpochSyncCommand;
 use near_flat_storage::commands::FlatStorageCommand;
@@ -13,6 +14,7 @@ use near_fork_network::cli::ForkNetworkCommand;
 use near_jsonrpc_primitives::types::light_client::RpcLightClientExecutionProofResponse;
 use near_mirror::MirrorCommand;
 use near_network::tcp;
+use near_network_replay::cli::ReplayNetworkCaptureCommand;
 use near_o11y::tracing_subscriber::EnvFilter;
 use near_o11y::{
     default_subscriber, default_subscriber_with_opentelemetry, BuildEnvFilterError,
@@ -123,6 +125,9 @@ impl NeardCmd {
             NeardSubCommand::StateParts(cmd) => {
                 cmd.run()?;
             }
+            NeardSubCommand::ReplayNetworkCapture(cmd) => {
+                cmd.run()?;
+            }
             NeardSubCommand::FlatStorage(cmd) => {
                 cmd.run(&home_dir, genesis_validation)?;
             }
@@ -146,6 +151,9 @@ impl NeardCmd {
             NeardSubCommand::StatePartsDumpCheck(cmd) => {
                 cmd.run()?;
             }
+            NeardSubCommand::EpochSimulator(cmd) => {
+                cmd.run(&home_dir, genesis_validation)?;
+            }
             #[cfg(feature = "new_epoch_sync")]
             NeardSubCommand::EpochSync(cmd) => {
                 cmd.run(&home_dir)?;
@@ -261,6 +269,10 @@ pub(super) enum NeardSubCommand {
     /// Connects to a NEAR node and sends state parts requests after the handshake is completed.
     StateParts(StatePartsCommand),
 
+    /// Connects to a NEAR node and sends it the messages received by another node, as
+    /// captured with `network.experimental.capture_path`.
+    ReplayNetworkCapture(ReplayNetworkCaptureCommand),
+
     /// Flat storage related tooling.
     FlatStorage(FlatStorageCommand),
 
@@ -279,6 +291,10 @@ pub(super) enum NeardSubCommand {
     /// Check completeness of dumped state parts of an epoch
     StatePartsDumpCheck(StatePartsDumpCheckCommand),
 
+    /// Simulates validator selection and rewards for future epochs, starting
+    /// from the stake in the database or in a JSON snapshot.
+    EpochSimulator(EpochSimulatorCommand),
+
     #[cfg(feature = "new_epoch_sync")]
     /// Testing tool for epoch sync
     EpochSync(EpochSyncCommand),
@@ -555,6 +571,7 @@ impl RunCmd {
                 rpc_servers,
                 cold_store_loop_handle,
                 state_sync_dump_handle,
+                state_garbage_auditor_handle,
                 flat_state_migration_handle,
                 state_split_handle,
                 ..
@@ -583,6 +600,9 @@ impl RunCmd {
             if let Some(handle) = state_sync_dump_handle {
                 handle.stop()
             }
+            if let Some(handle) = state_garbage_auditor_handle {
+                handle.stop()
+            }
             state_split_handle.stop();
             flat_state_migration_handle.stop();
             futures::future::join_all(rpc_servers.iter().map(|(name, server)| async move {
@@ -17,7 +17,7 @@ use near_primitives::receipt::{ActionReceipt, Receipt, ReceiptEnum};
 use near_primitives::runtime::config::AccountCreationConfig;
 use near_primitives::runtime::fees::RuntimeFeesConfig;
 use near_primitives::transaction::{
-    Action, AddKeyAction, DeleteAccountAction, DeleteKeyAction, DeployContractAction,
+    Action, AddKeyAction, DeleteAccountAction, DeleteKeyAction, DeployContractWithMigrationAction,
     FunctionCallAction, StakeAction, TransferAction,
 };
 use near_primitives::types::validator_stake::ValidatorStake;
@@ -515,11 +515,11 @@ pub(crate) fn action_deploy_contract(
     state_update: &mut TrieUpdate,
     account: &mut Account,
     account_id: &AccountId,
-    deploy_contract: &DeployContractAction,
+    code: &[u8],
     apply_state: &ApplyState,
 ) -> Result<(), StorageError> {
     let _span = tracing::debug_span!(target: "runtime", "action_deploy_contract").entered();
-    let code = ContractCode::new(deploy_contract.code.clone(), None);
+    let code = ContractCode::new(code.to_vec(), None);
     let prev_code = get_code(state_update, account_id, Some(account.code_hash()))?;
     let prev_code_length = prev_code.map(|code| code.code().len() as u64).unwrap_or_default();
     account.set_storage_usage(account.storage_usage().saturating_sub(prev_code_length));
@@ -540,6 +540,44 @@ pub(crate) fn action_deploy_contract(
     Ok(())
 }
 
+/// Deploys the code and calls the migration method of the new code.
+///
+/// A failed migration fails the action like a failed function call does, so the
+/// deployment is rolled back together with all other changes of the receipt.
+pub(crate) fn action_deploy_contract_with_migration(
+    state_update: &mut TrieUpdate,
+    apply_state: &ApplyState,
+    account: &mut Account,
+    receipt: &Receipt,
+    action_receipt: &ActionReceipt,
+    promise_results: &[PromiseResult],
+    result: &mut ActionResult,
+    account_id: &AccountId,
+    deploy_contract: &DeployContractWithMigrationAction,
+    action_hash: &CryptoHash,
+    is_last_action: bool,
+    epoch_info_provider: &dyn EpochInfoProvider,
+) -> Result<(), RuntimeError> {
+    let _span =
+        tracing::debug_span!(target: "runtime", "action_deploy_contract_with_migration").entered();
+    action_deploy_contract(state_update, account, account_id, &deploy_contract.code, apply_state)?;
+    action_function_call(
+        state_update,
+        apply_state,
+        account,
+        receipt,
+        action_receipt,
+        promise_results,
+        result,
+        account_id,
+        &deploy_contract.function_call(),
+        action_hash,
+        &apply_state.config,
+        is_last_action,
+        epoch_info_provider,
+    )
+}
+
 pub(crate) fn action_delete_account(
     state_update: &mut TrieUpdate,
     account: &mut Option<Account>,
@@ -880,7 +918,11 @@ pub(crate) fn check_actor_permissions(
     account_id: &AccountId,
 ) -> Result<(), ActionError> {
     match action {
-        Action::DeployContract(_) | Action::Stake(_) | Action::AddKey(_) | Action::DeleteKey(_) => {
+        Action::DeployContract(_)
+        | Action::DeployContractWithMigration(_)
+        | Action::Stake(_)
+        | Action::AddKey(_)
+        | Action::DeleteKey(_) => {
             if actor_id != account_id {
                 return Err(ActionErrorKind::ActorNoPermission {
                     account_id: account_id.clone(),
@@ -970,6 +1012,7 @@ pub(crate) fn check_account_existence(
             }
         }
         Action::DeployContract(_)
+        | Action::DeployContractWithMigration(_)
         | Action::FunctionCall(_)
         | Action::Stake(_)
         | Action::AddKey(_)
@@ -137,6 +137,18 @@ pub fn total_send_fees(
                         &delegate_action.receiver_id,
                     )?
             }
+            DeployContractWithMigration(action) => {
+                // Charged as a deployment followed by a function call.
+                let code_bytes = action.code.len() as u64;
+                let call_bytes =
+                    action.method_name.as_bytes().len() as u64 + action.args.len() as u64;
+                fees.fee(ActionCosts::deploy_contract_base).send_fee(sender_is_receiver)
+                    + fees.fee(ActionCosts::deploy_contract_byte).send_fee(sender_is_receiver)
+                        * code_bytes
+                    + fees.fee(ActionCosts::function_call_base).send_fee(sender_is_receiver)
+                    + fees.fee(ActionCosts::function_call_byte).send_fee(sender_is_receiver)
+                        * call_bytes
+            }
         };
         result = safe_add_gas(result, delta)?;
     }
@@ -218,6 +230,14 @@ pub fn exec_fee(config: &RuntimeConfig, action: &Action, receiver_id: &AccountId
         DeleteKey(_) => fees.fee(ActionCosts::delete_key).exec_fee(),
         DeleteAccount(_) => fees.fee(ActionCosts::delete_account).exec_fee(),
         Delegate(_) => fees.fee(ActionCosts::delegate).exec_fee(),
+        DeployContractWithMigration(action) => {
+            let code_bytes = action.code.len() as u64;
+            let call_bytes = action.method_name.as_bytes().len() as u64 + action.args.len() as u64;
+            fees.fee(ActionCosts::deploy_contract_base).exec_fee()
+                + fees.fee(ActionCosts::deploy_contract_byte).exec_fee() * code_bytes
+                + fees.fee(ActionCosts::function_call_base).exec_fee()
+                + fees.fee(ActionCosts::function_call_byte).exec_fee() * call_bytes
+        }
     }
 }
 
@@ -24,9 +24,9 @@ use near_primitives::runtime::config::RuntimeConfig;
 use near_primitives::runtime::migration_data::{MigrationData, MigrationFlags};
 use near_primitives::sandbox::state_patch::SandboxStatePatch;
 use near_primitives::state_record::StateRecord;
-use near_primitives::transaction::ExecutionMetadata;
 use near_primitives::transaction::{
-    Action, ExecutionOutcome, ExecutionOutcomeWithId, ExecutionStatus, LogEntry, SignedTransaction,
+    Action, ActionUsage, ExecutionMetadata, ExecutionMetadataV4, ExecutionOutcome,
+    ExecutionOutcomeWithId, ExecutionStatus, LogEntry, RefundInfo, SignedTransaction,
 };
 use near_primitives::trie_key::TrieKey;
 use near_primitives::types::{
@@ -217,6 +217,7 @@ impl Runtime {
         state_update: &mut TrieUpdate,
         apply_state: &ApplyState,
         signed_transaction: &SignedTransaction,
+        verify_signature: bool,
         stats: &mut ApplyStats,
     ) -> Result<(Receipt, ExecutionOutcomeWithId), RuntimeError> {
         let _span = tracing::debug_span!(target: "runtime", "process_transaction", tx_hash = %signed_transaction.get_hash()).entered();
@@ -227,7 +228,7 @@ impl Runtime {
             state_update,
             apply_state.gas_price,
             signed_transaction,
-            true,
+            verify_signature,
             Some(apply_state.block_height),
             apply_state.current_protocol_version,
         ) {
@@ -343,10 +344,26 @@ impl Runtime {
                     state_update,
                     account.as_mut().expect(EXPECT_ACCOUNT_EXISTS),
                     account_id,
-                    deploy_contract,
+                    &deploy_contract.code,
                     apply_state,
                 )?;
             }
+            Action::DeployContractWithMigration(deploy_contract) => {
+                action_deploy_contract_with_migration(
+                    state_update,
+                    apply_state,
+                    account.as_mut().expect(EXPECT_ACCOUNT_EXISTS),
+                    receipt,
+                    action_receipt,
+                    promise_results,
+                    &mut result,
+                    account_id,
+                    deploy_contract,
+                    action_hash,
+                    action_index + 1 == actions.len(),
+                    epoch_info_provider,
+                )?;
+            }
             Action::FunctionCall(function_call) => {
                 action_function_call(
                     state_update,
@@ -502,6 +519,7 @@ impl Runtime {
         result.gas_burnt = exec_fees;
         // TODO(#8806): Support compute costs for actions. For now they match burnt gas.
         result.compute_usage = exec_fees;
+        let mut actions_usage = Vec::with_capacity(action_receipt.actions.len());
         // Executing actions one by one
         for (action_index, action) in action_receipt.actions.iter().enumerate() {
             let action_hash = create_action_hash(
@@ -536,6 +554,11 @@ impl Runtime {
                     new_result.result = Err(ActionErrorKind::NewReceiptValidationError(e).into());
                 }
             }
+            actions_usage.push(ActionUsage {
+                gas_burnt: new_result.gas_burnt,
+                gas_used: new_result.gas_used,
+                compute_usage: new_result.compute_usage,
+            });
             result.merge(new_result)?;
             // TODO storage error
             if let Err(ref mut res) = result.result {
@@ -576,6 +599,8 @@ impl Runtime {
             }
         }
 
+        let mut refund =
+            RefundInfo { is_refund: receipt.predecessor_id.is_system(), ..Default::default() };
         let gas_deficit_amount = if receipt.predecessor_id.is_system() {
             // We will set gas_burnt for refund receipts to be 0 when we calculate tx_burnt_amount
             // Here we don't set result.gas_burnt to be zero if CountRefundReceiptsInGasLimit is
@@ -605,6 +630,7 @@ impl Runtime {
                 receipt,
                 action_receipt,
                 &mut result,
+                &mut refund,
                 &apply_state.config,
             )?
         };
@@ -714,6 +740,10 @@ impl Runtime {
                 );
 
                 new_receipt.receipt_id = receipt_id;
+                // Only refunds are sent on behalf of the system account.
+                if new_receipt.predecessor_id.is_system() {
+                    refund.refund_receipt_ids.push(receipt_id);
+                }
                 let is_action = matches!(&new_receipt.receipt, ReceiptEnum::Action(_));
                 outgoing_receipts.push(new_receipt);
                 if is_action {
@@ -751,7 +781,11 @@ impl Runtime {
                 compute_usage: Some(result.compute_usage),
                 tokens_burnt,
                 executor_id: account_id.clone(),
-                metadata: ExecutionMetadata::V3(result.profile),
+                metadata: ExecutionMetadata::V4(ExecutionMetadataV4 {
+                    profile: result.profile,
+                    actions: actions_usage,
+                    refund,
+                }),
             },
         })
     }
@@ -762,6 +796,7 @@ impl Runtime {
         receipt: &Receipt,
         action_receipt: &ActionReceipt,
         result: &mut ActionResult,
+        refund: &mut RefundInfo,
         config: &RuntimeConfig,
     ) -> Result<Balance, RuntimeError> {
         let total_deposit = total_deposit(&action_receipt.actions)?;
@@ -809,6 +844,9 @@ impl Runtime {
             )?;
         }
 
+        refund.gas_refund = gas_refund;
+        refund.gas_balance_refund = gas_balance_refund;
+        refund.deposit_refund = deposit_refund;
         if deposit_refund > 0 {
             result
                 .new_receipts
@@ -1258,6 +1296,7 @@ impl Runtime {
                 &mut state_update,
                 apply_state,
                 signed_transaction,
+                true,
                 &mut stats,
             )?;
             if receipt.receiver_id == signed_transaction.transaction.signer_id {
@@ -1525,7 +1564,8 @@ mod tests {
     use near_primitives::shard_layout::ShardUId;
     use near_primitives::test_utils::{account_new, MockEpochInfoProvider};
     use near_primitives::transaction::{
-        AddKeyAction, DeleteKeyAction, DeployContractAction, FunctionCallAction, TransferAction,
+        AddKeyAction, DeleteKeyAction, DeployContractAction, DeployContractWithMigrationAction,
+        FunctionCallAction, TransferAction,
     };
     use near_primitives::types::MerkleHash;
     use near_primitives::version::PROTOCOL_VERSION;
@@ -2467,6 +2507,61 @@ mod tests {
             .expect("Compilation result should be non-empty");
     }
 
+    #[test]
+    fn test_deploy_contract_with_migration() {
+        let (runtime, tries, mut root, apply_state, signer, epoch_info_provider) =
+            setup_runtime(to_yocto(1_000_000), to_yocto(500_000), 10u64.pow(15));
+
+        let wasm_code = near_test_contracts::rs_contract().to_vec();
+        let deploy_with_migration = |method_name: &str| {
+            Action::DeployContractWithMigration(Box::new(DeployContractWithMigrationAction {
+                code: wasm_code.clone(),
+                method_name: method_name.to_string(),
+                args: vec![],
+                gas: 10u64.pow(14),
+            }))
+        };
+        let mut apply = |actions: Vec<Action>| {
+            let receipts =
+                vec![create_receipt_with_actions(alice_account(), signer.clone(), actions)];
+            let apply_result = runtime
+                .apply(
+                    tries.get_trie_for_shard(ShardUId::single_shard(), root),
+                    &None,
+                    &apply_state,
+                    &receipts,
+                    &[],
+                    &epoch_info_provider,
+                    Default::default(),
+                )
+                .unwrap();
+            let mut store_update = tries.store_update();
+            root = tries.apply_all(
+                &apply_result.trie_changes,
+                ShardUId::single_shard(),
+                &mut store_update,
+            );
+            store_update.commit().unwrap();
+            let state_update = tries.new_trie_update(ShardUId::single_shard(), root);
+            let account = get_account(&state_update, &alice_account()).unwrap().unwrap();
+            (apply_result, account)
+        };
+
+        // Failed migration keeps the previous code.
+        let (apply_result, account) = apply(vec![deploy_with_migration("panic_with_message")]);
+        assert_matches!(&apply_result.outcomes[..], [outcome] => {
+            assert_matches!(outcome.outcome.status, ExecutionStatus::Failure(_));
+        });
+        assert_eq!(account.code_hash(), hash(&[]));
+
+        let (apply_result, account) = apply(vec![deploy_with_migration("log_something")]);
+        assert_matches!(&apply_result.outcomes[..], [outcome] => {
+            assert_matches!(outcome.outcome.status, ExecutionStatus::SuccessValue(_));
+            assert_eq!(outcome.outcome.logs, vec!["hello".to_string()]);
+        });
+        assert_eq!(account.code_hash(), hash(&wasm_code));
+    }
+
     #[test]
     fn test_compute_usage_limit() {
         let (runtime, tries, root, mut apply_state, signer, epoch_info_provider) =
@@ -54,6 +54,18 @@ pub enum CallFunctionError {
     VMError { error_message: String },
 }
 
+#[derive(thiserror::Error, Debug)]
+pub enum SimulateTransactionError {
+    #[error("Transaction is invalid: {error}")]
+    InvalidTransaction { error: near_primitives::errors::InvalidTxError },
+    #[error("The state of shard {shard_id} is not available")]
+    UnavailableShard { shard_id: near_primitives::types::ShardId },
+    #[error("Transaction produced more than {limit} receipts")]
+    TooManyReceipts { limit: usize },
+    #[error("Internal error: #{error_message}")]
+    InternalError { error_message: String },
+}
+
 impl From<ViewAccountError> for ViewContractCodeError {
     fn from(view_account_error: ViewAccountError) -> Self {
         match view_account_error {
@@ -99,3 +111,20 @@ impl From<near_primitives::errors::StorageError> for CallFunctionError {
         Self::InternalError { error_message: storage_error.to_string() }
     }
 }
+
+impl From<near_primitives::errors::StorageError> for SimulateTransactionError {
+    fn from(storage_error: near_primitives::errors::StorageError) -> Self {
+        Self::InternalError { error_message: storage_error.to_string() }
+    }
+}
+
+impl From<near_primitives::errors::RuntimeError> for SimulateTransactionError {
+    fn from(runtime_error: near_primitives::errors::RuntimeError) -> Self {
+        match runtime_error {
+            near_primitives::errors::RuntimeError::InvalidTxError(error) => {
+                Self::InvalidTransaction { error }
+            }
+            error => Self::InternalError { error_message: error.to_string() },
+        }
+    }
+}
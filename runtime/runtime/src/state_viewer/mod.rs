@@ -1,27 +1,50 @@
 use crate::near_primitives::version::PROTOCOL_VERSION;
 use crate::receipt_manager::ReceiptManager;
 use crate::{actions::execute_function_call, ext::RuntimeExt};
+use crate::{ApplyStats, Runtime};
 use near_crypto::{KeyType, PublicKey};
 use near_primitives::account::{AccessKey, Account};
 use near_primitives::borsh::BorshDeserialize;
 use near_primitives::hash::CryptoHash;
-use near_primitives::receipt::ActionReceipt;
+use near_primitives::receipt::{ActionReceipt, Receipt};
 use near_primitives::runtime::apply_state::ApplyState;
+use near_primitives::runtime::config::RuntimeConfig;
 use near_primitives::runtime::config_store::RuntimeConfigStore;
 use near_primitives::runtime::migration_data::{MigrationData, MigrationFlags};
-use near_primitives::transaction::FunctionCallAction;
+use near_primitives::shard_layout::{account_id_to_shard_id, ShardLayout};
+use near_primitives::transaction::{ExecutionOutcomeWithId, FunctionCallAction, SignedTransaction};
 use near_primitives::trie_key::trie_key_parsers;
-use near_primitives::types::{AccountId, EpochInfoProvider, Gas};
+use near_primitives::types::{
+    AccountId, Balance, EpochInfoProvider, Gas, RawStateChangesWithTrieKey, ShardId,
+};
 use near_primitives::views::{StateItem, ViewApplyState, ViewStateResult};
 use near_primitives_core::config::ViewConfig;
 use near_store::{get_access_key, get_account, get_code, TrieUpdate};
 use near_vm_runner::logic::ReturnData;
 use near_vm_runner::ContractCode;
+use std::collections::{BTreeMap, VecDeque};
 use std::{str, sync::Arc, time::Instant};
 use tracing::debug;
 
 pub mod errors;
 
+/// Upper bound on the number of receipts executed while simulating a single
+/// transaction, so that a contract spawning receipts forever can't keep the
+/// node busy.
+pub const MAX_SIMULATED_RECEIPTS: usize = 1_000;
+
+/// Everything a transaction did when executed on top of a view of the state.
+pub struct TransactionSimulation {
+    pub transaction_outcome: ExecutionOutcomeWithId,
+    /// All receipts produced by the transaction in the order they were
+    /// executed, including refunds and data receipts.
+    pub receipts: Vec<Receipt>,
+    /// Outcomes of the executed action receipts.
+    pub receipt_outcomes: Vec<ExecutionOutcomeWithId>,
+    /// Changes to the state of all touched shards, ordered by shard id.
+    pub state_changes: Vec<RawStateChangesWithTrieKey>,
+}
+
 pub struct TrieViewer {
     /// Upper bound of the byte size of contract state that is still viewable. None is no limit
     state_size_limit: Option<u64>,
@@ -253,4 +276,96 @@ impl TrieViewer {
             Ok(result)
         }
     }
+
+    /// Executes the transaction and all receipts it produces on top of the
+    /// given state, until no more receipts are left.  Receipts are executed
+    /// one by one in the order they were produced, even when they target other
+    /// shards, and nothing is committed to the storage.
+    ///
+    /// `state_updates` must contain a view of the state of every shard the
+    /// transaction touches.
+    pub fn simulate_transaction(
+        &self,
+        mut state_updates: BTreeMap<ShardId, TrieUpdate>,
+        shard_layout: &ShardLayout,
+        view_state: ViewApplyState,
+        config: Arc<RuntimeConfig>,
+        gas_price: Balance,
+        transaction: &SignedTransaction,
+        verify_signature: bool,
+        epoch_info_provider: &dyn EpochInfoProvider,
+    ) -> Result<TransactionSimulation, errors::SimulateTransactionError> {
+        let signer_shard_id =
+            account_id_to_shard_id(&transaction.transaction.signer_id, shard_layout);
+        let random_seed = *get_state_update(&mut state_updates, signer_shard_id)?.get_root();
+        let apply_state = ApplyState {
+            block_height: view_state.block_height,
+            prev_block_hash: view_state.prev_block_hash,
+            block_hash: view_state.block_hash,
+            epoch_id: view_state.epoch_id,
+            epoch_height: view_state.epoch_height,
+            gas_price,
+            block_timestamp: view_state.block_timestamp,
+            gas_limit: None,
+            random_seed,
+            current_protocol_version: view_state.current_protocol_version,
+            config,
+            cache: view_state.cache,
+            is_new_chunk: true,
+            migration_data: Arc::new(MigrationData::default()),
+            migration_flags: MigrationFlags::default(),
+        };
+
+        let runtime = Runtime::new();
+        let mut stats = ApplyStats::default();
+        let (receipt, transaction_outcome) = runtime.process_transaction(
+            get_state_update(&mut state_updates, signer_shard_id)?,
+            &apply_state,
+            transaction,
+            verify_signature,
+            &mut stats,
+        )?;
+
+        let mut receipts = vec![];
+        let mut receipt_outcomes = vec![];
+        let mut pending_receipts = VecDeque::from([receipt]);
+        let mut validator_proposals = vec![];
+        while let Some(receipt) = pending_receipts.pop_front() {
+            if receipts.len() >= MAX_SIMULATED_RECEIPTS {
+                return Err(errors::SimulateTransactionError::TooManyReceipts {
+                    limit: MAX_SIMULATED_RECEIPTS,
+                });
+            }
+            let shard_id = account_id_to_shard_id(&receipt.receiver_id, shard_layout);
+            let mut outgoing_receipts = vec![];
+            let outcome = runtime.process_receipt(
+                get_state_update(&mut state_updates, shard_id)?,
+                &apply_state,
+                &receipt,
+                &mut outgoing_receipts,
+                &mut validator_proposals,
+                &mut stats,
+                epoch_info_provider,
+            )?;
+            receipt_outcomes.extend(outcome);
+            receipts.push(receipt);
+            pending_receipts.extend(outgoing_receipts);
+        }
+
+        let mut state_changes = vec![];
+        for state_update in state_updates.into_values() {
+            let (_, _, changes) = state_update.finalize()?;
+            state_changes.extend(changes);
+        }
+        Ok(TransactionSimulation { transaction_outcome, receipts, receipt_outcomes, state_changes })
+    }
+}
+
+fn get_state_update(
+    state_updates: &mut BTreeMap<ShardId, TrieUpdate>,
+    shard_id: ShardId,
+) -> Result<&mut TrieUpdate, errors::SimulateTransactionError> {
+    state_updates
+        .get_mut(&shard_id)
+        .ok_or(errors::SimulateTransactionError::UnavailableShard { shard_id })
 }
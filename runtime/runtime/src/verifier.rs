@@ -13,7 +13,8 @@ use near_primitives::receipt::{ActionReceipt, DataReceipt, Receipt, ReceiptEnum}
 use near_primitives::runtime::config::RuntimeConfig;
 use near_primitives::transaction::DeleteAccountAction;
 use near_primitives::transaction::{
-    Action, AddKeyAction, DeployContractAction, FunctionCallAction, SignedTransaction, StakeAction,
+    Action, AddKeyAction, DeployContractAction, DeployContractWithMigrationAction,
+    FunctionCallAction, SignedTransaction, StakeAction,
 };
 use near_primitives::types::{AccountId, Balance};
 use near_primitives::types::{BlockHeight, StorageUsage};
@@ -370,6 +371,18 @@ pub(crate) fn validate_actions(
                 }
                 found_delegate_action = true;
             }
+            if let Action::DeployContractWithMigration(_) = action {
+                if !checked_feature!(
+                    "stable",
+                    DeployContractWithMigration,
+                    current_protocol_version
+                ) {
+                    return Err(ActionsValidationError::UnsupportedProtocolFeature {
+                        protocol_feature: String::from("DeployContractWithMigration"),
+                        version: ProtocolFeature::DeployContractWithMigration.protocol_version(),
+                    });
+                }
+            }
         }
         validate_action(limit_config, action, current_protocol_version)?;
     }
@@ -402,6 +415,9 @@ pub fn validate_action(
         Action::DeleteKey(_) => Ok(()),
         Action::DeleteAccount(a) => validate_delete_action(a),
         Action::Delegate(a) => validate_delegate_action(limit_config, a, current_protocol_version),
+        Action::DeployContractWithMigration(a) => {
+            validate_deploy_contract_with_migration_action(limit_config, a)
+        }
     }
 }
 
@@ -457,6 +473,22 @@ fn validate_function_call_action(
     Ok(())
 }
 
+/// Validates `DeployContractWithMigrationAction`. Checks the code the same way as for
+/// `DeployContractAction` and the migration call the same way as for `FunctionCallAction`.
+fn validate_deploy_contract_with_migration_action(
+    limit_config: &LimitConfig,
+    action: &DeployContractWithMigrationAction,
+) -> Result<(), ActionsValidationError> {
+    if action.code.len() as u64 > limit_config.max_contract_size {
+        return Err(ActionsValidationError::ContractSizeExceeded {
+            size: action.code.len() as u64,
+            limit: limit_config.max_contract_size,
+        });
+    }
+
+    validate_function_call_action(limit_config, &action.function_call())
+}
+
 /// Validates `StakeAction`. Checks that the `public_key` is a valid staking key.
 fn validate_stake_action(action: &StakeAction) -> Result<(), ActionsValidationError> {
     if !is_valid_staking_key(&action.public_key) {
@@ -5,7 +5,9 @@ use crate::compact::RunCompactionCommand;
 use crate::corrupt::CorruptStateSnapshotCommand;
 use crate::make_snapshot::MakeSnapshotCommand;
 use crate::memtrie::LoadMemTrieCommand;
+use crate::portable_snapshot::{ExportStateSnapshotCommand, ImportStateSnapshotCommand};
 use crate::run_migrations::RunMigrationsCommand;
+use crate::state_garbage::AuditStateGarbageCommand;
 use crate::state_perf::StatePerfCommand;
 use clap::Parser;
 use std::path::PathBuf;
@@ -25,6 +27,9 @@ enum SubCommand {
     /// Analyse gas usage in a chosen sequnce of blocks
     AnalyseGasUsage(AnalyseGasUsageCommand),
 
+    /// Find and remove State entries unreachable from the chain
+    AuditStateGarbage(AuditStateGarbageCommand),
+
     /// Change DbKind of hot or cold db.
     ChangeDbKind(ChangeDbKindCommand),
 
@@ -34,6 +39,13 @@ enum SubCommand {
     /// Corrupt the state snapshot.
     CorruptStateSnapshot(CorruptStateSnapshotCommand),
 
+    /// Export the state of a shard, or its changes since an earlier block, in
+    /// a portable format
+    ExportStateSnapshot(ExportStateSnapshotCommand),
+
+    /// Import a state snapshot written by export-state-snapshot
+    ImportStateSnapshot(ImportStateSnapshotCommand),
+
     /// Make snapshot of the database
     MakeSnapshot(MakeSnapshotCommand),
 
@@ -53,9 +65,33 @@ impl DatabaseCommand {
         match &self.subcmd {
             SubCommand::AnalyseDataSizeDistribution(cmd) => cmd.run(home),
             SubCommand::AnalyseGasUsage(cmd) => cmd.run(home),
+            SubCommand::AuditStateGarbage(cmd) => {
+                let near_config = nearcore::config::load_config(
+                    &home,
+                    near_chain_configs::GenesisValidationMode::UnsafeFast,
+                )
+                .unwrap_or_else(|e| panic!("Error loading config: {:#}", e));
+                cmd.run(near_config, home)
+            }
             SubCommand::ChangeDbKind(cmd) => cmd.run(home),
             SubCommand::CompactDatabase(cmd) => cmd.run(home),
             SubCommand::CorruptStateSnapshot(cmd) => cmd.run(home),
+            SubCommand::ExportStateSnapshot(cmd) => {
+                let near_config = nearcore::config::load_config(
+                    &home,
+                    near_chain_configs::GenesisValidationMode::UnsafeFast,
+                )
+                .unwrap_or_else(|e| panic!("Error loading config: {:#}", e));
+                cmd.run(home, near_config)
+            }
+            SubCommand::ImportStateSnapshot(cmd) => {
+                let near_config = nearcore::config::load_config(
+                    &home,
+                    near_chain_configs::GenesisValidationMode::UnsafeFast,
+                )
+                .unwrap_or_else(|e| panic!("Error loading config: {:#}", e));
+                cmd.run(home, near_config)
+            }
             SubCommand::MakeSnapshot(cmd) => {
                 let near_config = nearcore::config::load_config(
                     &home,
@@ -7,6 +7,8 @@ mod compact;
 mod corrupt;
 mod make_snapshot;
 mod memtrie;
+mod portable_snapshot;
 mod run_migrations;
+mod state_garbage;
 mod state_perf;
 mod utils;
@@ -0,0 +1,956 @@
+//! Export and import of the state of a single shard in a portable format.
+//!
+//! Unlike `make-snapshot`, which produces a RocksDB checkpoint readable only
+//! by neard with the same database version, these snapshots don't depend on
+//! the database layout at all.  A snapshot is either full, containing the
+//! whole state of the shard at a block, or incremental, containing the changes
+//! between two blocks of the same chain.
+//!
+//! # Format
+//!
+//! A snapshot file is:
+//!
+//! 1. The 8 byte magic `NEARSNAP`.
+//! 2. The format version as a little endian `u32`, currently
+//!    [`FORMAT_VERSION`].
+//! 3. A zstd stream of frames.  Each frame is a little endian `u32` length
+//!    followed by that many bytes of a borsh encoded [`SnapshotFrame`].
+//!
+//! The first frame is always [`SnapshotFrame::Header`] and the last one is
+//! always [`SnapshotFrame::End`].  In between, full snapshots contain
+//! [`SnapshotFrame::StatePart`]s and incremental snapshots contain
+//! [`SnapshotFrame::Changes`].
+//!
+//! State parts are the same as the ones used by state sync: the trie nodes of
+//! a range of keys together with the nodes on the paths from the root to the
+//! boundaries of that range.  Each part is therefore a proof against the state
+//! root in the header and is validated before it's written to the database.
+//! Changes of an incremental snapshot are the final values of all keys changed
+//! after the base block, up to and including the snapshot block, found from the
+//! trie changes the node keeps for garbage collection.  Applying
+//! them to the state root of the base must produce the state root in the
+//! header, which is checked both on export and on import.
+//!
+//! The header also carries the block header and the chunk extra of the shard
+//! at the snapshot block.  They are imported together with the state, so that
+//! the block flat storage is marked ready at is known to the node.
+
+use crate::utils::open_rocksdb;
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_epoch_manager::{EpochManager, EpochManagerAdapter};
+use near_primitives::block_header::BlockHeader;
+use near_primitives::challenge::PartialState;
+use near_primitives::hash::{hash, CryptoHash};
+use near_primitives::shard_layout::get_block_shard_uid;
+use near_primitives::state::ValueRef;
+use near_primitives::state_part::PartId;
+use near_primitives::state_sync::get_num_state_parts;
+use near_primitives::types::chunk_extra::ChunkExtra;
+use near_primitives::types::{BlockHeight, EpochId, ShardId, StateRoot};
+use near_store::flat::{
+    store_helper, BlockInfo, FlatStateChanges, FlatStorageManager, FlatStorageReadyStatus,
+    FlatStorageStatus,
+};
+use near_store::trie::Children;
+use near_store::{
+    DBCol, NibbleSlice, RawTrieNode, RawTrieNodeWithSize, ShardTries, ShardUId,
+    StateSnapshotConfig, Store, Trie, TrieChanges, TrieConfig, TrieDBStorage, TrieStorage,
+    FINAL_HEAD_KEY,
+};
+use nearcore::NearConfig;
+use std::collections::{BTreeMap, HashSet};
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+
+pub(crate) const MAGIC: &[u8; 8] = b"NEARSNAP";
+pub(crate) const FORMAT_VERSION: u32 = 1;
+
+/// Frames larger than this are rejected when reading, so that a corrupted
+/// length can't make the importer allocate arbitrary amounts of memory.
+const MAX_FRAME_SIZE: usize = 1 << 30;
+
+/// Number of changed keys written in a single frame.
+const CHANGES_PER_FRAME: usize = 10_000;
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct SnapshotHeader {
+    pub chain_id: String,
+    pub block_hash: CryptoHash,
+    pub prev_block_hash: CryptoHash,
+    pub block_height: BlockHeight,
+    pub epoch_id: EpochId,
+    pub shard_uid: ShardUId,
+    /// State root of the shard after applying the chunk of the block.
+    pub state_root: StateRoot,
+    /// Set for incremental snapshots only.
+    pub base: Option<SnapshotBase>,
+    /// Header of the block, must match the fields above.
+    pub block_header: BlockHeader,
+    /// Chunk extra of the shard at the block, must have `state_root`.
+    pub chunk_extra: ChunkExtra,
+}
+
+/// The block an incremental snapshot applies on top of.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct SnapshotBase {
+    pub block_hash: CryptoHash,
+    pub block_height: BlockHeight,
+    pub state_root: StateRoot,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub(crate) enum SnapshotFrame {
+    Header(SnapshotHeader),
+    StatePart {
+        part_id: u64,
+        num_parts: u64,
+        nodes: PartialState,
+    },
+    /// Raw trie keys with their new values, `None` meaning deletion.
+    Changes(Vec<(Vec<u8>, Option<Vec<u8>>)>),
+    /// Number of frames between the header and this one.
+    End {
+        num_frames: u64,
+    },
+}
+
+pub(crate) struct SnapshotWriter<W: Write> {
+    encoder: zstd::Encoder<'static, W>,
+    num_frames: u64,
+}
+
+impl<W: Write> SnapshotWriter<W> {
+    pub fn new(mut writer: W, header: SnapshotHeader) -> std::io::Result<Self> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        let encoder = zstd::Encoder::new(writer, 0)?;
+        let mut this = Self { encoder, num_frames: 0 };
+        this.write_raw(&SnapshotFrame::Header(header))?;
+        Ok(this)
+    }
+
+    pub fn write_frame(&mut self, frame: &SnapshotFrame) -> std::io::Result<()> {
+        assert!(matches!(frame, SnapshotFrame::StatePart { .. } | SnapshotFrame::Changes(_)));
+        self.write_raw(frame)?;
+        self.num_frames += 1;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> std::io::Result<W> {
+        self.write_raw(&SnapshotFrame::End { num_frames: self.num_frames })?;
+        self.encoder.finish()
+    }
+
+    fn write_raw(&mut self, frame: &SnapshotFrame) -> std::io::Result<()> {
+        let bytes = borsh::to_vec(frame)?;
+        let len = u32::try_from(bytes.len())
+            .ok()
+            .filter(|len| *len as usize <= MAX_FRAME_SIZE)
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("frame of {} bytes is too large", bytes.len()),
+                )
+            })?;
+        self.encoder.write_all(&len.to_le_bytes())?;
+        self.encoder.write_all(&bytes)
+    }
+}
+
+pub(crate) struct SnapshotReader<R: Read> {
+    decoder: zstd::Decoder<'static, std::io::BufReader<R>>,
+    header: SnapshotHeader,
+    num_frames: u64,
+    finished: bool,
+}
+
+impl<R: Read> SnapshotReader<R> {
+    pub fn new(mut reader: R) -> anyhow::Result<Self> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        anyhow::ensure!(&magic == MAGIC, "not a state snapshot file");
+        let mut version = [0; 4];
+        reader.read_exact(&mut version)?;
+        let version = u32::from_le_bytes(version);
+        anyhow::ensure!(
+            version == FORMAT_VERSION,
+            "unsupported snapshot format version {version}, expected {FORMAT_VERSION}"
+        );
+        let mut decoder = zstd::Decoder::new(reader)?;
+        let SnapshotFrame::Header(header) = read_raw(&mut decoder)? else {
+            anyhow::bail!("the snapshot doesn't start with a header");
+        };
+        Ok(Self { decoder, header, num_frames: 0, finished: false })
+    }
+
+    pub fn header(&self) -> &SnapshotHeader {
+        &self.header
+    }
+
+    /// Returns the next frame, or `None` once the end of the snapshot was
+    /// reached.  Fails if the snapshot is truncated.
+    pub fn next_frame(&mut self) -> anyhow::Result<Option<SnapshotFrame>> {
+        if self.finished {
+            return Ok(None);
+        }
+        match read_raw(&mut self.decoder)? {
+            SnapshotFrame::Header(_) => anyhow::bail!("unexpected header in the snapshot"),
+            SnapshotFrame::End { num_frames } => {
+                anyhow::ensure!(
+                    num_frames == self.num_frames,
+                    "the snapshot has {} frames, expected {num_frames}",
+                    self.num_frames
+                );
+                self.finished = true;
+                Ok(None)
+            }
+            frame => {
+                self.num_frames += 1;
+                Ok(Some(frame))
+            }
+        }
+    }
+}
+
+fn read_raw(reader: &mut impl Read) -> anyhow::Result<SnapshotFrame> {
+    let mut len = [0; 4];
+    reader.read_exact(&mut len).map_err(|err| match err.kind() {
+        std::io::ErrorKind::UnexpectedEof => anyhow::anyhow!("the snapshot is truncated"),
+        _ => err.into(),
+    })?;
+    let len = u32::from_le_bytes(len) as usize;
+    anyhow::ensure!(len <= MAX_FRAME_SIZE, "frame of {len} bytes is too large");
+    let mut bytes = vec![0; len];
+    reader.read_exact(&mut bytes)?;
+    Ok(SnapshotFrame::try_from_slice(&bytes)?)
+}
+
+/// Writes the whole state of the shard as a sequence of state parts.
+pub(crate) fn export_full<W: Write>(
+    store: &Store,
+    header: SnapshotHeader,
+    writer: W,
+) -> anyhow::Result<W> {
+    assert!(header.base.is_none());
+    let storage = TrieDBStorage::new(store.clone(), header.shard_uid);
+    let trie = Trie::new(Rc::new(storage), header.state_root, None);
+    let num_parts = get_num_state_parts(trie.retrieve_root_node()?.memory_usage);
+    let mut writer = SnapshotWriter::new(writer, header)?;
+    for part_id in 0..num_parts {
+        let nodes =
+            trie.get_trie_nodes_for_part_without_flat_storage(PartId::new(part_id, num_parts))?;
+        writer.write_frame(&SnapshotFrame::StatePart { part_id, num_parts, nodes })?;
+        tracing::info!(target: "portable-snapshot", part_id, num_parts, "Exported state part");
+    }
+    Ok(writer.finish()?)
+}
+
+/// Writes the changes of an incremental snapshot.  The changes must have been
+/// checked with [`verify_changes`].
+pub(crate) fn export_incremental<W: Write>(
+    header: SnapshotHeader,
+    changes: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+    writer: W,
+) -> anyhow::Result<W> {
+    assert!(header.base.is_some());
+    let mut writer = SnapshotWriter::new(writer, header)?;
+    let mut changes = changes.into_iter().peekable();
+    while changes.peek().is_some() {
+        let frame = changes.by_ref().take(CHANGES_PER_FRAME).collect();
+        writer.write_frame(&SnapshotFrame::Changes(frame))?;
+    }
+    Ok(writer.finish()?)
+}
+
+/// Collects the final values of all keys of the shard changed in the blocks
+/// after `base_block_hash` up to and including `header`, from the trie changes
+/// which every node keeps for the blocks that are not garbage collected yet.
+fn collect_state_changes(
+    store: &Store,
+    epoch_manager: &dyn EpochManagerAdapter,
+    shard_uid: ShardUId,
+    base_block_hash: &CryptoHash,
+    header: &BlockHeader,
+) -> anyhow::Result<BTreeMap<Vec<u8>, Option<Vec<u8>>>> {
+    let base_header = get_block_header(store, base_block_hash)?;
+    let mut headers = vec![];
+    let mut current = header.clone();
+    while current.height() > base_header.height() {
+        let prev_hash = *current.prev_hash();
+        headers.push(current);
+        current = get_block_header(store, &prev_hash)?;
+    }
+    anyhow::ensure!(
+        current.hash() == base_header.hash(),
+        "base block {base_block_hash} is not an ancestor of block {}",
+        header.hash()
+    );
+
+    let mut trie_changes = vec![];
+    for header in &headers {
+        let block_hash = header.hash();
+        anyhow::ensure!(
+            epoch_manager.shard_id_to_uid(shard_uid.shard_id.into(), header.epoch_id())?
+                == shard_uid,
+            "shard layout changed at block {block_hash}"
+        );
+        let changes = store
+            .get_ser::<TrieChanges>(
+                DBCol::TrieChanges,
+                &get_block_shard_uid(block_hash, &shard_uid),
+            )?
+            .ok_or_else(|| {
+                anyhow::anyhow!("no trie changes of {shard_uid} at block {block_hash}")
+            })?;
+        trie_changes.push(changes);
+    }
+    diff_states(
+        store,
+        shard_uid,
+        &get_state_root(store, base_block_hash, shard_uid)?,
+        &get_state_root(store, header.hash(), shard_uid)?,
+        &trie_changes,
+    )
+}
+
+/// Returns the keys whose values differ between the states at `base_root` and
+/// `root`, given the trie changes of all the blocks between them.
+///
+/// Every trie node on the path to a changed key is replaced, so only the nodes
+/// inserted by `trie_changes` have to be visited in the new state and only the
+/// deleted ones in the old state.
+fn diff_states(
+    store: &Store,
+    shard_uid: ShardUId,
+    base_root: &StateRoot,
+    root: &StateRoot,
+    trie_changes: &[TrieChanges],
+) -> anyhow::Result<BTreeMap<Vec<u8>, Option<Vec<u8>>>> {
+    let inserted: HashSet<CryptoHash> = trie_changes
+        .iter()
+        .flat_map(|changes| changes.insertions())
+        .map(|node| *node.hash())
+        .collect();
+    let deleted: HashSet<CryptoHash> = trie_changes
+        .iter()
+        .flat_map(|changes| changes.deletions())
+        .map(|node| *node.hash())
+        .collect();
+    let storage = TrieDBStorage::new(store.clone(), shard_uid);
+    let base_trie =
+        Trie::new(Rc::new(TrieDBStorage::new(store.clone(), shard_uid)), *base_root, None);
+    let trie = Trie::new(Rc::new(TrieDBStorage::new(store.clone(), shard_uid)), *root, None);
+
+    // Restructuring the trie also replaces nodes holding unchanged values, so
+    // the values found have to be compared with the other state.
+    let mut new_values = vec![];
+    collect_values(&storage, root, &mut vec![], &inserted, &mut new_values)?;
+    let mut changes = BTreeMap::new();
+    for (key, value) in new_values {
+        if base_trie.get(&key)?.map(|old_value| hash(&old_value)) == Some(value.hash) {
+            continue;
+        }
+        changes.insert(key, Some(storage.retrieve_raw_bytes(&value.hash)?.to_vec()));
+    }
+    let mut old_values = vec![];
+    collect_values(&storage, base_root, &mut vec![], &deleted, &mut old_values)?;
+    for (key, _) in old_values {
+        if !changes.contains_key(&key) && trie.get(&key)?.is_none() {
+            changes.insert(key, None);
+        }
+    }
+    Ok(changes)
+}
+
+/// Collects the keys and value references stored under the node `hash`,
+/// descending only into the nodes in `visit`.
+fn collect_values(
+    storage: &TrieDBStorage,
+    hash: &CryptoHash,
+    key_nibbles: &mut Vec<u8>,
+    visit: &HashSet<CryptoHash>,
+    values: &mut Vec<(Vec<u8>, ValueRef)>,
+) -> anyhow::Result<()> {
+    if hash == &Trie::EMPTY_ROOT || !visit.contains(hash) {
+        return Ok(());
+    }
+    let node = RawTrieNodeWithSize::try_from_slice(&storage.retrieve_raw_bytes(hash)?)?.node;
+    let len = key_nibbles.len();
+    match node {
+        RawTrieNode::Leaf(key, value) => {
+            key_nibbles.extend(NibbleSlice::from_encoded(&key).0.iter());
+            values.push((nibbles_to_bytes(key_nibbles)?, value));
+        }
+        RawTrieNode::BranchNoValue(children) => {
+            collect_children(storage, &children, key_nibbles, visit, values)?;
+        }
+        RawTrieNode::BranchWithValue(value, children) => {
+            values.push((nibbles_to_bytes(key_nibbles)?, value));
+            collect_children(storage, &children, key_nibbles, visit, values)?;
+        }
+        RawTrieNode::Extension(key, child) => {
+            key_nibbles.extend(NibbleSlice::from_encoded(&key).0.iter());
+            collect_values(storage, &child, key_nibbles, visit, values)?;
+        }
+    }
+    key_nibbles.truncate(len);
+    Ok(())
+}
+
+fn collect_children(
+    storage: &TrieDBStorage,
+    children: &Children,
+    key_nibbles: &mut Vec<u8>,
+    visit: &HashSet<CryptoHash>,
+    values: &mut Vec<(Vec<u8>, ValueRef)>,
+) -> anyhow::Result<()> {
+    for (nibble, child) in children.iter() {
+        key_nibbles.push(nibble);
+        collect_values(storage, child, key_nibbles, visit, values)?;
+        key_nibbles.pop();
+    }
+    Ok(())
+}
+
+fn nibbles_to_bytes(nibbles: &[u8]) -> anyhow::Result<Vec<u8>> {
+    anyhow::ensure!(nibbles.len() % 2 == 0, "value at an odd number of nibbles");
+    Ok(nibbles.chunks(2).map(|pair| pair[0] << 4 | pair[1]).collect())
+}
+
+/// Checks that applying `changes` to `base_root` results in `expected_root`,
+/// using the `State` of the shard in `store`.
+fn verify_changes(
+    store: &Store,
+    shard_uid: ShardUId,
+    base_root: StateRoot,
+    changes: &BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+    expected_root: StateRoot,
+) -> anyhow::Result<()> {
+    let storage = TrieDBStorage::new(store.clone(), shard_uid);
+    let trie = Trie::new(Rc::new(storage), base_root, None);
+    let trie_changes = trie.update(changes.iter().map(|(k, v)| (k.clone(), v.clone())))?;
+    anyhow::ensure!(
+        trie_changes.new_root == expected_root,
+        "state changes result in state root {}, expected {expected_root}",
+        trie_changes.new_root
+    );
+    Ok(())
+}
+
+/// Writes the state from the snapshot into `store` and returns its header.
+///
+/// A full snapshot can only be imported if the node doesn't have flat storage
+/// of the shard yet.  An incremental one can only be imported on top of the
+/// state of its base block.  In both cases flat storage of the shard is ready
+/// at the snapshot block afterwards, and the header and the chunk extra of that
+/// block are in the database.  If importing a full snapshot fails
+/// midway, the state parts imported so far stay in the database.
+pub(crate) fn import_snapshot<R: Read>(
+    store: &Store,
+    reader: R,
+    chain_id: &str,
+) -> anyhow::Result<SnapshotHeader> {
+    let mut reader = SnapshotReader::new(reader)?;
+    let header = reader.header().clone();
+    anyhow::ensure!(
+        header.chain_id == chain_id,
+        "snapshot of chain {} can't be imported into chain {chain_id}",
+        header.chain_id
+    );
+    let block_header = &header.block_header;
+    anyhow::ensure!(
+        block_header.hash() == &header.block_hash
+            && block_header.prev_hash() == &header.prev_block_hash
+            && block_header.height() == header.block_height
+            && block_header.epoch_id() == &header.epoch_id,
+        "block header {} in the snapshot doesn't match block {}",
+        block_header.hash(),
+        header.block_hash
+    );
+    anyhow::ensure!(
+        header.chunk_extra.state_root() == &header.state_root,
+        "chunk extra in the snapshot has state root {}, expected {}",
+        header.chunk_extra.state_root(),
+        header.state_root
+    );
+    let shard_uid = header.shard_uid;
+    // Only fails if the database knows the block and disagrees with the snapshot.
+    if let Some(chunk_extra) = store.get_ser::<ChunkExtra>(
+        DBCol::ChunkExtra,
+        &get_block_shard_uid(&header.block_hash, &shard_uid),
+    )? {
+        anyhow::ensure!(
+            chunk_extra.state_root() == &header.state_root,
+            "state root {} of block {} in the database doesn't match the snapshot",
+            chunk_extra.state_root(),
+            header.block_hash
+        );
+    }
+
+    let tries = ShardTries::new(
+        store.clone(),
+        TrieConfig::default(),
+        &[shard_uid],
+        FlatStorageManager::new(store.clone()),
+        StateSnapshotConfig::default(),
+    );
+    let flat_storage_status = store_helper::get_flat_storage_status(store, shard_uid)?;
+    match &header.base {
+        None => {
+            anyhow::ensure!(
+                matches!(flat_storage_status, FlatStorageStatus::Empty),
+                "flat storage of {shard_uid} already exists: {flat_storage_status:?}"
+            );
+            import_state_parts(&tries, &header, &mut reader)?;
+        }
+        Some(base) => {
+            let FlatStorageStatus::Ready(status) = flat_storage_status else {
+                anyhow::bail!("flat storage of {shard_uid} is not ready: {flat_storage_status:?}");
+            };
+            anyhow::ensure!(
+                status.flat_head.hash == base.block_hash,
+                "the state of {shard_uid} is at block {}, the snapshot applies on top of block {}",
+                status.flat_head.hash,
+                base.block_hash
+            );
+            import_changes(&tries, &header, base, &mut reader)?;
+        }
+    }
+
+    // Flat storage is only ready at a block the node has the header and the
+    // chunk extra of, so they are written in the same update.
+    let mut store_update = store.store_update();
+    store_update.set_ser(DBCol::BlockHeader, header.block_hash.as_ref(), &header.block_header)?;
+    store_update.set_ser(
+        DBCol::ChunkExtra,
+        &get_block_shard_uid(&header.block_hash, &shard_uid),
+        &header.chunk_extra,
+    )?;
+    store_helper::set_flat_storage_status(
+        &mut store_update,
+        shard_uid,
+        FlatStorageStatus::Ready(FlatStorageReadyStatus {
+            flat_head: BlockInfo {
+                hash: header.block_hash,
+                height: header.block_height,
+                prev_hash: header.prev_block_hash,
+            },
+        }),
+    );
+    store_update.commit()?;
+    Ok(header)
+}
+
+fn import_state_parts<R: Read>(
+    tries: &ShardTries,
+    header: &SnapshotHeader,
+    reader: &mut SnapshotReader<R>,
+) -> anyhow::Result<()> {
+    let mut expected_part_id = 0;
+    let mut expected_num_parts = None;
+    while let Some(frame) = reader.next_frame()? {
+        let SnapshotFrame::StatePart { part_id, num_parts, nodes } = frame else {
+            anyhow::bail!("unexpected frame in a full snapshot");
+        };
+        anyhow::ensure!(
+            part_id == expected_part_id
+                && part_id < num_parts
+                && *expected_num_parts.get_or_insert(num_parts) == num_parts,
+            "unexpected state part {part_id} of {num_parts}"
+        );
+        let part_id = PartId::new(part_id, num_parts);
+        Trie::validate_state_part(&header.state_root, part_id, nodes.clone())?;
+        let result = Trie::apply_state_part(&header.state_root, part_id, nodes);
+        let mut store_update = tries.store_update();
+        tries.apply_all(&result.trie_changes, header.shard_uid, &mut store_update);
+        result.flat_state_delta.apply_to_flat_state(&mut store_update, header.shard_uid);
+        store_update.commit()?;
+        tracing::info!(target: "portable-snapshot", part_id = part_id.idx, num_parts, "Imported state part");
+        expected_part_id += 1;
+    }
+    anyhow::ensure!(
+        expected_num_parts.map_or(header.state_root == Trie::EMPTY_ROOT, |num_parts| {
+            expected_part_id == num_parts
+        }),
+        "the snapshot is missing state parts"
+    );
+    Ok(())
+}
+
+fn import_changes<R: Read>(
+    tries: &ShardTries,
+    header: &SnapshotHeader,
+    base: &SnapshotBase,
+    reader: &mut SnapshotReader<R>,
+) -> anyhow::Result<()> {
+    let mut changes = vec![];
+    while let Some(frame) = reader.next_frame()? {
+        let SnapshotFrame::Changes(frame) = frame else {
+            anyhow::bail!("unexpected frame in an incremental snapshot");
+        };
+        changes.extend(frame);
+    }
+    let trie = tries.get_trie_for_shard(header.shard_uid, base.state_root);
+    let trie_changes = trie.update(changes.iter().cloned())?;
+    anyhow::ensure!(
+        trie_changes.new_root == header.state_root,
+        "changes result in state root {}, expected {}",
+        trie_changes.new_root,
+        header.state_root
+    );
+    let mut flat_state_changes = FlatStateChanges::default();
+    for (key, value) in changes {
+        flat_state_changes.insert(
+            key,
+            value.map(|value| near_primitives::state::FlatStateValue::on_disk(&value)),
+        );
+    }
+    let mut store_update = tries.store_update();
+    tries.apply_all(&trie_changes, header.shard_uid, &mut store_update);
+    flat_state_changes.apply_to_flat_state(&mut store_update, header.shard_uid);
+    store_update.commit()?;
+    Ok(())
+}
+
+fn get_block_header(store: &Store, block_hash: &CryptoHash) -> anyhow::Result<BlockHeader> {
+    store
+        .get_ser(DBCol::BlockHeader, block_hash.as_ref())?
+        .ok_or_else(|| anyhow::anyhow!("block header {block_hash} not found"))
+}
+
+fn get_chunk_extra(
+    store: &Store,
+    block_hash: &CryptoHash,
+    shard_uid: ShardUId,
+) -> anyhow::Result<ChunkExtra> {
+    store
+        .get_ser::<ChunkExtra>(DBCol::ChunkExtra, &get_block_shard_uid(block_hash, &shard_uid))?
+        .ok_or_else(|| anyhow::anyhow!("no chunk extra of {shard_uid} at block {block_hash}"))
+}
+
+fn get_state_root(
+    store: &Store,
+    block_hash: &CryptoHash,
+    shard_uid: ShardUId,
+) -> anyhow::Result<StateRoot> {
+    Ok(*get_chunk_extra(store, block_hash, shard_uid)?.state_root())
+}
+
+/// Exports the state of a shard in the portable snapshot format.
+#[derive(clap::Parser)]
+pub(crate) struct ExportStateSnapshotCommand {
+    /// Shard to export.
+    #[clap(long)]
+    shard_id: ShardId,
+    /// Block to export the state at.  Defaults to the final head.
+    #[clap(long)]
+    block_hash: Option<CryptoHash>,
+    /// Export only the changes since this block, which must be an ancestor of
+    /// the exported block.  Neither block may be garbage collected yet.
+    #[clap(long)]
+    base_block_hash: Option<CryptoHash>,
+    /// Output file.
+    #[clap(long)]
+    output: PathBuf,
+}
+
+impl ExportStateSnapshotCommand {
+    pub(crate) fn run(&self, home: &Path, near_config: NearConfig) -> anyhow::Result<()> {
+        let store = open_store(home, near_store::Mode::ReadOnly)?;
+        let epoch_manager =
+            EpochManager::new_arc_handle(store.clone(), &near_config.genesis.config);
+
+        let block_hash = match self.block_hash {
+            Some(block_hash) => block_hash,
+            None => {
+                store
+                    .get_ser::<near_primitives::block::Tip>(DBCol::BlockMisc, FINAL_HEAD_KEY)?
+                    .ok_or_else(|| anyhow::anyhow!("final head not found"))?
+                    .last_block_hash
+            }
+        };
+        let block_header = get_block_header(&store, &block_hash)?;
+        let shard_uid = epoch_manager.shard_id_to_uid(self.shard_id, block_header.epoch_id())?;
+        let chunk_extra = get_chunk_extra(&store, &block_hash, shard_uid)?;
+        let mut header = SnapshotHeader {
+            chain_id: near_config.genesis.config.chain_id.clone(),
+            block_hash,
+            prev_block_hash: *block_header.prev_hash(),
+            block_height: block_header.height(),
+            epoch_id: block_header.epoch_id().clone(),
+            shard_uid,
+            state_root: *chunk_extra.state_root(),
+            base: None,
+            block_header: block_header.clone(),
+            chunk_extra,
+        };
+
+        let output = BufWriter::new(File::create(&self.output)?);
+        let output = match self.base_block_hash {
+            None => export_full(&store, header, output)?,
+            Some(base_block_hash) => {
+                let base = SnapshotBase {
+                    block_hash: base_block_hash,
+                    block_height: get_block_header(&store, &base_block_hash)?.height(),
+                    state_root: get_state_root(&store, &base_block_hash, shard_uid)?,
+                };
+                let changes = collect_state_changes(
+                    &store,
+                    epoch_manager.as_ref(),
+                    shard_uid,
+                    &base_block_hash,
+                    &block_header,
+                )?;
+                verify_changes(&store, shard_uid, base.state_root, &changes, header.state_root)?;
+                println!("Exporting {} changed keys", changes.len());
+                header.base = Some(base);
+                export_incremental(header, changes, output)?
+            }
+        };
+        output.into_inner().map_err(|err| err.into_error())?.sync_all()?;
+        Ok(())
+    }
+}
+
+/// Imports a snapshot written by `export-state-snapshot`.  The node must not
+/// be running.
+#[derive(clap::Parser)]
+pub(crate) struct ImportStateSnapshotCommand {
+    /// Snapshot file to import.
+    #[clap(long)]
+    input: PathBuf,
+}
+
+impl ImportStateSnapshotCommand {
+    pub(crate) fn run(&self, home: &Path, near_config: NearConfig) -> anyhow::Result<()> {
+        let store = open_store(home, near_store::Mode::ReadWrite)?;
+        let input = std::io::BufReader::new(File::open(&self.input)?);
+        let header = import_snapshot(&store, input, &near_config.genesis.config.chain_id)?;
+        println!(
+            "Imported state of {} at block {} (height {}), state root {}",
+            header.shard_uid, header.block_hash, header.block_height, header.state_root
+        );
+        Ok(())
+    }
+}
+
+fn open_store(home: &Path, mode: near_store::Mode) -> anyhow::Result<Store> {
+    let rocksdb = Arc::new(open_rocksdb(home, mode)?);
+    Ok(near_store::NodeStorage::new(rocksdb).get_hot_store())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        diff_states, export_full, export_incremental, get_block_header, get_chunk_extra,
+        import_snapshot, SnapshotBase, SnapshotHeader, SnapshotReader, MAGIC,
+    };
+    use near_primitives::block_header::BlockHeader;
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::types::chunk_extra::ChunkExtra;
+    use near_primitives::types::StateRoot;
+    use near_primitives::utils::from_timestamp;
+    use near_primitives::version::PROTOCOL_VERSION;
+    use near_store::flat::{store_helper, FlatStorageStatus};
+    use near_store::test_utils::{create_test_store, test_populate_trie, TestTriesBuilder};
+    use near_store::{ShardUId, Store, Trie};
+    use std::collections::BTreeMap;
+
+    fn header(block_height: u64, state_root: StateRoot) -> SnapshotHeader {
+        let block_header = BlockHeader::genesis(
+            PROTOCOL_VERSION,
+            block_height,
+            state_root,
+            CryptoHash::default(),
+            CryptoHash::default(),
+            CryptoHash::default(),
+            CryptoHash::default(),
+            1,
+            CryptoHash::default(),
+            from_timestamp(0),
+            0,
+            0,
+            CryptoHash::default(),
+        );
+        SnapshotHeader {
+            chain_id: "test".to_string(),
+            block_hash: *block_header.hash(),
+            prev_block_hash: *block_header.prev_hash(),
+            block_height,
+            epoch_id: block_header.epoch_id().clone(),
+            shard_uid: ShardUId::single_shard(),
+            state_root,
+            base: None,
+            chunk_extra: ChunkExtra::new_with_only_state_root(&state_root),
+            block_header,
+        }
+    }
+
+    fn read_all(store: &Store, state_root: StateRoot) -> Vec<(Vec<u8>, Vec<u8>)> {
+        let tries = TestTriesBuilder::new().with_store(store.clone()).build();
+        let trie = tries.get_trie_for_shard(ShardUId::single_shard(), state_root);
+        trie.iter().unwrap().map(Result::unwrap).collect()
+    }
+
+    fn changes(range: std::ops::Range<u8>, value: u8) -> Vec<(Vec<u8>, Option<Vec<u8>>)> {
+        range.map(|i| (vec![i, i, i], Some(vec![value; i as usize + 1]))).collect()
+    }
+
+    /// Exports the state at one root fully and the changes to another root
+    /// incrementally, then imports both into an empty database.
+    #[test]
+    fn test_full_and_incremental_round_trip() {
+        let shard_uid = ShardUId::single_shard();
+        let source = create_test_store();
+        let tries = TestTriesBuilder::new().with_store(source.clone()).build();
+        let root1 = test_populate_trie(&tries, &Trie::EMPTY_ROOT, shard_uid, changes(0..100, 1));
+        let mut update = changes(50..150, 2);
+        update.push((vec![3, 3, 3], None));
+        let root2 = test_populate_trie(&tries, &root1, shard_uid, update.clone());
+
+        let full = export_full(&source, header(10, root1), vec![]).unwrap();
+        let mut incremental_header = header(20, root2);
+        incremental_header.base = Some(SnapshotBase {
+            block_hash: header(10, root1).block_hash,
+            block_height: 10,
+            state_root: root1,
+        });
+        let incremental =
+            export_incremental(incremental_header, update.into_iter().collect(), vec![]).unwrap();
+
+        let target = create_test_store();
+        // The incremental snapshot needs the state of its base.
+        assert!(import_snapshot(&target, incremental.as_slice(), "test").is_err());
+        assert!(import_snapshot(&target, full.as_slice(), "other").is_err());
+        import_snapshot(&target, full.as_slice(), "test").unwrap();
+        assert_eq!(read_all(&target, root1), read_all(&source, root1));
+        // A full snapshot can't be imported over existing state.
+        assert!(import_snapshot(&target, full.as_slice(), "test").is_err());
+
+        import_snapshot(&target, incremental.as_slice(), "test").unwrap();
+        assert_eq!(read_all(&target, root2), read_all(&source, root2));
+        let FlatStorageStatus::Ready(status) =
+            store_helper::get_flat_storage_status(&target, shard_uid).unwrap()
+        else {
+            panic!("flat storage is not ready");
+        };
+        assert_eq!(status.flat_head.height, 20);
+        // The node knows the block flat storage is at.
+        let block_hash = status.flat_head.hash;
+        assert_eq!(get_block_header(&target, &block_hash).unwrap().height(), 20);
+        assert_eq!(get_chunk_extra(&target, &block_hash, shard_uid).unwrap().state_root(), &root2);
+        // Flat storage must have exactly the keys of the new state.
+        let flat_state_keys: Vec<_> =
+            store_helper::iter_flat_state_entries(shard_uid, &target, None, None)
+                .map(|entry| entry.unwrap().0)
+                .collect();
+        let trie_keys: Vec<_> = read_all(&target, root2).into_iter().map(|(key, _)| key).collect();
+        assert_eq!(flat_state_keys, trie_keys);
+    }
+
+    #[test]
+    fn test_rejects_invalid_snapshots() {
+        let shard_uid = ShardUId::single_shard();
+        let source = create_test_store();
+        let tries = TestTriesBuilder::new().with_store(source.clone()).build();
+        let root = test_populate_trie(&tries, &Trie::EMPTY_ROOT, shard_uid, changes(0..100, 1));
+        let full = export_full(&source, header(10, root), vec![]).unwrap();
+
+        assert!(SnapshotReader::new(&full[..MAGIC.len()]).is_err());
+        let mut bad_magic = full.clone();
+        bad_magic[0] ^= 1;
+        assert!(SnapshotReader::new(bad_magic.as_slice()).is_err());
+        let mut bad_version = full.clone();
+        bad_version[MAGIC.len()] += 1;
+        assert!(SnapshotReader::new(bad_version.as_slice()).is_err());
+        let truncated = &full[..full.len() - 16];
+        assert!(import_snapshot(&create_test_store(), truncated, "test").is_err());
+
+        // State parts are checked against the state root in the header.
+        let forged = export_full(&source, header(10, CryptoHash::hash_bytes(b"forged")), vec![]);
+        assert!(forged.is_err());
+        let mut forged_header = header(10, root);
+        forged_header.state_root = CryptoHash::hash_bytes(b"forged");
+        forged_header.chunk_extra = ChunkExtra::new_with_only_state_root(&forged_header.state_root);
+        let mut reader = SnapshotReader::new(full.as_slice()).unwrap();
+        let mut writer = super::SnapshotWriter::new(vec![], forged_header).unwrap();
+        while let Some(frame) = reader.next_frame().unwrap() {
+            writer.write_frame(&frame).unwrap();
+        }
+        let forged = writer.finish().unwrap();
+        assert!(import_snapshot(&create_test_store(), forged.as_slice(), "test").is_err());
+
+        let mut incremental_header = header(20, root);
+        incremental_header.base = Some(SnapshotBase {
+            block_hash: header(10, root).block_hash,
+            block_height: 10,
+            state_root: root,
+        });
+        let wrong_changes = BTreeMap::from([(vec![1, 1, 1], Some(vec![42]))]);
+        let incremental = export_incremental(incremental_header, wrong_changes, vec![]).unwrap();
+        let target = create_test_store();
+        import_snapshot(&target, full.as_slice(), "test").unwrap();
+        assert!(import_snapshot(&target, incremental.as_slice(), "test").is_err());
+    }
+
+    /// The snapshot header must be consistent with the block header and the
+    /// chunk extra it carries.
+    #[test]
+    fn test_rejects_inconsistent_header() {
+        let shard_uid = ShardUId::single_shard();
+        let source = create_test_store();
+        let tries = TestTriesBuilder::new().with_store(source.clone()).build();
+        let root = test_populate_trie(&tries, &Trie::EMPTY_ROOT, shard_uid, changes(0..10, 1));
+
+        let mut wrong_block = header(10, root);
+        wrong_block.block_header = header(11, root).block_header;
+        let snapshot = export_full(&source, wrong_block, vec![]).unwrap();
+        assert!(import_snapshot(&create_test_store(), snapshot.as_slice(), "test").is_err());
+
+        let mut wrong_chunk_extra = header(10, root);
+        wrong_chunk_extra.chunk_extra = ChunkExtra::new_with_only_state_root(&Trie::EMPTY_ROOT);
+        let snapshot = export_full(&source, wrong_chunk_extra, vec![]).unwrap();
+        let target = create_test_store();
+        assert!(import_snapshot(&target, snapshot.as_slice(), "test").is_err());
+        let status = store_helper::get_flat_storage_status(&target, shard_uid).unwrap();
+        assert!(matches!(status, FlatStorageStatus::Empty));
+    }
+
+    /// The changes between two states are found from the trie changes of the
+    /// blocks between them.
+    #[test]
+    fn test_diff_states() {
+        let shard_uid = ShardUId::single_shard();
+        let store = create_test_store();
+        let tries = TestTriesBuilder::new().with_store(store.clone()).build();
+        let base_root =
+            test_populate_trie(&tries, &Trie::EMPTY_ROOT, shard_uid, changes(0..100, 1));
+
+        let mut root = base_root;
+        let mut trie_changes = vec![];
+        let mut expected = BTreeMap::new();
+        let mut updates = vec![changes(50..120, 2), changes(110..150, 3)];
+        updates[0].push((vec![3, 3, 3], None));
+        updates[1].push((vec![4, 4, 4], None));
+        // Created and deleted again in between.
+        updates[0].push((vec![200], Some(vec![1])));
+        updates[1].push((vec![200], None));
+        for update in updates {
+            let block_changes =
+                tries.get_trie_for_shard(shard_uid, root).update(update.clone()).unwrap();
+            let mut store_update = tries.store_update();
+            root = tries.apply_all(&block_changes, shard_uid, &mut store_update);
+            store_update.commit().unwrap();
+            trie_changes.push(block_changes);
+            expected.extend(update);
+        }
+        expected.remove(&vec![200]);
+
+        let diff = diff_states(&store, shard_uid, &base_root, &root, &trie_changes).unwrap();
+        assert_eq!(diff, expected);
+        assert!(diff_states(&store, shard_uid, &root, &root, &[]).unwrap().is_empty());
+    }
+}
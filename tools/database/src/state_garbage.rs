@@ -0,0 +1,72 @@
+use crate::utils::open_rocksdb;
+use near_epoch_manager::shard_tracker::{ShardTracker, TrackedConfig};
+use near_epoch_manager::EpochManager;
+use near_primitives::types::ShardId;
+use near_store::state_garbage_auditor::{audit_state_garbage, AuditMode};
+use nearcore::state_garbage_auditor::head_state_roots;
+use nearcore::NearConfig;
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+/// Finds `State` entries which can't be reached from the chain anymore and
+/// removes them.  The node must not be running.
+#[derive(clap::Parser)]
+pub struct AuditStateGarbageCommand {
+    /// Audit only this shard.  By default all shards of the head epoch are
+    /// audited.
+    #[clap(long)]
+    shard_id: Option<ShardId>,
+    /// Only report unreachable entries without removing them.
+    #[clap(long)]
+    dry_run: bool,
+}
+
+impl AuditStateGarbageCommand {
+    pub fn run(&self, near_config: NearConfig, home: &Path) -> anyhow::Result<()> {
+        let mode =
+            if self.dry_run { near_store::Mode::ReadOnly } else { near_store::Mode::ReadWrite };
+        let rocksdb = Arc::new(open_rocksdb(home, mode)?);
+        let store = near_store::NodeStorage::new(rocksdb).get_hot_store();
+        let epoch_manager =
+            EpochManager::new_arc_handle(store.clone(), &near_config.genesis.config);
+        let shard_tracker = ShardTracker::new(
+            TrackedConfig::from_config(&near_config.client_config),
+            epoch_manager.clone(),
+        );
+        let me = near_config.validator_signer.as_ref().map(|signer| signer.validator_id());
+
+        let audit_mode = if self.dry_run { AuditMode::DryRun } else { AuditMode::Remove };
+        let mut auditor_config = near_config.config.store.state_garbage_auditor.clone();
+        // There is no node to leave room for.
+        auditor_config.batch_delay = std::time::Duration::ZERO;
+        let keep_going = AtomicBool::new(true);
+        let state_roots = head_state_roots(&store, epoch_manager.as_ref(), &shard_tracker, me)?;
+        for (shard_uid, state_root) in state_roots {
+            if self.shard_id.is_some_and(|shard_id| u64::from(shard_uid.shard_id) != shard_id) {
+                continue;
+            }
+            let Some(state_root) = state_root else {
+                println!("{shard_uid}: skipped, the node doesn't track this shard");
+                continue;
+            };
+            let report = audit_state_garbage(
+                &store,
+                shard_uid,
+                state_root,
+                &auditor_config,
+                audit_mode,
+                &keep_going,
+            )?;
+            println!(
+                "{shard_uid}: {} entries, {} live, {} unreachable taking {} bytes{}",
+                report.total_entries,
+                report.live_nodes,
+                report.unreachable_entries,
+                report.unreachable_bytes,
+                if self.dry_run { "" } else { " removed" },
+            );
+        }
+        Ok(())
+    }
+}
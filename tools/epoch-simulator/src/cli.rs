@@ -0,0 +1,182 @@
+use anyhow::Context;
+use near_chain::{ChainStore, ChainStoreAccess};
+use near_chain_configs::GenesisValidationMode;
+use near_epoch_manager::simulation::{EpochSimulationState, EpochSimulator};
+use near_epoch_manager::EpochManager;
+use near_primitives::serialize::dec_format;
+use near_primitives::types::validator_stake::ValidatorStake;
+use near_primitives::types::{AccountId, AccountInfo, Balance, NumSeats};
+use near_primitives::version::{ProtocolVersion, PROTOCOL_VERSION};
+use near_store::{Mode, NodeStorage};
+use nearcore::{load_config, NearConfig};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+#[derive(clap::Parser)]
+pub struct EpochSimulatorCommand {
+    /// JSON file with validators of the current epoch and stake proposals,
+    /// used instead of the node database.
+    #[clap(long)]
+    snapshot: Option<PathBuf>,
+
+    /// Number of epochs to simulate.
+    #[clap(long, default_value = "5")]
+    epochs: u64,
+
+    /// Account which unstakes in the current epoch. Can be repeated.
+    #[clap(long)]
+    unstake: Vec<AccountId>,
+
+    /// Stake proposal made in the current epoch, as ACCOUNT=AMOUNT with
+    /// the amount in yoctoNEAR. Can be repeated.
+    #[clap(long, value_parser = parse_stake)]
+    stake: Vec<(AccountId, Balance)>,
+
+    /// Percentage of the assigned blocks and chunks produced by an account,
+    /// as ACCOUNT=PERCENT. Can be repeated.
+    #[clap(long, value_parser = parse_uptime)]
+    uptime: Vec<(AccountId, u8)>,
+
+    /// Percentage of the assigned blocks and chunks produced by accounts
+    /// without an --uptime.
+    #[clap(long, default_value = "100", value_parser = clap::value_parser!(u8).range(0..=100))]
+    default_uptime: u8,
+
+    /// Overrides the number of block producer seats.
+    #[clap(long)]
+    num_block_producer_seats: Option<NumSeats>,
+
+    /// Overrides the number of chunk-only producer seats.
+    #[clap(long)]
+    num_chunk_only_producer_seats: Option<NumSeats>,
+
+    /// Protocol version of the simulated epochs. Defaults to the version of
+    /// the next epoch, or to the latest version with --snapshot.
+    #[clap(long)]
+    protocol_version: Option<ProtocolVersion>,
+}
+
+/// Stake distribution to start the simulation from.
+#[derive(serde::Deserialize)]
+struct Snapshot {
+    /// Validators of the current epoch, in the same format as in genesis.
+    validators: Vec<AccountInfo>,
+    /// Stake proposals made in the current epoch.
+    #[serde(default)]
+    proposals: Vec<AccountInfo>,
+    /// Defaults to the total supply from genesis.
+    #[serde(default, with = "dec_format")]
+    total_supply: Option<Balance>,
+}
+
+fn validator_stake(info: AccountInfo) -> ValidatorStake {
+    ValidatorStake::new(info.account_id, info.public_key, info.amount)
+}
+
+fn parse_stake(arg: &str) -> Result<(AccountId, Balance), String> {
+    let (account_id, amount) = arg.split_once('=').ok_or("expected ACCOUNT=AMOUNT")?;
+    let account_id = account_id.parse().map_err(|err| format!("invalid account id: {err}"))?;
+    let amount = amount.parse().map_err(|err| format!("invalid amount: {err}"))?;
+    Ok((account_id, amount))
+}
+
+fn parse_uptime(arg: &str) -> Result<(AccountId, u8), String> {
+    let (account_id, percent) = arg.split_once('=').ok_or("expected ACCOUNT=PERCENT")?;
+    let account_id = account_id.parse().map_err(|err| format!("invalid account id: {err}"))?;
+    let percent = percent.parse().map_err(|err| format!("invalid percentage: {err}"))?;
+    if percent > 100 {
+        return Err(format!("percentage {percent} is above 100"));
+    }
+    Ok((account_id, percent))
+}
+
+impl EpochSimulatorCommand {
+    pub fn run(
+        self,
+        home_dir: &Path,
+        genesis_validation: GenesisValidationMode,
+    ) -> anyhow::Result<()> {
+        let near_config = load_config(home_dir, genesis_validation)
+            .unwrap_or_else(|e| panic!("Error loading config: {:#}", e));
+        let (simulator, mut state) = match &self.snapshot {
+            Some(path) => self.load_snapshot(&near_config, path)?,
+            None => self.load_from_db(&near_config, home_dir)?,
+        };
+        for account_id in &self.unstake {
+            state.propose(account_id.clone(), 0);
+        }
+        for (account_id, stake) in &self.stake {
+            state.propose(account_id.clone(), *stake);
+        }
+
+        let reports = crate::simulate(&simulator, &mut state, self.epochs)?;
+        println!("{}", serde_json::to_string_pretty(&reports)?);
+        Ok(())
+    }
+
+    fn simulator(
+        &self,
+        near_config: &NearConfig,
+        protocol_version: ProtocolVersion,
+    ) -> EpochSimulator {
+        let mut simulator =
+            EpochSimulator::from_genesis_config(&near_config.genesis.config, protocol_version);
+        if let Some(seats) = self.num_block_producer_seats {
+            simulator.config.num_block_producer_seats = seats;
+        }
+        if let Some(seats) = self.num_chunk_only_producer_seats {
+            simulator.config.validator_selection_config.num_chunk_only_producer_seats = seats;
+        }
+        simulator.default_uptime = self.default_uptime;
+        simulator.uptime = self.uptime.iter().cloned().collect();
+        simulator
+    }
+
+    fn load_snapshot(
+        &self,
+        near_config: &NearConfig,
+        path: &Path,
+    ) -> anyhow::Result<(EpochSimulator, EpochSimulationState)> {
+        let file = File::open(path).with_context(|| format!("failed to open {path:?}"))?;
+        let snapshot: Snapshot = serde_json::from_reader(BufReader::new(file))
+            .with_context(|| format!("failed to parse {path:?}"))?;
+        let simulator =
+            self.simulator(near_config, self.protocol_version.unwrap_or(PROTOCOL_VERSION));
+        let validators = snapshot.validators.into_iter().map(validator_stake).collect();
+        let total_supply = snapshot.total_supply.unwrap_or(near_config.genesis.config.total_supply);
+        let mut state = simulator.genesis_state(validators, total_supply)?;
+        for info in snapshot.proposals {
+            state.proposals.insert(info.account_id.clone(), validator_stake(info));
+        }
+        Ok((simulator, state))
+    }
+
+    fn load_from_db(
+        &self,
+        near_config: &NearConfig,
+        home_dir: &Path,
+    ) -> anyhow::Result<(EpochSimulator, EpochSimulationState)> {
+        let store_opener = NodeStorage::opener(
+            home_dir,
+            near_config.config.archive,
+            &near_config.config.store,
+            None,
+        );
+        let storage = store_opener.open_in_mode(Mode::ReadOnly)?;
+        let store = storage.get_hot_store();
+        let chain_store = ChainStore::new(
+            store.clone(),
+            near_config.genesis.config.genesis_height,
+            near_config.client_config.save_trie_changes,
+        );
+        let head = chain_store.head()?;
+        let epoch_manager =
+            EpochManager::new_from_genesis_config(store, &near_config.genesis.config)?;
+        let state =
+            EpochSimulationState::from_epoch_manager(&epoch_manager, &head.last_block_hash)?;
+        let protocol_version =
+            self.protocol_version.unwrap_or_else(|| state.next_epoch_info.protocol_version());
+        Ok((self.simulator(near_config, protocol_version), state))
+    }
+}
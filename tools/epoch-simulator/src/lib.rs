@@ -0,0 +1,113 @@
+use near_epoch_manager::simulation::{EpochSimulationState, EpochSimulator};
+use near_primitives::epoch_manager::epoch_info::EpochInfo;
+use near_primitives::errors::EpochError;
+use near_primitives::serialize::dec_format;
+use near_primitives::types::{
+    AccountId, Balance, EpochHeight, ValidatorId, ValidatorKickoutReason,
+};
+use near_primitives::version::ProtocolVersion;
+use std::collections::{BTreeMap, HashSet};
+
+pub mod cli;
+
+/// Outcome of the validator selection for a simulated epoch.
+#[derive(serde::Serialize, Debug)]
+pub struct EpochReport {
+    pub epoch_height: EpochHeight,
+    pub protocol_version: ProtocolVersion,
+    #[serde(with = "dec_format")]
+    pub seat_price: Balance,
+    /// Rewards minted for the epoch two epochs earlier.
+    #[serde(with = "dec_format")]
+    pub minted_amount: Balance,
+    /// Total supply once `minted_amount` is added.
+    #[serde(with = "dec_format")]
+    pub total_supply: Balance,
+    pub validators: Vec<AccountAmount>,
+    pub block_producers: Vec<AccountId>,
+    pub chunk_only_producers: Vec<AccountId>,
+    /// Chunk producers of each shard.
+    pub shard_assignment: Vec<Vec<AccountId>>,
+    pub kickouts: BTreeMap<AccountId, ValidatorKickoutReason>,
+    pub rewards: Vec<AccountAmount>,
+}
+
+#[derive(serde::Serialize, Debug)]
+pub struct AccountAmount {
+    pub account_id: AccountId,
+    #[serde(with = "dec_format")]
+    pub amount: Balance,
+}
+
+impl EpochReport {
+    fn new(epoch_info: &EpochInfo, total_supply: Balance) -> Self {
+        let account_id =
+            |validator_id: &ValidatorId| epoch_info.validator_account_id(*validator_id).clone();
+        let mut block_producers = Vec::new();
+        let mut seen = HashSet::new();
+        for validator_id in epoch_info.block_producers_settlement() {
+            if seen.insert(*validator_id) {
+                block_producers.push(account_id(validator_id));
+            }
+        }
+        let chunk_only_producers = epoch_info
+            .chunk_producers_settlement()
+            .iter()
+            .flatten()
+            .filter(|validator_id| seen.insert(**validator_id))
+            .map(account_id)
+            .collect();
+        let shard_assignment = epoch_info
+            .chunk_producers_settlement()
+            .iter()
+            .map(|chunk_producers| chunk_producers.iter().map(account_id).collect())
+            .collect();
+        let mut rewards: Vec<_> = epoch_info
+            .validator_reward()
+            .iter()
+            .map(|(account_id, amount)| AccountAmount {
+                account_id: account_id.clone(),
+                amount: *amount,
+            })
+            .collect();
+        rewards.sort_by(|a, b| a.account_id.cmp(&b.account_id));
+        Self {
+            epoch_height: epoch_info.epoch_height(),
+            protocol_version: epoch_info.protocol_version(),
+            seat_price: epoch_info.seat_price(),
+            minted_amount: epoch_info.minted_amount(),
+            total_supply,
+            validators: epoch_info
+                .validators_iter()
+                .map(|validator| {
+                    let (account_id, amount) = validator.account_and_stake();
+                    AccountAmount { account_id, amount }
+                })
+                .collect(),
+            block_producers,
+            chunk_only_producers,
+            shard_assignment,
+            kickouts: epoch_info
+                .validator_kickout()
+                .iter()
+                .map(|(account_id, reason)| (account_id.clone(), reason.clone()))
+                .collect(),
+            rewards,
+        }
+    }
+}
+
+/// Selects validators for the next `num_epochs` epochs after the ones already
+/// known in `state`.
+pub fn simulate(
+    simulator: &EpochSimulator,
+    state: &mut EpochSimulationState,
+    num_epochs: u64,
+) -> Result<Vec<EpochReport>, EpochError> {
+    let mut reports = Vec::new();
+    for _ in 0..num_epochs {
+        let epoch_info = simulator.simulate_epoch(state)?;
+        reports.push(EpochReport::new(&epoch_info, state.total_supply));
+    }
+    Ok(reports)
+}
@@ -0,0 +1,112 @@
+use anyhow::Context;
+use near_async::time;
+use near_crypto::PublicKey;
+use near_network::capture;
+use near_network::raw::Connection;
+use near_network::types::PeerInfo;
+use near_ping::cli::CHAIN_INFO;
+use near_primitives::hash::CryptoHash;
+use near_primitives::network::PeerId;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+#[derive(clap::Parser)]
+pub struct ReplayNetworkCaptureCommand {
+    /// Network capture written by a node with `network.experimental.capture_path` set.
+    #[clap(long)]
+    capture: PathBuf,
+
+    /// Chain id of the peer.
+    #[clap(long)]
+    chain_id: String,
+
+    #[clap(long)]
+    /// genesis hash to use in the Handshake we send. This must be provided if --chain-id
+    /// is not "mainnet" or "testnet"
+    genesis_hash: Option<String>,
+
+    #[clap(long)]
+    /// head height to use in the Handshake we send.
+    head_height: Option<u64>,
+
+    /// Protocol version to advertise in our handshake
+    #[clap(long)]
+    protocol_version: Option<u32>,
+
+    /// node public key and socket address in the format {pub key}@{socket addr}. e.g.:
+    /// ed25519:7PGseFbWxvYVgZ89K1uTJKYoKetWs7BJtbyXDzfbAcqX@127.0.0.1:24567
+    #[clap(long)]
+    peer: String,
+
+    /// Replay only the messages received from the peer with this public key.
+    #[clap(long)]
+    from_peer: Option<PublicKey>,
+
+    /// How many times faster than captured the messages are replayed.
+    /// 0 means as fast as possible.
+    #[clap(long, default_value = "1")]
+    speed: f64,
+
+    /// number of seconds to wait for incoming data before timing out
+    #[clap(long)]
+    recv_timeout_seconds: Option<u32>,
+}
+
+impl ReplayNetworkCaptureCommand {
+    pub fn run(&self) -> anyhow::Result<()> {
+        tracing::warn!(target: "network-replay", "the replay-network-capture command is not stable, and may be removed or changed arbitrarily at any time");
+        if self.speed.is_nan() || self.speed < 0. {
+            anyhow::bail!("--speed has to be non-negative");
+        }
+
+        let genesis_hash = match &self.genesis_hash {
+            Some(h) => CryptoHash::from_str(h)
+                .map_err(|e| anyhow::anyhow!("Could not parse --genesis-hash {}: {:?}", h, e))?,
+            None => match CHAIN_INFO.iter().find(|info| info.chain_id == self.chain_id) {
+                Some(info) => info.genesis_hash,
+                None => anyhow::bail!(
+                    "--genesis-hash not given, and genesis hash for --chain-id {} not known",
+                    &self.chain_id
+                ),
+            },
+        };
+        let peer = match PeerInfo::from_str(&self.peer) {
+            Ok(p) => p,
+            Err(e) => anyhow::bail!("Could not parse --peer {}: {:?}", &self.peer, e),
+        };
+        let Some(addr) = peer.addr else {
+            anyhow::bail!("--peer should be in the form [public key]@[socket addr]");
+        };
+        let mut reader = capture::Reader::open(&self.capture)
+            .with_context(|| format!("failed to open {}", self.capture.display()))?;
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async move {
+            let mut conn = Connection::connect(
+                addr,
+                peer.id.clone(),
+                self.protocol_version,
+                &self.chain_id,
+                genesis_hash,
+                self.head_height.unwrap_or(0),
+                vec![0],
+                time::Duration::seconds(self.recv_timeout_seconds.unwrap_or(5).into()),
+            )
+            .await
+            .context("failed to connect to the peer")?;
+            let stats = crate::replay(
+                &time::Clock::real(),
+                &mut conn,
+                &mut reader,
+                self.from_peer.clone().map(PeerId::new).as_ref(),
+                self.speed,
+            )
+            .await?;
+            for (msg_type, count) in &stats.sent {
+                println!("{:<30} {}", msg_type, count);
+            }
+            println!("skipped: {} invalid: {}", stats.skipped, stats.invalid);
+            Ok(())
+        })
+    }
+}
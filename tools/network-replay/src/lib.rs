@@ -0,0 +1,88 @@
+use near_async::time;
+use near_network::capture;
+use near_network::raw::Connection;
+use near_primitives::network::PeerId;
+use std::collections::BTreeMap;
+use std::io;
+pub mod cli;
+
+/// Summary of a replay.
+#[derive(Default, Debug)]
+pub struct ReplayStats {
+    /// Number of messages sent, by message type.
+    pub sent: BTreeMap<&'static str, u64>,
+    /// Number of messages skipped because they are specific to the captured connection.
+    pub skipped: u64,
+    /// Number of records which couldn't be parsed.
+    pub invalid: u64,
+}
+
+/// Sends the messages received by the captured node to `conn`.
+///
+/// Messages are sent with the same relative timing as they were received, sped up `speed`
+/// times (or as fast as possible if `speed` is 0). Messages coming from `conn` in the meantime
+/// are dropped. If `from` is set, only the messages received from that peer are replayed.
+/// Routed messages are re-targeted to the peer of `conn` and re-signed by the replaying node.
+pub async fn replay<R: io::Read>(
+    clock: &time::Clock,
+    conn: &mut Connection,
+    reader: &mut capture::Reader<R>,
+    from: Option<&PeerId>,
+    speed: f64,
+) -> anyhow::Result<ReplayStats> {
+    let mut stats = ReplayStats::default();
+    // Capture time and local time of the first replayed message.
+    let mut start: Option<(time::Utc, time::Instant)> = None;
+    loop {
+        let record = match reader.next() {
+            Ok(Some(record)) => record,
+            Ok(None) => break,
+            Err(err) => {
+                tracing::warn!(target: "network-replay", ?err, "capture is truncated, stopping");
+                break;
+            }
+        };
+        if record.direction != capture::Direction::Inbound {
+            continue;
+        }
+        if from.is_some() && record.peer_id.as_ref() != from {
+            continue;
+        }
+        let Some(msg_type) = record.message_type() else {
+            stats.invalid += 1;
+            continue;
+        };
+        let (capture_start, local_start) = *start.get_or_insert((record.timestamp(), clock.now()));
+        if speed > 0. {
+            let deadline = local_start + (record.timestamp() - capture_start) / speed;
+            wait_until(clock, conn, deadline).await?;
+        }
+        if conn.send_captured(&record).await? {
+            tracing::debug!(target: "network-replay", msg_type, "sent");
+            *stats.sent.entry(msg_type).or_default() += 1;
+        } else {
+            stats.skipped += 1;
+        }
+    }
+    Ok(stats)
+}
+
+/// Waits until the deadline, draining the messages sent by the peer, so that it doesn't
+/// block on a full TCP buffer.
+async fn wait_until(
+    clock: &time::Clock,
+    conn: &mut Connection,
+    deadline: time::Instant,
+) -> anyhow::Result<()> {
+    loop {
+        tokio::select! {
+            _ = clock.sleep_until(deadline) => return Ok(()),
+            res = conn.recv() => match res {
+                Ok((msg, _)) => tracing::trace!(target: "network-replay", %msg, "dropping"),
+                // Connection returns an error if the peer is silent for too long.
+                Err(err) if err.kind() == io::ErrorKind::TimedOut => {}
+                Err(err) => return Err(err.into()),
+            },
+        }
+    }
+}
@@ -221,7 +221,12 @@ fn apply_block_from_range(
             for tx in chunk.transactions() {
                 for action in &tx.transaction.actions {
                     has_contracts = has_contracts
-                        || matches!(action, Action::FunctionCall(_) | Action::DeployContract(_));
+                        || matches!(
+                            action,
+                            Action::FunctionCall(_)
+                                | Action::DeployContract(_)
+                                | Action::DeployContractWithMigration(_)
+                        );
                 }
             }
             if !has_contracts {
@@ -0,0 +1,360 @@
+use crate::commands::apply_block;
+use anyhow::Context;
+use borsh::BorshDeserialize;
+use near_chain::{Chain, ChainStore, ChainStoreAccess};
+use near_epoch_manager::{EpochManager, EpochManagerAdapter};
+use near_primitives::challenge::PartialState;
+use near_primitives::chunk_validation::ChunkStateWitness;
+use near_primitives::hash::{hash, CryptoHash};
+use near_primitives::merkle::merklize;
+use near_primitives::receipt::Receipt;
+use near_primitives::sharding::ChunkHash;
+use near_primitives::state::ValueRef;
+use near_store::trie::Children;
+use near_store::{
+    DBCol, NibbleSlice, RawTrieNode, RawTrieNodeWithSize, StorageError, Store, TrieDBStorage,
+    TrieStorage,
+};
+use nearcore::{NearConfig, NightshadeRuntime};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Columns the client saves chunk state witnesses to.
+const WITNESS_COLUMNS: [DBCol; 2] =
+    [DBCol::InvalidChunkStateWitnesses, DBCol::LatestChunkStateWitnesses];
+
+/// Prints the chunk state witnesses saved in the database.
+pub(crate) fn list_chunk_state_witnesses(store: &Store) -> anyhow::Result<()> {
+    for col in WITNESS_COLUMNS {
+        println!("{col}:");
+        for item in store.iter(col) {
+            let (_, value) = item?;
+            let witness = ChunkStateWitness::try_from_slice(&value)?;
+            let chunk_header = &witness.chunk_header;
+            println!(
+                "  height: {} shard_id: {} chunk_hash: {}",
+                chunk_header.height_created(),
+                chunk_header.shard_id(),
+                chunk_header.chunk_hash().0,
+            );
+        }
+    }
+    Ok(())
+}
+
+fn find_chunk_state_witness(
+    store: &Store,
+    chunk_hash: &ChunkHash,
+) -> anyhow::Result<(DBCol, ChunkStateWitness)> {
+    for col in WITNESS_COLUMNS {
+        for item in store.iter(col) {
+            let (key, value) = item?;
+            // The key ends with the chunk hash, see `get_chunk_state_witness_key`.
+            if key.ends_with(chunk_hash.as_ref()) {
+                return Ok((col, ChunkStateWitness::try_from_slice(&value)?));
+            }
+        }
+    }
+    anyhow::bail!("no chunk state witness saved for chunk {}", chunk_hash.0)
+}
+
+/// Re-executes the state transition attested by a saved chunk state witness
+/// and compares the produced state root and outgoing receipts with the ones
+/// in the witness.
+///
+/// The witness doesn't carry the full state yet, so the chunk is applied from
+/// the database, with the transactions and receipts stored there and on top of
+/// the state in the database, which has to include the block in which the
+/// transition happened. Only the expected results are taken from the witness.
+pub(crate) fn replay_chunk_state_witness(
+    chunk_hash: ChunkHash,
+    use_flat_storage: bool,
+    home_dir: &Path,
+    near_config: NearConfig,
+    store: Store,
+) -> anyhow::Result<()> {
+    let (col, witness) = find_chunk_state_witness(&store, &chunk_hash)?;
+    let chunk_header = &witness.chunk_header;
+    let shard_id = chunk_header.shard_id();
+    println!(
+        "Replaying witness from {col} for chunk {} at height {} of shard {}",
+        chunk_hash.0,
+        chunk_header.height_created(),
+        shard_id
+    );
+
+    let mut chain_store = ChainStore::new(
+        store.clone(),
+        near_config.genesis.config.genesis_height,
+        near_config.client_config.save_trie_changes,
+    );
+    let epoch_manager = EpochManager::new_arc_handle(store.clone(), &near_config.genesis.config);
+    let runtime = NightshadeRuntime::from_config(
+        home_dir,
+        store.clone(),
+        &near_config,
+        epoch_manager.clone(),
+    );
+
+    // The main transition is the application of the last chunk of the shard
+    // before this one. It is followed by implicit transitions for the blocks
+    // in which the shard had no chunk.
+    let mut block_hash = witness.main_state_transition.block_hash;
+    let mut num_implicit_transitions = witness.implicit_transitions.len();
+    if block_hash == CryptoHash::default() {
+        let prev_block = chain_store
+            .get_block(chunk_header.prev_block_hash())
+            .context("the previous block of the chunk is not in the database")?;
+        let height_included = prev_block.chunks()[shard_id as usize].height_included();
+        let mut header = prev_block.header().clone();
+        num_implicit_transitions = 0;
+        while header.height() > height_included {
+            header = chain_store.get_block_header(header.prev_hash())?;
+            num_implicit_transitions += 1;
+        }
+        block_hash = *header.hash();
+    }
+    println!(
+        "Applying the chunk of shard {} in block {} from the local database",
+        shard_id, block_hash
+    );
+    let (block, apply_result) = apply_block(
+        block_hash,
+        shard_id,
+        epoch_manager.as_ref(),
+        runtime.as_ref(),
+        &mut chain_store,
+        use_flat_storage,
+    );
+
+    let expected_state_root =
+        if witness.main_state_transition.post_state_root != CryptoHash::default() {
+            witness.main_state_transition.post_state_root
+        } else {
+            if num_implicit_transitions > 0 {
+                println!(
+                    "WARNING: {} implicit transitions which follow are not replayed",
+                    num_implicit_transitions
+                );
+            }
+            chunk_header.prev_state_root()
+        };
+    println!("expected state root: {}", expected_state_root);
+    println!("produced state root: {}", apply_result.new_root);
+
+    let epoch_id = epoch_manager.get_epoch_id_from_prev_block(chunk_header.prev_block_hash())?;
+    let shard_layout = epoch_manager.get_shard_layout(&epoch_id)?;
+    let receipts_hashes =
+        Chain::build_receipts_hashes(&apply_result.outgoing_receipts, &shard_layout);
+    let (produced_receipts_root, _) = merklize(&receipts_hashes);
+    let expected_receipts_root = chunk_header.prev_outgoing_receipts_root();
+    println!("expected outgoing receipts root: {}", expected_receipts_root);
+    println!("produced outgoing receipts root: {}", produced_receipts_root);
+    if produced_receipts_root != expected_receipts_root {
+        let stored_receipts = chain_store.get_outgoing_receipts(&block_hash, shard_id)?;
+        print_receipts_diff(&stored_receipts, &apply_result.outgoing_receipts);
+    }
+
+    if apply_result.new_root == expected_state_root {
+        println!("State roots match");
+        return Ok(());
+    }
+    let shard_uid = epoch_manager.shard_id_to_uid(shard_id, block.header().epoch_id())?;
+    let mut nodes: HashMap<CryptoHash, Arc<[u8]>> = apply_result
+        .trie_changes
+        .trie_changes()
+        .insertions()
+        .iter()
+        .map(|insertion| (*insertion.hash(), insertion.payload().into()))
+        .collect();
+    let PartialState::TrieValues(values) = &witness.main_state_transition.base_state;
+    nodes.extend(values.iter().map(|value| (hash(value), value.clone())));
+    let storage = ReplayTrieStorage { nodes, db: TrieDBStorage::new(store, shard_uid) };
+    match find_first_divergence(&storage, expected_state_root, apply_result.new_root)? {
+        Some((path, reason)) => {
+            println!("first diverging trie path: {}", format_nibbles(&path));
+            println!("{}", reason);
+        }
+        None => println!("No diverging trie path found"),
+    }
+    Ok(())
+}
+
+/// Prints the receipts which are only among the receipts stored in the
+/// database for the block or only among the produced ones.
+fn print_receipts_diff(stored_receipts: &[Receipt], produced_receipts: &[Receipt]) {
+    let stored: HashSet<_> = stored_receipts.iter().map(|receipt| receipt.receipt_id).collect();
+    let produced: HashSet<_> = produced_receipts.iter().map(|receipt| receipt.receipt_id).collect();
+    for receipt in stored_receipts.iter().filter(|receipt| !produced.contains(&receipt.receipt_id))
+    {
+        println!("  only stored: {} to {}", receipt.receipt_id, receipt.receiver_id);
+    }
+    for receipt in produced_receipts.iter().filter(|receipt| !stored.contains(&receipt.receipt_id))
+    {
+        println!("  only produced: {} to {}", receipt.receipt_id, receipt.receiver_id);
+    }
+}
+
+/// Trie nodes produced by the replay and included in the witness, on top of
+/// the nodes in the database.
+struct ReplayTrieStorage {
+    nodes: HashMap<CryptoHash, Arc<[u8]>>,
+    db: TrieDBStorage,
+}
+
+impl ReplayTrieStorage {
+    /// Returns `None` if the node is neither in the replay nor in the database.
+    fn get_node(&self, hash: &CryptoHash) -> anyhow::Result<Option<RawTrieNode>> {
+        let bytes = match self.nodes.get(hash) {
+            Some(bytes) => bytes.clone(),
+            None => match self.db.retrieve_raw_bytes(hash) {
+                Ok(bytes) => bytes,
+                Err(StorageError::MissingTrieValue(..)) => return Ok(None),
+                Err(err) => return Err(err.into()),
+            },
+        };
+        Ok(Some(RawTrieNodeWithSize::try_from_slice(&bytes)?.node))
+    }
+}
+
+fn branch(node: &RawTrieNode) -> Option<(Option<&ValueRef>, &Children)> {
+    match node {
+        RawTrieNode::BranchNoValue(children) => Some((None, children)),
+        RawTrieNode::BranchWithValue(value, children) => Some((Some(value), children)),
+        _ => None,
+    }
+}
+
+/// Walks down both tries along the nodes that differ and returns the nibbles
+/// of the first path at which they diverge, with a description of the
+/// difference.
+fn find_first_divergence(
+    storage: &ReplayTrieStorage,
+    mut expected: CryptoHash,
+    mut produced: CryptoHash,
+) -> anyhow::Result<Option<(Vec<u8>, String)>> {
+    let mut path = vec![];
+    while expected != produced {
+        let Some(expected_node) = storage.get_node(&expected)? else {
+            return Ok(Some((path, format!("expected node {} is not available", expected))));
+        };
+        let Some(produced_node) = storage.get_node(&produced)? else {
+            return Ok(Some((path, format!("produced node {} is not available", produced))));
+        };
+        let mismatch = format!("expected {:?}, produced {:?}", expected_node, produced_node);
+        match (&expected_node, &produced_node) {
+            (
+                RawTrieNode::Extension(expected_key, expected_child),
+                RawTrieNode::Extension(produced_key, produced_child),
+            ) if expected_key == produced_key => {
+                let (key, _) = NibbleSlice::from_encoded(expected_key);
+                path.extend(key.iter());
+                expected = *expected_child;
+                produced = *produced_child;
+            }
+            (
+                RawTrieNode::Leaf(expected_key, expected_value),
+                RawTrieNode::Leaf(produced_key, produced_value),
+            ) if expected_key == produced_key => {
+                let (key, _) = NibbleSlice::from_encoded(expected_key);
+                path.extend(key.iter());
+                let reason = format!(
+                    "values differ: expected {:?}, produced {:?}",
+                    expected_value, produced_value
+                );
+                return Ok(Some((path, reason)));
+            }
+            _ => {
+                let (
+                    Some((expected_value, expected_children)),
+                    Some((produced_value, produced_children)),
+                ) = (branch(&expected_node), branch(&produced_node))
+                else {
+                    return Ok(Some((path, mismatch)));
+                };
+                if expected_value != produced_value {
+                    let reason = format!(
+                        "values differ: expected {:?}, produced {:?}",
+                        expected_value, produced_value
+                    );
+                    return Ok(Some((path, reason)));
+                }
+                let Some(index) =
+                    (0..16u8).find(|&index| expected_children[index] != produced_children[index])
+                else {
+                    return Ok(Some((path, mismatch)));
+                };
+                path.push(index);
+                match (expected_children[index], produced_children[index]) {
+                    (Some(expected_child), Some(produced_child)) => {
+                        expected = expected_child;
+                        produced = produced_child;
+                    }
+                    (Some(_), None) => {
+                        return Ok(Some((path, "missing in the produced trie".to_string())))
+                    }
+                    (None, _) => {
+                        return Ok(Some((path, "missing in the expected trie".to_string())))
+                    }
+                }
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Formats a path as hex nibbles, followed by the full bytes of the path if
+/// any. Trie keys start with a column byte and usually an account id, so
+/// printing the bytes lossily keeps the account readable.
+fn format_nibbles(path: &[u8]) -> String {
+    let nibbles: String = path.iter().map(|nibble| format!("{:x}", nibble)).collect();
+    let bytes: Vec<u8> = path.chunks_exact(2).map(|pair| (pair[0] << 4) | pair[1]).collect();
+    match bytes.split_first() {
+        Some((col, rest)) => {
+            format!("{} (column {}, {:?})", nibbles, col, String::from_utf8_lossy(rest))
+        }
+        None => nibbles,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_first_divergence, ReplayTrieStorage};
+    use near_primitives::hash::CryptoHash;
+    use near_store::test_utils::{create_test_store, test_populate_trie, TestTriesBuilder};
+    use near_store::{ShardUId, TrieDBStorage};
+
+    #[test]
+    fn test_find_first_divergence() {
+        let store = create_test_store();
+        let tries = TestTriesBuilder::new().with_store(store.clone()).build();
+        let shard_uid = ShardUId::single_shard();
+        let changes = |value: &[u8]| {
+            vec![
+                (b"a".to_vec(), Some(b"1".to_vec())),
+                (b"b".to_vec(), Some(value.to_vec())),
+                (b"c".to_vec(), Some(b"3".to_vec())),
+            ]
+        };
+        let expected = test_populate_trie(&tries, &CryptoHash::default(), shard_uid, changes(b"2"));
+        let produced = test_populate_trie(&tries, &CryptoHash::default(), shard_uid, changes(b"4"));
+        let storage = ReplayTrieStorage {
+            nodes: Default::default(),
+            db: TrieDBStorage::new(store, shard_uid),
+        };
+
+        assert_eq!(find_first_divergence(&storage, expected, expected).unwrap(), None);
+        let (path, reason) = find_first_divergence(&storage, expected, produced).unwrap().unwrap();
+        // b"b" is 0x62.
+        assert_eq!(path, vec![6, 2]);
+        assert!(reason.starts_with("values differ"), "{reason}");
+
+        let (path, reason) =
+            find_first_divergence(&storage, expected, CryptoHash::hash_bytes(b"missing"))
+                .unwrap()
+                .unwrap();
+        assert!(path.is_empty());
+        assert!(reason.starts_with("produced node"), "{reason}");
+    }
+}
@@ -1,8 +1,10 @@
+use crate::chunk_state_witness::{list_chunk_state_witnesses, replay_chunk_state_witness};
 use crate::commands::*;
 use crate::contract_accounts::ContractAccountFilter;
 use crate::rocksdb_stats::get_rocksdb_stats;
 use crate::trie_iteration_benchmark::TrieIterationBenchmarkCmd;
 
+use near_chain::resharding_proposal::SplitThresholds;
 use near_chain_configs::{GenesisChangeConfig, GenesisValidationMode};
 use near_primitives::account::id::AccountId;
 use near_primitives::hash::CryptoHash;
@@ -46,6 +48,9 @@ pub enum StateViewerSubCommand {
     /// List account names with contracts deployed.
     #[clap(alias = "contract_accounts")]
     ContractAccounts(ContractAccountsCmd),
+    /// Report storage used by contract data of an account, grouped by key prefix.
+    #[clap(alias = "contract_storage_report")]
+    ContractStorageReport(ContractStorageReportCmd),
     /// Dump contract data in storage of given account to binary file.
     #[clap(alias = "dump_account_storage")]
     DumpAccountStorage(DumpAccountStorageCmd),
@@ -67,10 +72,17 @@ pub enum StateViewerSubCommand {
     /// Looks up a certain partial chunk.
     #[clap(alias = "partial_chunks")]
     PartialChunks(PartialChunksCmd),
+    /// Propose a shard layout splitting the shards with the most state, gas
+    /// usage or delayed receipts.
+    ProposeShardLayout(ProposeShardLayoutCmd),
     /// Looks up a certain receipt.
     Receipts(ReceiptsCmd),
     /// Replay headers from chain.
     Replay(ReplayCmd),
+    /// Re-execute a chunk state witness saved by the node with
+    /// `save_invalid_witnesses` or `save_latest_witnesses` and compare the
+    /// results with the ones expected by the witness.
+    ReplayChunkStateWitness(ReplayChunkStateWitnessCmd),
     /// Dump stats for the RocksDB storage.
     #[clap(name = "rocksdb-stats", alias = "rocksdb_stats")]
     RocksDBStats(RocksDBStatsCmd),
@@ -133,6 +145,9 @@ impl StateViewerSubCommand {
             StateViewerSubCommand::Chunks(cmd) => cmd.run(near_config, store),
             StateViewerSubCommand::ClearCache => clear_cache(store),
             StateViewerSubCommand::ContractAccounts(cmd) => cmd.run(home_dir, near_config, store),
+            StateViewerSubCommand::ContractStorageReport(cmd) => {
+                cmd.run(home_dir, near_config, store)
+            }
             StateViewerSubCommand::DumpAccountStorage(cmd) => cmd.run(home_dir, near_config, store),
             StateViewerSubCommand::DumpCode(cmd) => cmd.run(home_dir, near_config, store),
             StateViewerSubCommand::DumpState(cmd) => cmd.run(home_dir, near_config, store),
@@ -140,8 +155,12 @@ impl StateViewerSubCommand {
             StateViewerSubCommand::DumpTx(cmd) => cmd.run(home_dir, near_config, store),
             StateViewerSubCommand::EpochInfo(cmd) => cmd.run(near_config, store),
             StateViewerSubCommand::PartialChunks(cmd) => cmd.run(near_config, store),
+            StateViewerSubCommand::ProposeShardLayout(cmd) => cmd.run(home_dir, near_config, store),
             StateViewerSubCommand::Receipts(cmd) => cmd.run(near_config, store),
             StateViewerSubCommand::Replay(cmd) => cmd.run(near_config, store),
+            StateViewerSubCommand::ReplayChunkStateWitness(cmd) => {
+                cmd.run(home_dir, near_config, store)
+            }
             StateViewerSubCommand::RocksDBStats(cmd) => cmd.run(store_opener.path()),
             StateViewerSubCommand::ScanDbColumn(cmd) => cmd.run(store),
             StateViewerSubCommand::State => state(home_dir, near_config, store),
@@ -308,6 +327,49 @@ impl ContractAccountsCmd {
     }
 }
 
+#[derive(clap::Parser)]
+pub struct ContractStorageReportCmd {
+    #[clap(long)]
+    account_id: AccountId,
+    /// Key prefix to group contract data by, e.g. a near-sdk collection
+    /// prefix. Can be repeated. If no prefixes are given, keys are grouped by
+    /// their first byte.
+    #[clap(long)]
+    prefix: Vec<String>,
+    /// Same as `--prefix`, but the prefix is base64 encoded.
+    #[clap(long)]
+    prefix_base64: Vec<String>,
+    /// Number of largest keys to list.
+    #[clap(long, default_value = "10")]
+    top: usize,
+    /// Height of the block to view the state at. Defaults to the chain head.
+    #[clap(long)]
+    block_height: Option<BlockHeight>,
+}
+
+impl ContractStorageReportCmd {
+    pub fn run(self, home_dir: &Path, near_config: NearConfig, store: Store) {
+        let prefixes = self
+            .prefix
+            .into_iter()
+            .map(String::into_bytes)
+            .chain(self.prefix_base64.iter().map(|prefix| {
+                near_primitives_core::serialize::from_base64(prefix)
+                    .expect("prefix_base64 should be valid base64")
+            }))
+            .collect();
+        print_contract_storage_report(
+            self.account_id,
+            prefixes,
+            self.top,
+            self.block_height,
+            home_dir,
+            near_config,
+            store,
+        );
+    }
+}
+
 #[derive(clap::Parser)]
 pub struct DumpAccountStorageCmd {
     #[clap(long)]
@@ -476,6 +538,35 @@ impl PartialChunksCmd {
     }
 }
 
+#[derive(clap::Parser)]
+pub struct ProposeShardLayoutCmd {
+    /// State size of a shard in bytes above which it is split.
+    #[clap(long, default_value = "100000000000")]
+    max_state_size: u64,
+    /// Percentage of the gas limit used by the chunks of a shard over the
+    /// last epoch above which it is split.
+    #[clap(long, default_value = "50")]
+    max_gas_used_percent: u64,
+    /// Number of delayed receipts in a shard above which it is split.
+    #[clap(long, default_value = "20000")]
+    max_delayed_receipts: u64,
+    /// Maximum number of shards to split.
+    #[clap(long, default_value = "1")]
+    max_splits: usize,
+}
+
+impl ProposeShardLayoutCmd {
+    pub fn run(self, home_dir: &Path, near_config: NearConfig, store: Store) {
+        let thresholds = SplitThresholds {
+            max_state_size: self.max_state_size,
+            max_gas_used_percent: self.max_gas_used_percent,
+            max_delayed_receipts: self.max_delayed_receipts,
+            max_splits: self.max_splits,
+        };
+        propose_shard_layout(thresholds, home_dir, near_config, store).unwrap();
+    }
+}
+
 #[derive(clap::Parser)]
 pub struct ReceiptsCmd {
     #[clap(long)]
@@ -502,6 +593,35 @@ impl ReplayCmd {
     }
 }
 
+#[derive(clap::Parser)]
+pub struct ReplayChunkStateWitnessCmd {
+    /// Hash of the chunk of the witness. If not set, the saved witnesses are
+    /// listed.
+    #[clap(long)]
+    chunk_hash: Option<String>,
+    #[clap(long)]
+    use_flat_storage: bool,
+}
+
+impl ReplayChunkStateWitnessCmd {
+    pub fn run(self, home_dir: &Path, near_config: NearConfig, store: Store) {
+        match self.chunk_hash {
+            Some(chunk_hash) => {
+                let chunk_hash = ChunkHash::from(CryptoHash::from_str(&chunk_hash).unwrap());
+                replay_chunk_state_witness(
+                    chunk_hash,
+                    self.use_flat_storage,
+                    home_dir,
+                    near_config,
+                    store,
+                )
+            }
+            None => list_chunk_state_witnesses(&store),
+        }
+        .unwrap();
+    }
+}
+
 #[derive(clap::Parser)]
 pub struct RocksDBStatsCmd {
     /// Location of the dumped Rocks DB stats.
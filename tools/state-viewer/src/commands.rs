@@ -11,6 +11,10 @@ use itertools::GroupBy;
 use itertools::Itertools;
 use near_chain::chain::collect_receipts_from_response;
 use near_chain::migrations::check_if_block_is_first_with_chunk_of_version;
+use near_chain::resharding_proposal::{
+    pick_boundary_account, shards_to_split, split_shard_layout, AccountLoad, ShardStats,
+    SplitThresholds,
+};
 use near_chain::types::ApplyTransactionResult;
 use near_chain::types::ApplyTransactionsBlockContext;
 use near_chain::types::ApplyTransactionsChunkContext;
@@ -37,11 +41,12 @@ use near_primitives_core::types::Gas;
 use near_store::flat::FlatStorageChunkView;
 use near_store::flat::FlatStorageManager;
 use near_store::test_utils::create_test_store;
-use near_store::TrieStorage;
+use near_store::{get_delayed_receipt_indices, TrieStorage};
 use near_store::{DBCol, Store, Trie, TrieCache, TrieCachingStorage, TrieConfig, TrieDBStorage};
 use nearcore::{NearConfig, NightshadeRuntime};
 use node_runtime::adapter::ViewRuntimeAdapter;
 use serde_json::json;
+use std::collections::BTreeMap;
 use std::collections::BinaryHeap;
 use std::collections::HashMap;
 use std::fs::{self, File};
@@ -1087,6 +1092,209 @@ pub(crate) fn print_state_stats(home_dir: &Path, store: Store, near_config: Near
     }
 }
 
+/// Prints storage used by contract data of `account_id`, grouped by the given
+/// key prefixes.
+pub(crate) fn print_contract_storage_report(
+    account_id: AccountId,
+    prefixes: Vec<Vec<u8>>,
+    top: usize,
+    block_height: Option<BlockHeight>,
+    home_dir: &Path,
+    near_config: NearConfig,
+    store: Store,
+) {
+    let mode = block_height.map_or(LoadTrieMode::Latest, LoadTrieMode::Height);
+    let (epoch_manager, runtime, _, header) =
+        load_trie_stop_at_height(store, home_dir, &near_config, mode);
+    let block_hash = *header.hash();
+    let epoch_id = header.epoch_id();
+    let shard_id = epoch_manager.account_id_to_shard_id(&account_id, epoch_id).unwrap();
+    let shard_uid = epoch_manager.shard_id_to_uid(shard_id, epoch_id).unwrap();
+    let storage_usage_config =
+        runtime.get_protocol_config(epoch_id).unwrap().runtime_config.fees.storage_usage_config;
+
+    let flat_storage_manager = runtime.get_flat_storage_manager();
+    flat_storage_manager.create_flat_storage_for_shard(shard_uid).unwrap();
+    let chunk_view = flat_storage_manager
+        .chunk_view(shard_uid, block_hash)
+        .expect("flat storage is not available for the shard");
+    let report = near_store::flat::contract_storage_report(
+        &chunk_view,
+        block_hash,
+        header.height(),
+        &account_id,
+        &near_store::flat::ContractStorageReportOptions {
+            prefixes: &prefixes,
+            top,
+            start_key: None,
+            max_keys: usize::MAX,
+        },
+        storage_usage_config.num_extra_bytes_record,
+    )
+    .unwrap();
+    println!("{}", serde_json::to_string_pretty(&report).unwrap());
+}
+
+/// Candidate shard layout together with the statistics it is based on.
+#[derive(serde::Serialize)]
+struct ShardLayoutProposal {
+    /// The epoch over which gas usage and delayed receipts are collected.
+    epoch_id: CryptoHash,
+    /// The block at which the state size is measured.
+    block_hash: CryptoHash,
+    shards: Vec<ShardStats>,
+    boundary_accounts: BTreeMap<ShardId, AccountId>,
+    /// `None` if no shard is above the thresholds.
+    shard_layout: Option<ShardLayout>,
+}
+
+/// Collects per shard statistics over the last complete epoch and prints the
+/// shard layout in which the shards above `thresholds` are split.
+pub(crate) fn propose_shard_layout(
+    thresholds: SplitThresholds,
+    home_dir: &Path,
+    near_config: NearConfig,
+    store: Store,
+) -> anyhow::Result<()> {
+    let proposal = get_shard_layout_proposal(&thresholds, home_dir, &near_config, store)?;
+    println!("{}", serde_json::to_string_pretty(&proposal)?);
+    Ok(())
+}
+
+fn get_shard_layout_proposal(
+    thresholds: &SplitThresholds,
+    home_dir: &Path,
+    near_config: &NearConfig,
+    store: Store,
+) -> anyhow::Result<ShardLayoutProposal> {
+    let (epoch_manager, runtime, _, head_header) = load_trie(store.clone(), home_dir, near_config);
+    let chain_store = ChainStore::new(
+        store.clone(),
+        near_config.genesis.config.genesis_height,
+        near_config.client_config.save_trie_changes,
+    );
+    let block_hash = *head_header.hash();
+    let shard_layout = epoch_manager.get_shard_layout(head_header.epoch_id())?;
+
+    let epoch_start_height = epoch_manager.get_epoch_start_height(&block_hash)?;
+    let epoch_start_hash = chain_store.get_block_hash_by_height(epoch_start_height)?;
+    let mut header = chain_store
+        .get_block_header(chain_store.get_block_header(&epoch_start_hash)?.prev_hash())?;
+    let epoch_id = header.epoch_id().clone();
+    anyhow::ensure!(
+        epoch_manager.get_shard_layout(&epoch_id)? == shard_layout,
+        "shard layout changed in the current epoch, statistics of the previous epoch don't apply"
+    );
+
+    let mut stats: Vec<ShardStats> = shard_layout
+        .shard_ids()
+        .map(|shard_id| ShardStats { shard_id, ..Default::default() })
+        .collect();
+    let trie_storages: Vec<Rc<dyn TrieStorage>> = shard_layout
+        .shard_uids()
+        .map(|shard_uid| Rc::new(TrieDBStorage::new(store.clone(), shard_uid)) as _)
+        .collect();
+    let mut gas_by_account: HashMap<AccountId, Gas> = HashMap::new();
+    while header.epoch_id() == &epoch_id
+        && header.height() > near_config.genesis.config.genesis_height
+    {
+        let hash = header.hash();
+        let block = chain_store.get_block(hash)?;
+        for chunk_header in block.chunks().iter() {
+            let shard_id = chunk_header.shard_id();
+            let shard_uid = ShardUId::from_shard_id_and_layout(shard_id, &shard_layout);
+            let chunk_extra = chain_store.get_chunk_extra(hash, &shard_uid)?;
+            let shard_stats = &mut stats[shard_id as usize];
+            if chunk_header.height_included() == header.height() {
+                shard_stats.gas_used += chunk_extra.gas_used();
+                shard_stats.gas_limit += chunk_extra.gas_limit();
+                let outcome_ids =
+                    chain_store.get_outcomes_by_block_hash_and_shard_id(hash, shard_id)?;
+                for outcome_id in outcome_ids {
+                    let Some(outcome) =
+                        chain_store.get_outcome_by_id_and_block_hash(&outcome_id, hash)?
+                    else {
+                        continue;
+                    };
+                    *gas_by_account.entry(outcome.outcome.executor_id).or_default() +=
+                        outcome.outcome.gas_burnt;
+                }
+            }
+            let trie = Trie::new(
+                trie_storages[shard_id as usize].clone(),
+                *chunk_extra.state_root(),
+                None,
+            );
+            let indices = get_delayed_receipt_indices(&trie)?;
+            shard_stats.max_delayed_receipts = shard_stats
+                .max_delayed_receipts
+                .max(indices.next_available_index - indices.first_index);
+        }
+        header = chain_store.get_block_header(header.prev_hash())?;
+    }
+
+    // The state size is only available at the head, from flat storage.
+    let flat_storage_manager = runtime.get_flat_storage_manager();
+    for shard_uid in shard_layout.shard_uids() {
+        let state_size = &mut stats[shard_uid.shard_id as usize].state_size;
+        for_each_account(&store, &flat_storage_manager, block_hash, shard_uid, |account| {
+            *state_size += account.size.as_u64()
+        });
+    }
+
+    let mut boundary_accounts = BTreeMap::new();
+    for shard_id in shards_to_split(&stats, thresholds) {
+        let shard_uid = ShardUId::from_shard_id_and_layout(shard_id, &shard_layout);
+        let mut accounts = vec![];
+        for_each_account(&store, &flat_storage_manager, block_hash, shard_uid, |account| {
+            accounts.push(AccountLoad {
+                gas_used: gas_by_account.get(&account.account_id).copied().unwrap_or_default(),
+                account_id: account.account_id,
+                state_size: account.size.as_u64(),
+            })
+        });
+        match pick_boundary_account(&accounts) {
+            Some(account_id) => {
+                boundary_accounts.insert(shard_id, account_id);
+            }
+            None => {
+                tracing::warn!(target: "state_viewer", shard_id, "shard has too few accounts to be split")
+            }
+        }
+    }
+    let new_shard_layout = if boundary_accounts.is_empty() {
+        None
+    } else {
+        Some(split_shard_layout(&shard_layout, &boundary_accounts)?)
+    };
+    Ok(ShardLayoutProposal {
+        epoch_id: epoch_id.0,
+        block_hash,
+        shards: stats,
+        boundary_accounts,
+        shard_layout: new_shard_layout,
+    })
+}
+
+/// Calls `f` with every account in the flat state of the shard at
+/// `block_hash`, in the order of account ids.
+fn for_each_account(
+    store: &Store,
+    flat_storage_manager: &FlatStorageManager,
+    block_hash: CryptoHash,
+    shard_uid: ShardUId,
+    f: impl FnMut(StateStatsAccount),
+) {
+    // Flat storage of a shard can only be created once.
+    if flat_storage_manager.get_flat_storage_for_shard(shard_uid).is_none() {
+        flat_storage_manager.create_flat_storage_for_shard(shard_uid).unwrap();
+    }
+    let trie_storage = TrieDBStorage::new(store.clone(), shard_uid);
+    let chunk_view = flat_storage_manager.chunk_view(shard_uid, block_hash).unwrap();
+    let group_by = get_state_stats_group_by(&chunk_view, &trie_storage);
+    get_state_stats_account_iter(&group_by).for_each(f);
+}
+
 /// Prints the state statistics for a single shard.
 fn print_state_stats_for_shard_uid(
     store: &Store,
@@ -1319,6 +1527,7 @@ impl std::fmt::Debug for StateStatsAccount {
 
 #[cfg(test)]
 mod tests {
+    use near_chain::resharding_proposal::SplitThresholds;
     use near_chain::types::RuntimeAdapter;
     use near_chain::ChainGenesis;
     use near_chain_configs::Genesis;
@@ -1396,4 +1605,59 @@ mod tests {
         assert_eq!(&state_roots[0], chunk_extras[1].state_root());
         assert_eq!(block_header.height(), 2);
     }
+
+    /// Runs the shard layout proposal on a node with a single shard above the
+    /// state size threshold and checks that the shard is split between its
+    /// accounts.
+    #[test]
+    fn test_propose_shard_layout() {
+        near_o11y::testonly::init_test_logger();
+        let accounts: Vec<AccountId> =
+            (0..4).map(|i| format!("test{i}").parse().unwrap()).collect();
+        let mut genesis = Genesis::test_sharded_new_version(accounts.clone(), 1, vec![1]);
+        genesis.config.epoch_length = 5;
+        let chain_genesis = ChainGenesis::new(&genesis);
+
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let home_dir = tmp_dir.path();
+
+        let store = near_store::test_utils::create_test_store();
+        initialize_genesis_state(store.clone(), &genesis, Some(home_dir));
+        let epoch_manager = EpochManager::new_arc_handle(store.clone(), &genesis.config);
+        let runtime = NightshadeRuntime::test(
+            home_dir,
+            store.clone(),
+            &genesis.config,
+            epoch_manager.clone(),
+        ) as Arc<dyn RuntimeAdapter>;
+        let mut env = TestEnv::builder(chain_genesis)
+            .stores(vec![store.clone()])
+            .epoch_managers(vec![epoch_manager])
+            .runtimes(vec![runtime])
+            .build();
+        // Finish the first epoch so that its statistics are available.
+        for height in 1..=12 {
+            env.produce_block(0, height);
+        }
+
+        let signer = InMemorySigner::from_seed("test0".parse().unwrap(), KeyType::ED25519, "test0");
+        let near_config =
+            NearConfig::new(Config::default(), genesis, KeyFile::from(&signer), None).unwrap();
+        let thresholds = SplitThresholds {
+            max_state_size: 1,
+            max_gas_used_percent: 100,
+            max_delayed_receipts: 1000,
+            max_splits: 1,
+        };
+        let proposal =
+            super::get_shard_layout_proposal(&thresholds, home_dir, &near_config, store).unwrap();
+        assert_eq!(proposal.shards.len(), 1);
+        assert!(proposal.shards[0].state_size > 0);
+        assert!(proposal.shards[0].gas_limit > 0);
+        let boundary_account = proposal.boundary_accounts.get(&0).unwrap();
+        assert!(accounts.contains(boundary_account));
+        let shard_layout = proposal.shard_layout.unwrap();
+        assert_eq!(shard_layout.boundary_accounts(), Some(&[boundary_account.clone()][..]));
+        assert_eq!(shard_layout.version(), 2);
+    }
 }
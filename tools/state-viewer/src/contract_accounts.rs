@@ -134,6 +134,7 @@ pub(crate) enum ActionType {
     DeleteAccount,
     DataReceipt,
     Delegate,
+    DeployContractWithMigration,
 }
 
 impl ContractAccount {
@@ -338,6 +339,9 @@ fn try_find_actions_spawned_by_receipt(
                                     Action::DeleteKey(_) => ActionType::DeleteKey,
                                     Action::DeleteAccount(_) => ActionType::DeleteAccount,
                                     Action::Delegate(_) => ActionType::Delegate,
+                                    Action::DeployContractWithMigration(_) => {
+                                        ActionType::DeployContractWithMigration
+                                    }
                                 };
                                 entry
                                     .actions
@@ -2,6 +2,7 @@
 
 mod apply_chain_range;
 mod apply_chunk;
+mod chunk_state_witness;
 pub mod cli;
 mod commands;
 mod contract_accounts;